@@ -0,0 +1,84 @@
+use barnacle_beats::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const FACT_COUNT: usize = 10_000;
+const RULE_COUNT: usize = 1_000;
+
+fn populated_store() -> FactsOfTheWorld {
+    let mut store = FactsOfTheWorld::new();
+    for i in 0..FACT_COUNT {
+        store.store_int(format!("fact.{}", i), i as i32).unwrap();
+    }
+    store.updated_facts.clear();
+    store.previous_facts.clear();
+    store
+}
+
+fn rules() -> Vec<Rule> {
+    (0..RULE_COUNT)
+        .map(|i| {
+            RuleBuilder::new(format!("rule.{}", i))
+                .with_condition(Condition::IntMoreThan {
+                    fact_name: format!("fact.{}", (i * 7) % FACT_COUNT),
+                    expected_value: 500,
+                })
+                .with_condition(Condition::IntLessThan {
+                    fact_name: format!("fact.{}", (i * 13) % FACT_COUNT),
+                    expected_value: 9_000,
+                })
+                .build()
+                .expect("benchmark rules have conditions")
+        })
+        .collect()
+}
+
+fn evaluate_rules(c: &mut Criterion) {
+    let store = populated_store();
+    let rules = rules();
+    c.bench_function("evaluate 1k rules against 10k facts", |b| {
+        b.iter(|| {
+            rules
+                .iter()
+                .filter(|rule| rule.evaluate(black_box(&store)))
+                .count()
+        })
+    });
+}
+
+fn store_facts(c: &mut Criterion) {
+    c.bench_function("store_int 10k new facts", |b| {
+        b.iter(|| {
+            let mut store = FactsOfTheWorld::new();
+            for i in 0..FACT_COUNT {
+                store.store_int(format!("fact.{}", i), black_box(i as i32)).unwrap();
+            }
+            store
+        })
+    });
+
+    let mut store = populated_store();
+    let mut round = 0;
+    c.bench_function("store_int 10k updated facts", |b| {
+        b.iter(|| {
+            round += 1;
+            for i in 0..FACT_COUNT {
+                store.store_int(format!("fact.{}", i), black_box(round)).unwrap();
+            }
+            store.updated_facts.clear();
+            store.previous_facts.clear();
+        })
+    });
+
+    c.bench_function("store_string 10k new facts", |b| {
+        b.iter(|| {
+            let mut store = FactsOfTheWorld::new();
+            for i in 0..FACT_COUNT {
+                store.store_string(format!("fact.{}", i), black_box("value".to_string())).unwrap();
+            }
+            store
+        })
+    });
+}
+
+criterion_group!(benches, evaluate_rules, store_facts);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+// Lints story files without starting the game:
+//     cargo run --example story_check [story files...]
+// With no files given it checks every story under assets/stories. Facts from the data tables
+// under assets/data count as written. Exits with 1 when there are findings.
+use barnacle_beats::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+fn files_in(dir: &Path, suffix: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.to_string_lossy().ends_with(suffix))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn main() -> ExitCode {
+    let assets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets");
+    let mut paths: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        paths = files_in(&assets.join("stories"), ".story.ron");
+    }
+
+    let mut stories = Vec::new();
+    for path in paths.iter() {
+        let parsed = fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|source| parse_story(&source).map_err(|error| error.to_string()));
+        match parsed {
+            Ok(story) => stories.push(story),
+            Err(error) => {
+                eprintln!("{}: {}", path.display(), error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut known = KnownFacts::game();
+    for path in files_in(&assets.join("data"), ".csv") {
+        let namespace = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let table = fs::read_to_string(&path)
+            .ok()
+            .and_then(|source| parse_fact_table(&namespace, &source).ok());
+        for fact in table.iter().flat_map(|table| table.facts.iter()) {
+            known = known.writes(fact.key());
+        }
+    }
+
+    let lints = lint_stories(&stories, &known);
+    for lint in lints.iter() {
+        println!("{}", lint);
+    }
+    println!("{} stories, {} findings", stories.len(), lints.len());
+    if lints.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
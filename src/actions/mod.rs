@@ -2,10 +2,15 @@ use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 
 use crate::actions::game_control::{get_movement, GameControl};
+use crate::actions::recording::{
+    play_input_actions, record_input_actions, recorder_command_system, recorder_keys,
+    InputRecorder, RecorderCommand,
+};
 use crate::player::Player;
 use crate::GameState;
 
 mod game_control;
+pub mod recording;
 
 pub const FOLLOW_EPSILON: f32 = 5.;
 
@@ -15,10 +20,28 @@ pub struct ActionsPlugin;
 // Actions can then be used as a resource in other systems to act on the player input.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Actions>().add_systems(
-            Update,
-            set_movement_actions.run_if(in_state(GameState::Playing)),
-        );
+        app.init_resource::<Actions>()
+            .init_resource::<InputRecorder>()
+            .add_event::<RecorderCommand>()
+            .add_systems(
+                Update,
+                set_movement_actions.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    recorder_command_system,
+                    record_input_actions,
+                    play_input_actions,
+                )
+                    .chain()
+                    .after(set_movement_actions),
+            );
+
+        #[cfg(debug_assertions)]
+        {
+            app.add_systems(Update, recorder_keys.before(recorder_command_system));
+        }
     }
 }
 
@@ -0,0 +1,215 @@
+use crate::actions::Actions;
+use crate::beats::choices::ChoiceMade;
+use crate::beats::save_location::SaveLocation;
+use crate::dialogue::AdvanceDialogue;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const INPUT_RECORDING_FILE: &str = "input_recording.ron";
+
+// What the player did, rather than which keys they pressed, so recordings survive rebinding
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum InputAction {
+    Move(Option<Vec2>),
+    AdvanceDialogue,
+    Choose {
+        story: String,
+        beat: String,
+        index: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RecordedAction {
+    // Seconds since the recording started
+    pub at: f64,
+    pub action: InputAction,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct InputRecording {
+    pub actions: Vec<RecordedAction>,
+}
+
+#[derive(Debug, Default)]
+enum RecorderState {
+    #[default]
+    Idle,
+    Recording {
+        started: f64,
+        recording: InputRecording,
+    },
+    Playing {
+        started: f64,
+        recording: InputRecording,
+        next: usize,
+        movement: Option<Vec2>,
+    },
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct InputRecorder {
+    state: RecorderState,
+}
+
+impl InputRecorder {
+    pub fn is_recording(&self) -> bool {
+        matches!(self.state, RecorderState::Recording { .. })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.state, RecorderState::Playing { .. })
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub enum RecorderCommand {
+    // Starts recording, or stops and writes the recording to `INPUT_RECORDING_FILE`
+    ToggleRecording,
+    // Replays the given recording, or the one in `INPUT_RECORDING_FILE`
+    Play(Option<InputRecording>),
+    Stop,
+}
+
+pub fn recorder_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut commands: EventWriter<RecorderCommand>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        commands.send(RecorderCommand::ToggleRecording);
+    }
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        commands.send(RecorderCommand::Play(None));
+    }
+}
+
+pub fn recorder_command_system(
+    mut commands: EventReader<RecorderCommand>,
+    mut recorder: ResMut<InputRecorder>,
+    time: Res<Time<Real>>,
+    location: Res<SaveLocation>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for command in commands.read() {
+        match command {
+            RecorderCommand::ToggleRecording => {
+                if let RecorderState::Recording { recording, .. } =
+                    std::mem::take(&mut recorder.state)
+                {
+                    info!("Recorded {} input actions", recording.actions.len());
+                    match ron::ser::to_string_pretty(&recording, ron::ser::PrettyConfig::default())
+                    {
+                        Ok(source) => location.write(INPUT_RECORDING_FILE, &source),
+                        Err(error) => warn!("Could not serialize input recording: {}", error),
+                    }
+                } else {
+                    recorder.state = RecorderState::Recording {
+                        started: now,
+                        recording: InputRecording::default(),
+                    };
+                }
+            }
+            RecorderCommand::Play(recording) => {
+                let recording = match recording {
+                    Some(recording) => Ok(recording.clone()),
+                    None => location.read(INPUT_RECORDING_FILE).and_then(|source| {
+                        ron::from_str(&source).map_err(|error| error.to_string())
+                    }),
+                };
+                match recording {
+                    Ok(recording) => {
+                        recorder.state = RecorderState::Playing {
+                            started: now,
+                            recording,
+                            next: 0,
+                            movement: None,
+                        }
+                    }
+                    Err(error) => warn!("Could not read input recording: {}", error),
+                }
+            }
+            RecorderCommand::Stop => recorder.state = RecorderState::Idle,
+        }
+    }
+}
+
+// Runs after the input systems, so it sees what the player did this frame
+pub fn record_input_actions(
+    mut recorder: ResMut<InputRecorder>,
+    time: Res<Time<Real>>,
+    actions: Res<Actions>,
+    mut advances: EventReader<AdvanceDialogue>,
+    mut choices_made: EventReader<ChoiceMade>,
+    mut last_movement: Local<Option<Vec2>>,
+) {
+    let RecorderState::Recording { started, recording } = &mut recorder.state else {
+        advances.clear();
+        choices_made.clear();
+        return;
+    };
+    let at = time.elapsed_seconds_f64() - *started;
+    if actions.player_movement != *last_movement {
+        *last_movement = actions.player_movement;
+        recording.actions.push(RecordedAction {
+            at,
+            action: InputAction::Move(actions.player_movement),
+        });
+    }
+    for _ in advances.read() {
+        recording.actions.push(RecordedAction {
+            at,
+            action: InputAction::AdvanceDialogue,
+        });
+    }
+    for made in choices_made.read() {
+        recording.actions.push(RecordedAction {
+            at,
+            action: InputAction::Choose {
+                story: made.story.clone(),
+                beat: made.beat.clone(),
+                index: made.index,
+            },
+        });
+    }
+}
+
+// Runs after the input systems and overrides them with what was recorded
+pub fn play_input_actions(
+    mut recorder: ResMut<InputRecorder>,
+    time: Res<Time<Real>>,
+    mut actions: ResMut<Actions>,
+    mut advances: EventWriter<AdvanceDialogue>,
+    mut choices_made: EventWriter<ChoiceMade>,
+) {
+    let RecorderState::Playing {
+        started,
+        recording,
+        next,
+        movement,
+    } = &mut recorder.state
+    else {
+        return;
+    };
+    let at = time.elapsed_seconds_f64() - *started;
+    while let Some(recorded) = recording.actions.get(*next).filter(|r| r.at <= at) {
+        match &recorded.action {
+            InputAction::Move(direction) => *movement = *direction,
+            InputAction::AdvanceDialogue => {
+                advances.send(AdvanceDialogue);
+            }
+            InputAction::Choose { story, beat, index } => {
+                choices_made.send(ChoiceMade {
+                    story: story.clone(),
+                    beat: beat.clone(),
+                    index: *index,
+                });
+            }
+        }
+        *next += 1;
+    }
+    actions.player_movement = *movement;
+    if *next >= recording.actions.len() {
+        info!("Input playback finished");
+        recorder.state = RecorderState::Idle;
+    }
+}
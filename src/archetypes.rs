@@ -0,0 +1,356 @@
+use crate::beats::data::EffectOutput;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::TypeRegistry;
+use bevy::utils::BoxedFuture;
+use serde::de::{DeserializeSeed, Error as _, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+// Archetype files bundled with the game, relative to the assets folder
+pub const ARCHETYPE_FILES: &[&str] = &[
+    "archetypes/harbour_buoy.archetype.ron",
+    "archetypes/sparkle_burst.archetype.ron",
+];
+
+pub struct ArchetypePlugin;
+
+/// Props spawned by `Effect::Spawn`, described in archetype files so new ones need no code
+impl Plugin for ArchetypePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StoryProp>()
+            .init_asset::<Archetype>()
+            .init_asset_loader::<ArchetypeLoader>()
+            .init_resource::<ArchetypeFiles>()
+            .add_systems(Startup, load_archetype_files)
+            .add_systems(Update, spawn_archetypes.run_if(in_state(GameState::Story)))
+            .add_systems(OnExit(GameState::Story), despawn_story_props);
+    }
+}
+
+// A named entity a story can spawn. Components are any registered component types, written
+// like in a scene file:
+//
+//     components: {
+//         "bevy_sprite::sprite::Sprite": (flip_x: true),
+//     }
+//
+// Fields left out keep the component's default value.
+#[derive(Asset, TypePath)]
+pub struct Archetype {
+    pub name: String,
+    #[dependency]
+    pub sprite: Option<Handle<Image>>,
+    pub transform: Transform,
+    pub components: Vec<Box<dyn Reflect>>,
+}
+
+// Placement of a spawned prop. Props are 2D, so rotation is degrees around the z axis.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct ArchetypeTransform {
+    pub translation: (f32, f32, f32),
+    pub rotation: f32,
+    pub scale: f32,
+}
+
+impl Default for ArchetypeTransform {
+    fn default() -> Self {
+        ArchetypeTransform {
+            translation: (0., 0., 0.),
+            rotation: 0.,
+            scale: 1.,
+        }
+    }
+}
+
+impl From<ArchetypeTransform> for Transform {
+    fn from(transform: ArchetypeTransform) -> Self {
+        let (x, y, z) = transform.translation;
+        Transform::from_xyz(x, y, z)
+            .with_rotation(Quat::from_rotation_z(transform.rotation.to_radians()))
+            .with_scale(Vec3::splat(transform.scale))
+    }
+}
+
+// Marks entities spawned from an archetype, they are removed when the story is left
+#[derive(Component, Reflect, Debug, Clone, Default)]
+#[reflect(Component)]
+pub struct StoryProp {
+    pub archetype: String,
+}
+
+// The file as written, before the sprite is loaded
+struct ArchetypeSource {
+    name: String,
+    sprite: Option<String>,
+    transform: ArchetypeTransform,
+    components: Vec<Box<dyn Reflect>>,
+}
+
+const ARCHETYPE_FIELDS: &[&str] = &["name", "sprite", "transform", "components"];
+
+// Components need the type registry to be read, so the file is read with a seed holding it
+struct ArchetypeDeserializer<'a> {
+    registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ArchetypeDeserializer<'a> {
+    type Value = ArchetypeSource;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("Archetype", ARCHETYPE_FIELDS, self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for ArchetypeDeserializer<'a> {
+    type Value = ArchetypeSource;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an archetype")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut name = None;
+        let mut sprite = None;
+        let mut transform = ArchetypeTransform::default();
+        let mut components = Vec::new();
+        while let Some(field) = map.next_key::<String>()? {
+            match field.as_str() {
+                "name" => name = Some(map.next_value()?),
+                "sprite" => sprite = map.next_value()?,
+                "transform" => transform = map.next_value()?,
+                "components" => {
+                    components = map.next_value_seed(ComponentsDeserializer {
+                        registry: self.registry,
+                    })?
+                }
+                _ => return Err(A::Error::unknown_field(&field, ARCHETYPE_FIELDS)),
+            }
+        }
+        Ok(ArchetypeSource {
+            name: name.ok_or_else(|| A::Error::missing_field("name"))?,
+            sprite,
+            transform,
+            components,
+        })
+    }
+}
+
+// A map from component type path to its value
+struct ComponentsDeserializer<'a> {
+    registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ComponentsDeserializer<'a> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for ComponentsDeserializer<'a> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of component type paths to values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::new();
+        while let Some(type_path) = map.next_key::<String>()? {
+            let Some(registration) = self.registry.get_with_type_path(&type_path) else {
+                return Err(A::Error::custom(format!(
+                    "{} is not a registered type",
+                    type_path
+                )));
+            };
+            if registration.data::<ReflectComponent>().is_none() {
+                return Err(A::Error::custom(format!(
+                    "{} is not a reflected component",
+                    type_path
+                )));
+            }
+            components.push(
+                map.next_value_seed(TypedReflectDeserializer::new(registration, self.registry))?,
+            );
+        }
+        Ok(components)
+    }
+}
+
+#[derive(Debug)]
+pub enum ArchetypeLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl fmt::Display for ArchetypeLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchetypeLoadError::Io(error) => write!(f, "could not read archetype: {}", error),
+            ArchetypeLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ArchetypeLoadError {}
+
+pub struct ArchetypeLoader {
+    registry: AppTypeRegistry,
+}
+
+impl FromWorld for ArchetypeLoader {
+    fn from_world(world: &mut World) -> Self {
+        ArchetypeLoader {
+            registry: world.resource::<AppTypeRegistry>().clone(),
+        }
+    }
+}
+
+impl AssetLoader for ArchetypeLoader {
+    type Asset = Archetype;
+    type Settings = ();
+    type Error = ArchetypeLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(ArchetypeLoadError::Io)?;
+            let archetype = read_archetype(&source, &self.registry.read())?;
+            Ok(Archetype {
+                name: archetype.name,
+                sprite: archetype.sprite.map(|path| load_context.load(path)),
+                transform: archetype.transform.into(),
+                components: archetype.components,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["archetype.ron"]
+    }
+}
+
+fn read_archetype(
+    source: &str,
+    registry: &TypeRegistry,
+) -> Result<ArchetypeSource, ArchetypeLoadError> {
+    let mut deserializer =
+        ron::Deserializer::from_str(source).map_err(ArchetypeLoadError::Parse)?;
+    let archetype = ArchetypeDeserializer { registry }
+        .deserialize(&mut deserializer)
+        .map_err(|error| ArchetypeLoadError::Parse(deserializer.span_error(error)))?;
+    deserializer
+        .end()
+        .map_err(|error| ArchetypeLoadError::Parse(deserializer.span_error(error)))?;
+    Ok(archetype)
+}
+
+// Keeps the archetype files loaded
+#[derive(Resource, Default)]
+pub struct ArchetypeFiles(pub Vec<Handle<Archetype>>);
+
+fn load_archetype_files(asset_server: Res<AssetServer>, mut files: ResMut<ArchetypeFiles>) {
+    for path in ARCHETYPE_FILES {
+        files.0.push(asset_server.load(*path));
+    }
+}
+
+pub(crate) fn find_archetype<'a>(
+    archetypes: &'a Assets<Archetype>,
+    name: &str,
+) -> Option<&'a Archetype> {
+    archetypes
+        .iter()
+        .map(|(_, archetype)| archetype)
+        .find(|archetype| archetype.name == name)
+}
+
+fn spawn_archetypes(
+    mut commands: Commands,
+    mut effect_outputs: EventReader<EffectOutput>,
+    archetypes: Res<Assets<Archetype>>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::Spawn(name) = output else {
+            continue;
+        };
+        let Some(archetype) = find_archetype(&archetypes, name) else {
+            warn!("No archetype named {} is loaded", name);
+            continue;
+        };
+        spawn_archetype(&mut commands, archetype, archetype.transform);
+    }
+}
+
+// Spawns the archetype as a story prop, placed by `transform` instead of its own
+pub(crate) fn spawn_archetype(
+    commands: &mut Commands,
+    archetype: &Archetype,
+    transform: Transform,
+) -> Entity {
+    let prop = StoryProp {
+        archetype: archetype.name.clone(),
+    };
+    let entity = match &archetype.sprite {
+        Some(texture) => commands.spawn((
+            SpriteBundle {
+                texture: texture.clone(),
+                transform,
+                ..default()
+            },
+            prop,
+        )),
+        None => commands.spawn((SpatialBundle::from_transform(transform), prop)),
+    }
+    .id();
+    let components: Vec<Box<dyn Reflect>> = archetype
+        .components
+        .iter()
+        .map(|component| component.clone_value())
+        .collect();
+    commands.add(move |world: &mut World| insert_components(world, entity, components));
+    entity
+}
+
+fn insert_components(world: &mut World, entity: Entity, components: Vec<Box<dyn Reflect>>) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let Some(mut entity) = world.get_entity_mut(entity) else {
+        return;
+    };
+    for component in components {
+        // Checked when the file was loaded, so only a type unregistered since then is missed
+        let Some(reflect_component) = component
+            .get_represented_type_info()
+            .and_then(|info| registry.get(info.type_id()))
+            .and_then(|registration| registration.data::<ReflectComponent>())
+        else {
+            warn!(
+                "{} is not a reflected component",
+                component.reflect_type_path()
+            );
+            continue;
+        };
+        reflect_component.insert(&mut entity, &*component, &registry);
+    }
+}
+
+fn despawn_story_props(mut commands: Commands, props: Query<Entity, With<StoryProp>>) {
+    for entity in props.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,5 +1,6 @@
 use crate::actions::{set_movement_actions, Actions};
 use crate::loading::AudioAssets;
+use crate::settings::Settings;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
@@ -23,12 +24,17 @@ impl Plugin for InternalAudioPlugin {
 #[derive(Resource)]
 struct FlyingAudio(Handle<AudioInstance>);
 
-fn start_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
+fn start_audio(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+) {
     audio.pause();
     let handle = audio
         .play(audio_assets.flying.clone())
         .looped()
-        .with_volume(0.3)
+        .with_volume(0.3 * settings.volume as f64)
         .handle();
     commands.insert_resource(FlyingAudio(handle));
 }
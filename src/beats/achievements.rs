@@ -0,0 +1,159 @@
+use crate::beats::data::EffectOutput;
+use crate::beats::save_location::SaveLocation;
+use crate::beats::sorted;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+pub const ACHIEVEMENTS_FILE: &str = "achievements.ron";
+
+// Achievements unlocked by `Effect::UnlockAchievement`, kept apart from saves so they survive
+// starting over
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Achievements {
+    #[serde(serialize_with = "sorted::set")]
+    pub unlocked: HashSet<String>,
+}
+
+impl Achievements {
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(id)
+    }
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct AchievementUnlocked(pub String);
+
+// A platform that also gets told about unlocks, like Steam
+pub trait AchievementBackend: Send + Sync + 'static {
+    fn name(&self) -> &str;
+
+    fn unlock(&mut self, id: &str) -> Result<(), String>;
+}
+
+#[derive(Resource, Default)]
+pub struct AchievementBackends {
+    backends: Vec<Box<dyn AchievementBackend>>,
+}
+
+impl AchievementBackends {
+    pub fn register(&mut self, backend: impl AchievementBackend) -> &mut Self {
+        self.backends.push(Box::new(backend));
+        self
+    }
+
+    fn unlock(&mut self, id: &str) {
+        for backend in self.backends.iter_mut() {
+            if let Err(error) = backend.unlock(id) {
+                warn!(
+                    "Could not unlock achievement {} on {}: {}",
+                    id,
+                    backend.name(),
+                    error
+                );
+            }
+        }
+    }
+}
+
+// Unlocks from earlier sessions are passed on again, in case they were made offline or before
+// a backend was added
+pub fn load_achievements(
+    mut achievements: ResMut<Achievements>,
+    mut backends: ResMut<AchievementBackends>,
+    location: Res<SaveLocation>,
+) {
+    let Ok(source) = location.read(ACHIEVEMENTS_FILE) else {
+        return;
+    };
+    match ron::from_str::<Achievements>(&source) {
+        Ok(loaded) => *achievements = loaded,
+        Err(error) => warn!("Could not read {}: {}", ACHIEVEMENTS_FILE, error),
+    }
+    for id in achievements.unlocked.iter() {
+        backends.unlock(id);
+    }
+}
+
+pub fn unlock_achievements(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut achievements: ResMut<Achievements>,
+    mut backends: ResMut<AchievementBackends>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    location: Res<SaveLocation>,
+) {
+    let mut changed = false;
+    for output in effect_outputs.read() {
+        if let EffectOutput::UnlockAchievement(id) = output {
+            if !achievements.unlocked.insert(id.clone()) {
+                continue;
+            }
+            backends.unlock(id);
+            unlocked.send(AchievementUnlocked(id.clone()));
+            changed = true;
+        }
+    }
+    if !changed {
+        return;
+    }
+    match ron::ser::to_string_pretty(achievements.as_ref(), ron::ser::PrettyConfig::default()) {
+        Ok(source) => location.write(ACHIEVEMENTS_FILE, &source),
+        Err(error) => warn!("Could not serialize achievements: {}", error),
+    }
+}
+
+#[cfg(feature = "steam")]
+pub use steam::{SteamAchievements, SteamAchievementsPlugin};
+
+#[cfg(feature = "steam")]
+mod steam {
+    use super::{AchievementBackend, AchievementBackends};
+    use bevy::prelude::*;
+
+    // Achievement ids are used as the API names set up on Steamworks
+    pub struct SteamAchievements {
+        client: steamworks::Client,
+    }
+
+    impl AchievementBackend for SteamAchievements {
+        fn name(&self) -> &str {
+            "Steam"
+        }
+
+        fn unlock(&mut self, id: &str) -> Result<(), String> {
+            let stats = self.client.user_stats();
+            stats
+                .achievement(id)
+                .set()
+                .map_err(|_| "unknown achievement".to_string())?;
+            stats
+                .store_stats()
+                .map_err(|_| "could not store stats".to_string())
+        }
+    }
+
+    // Starts the Steam client and registers it as an achievement backend. Without Steam running
+    // the game carries on with in-game achievements only.
+    pub struct SteamAchievementsPlugin;
+
+    impl Plugin for SteamAchievementsPlugin {
+        fn build(&self, app: &mut App) {
+            let (client, single) = match steamworks::Client::init() {
+                Ok(clients) => clients,
+                Err(error) => {
+                    warn!("Steam is not available: {}", error);
+                    return;
+                }
+            };
+            app.insert_non_send_resource(single)
+                .add_systems(Update, run_steam_callbacks);
+            app.world
+                .get_resource_or_insert_with(AchievementBackends::default)
+                .register(SteamAchievements { client });
+        }
+    }
+
+    fn run_steam_callbacks(single: NonSend<steamworks::SingleClient>) {
+        single.run_callbacks();
+    }
+}
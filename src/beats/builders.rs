@@ -1,5 +1,12 @@
 use bevy::utils::HashSet;
-use crate::beats::data::{Condition, Effect, Fact, Rule, Story, StoryBeat, StringHashSet};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use crate::beats::data::{
+    default_story_version, Choice, Condition, CrewMember, DialogueLine, Easing, Effect, Fact,
+    FactValue, Rule, RumbleIntensity, SceneTransitionKind, ShakeTrauma, Story, StoryBeat,
+    StoryEngine, StringHashSet, TimeScale, Transition, Weather, WorldPoint,
+};
+use crate::map::discovered_fact;
 
 #[derive(Debug, Default)]
 pub struct EffectBuilder {
@@ -33,6 +40,208 @@ impl EffectBuilder {
         self
     }
 
+    pub fn one_of<F>(mut self, build_fn: F) -> Self
+        where
+            F: FnOnce(EffectBuilder) -> EffectBuilder,
+    {
+        let effects = build_fn(EffectBuilder::new()).build();
+        self.effects.push(Effect::OneOf(effects));
+        self
+    }
+
+    pub fn tag(mut self, name: impl Into<String>, tag: impl Into<String>) -> Self {
+        self.effects.push(Effect::Tag {
+            fact_name: name.into(),
+            tag: tag.into(),
+        });
+        self
+    }
+
+    pub fn clear_tag(mut self, tag: impl Into<String>) -> Self {
+        self.effects.push(Effect::ClearTag(tag.into()));
+        self
+    }
+
+    pub fn say(mut self, speaker: impl Into<String>, text: impl Into<String>) -> Self {
+        self.effects.push(Effect::Say(DialogueLine::new(speaker, text)));
+        self
+    }
+
+    pub fn roll_credits(mut self) -> Self {
+        self.effects.push(Effect::RollCredits);
+        self
+    }
+
+    pub fn set_time_scale(mut self, scale: f32, duration: Duration) -> Self {
+        self.effects.push(Effect::SetTimeScale(TimeScale(scale), duration));
+        self
+    }
+
+    pub fn unlock_achievement(mut self, id: impl Into<String>) -> Self {
+        self.effects.push(Effect::UnlockAchievement(id.into()));
+        self
+    }
+
+    pub fn rumble(mut self, intensity: f32, duration: Duration) -> Self {
+        self.effects.push(Effect::Rumble {
+            intensity: RumbleIntensity(intensity),
+            duration,
+        });
+        self
+    }
+
+    pub fn show_tutorial(mut self, id: impl Into<String>, text: impl Into<String>) -> Self {
+        self.effects.push(Effect::ShowTutorial {
+            id: id.into(),
+            text: text.into(),
+            anchor_to: None,
+        });
+        self
+    }
+
+    pub fn hire_crew(mut self, member: CrewMember) -> Self {
+        self.effects.push(Effect::HireCrew(member));
+        self
+    }
+
+    pub fn dismiss_crew(mut self, name: impl Into<String>) -> Self {
+        self.effects.push(Effect::DismissCrew(name.into()));
+        self
+    }
+
+    pub fn open_shop(mut self, name: impl Into<String>) -> Self {
+        self.effects.push(Effect::OpenShop(name.into()));
+        self
+    }
+
+    pub fn open_map(mut self) -> Self {
+        self.effects.push(Effect::OpenMap);
+        self
+    }
+
+    pub fn set_weather(mut self, weather: Weather, hold: Option<Duration>) -> Self {
+        self.effects.push(Effect::SetWeather { weather, hold });
+        self
+    }
+
+    pub fn travel_to(mut self, location: impl Into<String>) -> Self {
+        self.effects.push(Effect::TravelTo(location.into()));
+        self
+    }
+
+    pub fn tween_fact(
+        mut self,
+        key: impl Into<String>,
+        to: i32,
+        duration: Duration,
+        easing: Easing,
+    ) -> Self {
+        self.effects.push(Effect::TweenFact {
+            key: key.into(),
+            to,
+            duration,
+            easing,
+        });
+        self
+    }
+
+    pub fn start_mini_game(mut self, id: impl Into<String>) -> Self {
+        self.effects.push(Effect::StartMiniGame(id.into()));
+        self
+    }
+
+    pub fn emit_signal(mut self, topic: impl Into<String>) -> Self {
+        self.effects.push(Effect::EmitSignal(topic.into()));
+        self
+    }
+
+    // Shows the location's pin on the world map
+    pub fn discover(mut self, location: impl AsRef<str>) -> Self {
+        self.effects
+            .push(Effect::SetFact(Fact::Bool(discovered_fact(location.as_ref()), true)));
+        self
+    }
+
+    pub fn spawn(mut self, archetype: impl Into<String>) -> Self {
+        self.effects.push(Effect::Spawn(archetype.into()));
+        self
+    }
+
+    pub fn shake_camera(mut self, trauma: f32) -> Self {
+        self.effects.push(Effect::CameraShake(ShakeTrauma(trauma)));
+        self
+    }
+
+    pub fn change_scene(
+        mut self,
+        level: impl Into<String>,
+        transition: SceneTransitionKind,
+    ) -> Self {
+        self.effects.push(Effect::ChangeScene {
+            level: level.into(),
+            transition,
+        });
+        self
+    }
+
+    pub fn particle_burst(mut self, archetype: impl Into<String>, at: Option<(f32, f32)>) -> Self {
+        self.effects.push(Effect::ParticleBurst {
+            archetype: archetype.into(),
+            at: at.map(|(x, y)| WorldPoint(x, y)),
+        });
+        self
+    }
+
+    pub fn play_sound(mut self, sound: impl Into<String>, at: Option<(f32, f32)>) -> Self {
+        self.effects.push(Effect::PlaySound {
+            sound: sound.into(),
+            at: at.map(|(x, y)| WorldPoint(x, y)),
+            caption: None,
+        });
+        self
+    }
+
+    pub fn play_captioned_sound(
+        mut self,
+        sound: impl Into<String>,
+        at: Option<(f32, f32)>,
+        caption: impl Into<String>,
+    ) -> Self {
+        self.effects.push(Effect::PlaySound {
+            sound: sound.into(),
+            at: at.map(|(x, y)| WorldPoint(x, y)),
+            caption: Some(caption.into()),
+        });
+        self
+    }
+
+    pub fn change_affinity(mut self, character: impl Into<String>, amount: i32) -> Self {
+        self.effects.push(Effect::ChangeAffinity {
+            character: character.into(),
+            amount,
+        });
+        self
+    }
+
+    pub fn show_tutorial_at(
+        mut self,
+        id: impl Into<String>,
+        text: impl Into<String>,
+        anchor_to: impl Into<String>,
+    ) -> Self {
+        self.effects.push(Effect::ShowTutorial {
+            id: id.into(),
+            text: text.into(),
+            anchor_to: Some(anchor_to.into()),
+        });
+        self
+    }
+
+    pub fn run_script(mut self, script: impl Into<String>) -> Self {
+        self.effects.push(Effect::Script(script.into()));
+        self
+    }
+
     pub fn build(self) -> Vec<Effect> {
         self.effects
     }
@@ -43,6 +252,11 @@ pub struct StoryBeatBuilder {
     name: String,
     rules: Vec<Rule>,
     effects: Vec<Effect>,
+    transitions: Vec<Transition>,
+    choices: Vec<Choice>,
+    metadata: BTreeMap<String, String>,
+    weight: u32,
+    errors: Vec<RuleBuildError>,
 }
 
 impl StoryBeatBuilder {
@@ -51,15 +265,81 @@ impl StoryBeatBuilder {
             name: name.into(),
             rules: Vec::new(),
             effects: Vec::new(),
+            transitions: Vec::new(),
+            choices: Vec::new(),
+            metadata: BTreeMap::new(),
+            weight: 1,
+            errors: Vec::new(),
+        }
+    }
+
+    // Jump to another beat when this one finishes and the rule holds
+    pub fn transition_to<F>(mut self, target: impl Into<String>, build_fn: F) -> Self
+        where
+            F: FnOnce(RuleBuilder) -> RuleBuilder,
+    {
+        let target = target.into();
+        let builder = RuleBuilder::new(format!("{} -> {}", self.name, target));
+        match build_fn(builder).build() {
+            Ok(rule) => self.transitions.push(Transition { target, rule }),
+            Err(error) => self.errors.push(error),
         }
+        self
     }
+
+    pub fn choice<F>(mut self, label: impl Into<String>, build_fn: F) -> Self
+        where
+            F: FnOnce(EffectBuilder) -> EffectBuilder,
+    {
+        self.choices.push(Choice {
+            label: label.into(),
+            effects: build_fn(EffectBuilder::new()).build(),
+            requires: Vec::new(),
+            locked_reason: None,
+        });
+        self
+    }
+
+    // A choice that stays locked, with the reason shown, until the conditions pass
+    pub fn gated_choice<C, F>(
+        mut self,
+        label: impl Into<String>,
+        locked_reason: impl Into<String>,
+        conditions_fn: C,
+        build_fn: F,
+    ) -> Self
+        where
+            C: FnOnce(ConditionBuilder) -> ConditionBuilder,
+            F: FnOnce(EffectBuilder) -> EffectBuilder,
+    {
+        self.choices.push(Choice {
+            label: label.into(),
+            effects: build_fn(EffectBuilder::new()).build(),
+            requires: conditions_fn(ConditionBuilder::new()).build(),
+            locked_reason: Some(locked_reason.into()),
+        });
+        self
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
     pub fn with_rule<F>(mut self, name: impl Into<String>, build_fn: F) -> Self
         where
             F: FnOnce(RuleBuilder) -> RuleBuilder,
     {
         let builder = RuleBuilder::new(name.into());
-        let rule = build_fn(builder).build();
-        self.rules.push(rule);
+        match build_fn(builder).build() {
+            Ok(rule) => self.rules.push(rule),
+            Err(error) => self.errors.push(error),
+        }
         self
     }
     
@@ -73,20 +353,148 @@ impl StoryBeatBuilder {
         self
     }
 
-    pub fn build(self) -> StoryBeat {
-        StoryBeat {
+    pub fn build(mut self) -> Result<StoryBeat, RuleBuildError> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        Ok(StoryBeat {
             name: self.name,
             rules: self.rules,
             effects: self.effects,
+            transitions: self.transitions,
+            choices: self.choices,
+            metadata: self.metadata,
+            weight: self.weight,
             finished: false,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConditionBuilder {
+    conditions: Vec<Condition>,
+}
+
+impl ConditionBuilder {
+    pub fn new() -> Self {
+        ConditionBuilder {
+            conditions: Vec::new(),
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    // True when the conditions built inside are not all true
+    pub fn not<F>(mut self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        let mut conditions = build_fn(ConditionBuilder::new()).build();
+        let inner = if conditions.len() == 1 {
+            conditions.remove(0)
+        } else {
+            Condition::All(conditions)
+        };
+        self.conditions.push(Condition::Not(Box::new(inner)));
+        self
+    }
+
+    pub fn any<F>(mut self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        let conditions = build_fn(ConditionBuilder::new()).build();
+        self.conditions.push(Condition::Any(conditions));
+        self
+    }
+
+    pub fn all<F>(mut self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        let conditions = build_fn(ConditionBuilder::new()).build();
+        self.conditions.push(Condition::All(conditions));
+        self
+    }
+
+    // More than `more_than` entities under the prefix have their `sub_key` fact equal to the value
+    pub fn count_where(
+        mut self,
+        prefix: impl Into<String>,
+        sub_key: impl Into<String>,
+        equals: FactValue,
+        more_than: u32,
+    ) -> Self {
+        self.conditions.push(Condition::CountWhere {
+            prefix: prefix.into(),
+            sub_key: sub_key.into(),
+            equals,
+            more_than,
+        });
+        self
+    }
+
+    // Conditions built inside are ordered by this cost instead of their estimated one
+    pub fn with_cost<F>(mut self, cost: u32, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        let mut conditions = build_fn(ConditionBuilder::new()).build();
+        let inner = if conditions.len() == 1 {
+            conditions.remove(0)
+        } else {
+            Condition::All(conditions)
+        };
+        self.conditions.push(Condition::Costed {
+            cost,
+            condition: Box::new(inner),
+        });
+        self
+    }
+
+    pub fn build(self) -> Vec<Condition> {
+        self.conditions
+    }
+}
+
+// Facts under these prefixes belong to the engine and can't be used in authored rules
+pub const RESERVED_FACT_PREFIXES: &[&str] = &["__", "engine."];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleBuildError {
+    NoConditions { rule: String },
+    DuplicateName { rule: String },
+    ReservedFact { rule: String, fact_name: String },
+    UnknownTransitionTarget { beat: String, target: String },
+}
+
+impl std::fmt::Display for RuleBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleBuildError::NoConditions { rule } => write!(f, "rule {} has no conditions", rule),
+            RuleBuildError::DuplicateName { rule } => {
+                write!(f, "a rule named {} is already registered", rule)
+            }
+            RuleBuildError::ReservedFact { rule, fact_name } => {
+                write!(f, "rule {} uses reserved fact {}", rule, fact_name)
+            }
+            RuleBuildError::UnknownTransitionTarget { beat, target } => {
+                write!(f, "beat {} transitions to unknown beat {}", beat, target)
+            }
         }
     }
 }
 
+impl std::error::Error for RuleBuildError {}
+
 #[derive(Debug, Default)]
 pub struct RuleBuilder {
     name: String,
     conditions: Vec<Condition>,
+    already_registered: bool,
 }
 
 impl RuleBuilder {
@@ -94,19 +502,76 @@ impl RuleBuilder {
         RuleBuilder {
             name: name.into(),
             conditions: Vec::new(),
+            already_registered: false,
         }
     }
 
+    // Makes build fail if the engine already has a rule with this name
+    pub fn checked_against(mut self, story_engine: &StoryEngine) -> Self {
+        self.already_registered = story_engine.has_rule(&self.name);
+        self
+    }
+
     pub fn with_condition(mut self, condition: Condition) -> Self {
         self.conditions.push(condition);
         self
     }
 
-    pub fn build(self) -> Rule {
-        Rule {
-            name: self.name,
-            conditions: self.conditions,
+    pub fn with_conditions<F>(mut self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        self.conditions.extend(build_fn(ConditionBuilder::new()).build());
+        self
+    }
+
+    pub fn not<F>(self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        self.with_conditions(|conditions| conditions.not(build_fn))
+    }
+
+    pub fn any<F>(self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        self.with_conditions(|conditions| conditions.any(build_fn))
+    }
+
+    pub fn all<F>(self, build_fn: F) -> Self
+        where
+            F: FnOnce(ConditionBuilder) -> ConditionBuilder,
+    {
+        self.with_conditions(|conditions| conditions.all(build_fn))
+    }
+
+    pub fn build(self) -> Result<Rule, RuleBuildError> {
+        if self.conditions.is_empty() {
+            return Err(RuleBuildError::NoConditions { rule: self.name });
+        }
+        if self.already_registered {
+            return Err(RuleBuildError::DuplicateName { rule: self.name });
         }
+        let reserved = self
+            .conditions
+            .iter()
+            .flat_map(|condition| condition.fact_names())
+            .find(|fact_name| {
+                RESERVED_FACT_PREFIXES
+                    .iter()
+                    .any(|prefix| fact_name.starts_with(prefix))
+            });
+        if let Some(fact_name) = reserved {
+            return Err(RuleBuildError::ReservedFact {
+                fact_name: fact_name.to_string(),
+                rule: self.name,
+            });
+        }
+        Ok(Rule {
+            name: self.name,
+            conditions: self.conditions.into(),
+        })
     }
 }
 
@@ -115,6 +580,9 @@ pub struct StoryBuilder {
     name: String,
     pre_requisites: Vec<Rule>,
     beats: Vec<StoryBeat>,
+    version: u32,
+    metadata: BTreeMap<String, String>,
+    errors: Vec<RuleBuildError>,
 }
 
 impl StoryBuilder {
@@ -123,6 +591,9 @@ impl StoryBuilder {
             name: name.into(),
             beats: Vec::new(),
             pre_requisites: Vec::new(),
+            version: default_story_version(),
+            metadata: BTreeMap::new(),
+            errors: Vec::new(),
         }
     }
 
@@ -131,8 +602,10 @@ impl StoryBuilder {
             F: FnOnce(StoryBeatBuilder) -> StoryBeatBuilder,
     {
         let builder = StoryBeatBuilder::new(name.into());
-        let beat = build_fn(builder).build();
-        self.beats.push(beat);
+        match build_fn(builder).build() {
+            Ok(beat) => self.beats.push(beat),
+            Err(error) => self.errors.push(error),
+        }
         self
     }
 
@@ -141,12 +614,40 @@ impl StoryBuilder {
             F: FnOnce(RuleBuilder) -> RuleBuilder,
     {
         let builder = RuleBuilder::new(name.into());
-        let rule = build_fn(builder).build();
-        self.pre_requisites.push(rule);
+        match build_fn(builder).build() {
+            Ok(rule) => self.pre_requisites.push(rule),
+            Err(error) => self.errors.push(error),
+        }
+        self
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
         self
     }
 
-    pub fn build(self) -> Story {
-        Story::new(self.name, self.pre_requisites, self.beats)
+    pub fn build(mut self) -> Result<Story, RuleBuildError> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        for beat in self.beats.iter() {
+            for transition in beat.transitions.iter() {
+                if !self.beats.iter().any(|b| b.name == transition.target) {
+                    return Err(RuleBuildError::UnknownTransitionTarget {
+                        beat: beat.name.clone(),
+                        target: transition.target.clone(),
+                    });
+                }
+            }
+        }
+        let mut story = Story::new(self.name, self.pre_requisites, self.beats);
+        story.version = self.version;
+        story.metadata = self.metadata;
+        Ok(story)
     }
 }
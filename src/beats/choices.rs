@@ -0,0 +1,224 @@
+use crate::beats::data::{Choice, EffectOutput, FactsOfTheWorld, Story, StoryEngine};
+use crate::beats::errors::{recover, EngineError};
+use crate::beats::logging::{BeatsLogLevel, BEATS_LOG_TARGET};
+use crate::beats::rng::StoryRng;
+use crate::beats::storage::{write_facts, FactStorage};
+use crate::ui::animation::{Easing, UiAnimation};
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use bevy::log::Level;
+use bevy::prelude::*;
+
+// A beat with choices became active and the player should pick one
+#[derive(Event, Debug, Clone)]
+pub struct PresentChoices {
+    pub story: String,
+    pub beat: String,
+    pub choices: Vec<Choice>,
+}
+
+impl PresentChoices {
+    pub fn for_active_beat(story: &Story) -> Option<Self> {
+        let beat = story.active_beat()?;
+        if beat.choices.is_empty() {
+            return None;
+        }
+        Some(PresentChoices {
+            story: story.name.clone(),
+            beat: beat.name.clone(),
+            choices: beat.choices.clone(),
+        })
+    }
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceMade {
+    pub story: String,
+    pub beat: String,
+    pub index: usize,
+}
+
+pub fn apply_choices<S: FactStorage + Resource>(
+    mut choices_made: EventReader<ChoiceMade>,
+    story_engine: Res<StoryEngine>,
+    mut storage: ResMut<S>,
+    mut rng: ResMut<StoryRng>,
+    mut effect_outputs: EventWriter<EffectOutput>,
+    mut errors: EventWriter<EngineError>,
+    log_level: Res<BeatsLogLevel>,
+) {
+    write_facts(&mut storage, |storage| {
+        for made in choices_made.read() {
+            let Some(story) = story_engine.stories.iter().find(|s| s.name == made.story) else {
+                continue;
+            };
+            // Choices only count while their beat is still the one being played
+            let Some(beat) = story.active_beat().filter(|beat| beat.name == made.beat) else {
+                continue;
+            };
+            let Some(choice) = beat.choices.get(made.index) else {
+                continue;
+            };
+            if !choice.is_available(&*storage) {
+                warn!("Choice {} of {} is locked", choice.label, beat.name);
+                continue;
+            }
+            let _choice = log_level.logs(Level::DEBUG).then(|| {
+                debug_span!(
+                    target: BEATS_LOG_TARGET,
+                    "choice",
+                    story = %story.name,
+                    beat = %beat.name,
+                    choice = %choice.label
+                )
+                .entered()
+            });
+            for effect in choice.effects.iter() {
+                if log_level.logs(Level::DEBUG) {
+                    debug!(target: BEATS_LOG_TARGET, ?effect, "Applying effect");
+                }
+                let applied = recover(|| effect.apply(&mut *storage, &mut rng))
+                    .and_then(|applied| applied.map_err(|error| error.to_string()));
+                match applied {
+                    Ok(outputs) => {
+                        effect_outputs.send_batch(outputs);
+                    }
+                    Err(message) => {
+                        errors.send(EngineError::Effect {
+                            story: story.name.clone(),
+                            beat: beat.name.clone(),
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[derive(Component)]
+pub struct ChoicePanel;
+
+#[derive(Component)]
+pub struct ChoiceButton {
+    pub story: String,
+    pub beat: String,
+    pub index: usize,
+    pub locked: bool,
+}
+
+const CHOICE_BUTTON: Color = Color::rgb(0.15, 0.15, 0.25);
+const HOVERED_CHOICE_BUTTON: Color = Color::rgb(0.25, 0.25, 0.4);
+const LOCKED_CHOICE_BUTTON: Color = Color::rgb(0.2, 0.2, 0.2);
+const LOCKED_CHOICE_TEXT: Color = Color::rgb(0.5, 0.5, 0.5);
+
+pub fn spawn_choice_panel(
+    mut commands: Commands,
+    mut present_choices: EventReader<PresentChoices>,
+    facts: Res<FactsOfTheWorld>,
+    panels: Query<Entity, With<ChoicePanel>>,
+) {
+    let Some(present) = present_choices.read().last() else {
+        return;
+    };
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.bottom_center(40.).flex_column().row_gap_px(8.))
+                .on_layer(UiLayer::Dialogue)
+                .build(),
+            ChoicePanel,
+            UiLayer::Dialogue,
+            UiAnimation::slide_in_from(Vec2::new(0., 40.), 0.3).with_easing(Easing::EaseOut),
+        ))
+        .with_children(|parent| {
+            for (index, choice) in present.choices.iter().enumerate() {
+                let lock_reason = choice.lock_reason(&*facts);
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                                flex_direction: FlexDirection::Column,
+                                ..default()
+                            },
+                            background_color: if lock_reason.is_some() {
+                                LOCKED_CHOICE_BUTTON.into()
+                            } else {
+                                CHOICE_BUTTON.into()
+                            },
+                            ..default()
+                        },
+                        ChoiceButton {
+                            story: present.story.clone(),
+                            beat: present.beat.clone(),
+                            index,
+                            locked: lock_reason.is_some(),
+                        },
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            choice.label.clone(),
+                            TextStyle {
+                                font_size: 20.0,
+                                color: if lock_reason.is_some() {
+                                    LOCKED_CHOICE_TEXT
+                                } else {
+                                    Color::WHITE
+                                },
+                                ..default()
+                            },
+                        ));
+                        if let Some(reason) = lock_reason {
+                            button.spawn(TextBundle::from_section(
+                                reason,
+                                TextStyle {
+                                    font_size: 14.0,
+                                    color: LOCKED_CHOICE_TEXT,
+                                    ..default()
+                                },
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+pub fn choice_button_system(
+    mut buttons: Query<(&Interaction, &ChoiceButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut choices_made: EventWriter<ChoiceMade>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        if button.locked {
+            continue;
+        }
+        match *interaction {
+            Interaction::Pressed => {
+                choices_made.send(ChoiceMade {
+                    story: button.story.clone(),
+                    beat: button.beat.clone(),
+                    index: button.index,
+                });
+            }
+            Interaction::Hovered => *color = HOVERED_CHOICE_BUTTON.into(),
+            Interaction::None => *color = CHOICE_BUTTON.into(),
+        }
+    }
+}
+
+// However the choice was made, the panel has served its purpose
+pub fn close_choice_panel(
+    mut commands: Commands,
+    mut choices_made: EventReader<ChoiceMade>,
+    panels: Query<Entity, With<ChoicePanel>>,
+) {
+    if choices_made.read().count() == 0 {
+        return;
+    }
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,12 +1,44 @@
+use crate::beats::intern::Interned;
+use crate::beats::rng::StoryRng;
+use crate::beats::scripting;
+use crate::beats::sorted;
+use crate::beats::storage::FactStorage;
+use bevy::ecs::component::Tick;
+use bevy::ecs::system::{SystemChangeTick, SystemParam};
 use bevy::prelude::*;
 use bevy::utils::hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
+use std::time::Duration;
 pub const X_EXTENT: f32 = 600.;
 
-#[derive(Event)]
+#[derive(Event, Debug, Clone)]
 pub struct FactUpdated {
     pub fact: Fact,
+    // None when the fact did not exist before
+    pub previous: Option<Fact>,
+}
+
+// A fact was taken out of the store, e.g. by undoing the write that created it
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct FactRemoved {
+    // The value it had when it was removed
+    pub previous: Fact,
+}
+
+// A write to a constant fact was refused, the stored value is unchanged
+#[derive(Event, Debug, Clone)]
+pub struct FactWriteDenied {
+    pub fact: Fact,
+}
+
+// Something used an old fact name that is now an alias for another fact
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct FactAliasUsed {
+    pub alias: String,
+    pub target: String,
 }
 
 #[derive(Event)]
@@ -23,8 +55,107 @@ pub enum Fact {
     StringList(String, StringHashSet),
 }
 
+impl Fact {
+    pub fn key(&self) -> &str {
+        match self {
+            Fact::Int(key, _)
+            | Fact::String(key, _)
+            | Fact::Bool(key, _)
+            | Fact::StringList(key, _) => key,
+        }
+    }
+
+    pub fn key_mut(&mut self) -> &mut String {
+        match self {
+            Fact::Int(key, _)
+            | Fact::String(key, _)
+            | Fact::Bool(key, _)
+            | Fact::StringList(key, _) => key,
+        }
+    }
+
+    // The same fact reset to the empty value of its type
+    pub fn cleared(&self) -> Fact {
+        match self {
+            Fact::Int(key, _) => Fact::Int(key.clone(), 0),
+            Fact::String(key, _) => Fact::String(key.clone(), String::new()),
+            Fact::Bool(key, _) => Fact::Bool(key.clone(), false),
+            Fact::StringList(key, _) => Fact::StringList(key.clone(), StringHashSet::new()),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Fact::Int(..) => "int",
+            Fact::String(..) => "string",
+            Fact::Bool(..) => "bool",
+            Fact::StringList(..) => "string list",
+        }
+    }
+}
+
+// A value to compare facts of any type against, written as a plain `true`, `3` or `"text"`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FactValue {
+    Bool(bool),
+    Int(i32),
+    String(String),
+}
+
+impl FactValue {
+    // Facts of another type never match
+    pub fn matches(&self, fact: &Fact) -> bool {
+        match (self, fact) {
+            (FactValue::Bool(expected), Fact::Bool(_, value)) => expected == value,
+            (FactValue::Int(expected), Fact::Int(_, value)) => expected == value,
+            (FactValue::String(expected), Fact::String(_, value)) => expected == value,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FactError {
+    TypeMismatch {
+        key: String,
+        stored: &'static str,
+        written: &'static str,
+    },
+    ReadOnly {
+        key: String,
+    },
+    Reserved {
+        key: String,
+    },
+    // An effect script that didn't run, nothing it assigned was written
+    Script {
+        script: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for FactError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FactError::TypeMismatch {
+                key,
+                stored,
+                written,
+            } => write!(f, "fact {} is a {} but a {} was written to it", key, stored, written),
+            FactError::ReadOnly { key } => write!(f, "fact {} is constant and can't be changed", key),
+            FactError::Reserved { key } => write!(f, "fact {} is reserved for the engine", key),
+            FactError::Script { script, message } => {
+                write!(f, "script `{}` failed: {}", script, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FactError {}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
-pub struct StringHashSet(pub HashSet<String>);
+pub struct StringHashSet(#[serde(serialize_with = "sorted::set")] pub HashSet<String>);
 
 impl StringHashSet {
     pub fn new() -> Self {
@@ -38,6 +169,10 @@ impl StringHashSet {
     pub fn remove(&mut self, value: &String) -> bool {
         self.0.remove(value)
     }
+
+    pub fn contains(&self, value: &String) -> bool {
+        self.0.contains(value)
+    }
 }
 
 impl Hash for StringHashSet {
@@ -52,8 +187,31 @@ impl Hash for StringHashSet {
 
 #[derive(Resource, Deserialize, Serialize)]
 pub struct FactsOfTheWorld {
+    #[serde(serialize_with = "sorted::map")]
     pub facts: HashMap<String, Fact>,
+    #[serde(serialize_with = "sorted::facts")]
     pub updated_facts: HashSet<Fact>,
+    // Value each updated fact had before its first change since the last drain
+    #[serde(default, serialize_with = "sorted::map")]
+    pub previous_facts: HashMap<String, Option<Fact>>,
+    // Fact keys by tag, for inspecting or resetting whole categories of state
+    #[serde(default, serialize_with = "sorted::map_of_sets")]
+    pub tags: HashMap<String, HashSet<String>>,
+    // Keys of facts that can't be written after being stored with store_constant
+    #[serde(default, serialize_with = "sorted::set")]
+    pub constants: HashSet<String>,
+    #[serde(skip)]
+    pub removed_facts: Vec<FactRemoved>,
+    #[serde(skip)]
+    pub denied_writes: Vec<FactWriteDenied>,
+    // Old fact names mapped to the names they were renamed to
+    #[serde(default, serialize_with = "sorted::map")]
+    pub aliases: HashMap<String, String>,
+    #[serde(skip)]
+    pub alias_hits: Vec<FactAliasUsed>,
+    // Counts the writes to this store, so callers can tell whether a fact actually changed
+    #[serde(skip)]
+    revision: u64,
 }
 
 impl FactsOfTheWorld {
@@ -61,101 +219,262 @@ impl FactsOfTheWorld {
         FactsOfTheWorld {
             facts: HashMap::new(),
             updated_facts: HashSet::new(),
+            previous_facts: HashMap::new(),
+            removed_facts: Vec::new(),
+            tags: HashMap::new(),
+            constants: HashSet::new(),
+            denied_writes: Vec::new(),
+            aliases: HashMap::new(),
+            alias_hits: Vec::new(),
+            revision: 0,
         }
     }
 
-    pub fn store_int(&mut self, key: String, value: i32) {
-        if let Some(fact) = self.facts.get_mut(&key) {
-            if let Fact::Int(_, current_value) = fact {
-                if current_value != &value {
-                    *fact = Fact::Int(key.clone(), value);
-                    self.updated_facts.insert(fact.clone());
-                }
-            } else {
-                panic!("Fact with key {} is not an integer", key)
+    fn mark_updated(&mut self, previous: Option<Fact>, fact: Fact) {
+        self.previous_facts
+            .entry(fact.key().to_string())
+            .or_insert(previous);
+        self.updated_facts.insert(fact);
+        self.revision += 1;
+    }
+
+    // Swaps in a whole set of facts, e.g. from a save. Everything counts as updated so rules
+    // and UI catch up with the new state.
+    pub fn replace_all(&mut self, facts: HashMap<String, Fact>) {
+        let mut replaced = std::mem::replace(&mut self.facts, facts);
+        self.updated_facts = self.facts.values().cloned().collect();
+        self.previous_facts = self
+            .facts
+            .keys()
+            .map(|key| (key.clone(), replaced.remove(key)))
+            .collect();
+        // Whatever the new facts don't have was removed
+        self.removed_facts
+            .extend(replaced.into_values().map(|previous| FactRemoved { previous }));
+        self.revision += 1;
+    }
+
+    // Takes the fact out of the store, reported as a FactRemoved rather than an update
+    pub fn remove(&mut self, key: &str) -> Option<Fact> {
+        let removed = self.facts.remove(key)?;
+        self.updated_facts.retain(|fact| fact.key() != key);
+        self.previous_facts.remove(key);
+        self.removed_facts.push(FactRemoved {
+            previous: removed.clone(),
+        });
+        self.revision += 1;
+        Some(removed)
+    }
+
+    // Moves on with every write that changed a fact
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn replace(&mut self, fact: Fact) -> Result<(), FactError> {
+        if self.constants.contains(fact.key()) {
+            let key = fact.key().to_string();
+            self.denied_writes.push(FactWriteDenied { fact });
+            return Err(FactError::ReadOnly { key });
+        }
+        let previous = self.facts.insert(fact.key().to_string(), fact.clone());
+        self.mark_updated(previous, fact);
+        Ok(())
+    }
+
+    // Whether any fact changed or was removed since the last drain
+    pub fn has_updates(&self) -> bool {
+        !self.updated_facts.is_empty() || !self.removed_facts.is_empty()
+    }
+
+    // Takes the facts that changed since the last drain, paired with their earlier values
+    pub fn drain_updated(&mut self) -> Vec<FactUpdated> {
+        let mut previous_facts = std::mem::take(&mut self.previous_facts);
+        self.updated_facts
+            .drain()
+            .map(|fact| FactUpdated {
+                previous: previous_facts.remove(fact.key()).flatten(),
+                fact,
+            })
+            .collect()
+    }
+
+    // Takes the facts removed since the last drain
+    pub fn drain_removed(&mut self) -> Vec<FactRemoved> {
+        std::mem::take(&mut self.removed_facts)
+    }
+
+    // Reads and writes of `alias` go to `target` from now on
+    pub fn alias(&mut self, alias: impl Into<String>, target: impl Into<String>) {
+        self.aliases.insert(alias.into(), target.into());
+    }
+
+    pub fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        self.aliases.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    // Like resolve, but remembers the hit so it can be reported
+    pub(crate) fn resolve_write(&mut self, key: String) -> String {
+        match self.aliases.get(&key) {
+            Some(target) => {
+                let target = target.clone();
+                self.alias_hits.push(FactAliasUsed {
+                    alias: key,
+                    target: target.clone(),
+                });
+                target
             }
-        } else {
-            self.facts
-                .insert(key.clone(), Fact::Int(key.clone(), value));
-            self.updated_facts.insert(Fact::Int(key.clone(), value));
+            None => key,
+        }
+    }
+
+    pub fn drain_alias_hits(&mut self) -> Vec<FactAliasUsed> {
+        std::mem::take(&mut self.alias_hits)
+    }
+
+    // Stores the fact and refuses any later writes to it
+    pub fn store_constant(&mut self, mut fact: Fact) {
+        *fact.key_mut() = self.resolve_write(fact.key().to_string());
+        let key = fact.key().to_string();
+        // Not constant yet while it's written, so this can't be refused
+        self.constants.remove(&key);
+        let _ = self.replace(fact);
+        self.constants.insert(key);
+    }
+
+    pub fn is_constant(&self, key: &str) -> bool {
+        self.constants.contains(key)
+    }
+
+    pub fn drain_denied(&mut self) -> Vec<FactWriteDenied> {
+        std::mem::take(&mut self.denied_writes)
+    }
+
+    // A fact of another type under the key is left as it is and reported, never overwritten
+    fn mismatch(&self, key: String, written: &'static str) -> FactError {
+        FactError::TypeMismatch {
+            stored: self.facts.get(&key).map(Fact::type_name).unwrap_or(written),
+            key,
+            written,
         }
     }
 
-    pub fn add_to_int(&mut self, key: String, value: i32) {
+    pub fn store_int(&mut self, key: String, value: i32) -> Result<(), FactError> {
+        let key = self.resolve_write(key);
+        match self.facts.get(&key) {
+            Some(Fact::Int(_, current_value)) if current_value == &value => {}
+            Some(Fact::Int(..)) | None => self.replace(Fact::Int(key, value))?,
+            Some(_) => return Err(self.mismatch(key, "int")),
+        }
+        Ok(())
+    }
+
+    pub fn add_to_int(&mut self, key: String, value: i32) -> Result<(), FactError> {
         let current = self.get_int(&key).unwrap_or(&0);
-        self.store_int(key, current + value);
+        self.store_int(key, current + value)
     }
 
-    fn subtract_from_int(&mut self, key: String, value: i32) {
+    fn subtract_from_int(&mut self, key: String, value: i32) -> Result<(), FactError> {
         let current = self.get_int(&key).unwrap_or(&0);
-        self.store_int(key, current + value);
+        self.store_int(key, current + value)
     }
 
-    pub fn store_string(&mut self, key: String, value: String) {
-        if let Some(fact) = self.facts.get_mut(&key) {
-            if let Fact::String(_, current_value) = fact {
-                if current_value != &value {
-                    *fact = Fact::String(key.clone(), value.clone());
-                    self.updated_facts.insert(fact.clone());
-                }
-            } else {
-                panic!("Fact with key {} is not a string", key)
-            }
-        } else {
-            self.facts
-                .insert(key.clone(), Fact::String(key.clone(), value.clone()));
-            self.updated_facts
-                .insert(Fact::String(key.clone(), value.clone()));
+    pub fn store_string(&mut self, key: String, value: String) -> Result<(), FactError> {
+        let key = self.resolve_write(key);
+        match self.facts.get(&key) {
+            Some(Fact::String(_, current_value)) if current_value == &value => {}
+            Some(Fact::String(..)) | None => self.replace(Fact::String(key, value))?,
+            Some(_) => return Err(self.mismatch(key, "string")),
         }
+        Ok(())
     }
 
-    pub fn store_bool(&mut self, key: String, value: bool) {
-        if let Some(fact) = self.facts.get_mut(&key) {
-            if let Fact::Bool(_, current_value) = fact {
-                if current_value != &value {
-                    *fact = Fact::Bool(key.clone(), value);
-                    self.updated_facts.insert(fact.clone());
-                }
-            } else {
-                panic!("Fact with key {} is not a boolean", key)
-            }
-        } else {
-            self.facts
-                .insert(key.clone(), Fact::Bool(key.clone(), value.clone()));
-            self.updated_facts
-                .insert(Fact::Bool(key.clone(), value.clone()));
+    pub fn store_bool(&mut self, key: String, value: bool) -> Result<(), FactError> {
+        let key = self.resolve_write(key);
+        match self.facts.get(&key) {
+            Some(Fact::Bool(_, current_value)) if current_value == &value => {}
+            Some(Fact::Bool(..)) | None => self.replace(Fact::Bool(key, value))?,
+            Some(_) => return Err(self.mismatch(key, "bool")),
         }
+        Ok(())
     }
 
-    pub fn add_to_list(&mut self, key: String, value: String) {
-        if let Some(list_fact) = self.facts.get_mut(&key) {
-            if let Fact::StringList(_, list) = list_fact {
-                if list.insert(value) {
-                    self.updated_facts.insert(list_fact.clone());
-                }
+    pub fn add_to_list(&mut self, key: String, value: String) -> Result<(), FactError> {
+        let key = self.resolve_write(key);
+        match self.facts.get(&key) {
+            Some(Fact::StringList(_, list)) if list.contains(&value) => {}
+            Some(Fact::StringList(_, list)) => {
+                let mut list = list.clone();
+                list.insert(value);
+                self.replace(Fact::StringList(key, list))?;
+            }
+            Some(_) => return Err(self.mismatch(key, "string list")),
+            None => {
+                let mut new_list = StringHashSet::new();
+                new_list.insert(value);
+                self.replace(Fact::StringList(key, new_list))?;
             }
-        } else {
-            let mut new_list = StringHashSet::new();
-            new_list.insert(value);
-            self.facts
-                .insert(key.clone(), Fact::StringList(key.clone(), new_list.clone()));
-            self.updated_facts
-                .insert(Fact::StringList(key.clone(), new_list.clone()));
         }
+        Ok(())
     }
 
-    pub fn remove_from_list(&mut self, key: String, value: String) {
-        if let Some(list_fact) = self.facts.get_mut(&key) {
-            if let Fact::StringList(_, list) = list_fact {
-                if list.remove(&value) {
-                    self.updated_facts.insert(list_fact.clone());
-                }
+    pub fn store_list(&mut self, key: String, values: StringHashSet) -> Result<(), FactError> {
+        let key = self.resolve_write(key);
+        match self.facts.get(&key) {
+            Some(Fact::StringList(_, current_values)) if current_values == &values => {}
+            Some(Fact::StringList(..)) | None => self.replace(Fact::StringList(key, values))?,
+            Some(_) => return Err(self.mismatch(key, "string list")),
+        }
+        Ok(())
+    }
+
+    pub fn remove_from_list(&mut self, key: String, value: String) -> Result<(), FactError> {
+        let key = self.resolve_write(key);
+        if let Some(Fact::StringList(_, list)) = self.facts.get(&key) {
+            if list.contains(&value) {
+                let mut list = list.clone();
+                list.remove(&value);
+                self.replace(Fact::StringList(key, list))?;
             }
         }
+        Ok(())
+    }
+
+    pub fn tag(&mut self, key: impl Into<String>, tag: impl Into<String>) {
+        self.tags.entry(tag.into()).or_default().insert(key.into());
+    }
+
+    // Tags go on the fact an alias stands for, where the value is stored
+    pub fn store_tagged(&mut self, fact: Fact, tags: &[&str]) {
+        let key = self.resolve(fact.key()).to_string();
+        for tag in tags {
+            self.tag(key.clone(), *tag);
+        }
+        FactStorage::set(self, fact);
+    }
+
+    pub fn tagged_keys(&self, tag: &str) -> Vec<String> {
+        self.tags
+            .get(tag)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn facts_with_tag<'a>(&'a self, tag: &str) -> impl Iterator<Item = &'a Fact> {
+        self.tags
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.facts.get(key))
+    }
+
+    // Applies all mutations before anything is drained, so rules never see half a batch
+    pub fn apply_batch(&mut self, mutations: Vec<FactMutation>) -> Result<(), FactError> {
+        FactStorage::apply_batch(self, mutations)
     }
 
     pub fn get_int(&self, key: &str) -> Option<&i32> {
-        return if let Some(Fact::Int(_, value)) = self.facts.get(key) {
+        return if let Some(Fact::Int(_, value)) = self.facts.get(self.resolve(key)) {
             Some(&value)
         } else {
             None
@@ -163,7 +482,7 @@ impl FactsOfTheWorld {
     }
 
     pub fn get_string(&self, key: &str) -> Option<&String> {
-        return if let Some(Fact::String(_, value)) = self.facts.get(key) {
+        return if let Some(Fact::String(_, value)) = self.facts.get(self.resolve(key)) {
             Some(&value)
         } else {
             None
@@ -171,7 +490,7 @@ impl FactsOfTheWorld {
     }
 
     pub fn get_bool(&self, key: &str) -> Option<&bool> {
-        return if let Some(Fact::Bool(_, value)) = self.facts.get(key) {
+        return if let Some(Fact::Bool(_, value)) = self.facts.get(self.resolve(key)) {
             Some(&value)
         } else {
             None
@@ -179,7 +498,7 @@ impl FactsOfTheWorld {
     }
 
     pub fn get_list(&self, key: &str) -> Option<&StringHashSet> {
-        return if let Some(Fact::StringList(_, value)) = self.facts.get(key) {
+        return if let Some(Fact::StringList(_, value)) = self.facts.get(self.resolve(key)) {
             Some(&value)
         } else {
             None
@@ -187,6 +506,152 @@ impl FactsOfTheWorld {
     }
 }
 
+// A single change to the fact store, as data. Used where mutations need to be recorded or replayed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum FactMutation {
+    StoreInt(String, i32),
+    StoreString(String, String),
+    StoreBool(String, bool),
+    StoreList(String, StringHashSet),
+    AddToList(String, String),
+    RemoveFromList(String, String),
+}
+
+impl FactMutation {
+    pub fn from_fact(fact: Fact) -> Self {
+        match fact {
+            Fact::Int(key, value) => FactMutation::StoreInt(key, value),
+            Fact::String(key, value) => FactMutation::StoreString(key, value),
+            Fact::Bool(key, value) => FactMutation::StoreBool(key, value),
+            Fact::StringList(key, values) => FactMutation::StoreList(key, values),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            FactMutation::StoreInt(key, _)
+            | FactMutation::StoreString(key, _)
+            | FactMutation::StoreBool(key, _)
+            | FactMutation::StoreList(key, _)
+            | FactMutation::AddToList(key, _)
+            | FactMutation::RemoveFromList(key, _) => key,
+        }
+    }
+
+    pub fn key_mut(&mut self) -> &mut String {
+        match self {
+            FactMutation::StoreInt(key, _)
+            | FactMutation::StoreString(key, _)
+            | FactMutation::StoreBool(key, _)
+            | FactMutation::StoreList(key, _)
+            | FactMutation::AddToList(key, _)
+            | FactMutation::RemoveFromList(key, _) => key,
+        }
+    }
+
+    // The type of fact this mutation writes, if it replaces the stored value
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self {
+            FactMutation::StoreInt(..) => Some("int"),
+            FactMutation::StoreString(..) => Some("string"),
+            FactMutation::StoreBool(..) => Some("bool"),
+            FactMutation::StoreList(..) => Some("string list"),
+            FactMutation::AddToList(..) | FactMutation::RemoveFromList(..) => None,
+        }
+    }
+
+    pub fn apply<S: FactStorage>(&self, fact_store: &mut S) {
+        match self {
+            FactMutation::StoreInt(key, value) => fact_store.set(Fact::Int(key.clone(), *value)),
+            FactMutation::StoreString(key, value) => {
+                fact_store.set(Fact::String(key.clone(), value.clone()))
+            }
+            FactMutation::StoreBool(key, value) => fact_store.set(Fact::Bool(key.clone(), *value)),
+            FactMutation::StoreList(key, values) => {
+                fact_store.set(Fact::StringList(key.clone(), values.clone()))
+            }
+            FactMutation::AddToList(key, value) => {
+                fact_store.add_to_list(key.clone(), value.clone())
+            }
+            FactMutation::RemoveFromList(key, value) => {
+                fact_store.remove_from_list(key.clone(), value.clone())
+            }
+        }
+    }
+
+    // Like `apply`, but a constant or a fact of another type stops the write and is reported
+    pub fn try_apply<S: FactStorage>(&self, fact_store: &mut S) -> Result<(), FactError> {
+        match self {
+            FactMutation::StoreInt(key, value) => fact_store.try_set(Fact::Int(key.clone(), *value)),
+            FactMutation::StoreString(key, value) => {
+                fact_store.try_set(Fact::String(key.clone(), value.clone()))
+            }
+            FactMutation::StoreBool(key, value) => {
+                fact_store.try_set(Fact::Bool(key.clone(), *value))
+            }
+            FactMutation::StoreList(key, values) => {
+                fact_store.try_set(Fact::StringList(key.clone(), values.clone()))
+            }
+            FactMutation::AddToList(key, _) | FactMutation::RemoveFromList(key, _) => {
+                fact_store.check_type(&Fact::StringList(key.clone(), StringHashSet::new()))?;
+                fact_store.check_writable(key)?;
+                self.apply(fact_store);
+                Ok(())
+            }
+        }
+    }
+}
+
+// Read-only access to the facts, for systems that only need to look things up
+#[derive(SystemParam)]
+pub struct FactQuery<'w> {
+    store: Res<'w, FactsOfTheWorld>,
+    ticks: SystemChangeTick,
+}
+
+impl<'w> FactQuery<'w> {
+    // The change tick of the last frame a fact was written, keep it to pass to is_changed_since
+    pub fn last_changed(&self) -> Tick {
+        self.store.last_changed()
+    }
+
+    // Whether a fact was written after the tick, e.g. one taken from last_changed earlier
+    pub fn is_changed_since(&self, tick: Tick) -> bool {
+        self.store
+            .last_changed()
+            .is_newer_than(tick, self.ticks.this_run())
+    }
+
+    // Whether a fact was written since this system last ran
+    pub fn is_changed(&self) -> bool {
+        self.store.is_changed()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Fact> {
+        self.store.get(key)
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<&i32> {
+        self.store.get_int(key)
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<&String> {
+        self.store.get_string(key)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<&bool> {
+        self.store.get_bool(key)
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<&StringHashSet> {
+        self.store.get_list(key)
+    }
+
+    pub fn facts(&self) -> &HashMap<String, Fact> {
+        &self.store.facts
+    }
+}
+
 // Condition enum
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Condition {
@@ -204,7 +669,7 @@ pub enum Condition {
     },
     StringEquals {
         fact_name: String,
-        expected_value: String,
+        expected_value: Interned,
     },
     BoolEquals {
         fact_name: String,
@@ -212,18 +677,135 @@ pub enum Condition {
     },
     ListContains {
         fact_name: String,
-        expected_value: String,
+        expected_value: Interned,
+    },
+    // Compares the size of a list fact with the value of an int fact
+    ListLenEqualsFact {
+        list_fact: String,
+        int_fact: String,
+    },
+    // Counts the entities in a namespace whose `<prefix><entity>.<sub_key>` fact equals the
+    // value and checks there are more than `more_than` of them, e.g. prefix "crew.", sub_key
+    // "sick" for the sick crew members. Leaving out `more_than` asks whether there is any.
+    CountWhere {
+        prefix: String,
+        sub_key: String,
+        equals: FactValue,
+        #[serde(default)]
+        more_than: u32,
+    },
+    // A boolean expression run by the scripting module, e.g. "score > level * 10"
+    Script(String),
+    Not(Box<Condition>),
+    Any(Vec<Condition>),
+    All(Vec<Condition>),
+    // Overrides the estimated cost of the inner condition, e.g. for a script known to be cheap
+    Costed {
+        cost: u32,
+        condition: Box<Condition>,
     },
 }
 
+// Rough relative costs used to order conditions, fact lookups first and scripts last
+const FACT_CONDITION_COST: u32 = 1;
+const STRING_CONDITION_COST: u32 = 2;
+const LIST_CONDITION_COST: u32 = 4;
+// Looks at every fact to find the ones in the namespace
+const NAMESPACE_CONDITION_COST: u32 = 20;
+const SCRIPT_CONDITION_COST: u32 = 100;
+
 impl Condition {
-    pub fn evaluate(&self, facts: &HashMap<String, Fact>) -> bool {
+    // The facts this condition reads. Scripts are opaque and report none, conditions over a
+    // namespace report its prefix.
+    pub fn fact_names(&self) -> Vec<&str> {
+        match self {
+            Condition::IntEquals { fact_name, .. }
+            | Condition::IntMoreThan { fact_name, .. }
+            | Condition::IntLessThan { fact_name, .. }
+            | Condition::StringEquals { fact_name, .. }
+            | Condition::BoolEquals { fact_name, .. }
+            | Condition::ListContains { fact_name, .. } => vec![fact_name.as_str()],
+            Condition::ListLenEqualsFact {
+                list_fact,
+                int_fact,
+            } => vec![list_fact.as_str(), int_fact.as_str()],
+            Condition::CountWhere { prefix, .. } => vec![prefix.as_str()],
+            Condition::Script(_) => Vec::new(),
+            Condition::Not(condition) | Condition::Costed { condition, .. } => {
+                condition.fact_names()
+            }
+            Condition::Any(conditions) | Condition::All(conditions) => conditions
+                .iter()
+                .flat_map(|condition| condition.fact_names())
+                .collect(),
+        }
+    }
+
+    pub fn rename_facts(&mut self, rename: &mut impl FnMut(&mut String)) {
+        match self {
+            Condition::IntEquals { fact_name, .. }
+            | Condition::IntMoreThan { fact_name, .. }
+            | Condition::IntLessThan { fact_name, .. }
+            | Condition::StringEquals { fact_name, .. }
+            | Condition::BoolEquals { fact_name, .. }
+            | Condition::ListContains { fact_name, .. } => rename(fact_name),
+            Condition::ListLenEqualsFact {
+                list_fact,
+                int_fact,
+            } => {
+                rename(list_fact);
+                rename(int_fact);
+            }
+            // Aliases are whole fact names, a namespace prefix never is one
+            Condition::CountWhere { .. } | Condition::Script(_) => {}
+            Condition::Not(condition) | Condition::Costed { condition, .. } => {
+                condition.rename_facts(rename)
+            }
+            Condition::Any(conditions) | Condition::All(conditions) => {
+                for condition in conditions {
+                    condition.rename_facts(rename);
+                }
+            }
+        }
+    }
+
+    // Estimated cost of evaluating this condition, used to run cheap checks first
+    pub fn cost(&self) -> u32 {
+        match self {
+            Condition::IntEquals { .. }
+            | Condition::IntMoreThan { .. }
+            | Condition::IntLessThan { .. }
+            | Condition::BoolEquals { .. } => FACT_CONDITION_COST,
+            Condition::StringEquals { .. } => STRING_CONDITION_COST,
+            Condition::ListContains { .. } | Condition::ListLenEqualsFact { .. } => {
+                LIST_CONDITION_COST
+            }
+            Condition::CountWhere { .. } => NAMESPACE_CONDITION_COST,
+            Condition::Script(_) => SCRIPT_CONDITION_COST,
+            Condition::Not(condition) => condition.cost(),
+            Condition::Any(conditions) | Condition::All(conditions) => {
+                conditions.iter().map(Condition::cost).sum()
+            }
+            Condition::Costed { cost, .. } => *cost,
+        }
+    }
+
+    // For checks outside of story progression, like UI, with the clock at zero and a throwaway rng
+    pub fn evaluate<S: FactStorage>(&self, facts: &S) -> bool {
+        self.evaluate_with(&mut EvaluationContext::detached(
+            facts,
+            &mut StoryRng::new(0),
+        ))
+    }
+
+    pub fn evaluate_with(&self, context: &mut EvaluationContext) -> bool {
+        let facts = context.facts;
         match self {
             Condition::IntEquals {
                 fact_name,
                 expected_value,
             } => {
-                if let Some(Fact::Int(_, value)) = facts.get(fact_name) {
+                if let Some(Fact::Int(_, value)) = context.fact(fact_name) {
                     return *value == *expected_value;
                 }
             }
@@ -231,15 +813,15 @@ impl Condition {
                 fact_name,
                 expected_value,
             } => {
-                if let Some(Fact::String(_, value)) = facts.get(fact_name) {
-                    return value == expected_value;
+                if let Some(Fact::String(_, value)) = context.fact(fact_name) {
+                    return expected_value == value;
                 }
             }
             Condition::BoolEquals {
                 fact_name,
                 expected_value,
             } => {
-                if let Some(Fact::Bool(_, value)) = facts.get(fact_name) {
+                if let Some(Fact::Bool(_, value)) = context.fact(fact_name) {
                     return *value == *expected_value;
                 }
             }
@@ -247,7 +829,7 @@ impl Condition {
                 fact_name,
                 expected_value,
             } => {
-                if let Some(Fact::Int(_, value)) = facts.get(fact_name) {
+                if let Some(Fact::Int(_, value)) = context.fact(fact_name) {
                     return *value > *expected_value;
                 }
             }
@@ -255,7 +837,7 @@ impl Condition {
                 fact_name,
                 expected_value,
             } => {
-                if let Some(Fact::Int(_, value)) = facts.get(fact_name) {
+                if let Some(Fact::Int(_, value)) = context.fact(fact_name) {
                     return *value < *expected_value;
                 }
             }
@@ -263,31 +845,164 @@ impl Condition {
                 fact_name,
                 expected_value,
             } => {
-                if let Some(Fact::StringList(_, value)) = facts.get(fact_name) {
-                    return value.0.contains(expected_value);
+                if let Some(Fact::StringList(_, value)) = context.fact(fact_name) {
+                    return value.0.contains(expected_value.as_str());
+                }
+            }
+            Condition::ListLenEqualsFact {
+                list_fact,
+                int_fact,
+            } => {
+                if let (Some(Fact::StringList(_, list)), Some(Fact::Int(_, expected_len))) =
+                    (context.fact(list_fact), context.fact(int_fact))
+                {
+                    return list.0.len() as i64 == *expected_len as i64;
                 }
             }
+            Condition::CountWhere {
+                prefix,
+                sub_key,
+                equals,
+                more_than,
+            } => {
+                let suffix = format!(".{}", sub_key);
+                let count = facts
+                    .iter()
+                    .filter(|(key, _)| {
+                        key.strip_prefix(prefix.as_str())
+                            .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+                            .is_some_and(|entity| !entity.is_empty() && !entity.contains('.'))
+                    })
+                    .filter(|(_, fact)| equals.matches(fact))
+                    .count();
+                return count > *more_than as usize;
+            }
+            Condition::Script(script) => {
+                return scripting::evaluate_script(script, context);
+            }
+            Condition::Not(condition) => {
+                return !condition.evaluate_with(context);
+            }
+            Condition::Any(conditions) => {
+                return cheapest_first(conditions)
+                    .any(|condition| condition.evaluate_with(context));
+            }
+            Condition::All(conditions) => {
+                return all_conditions_hold(conditions, context);
+            }
+            Condition::Costed { condition, .. } => {
+                return condition.evaluate_with(context);
+            }
         }
         false
     }
 }
 
+// Conditions don't change anything, so the order they run in only affects how soon
+// `all`/`any` can stop. Already ordered lists, the common case, skip the sort.
+fn cheapest_first(conditions: &[Condition]) -> impl Iterator<Item = &Condition> {
+    let mut ordered: Vec<&Condition> = conditions.iter().collect();
+    if !conditions
+        .windows(2)
+        .all(|pair| pair[0].cost() <= pair[1].cost())
+    {
+        // Stable, so conditions of the same cost keep their authored order
+        ordered.sort_by_key(|condition| condition.cost());
+    }
+    ordered.into_iter()
+}
+
+fn all_conditions_hold(conditions: &[Condition], context: &mut EvaluationContext) -> bool {
+    cheapest_first(conditions).all(|condition| condition.evaluate_with(context))
+}
+
+// Everything evaluation may look at, passed in rather than read from resources so the engine
+// can be driven with a fixed clock and rng, in tests or without Bevy
+pub struct EvaluationContext<'a> {
+    // Story time in seconds
+    pub now: f64,
+    pub rng: &'a mut StoryRng,
+    pub facts: &'a HashMap<String, Fact>,
+    // Old fact names conditions may still use, mapped to the facts they stand for
+    pub aliases: &'a HashMap<String, String>,
+}
+
+impl<'a> EvaluationContext<'a> {
+    pub fn new<S: FactStorage>(now: f64, rng: &'a mut StoryRng, store: &'a S) -> Self {
+        EvaluationContext {
+            now,
+            rng,
+            facts: store.facts(),
+            aliases: store.aliases(),
+        }
+    }
+
+    // Facts only, for evaluating outside of story progression
+    pub fn detached<S: FactStorage>(store: &'a S, rng: &'a mut StoryRng) -> Self {
+        EvaluationContext::new(0.0, rng, store)
+    }
+
+    // Looks the fact up under the name it is stored as, following aliases
+    pub fn fact(&self, key: &str) -> Option<&'a Fact> {
+        let key = self.aliases.get(key).map(String::as_str).unwrap_or(key);
+        self.facts.get(key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionResult {
+    pub condition: Condition,
+    pub holds: bool,
+}
+
+// Every condition of a rule evaluated, without stopping at the first that fails
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleEvaluation {
+    pub rule: String,
+    pub conditions: Vec<ConditionResult>,
+}
+
+impl RuleEvaluation {
+    pub fn holds(&self) -> bool {
+        self.conditions.iter().all(|result| result.holds)
+    }
+}
+
 // Rule struct
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct Rule {
     pub name: String,
-    pub conditions: Vec<Condition>,
+    pub conditions: Conditions,
 }
 
+// Most rules check one or two facts, so those are kept inline with the rule
+pub type Conditions = SmallVec<[Condition; 2]>;
+
 impl Rule {
     pub fn new(name: String, conditions: Vec<Condition>) -> Self {
-        Rule { name, conditions }
+        Rule {
+            name,
+            conditions: conditions.into(),
+        }
     }
 
-    pub fn evaluate(&self, facts: &HashMap<String, Fact>) -> bool {
-        self.conditions
-            .iter()
-            .all(|condition| condition.evaluate(facts))
+    // For checks outside of story progression, like UI, with the clock at zero and a throwaway rng
+    pub fn evaluate<S: FactStorage>(&self, facts: &S) -> bool {
+        self.evaluate_with(&mut EvaluationContext::detached(
+            facts,
+            &mut StoryRng::new(0),
+        ))
+    }
+
+    // Cheap conditions run first, so a failing fact check skips any scripts after it
+    pub fn evaluate_with(&self, context: &mut EvaluationContext) -> bool {
+        all_conditions_hold(&self.conditions, context)
+    }
+
+    pub fn rename_facts(&mut self, rename: &mut impl FnMut(&mut String)) {
+        for condition in self.conditions.iter_mut() {
+            condition.rename_facts(rename);
+        }
     }
 }
 
@@ -297,9 +1012,64 @@ pub struct StoryBeat {
     pub name: String,
     pub rules: Vec<Rule>,
     pub effects: Vec<Effect>,
+    // Where to go once this beat finishes. The first transition whose rule holds wins,
+    // without one the story moves on to the next beat.
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+    // Options offered to the player while this beat is active
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+    // Free-form annotations for tools and presentation, ignored by the engine
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    // How much the beat counts towards the story's progress
+    #[serde(default = "default_beat_weight")]
+    pub weight: u32,
+    #[serde(default)]
     pub finished: bool,
 }
 
+fn default_beat_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Transition {
+    pub target: String,
+    pub rule: Rule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Choice {
+    pub label: String,
+    pub effects: Vec<Effect>,
+    // Shown but locked until these pass, e.g. enough affinity with a character
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<Condition>,
+    // Told to the player while the choice is locked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_reason: Option<String>,
+}
+
+impl Choice {
+    pub fn is_available<S: FactStorage>(&self, facts: &S) -> bool {
+        self.requires
+            .iter()
+            .all(|condition| condition.evaluate(facts))
+    }
+
+    pub fn lock_reason<S: FactStorage>(&self, facts: &S) -> Option<String> {
+        if self.is_available(facts) {
+            return None;
+        }
+        Some(
+            self.locked_reason
+                .clone()
+                .unwrap_or_else(|| "Not available yet".to_string()),
+        )
+    }
+}
+
 impl StoryBeat {
     // Constructor for StoryBeat
     pub fn new(name: String, rules: Vec<Rule>, effects: Vec<Effect>) -> Self {
@@ -307,13 +1077,17 @@ impl StoryBeat {
             name,
             rules,
             effects,
+            transitions: Vec::new(),
+            choices: Vec::new(),
+            metadata: BTreeMap::new(),
+            weight: default_beat_weight(),
             finished: false,
         }
     }
 
     // Evaluate all rules for the story beat based on the provided facts
-    pub fn evaluate(&mut self, facts: &HashMap<String, Fact>) {
-        self.finished = self.rules.iter().all(|rule| rule.evaluate(facts));
+    pub fn evaluate(&mut self, context: &mut EvaluationContext) {
+        self.finished = self.rules.iter().all(|rule| rule.evaluate_with(context));
     }
 }
 
@@ -323,28 +1097,77 @@ pub struct Story {
     pub name: String,
     pub pre_requisites: Vec<Rule>,
     pub beats: Vec<StoryBeat>,
+    // Bumped when the story changes in ways saves need migrating for
+    #[serde(default = "default_story_version")]
+    pub version: u32,
+    #[serde(default)]
     pub is_started: bool,
+    #[serde(default)]
     pub active_beat_index: usize,
+    // Free-form annotations like the beats have, e.g. "chapter" for stories played as chapters
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+pub fn default_story_version() -> u32 {
+    1
 }
 
 impl Story {
+    // Every fact name the story reads or writes, scripts excepted
+    pub fn rename_facts(&mut self, rename: &mut impl FnMut(&mut String)) {
+        for rule in self.pre_requisites.iter_mut() {
+            rule.rename_facts(rename);
+        }
+        for beat in self.beats.iter_mut() {
+            for rule in beat.rules.iter_mut() {
+                rule.rename_facts(rename);
+            }
+            for transition in beat.transitions.iter_mut() {
+                transition.rule.rename_facts(rename);
+            }
+            for condition in beat.choices.iter_mut().flat_map(|choice| choice.requires.iter_mut()) {
+                condition.rename_facts(rename);
+            }
+            let choice_effects = beat.choices.iter_mut().flat_map(|choice| choice.effects.iter_mut());
+            for effect in beat.effects.iter_mut().chain(choice_effects) {
+                effect.rename_facts(rename);
+            }
+        }
+    }
+
     pub fn new(name: String, pre_requisites: Vec<Rule>, beats: Vec<StoryBeat>) -> Self {
         Story {
             name,
             pre_requisites,
             beats,
+            version: default_story_version(),
             is_started: false,
             active_beat_index: 0,
+            metadata: BTreeMap::new(),
         }
     }
 
-    pub fn evaluate_active_beat(&mut self, facts: &HashMap<String, Fact>) -> Option<StoryBeat> {
+    pub fn evaluate_active_beat(&mut self, context: &mut EvaluationContext) -> Option<StoryBeat> {
         if self.active_beat_index < self.beats.len() {
             let active_beat = &mut self.beats[self.active_beat_index];
-            active_beat.evaluate(facts);
+            active_beat.evaluate(context);
             if active_beat.finished {
-                self.active_beat_index += 1;
-                Some(active_beat.clone())
+                let finished_beat = active_beat.clone();
+                let next = finished_beat
+                    .transitions
+                    .iter()
+                    .find(|transition| transition.rule.evaluate_with(context))
+                    .and_then(|transition| self.beat_index(&transition.target))
+                    .unwrap_or(self.active_beat_index + 1);
+                // Going back reopens the beats from the target on, so progress drops with it
+                if next <= self.active_beat_index {
+                    for beat in self.beats[next..].iter_mut() {
+                        beat.finished = false;
+                    }
+                }
+                self.active_beat_index = next;
+                Some(finished_beat)
             } else {
                 None
             }
@@ -353,9 +1176,20 @@ impl Story {
         }
     }
 
-    pub fn start_if_possible(&mut self, facts: &HashMap<String, Fact>) -> bool {
+    pub fn beat_index(&self, name: &str) -> Option<usize> {
+        self.beats.iter().position(|beat| beat.name == name)
+    }
+
+    pub fn active_beat(&self) -> Option<&StoryBeat> {
+        self.beats.get(self.active_beat_index)
+    }
+
+    pub fn start_if_possible(&mut self, context: &mut EvaluationContext) -> bool {
         if !self.is_started {
-            self.is_started = self.pre_requisites.iter().all(|rule| rule.evaluate(facts));
+            self.is_started = self
+                .pre_requisites
+                .iter()
+                .all(|rule| rule.evaluate_with(context));
         }
         self.is_started
     }
@@ -363,23 +1197,169 @@ impl Story {
     pub fn is_finished(&self) -> bool {
         self.active_beat_index >= self.beats.len()
     }
+
+    // Moves straight to the named beat. Beats passed over are marked finished and returned
+    // in order, jumping backwards reopens the beats from the target on.
+    pub fn jump_to_beat(&mut self, beat: &str) -> Option<Vec<StoryBeat>> {
+        let target = self.beat_index(beat)?;
+        self.is_started = true;
+        let start = self.active_beat_index.min(self.beats.len());
+        let mut skipped = Vec::new();
+        if target >= start {
+            for beat in self.beats[start..target].iter_mut() {
+                beat.finished = true;
+                skipped.push(beat.clone());
+            }
+        } else {
+            for beat in self.beats[target..].iter_mut() {
+                beat.finished = false;
+            }
+        }
+        self.active_beat_index = target;
+        Some(skipped)
+    }
+
+    // Share of the total beat weight that has been finished, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.is_finished() {
+            return 1.0;
+        }
+        let total: u32 = self.beats.iter().map(|beat| beat.weight).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let finished: u32 = self
+            .beats
+            .iter()
+            .filter(|beat| beat.finished)
+            .map(|beat| beat.weight)
+            .sum();
+        finished as f32 / total as f32
+    }
 }
 
 // StoryEngine struct
 #[derive(Resource, Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct StoryEngine {
     pub stories: Vec<Story>,
+    // Set when stories were added or loaded, so the fact aliases are applied to them once
+    #[serde(skip, default = "aliases_pending")]
+    pub aliases_pending: bool,
+}
+
+fn aliases_pending() -> bool {
+    true
 }
 
 impl StoryEngine {
     pub fn new() -> Self {
         StoryEngine {
             stories: Vec::new(),
+            aliases_pending: false,
         }
     }
 
     pub fn add_story(&mut self, story: Story) {
         self.stories.push(story);
+        self.aliases_pending = true;
+    }
+
+    // Swaps in a new definition for a story with the same name, keeping how far it has progressed
+    pub fn add_or_replace_story(&mut self, mut story: Story) {
+        if let Some(existing) = self.stories.iter_mut().find(|s| s.name == story.name) {
+            story.is_started = existing.is_started;
+            story.active_beat_index = existing.active_beat_index.min(story.beats.len());
+            for beat in story.beats.iter_mut() {
+                beat.finished = existing
+                    .beats
+                    .iter()
+                    .any(|old| old.name == beat.name && old.finished);
+            }
+            *existing = story;
+            self.aliases_pending = true;
+        } else {
+            self.add_story(story);
+        }
+    }
+
+    // Developer shortcut to a beat, returns the beats that were skipped to get there
+    pub fn jump_to_beat(&mut self, story: &str, beat: &str) -> Option<Vec<StoryBeatFinished>> {
+        let story = self.stories.iter_mut().find(|s| s.name == story)?;
+        let skipped = story.jump_to_beat(beat)?;
+        Some(
+            skipped
+                .into_iter()
+                .map(|beat| StoryBeatFinished {
+                    story: story.clone(),
+                    beat,
+                })
+                .collect(),
+        )
+    }
+
+    // Jumps to the beat and finishes it as if its rules had held, the story moves on
+    // to the beat after it
+    pub fn force_finish_beat(
+        &mut self,
+        story: &str,
+        beat: &str,
+    ) -> Option<Vec<StoryBeatFinished>> {
+        let mut finished = self.jump_to_beat(story, beat)?;
+        let story = self.stories.iter_mut().find(|s| s.name == story)?;
+        let index = story.active_beat_index;
+        story.beats[index].finished = true;
+        story.active_beat_index += 1;
+        finished.push(StoryBeatFinished {
+            story: story.clone(),
+            beat: story.beats[index].clone(),
+        });
+        Some(finished)
+    }
+
+    pub fn has_rule(&self, name: &str) -> bool {
+        self.stories.iter().any(|story| {
+            story.pre_requisites.iter().any(|rule| rule.name == name)
+                || story
+                    .beats
+                    .iter()
+                    .any(|beat| beat.rules.iter().any(|rule| rule.name == name))
+        })
+    }
+
+    // Looks through prerequisites, beat rules and transitions of every story
+    pub fn find_rule(&self, name: &str) -> Option<&Rule> {
+        self.stories.iter().find_map(|story| {
+            story
+                .pre_requisites
+                .iter()
+                .chain(story.beats.iter().flat_map(|beat| {
+                    beat.rules
+                        .iter()
+                        .chain(beat.transitions.iter().map(|transition| &transition.rule))
+                }))
+                .find(|rule| rule.name == name)
+        })
+    }
+
+    // Evaluates one rule on its own, condition by condition. Nothing about the rule or its
+    // story changes, so it's safe for explanations, gating and checks from outside the engine.
+    pub fn evaluate_single(
+        &self,
+        name: &str,
+        context: &mut EvaluationContext,
+    ) -> Option<RuleEvaluation> {
+        let rule = self.find_rule(name)?;
+        Some(RuleEvaluation {
+            rule: rule.name.clone(),
+            conditions: rule
+                .conditions
+                .iter()
+                .map(|condition| ConditionResult {
+                    condition: condition.clone(),
+                    holds: condition.evaluate_with(context),
+                })
+                .collect(),
+        })
     }
 
     // Check if all stories are finished
@@ -397,27 +1377,489 @@ pub struct StoryBeatFinished {
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Effect {
     SetFact(Fact),
+    // Statements run by the scripting module, facts it assigns are written back to the store
+    Script(String),
+    // Applies one of the effects, picked with the story rng
+    OneOf(Vec<Effect>),
+    Tag { fact_name: String, tag: String },
+    // Resets every fact with the tag to the empty value of its type
+    ClearTag(String),
+    Say(DialogueLine),
+    // Ends the game on the credits screen
+    RollCredits,
+    // Runs the game clock at this speed for a while, for slow motion moments
+    SetTimeScale(TimeScale, Duration),
+    // Unlocks the achievement in game and on any registered platform backends
+    UnlockAchievement(String),
+    // Shakes connected gamepads, intensity going from 0.0 to 1.0
+    Rumble {
+        intensity: RumbleIntensity,
+        duration: Duration,
+    },
+    // Shows an onboarding hint, next to the UI node with the Name `anchor_to` if given.
+    // Hints the player chose not to see again are skipped.
+    ShowTutorial {
+        id: String,
+        text: String,
+        #[serde(default)]
+        anchor_to: Option<String>,
+    },
+    // Changes how much a character likes the player, kept within MIN_AFFINITY..=MAX_AFFINITY
+    // in the `affinity.<character>` fact
+    ChangeAffinity { character: String, amount: i32 },
+    // Puts someone on the crew, writing their skills and traits as `crew.<name>.*` facts
+    HireCrew(CrewMember),
+    // Takes the crew member with this name off the roster and clears their facts
+    DismissCrew(String),
+    // Opens the shop with this name from the loaded shop files
+    OpenShop(String),
+    // Opens the world map, where discovered locations can be travelled to
+    OpenMap,
+    // Changes the weather and the `weather` fact. It holds for the given time, or a usual
+    // spell, before changing on its own again.
+    SetWeather {
+        weather: Weather,
+        #[serde(default)]
+        hold: Option<Duration>,
+    },
+    // Asks the player to confirm, then travels to the location on the world map: the travel
+    // facts change together, the clock moves on and the location's level fades in
+    TravelTo(String),
+    // Moves an int fact to `to` gradually over story time, a whole step at a time
+    TweenFact {
+        key: String,
+        to: i32,
+        duration: Duration,
+        #[serde(default)]
+        easing: Easing,
+    },
+    // Leaves the story for the registered mini-game with this id. Its result comes back in
+    // the `minigame.<id>.result` fact.
+    StartMiniGame(String),
+    // Emits a signal on this topic to whatever subscribed to it, e.g. `mod.harbour.bell`
+    EmitSignal(String),
+    // Spawns the archetype with this name from the loaded archetype files
+    Spawn(String),
+    // Adds trauma to the camera rig, which shakes harder the more it has
+    CameraShake(ShakeTrauma),
+    // Swaps the level shown behind the story, hidden by the transition
+    ChangeScene {
+        level: String,
+        #[serde(default)]
+        transition: SceneTransitionKind,
+    },
+    // Spawns a particle archetype, at the point given or else where the camera looks
+    ParticleBurst {
+        archetype: String,
+        #[serde(default)]
+        at: Option<WorldPoint>,
+    },
+    // Plays a sound file from the assets folder. Given a point it's panned and fades with
+    // distance from the camera, otherwise it plays centred. The caption, e.g. "[Foghorn]",
+    // is shown to players with subtitles on.
+    PlaySound {
+        sound: String,
+        #[serde(default)]
+        at: Option<WorldPoint>,
+        #[serde(default)]
+        caption: Option<String>,
+    },
+}
+
+// Speed of the game clock, 1.0 being normal. Compared bit for bit so effects stay hashable.
+// Scales below `time_scale::MIN_TIME_SCALE`, zero included, are raised to it when applied.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct TimeScale(pub f32);
+
+impl PartialEq for TimeScale {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for TimeScale {}
+
+impl Hash for TimeScale {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+// Strength of a gamepad rumble, compared bit for bit like TimeScale
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct RumbleIntensity(pub f32);
+
+impl PartialEq for RumbleIntensity {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for RumbleIntensity {}
+
+impl Hash for RumbleIntensity {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+// A point in the world, compared bit for bit like TimeScale
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct WorldPoint(pub f32, pub f32);
+
+impl PartialEq for WorldPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits() && self.1.to_bits() == other.1.to_bits()
+    }
+}
+
+impl Eq for WorldPoint {}
+
+impl Hash for WorldPoint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+        self.1.to_bits().hash(state);
+    }
+}
+
+// How the screen changes over to another scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum SceneTransitionKind {
+    // Fades to a colour and back
+    #[default]
+    Fade,
+    // Closes in from the edges of the screen and opens out again
+    Iris,
+    // Blends the old background into the new one
+    Crossfade,
+}
+
+// Camera shake added by an effect, 0.0 to 1.0, compared bit for bit like TimeScale
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ShakeTrauma(pub f32);
+
+impl PartialEq for ShakeTrauma {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for ShakeTrauma {}
+
+impl Hash for ShakeTrauma {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+// The weather at sea, changed by `Effect::SetWeather` and on its own by the weather plugin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Cloudy,
+    Rain,
+    Storm,
+    Fog,
+}
+
+// The curve `Effect::TweenFact` follows, and any other tween
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+// Someone `Effect::HireCrew` puts on the crew
+// (name: "ada", skills: {"carpentry": 3, "sailing": 1}, traits: ["brave"])
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct CrewMember {
+    pub name: String,
+    // Int facts `crew.<name>.<skill>`
+    #[serde(default)]
+    pub skills: BTreeMap<String, i32>,
+    // Bool facts `crew.<name>.<trait>`
+    #[serde(default)]
+    pub traits: Vec<String>,
+}
+
+// A line spoken by a character, shown by the dialogue box
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct DialogueLine {
+    // Stable id for tracking seen lines, derived from speaker and text when empty
+    #[serde(default)]
+    pub id: String,
+    pub speaker: String,
+    pub text: String,
+    // Recording of the line played when it's shown, relative to the assets folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+}
+
+impl DialogueLine {
+    pub fn new(speaker: impl Into<String>, text: impl Into<String>) -> Self {
+        DialogueLine {
+            id: String::new(),
+            speaker: speaker.into(),
+            text: text.into(),
+            voice: None,
+        }
+    }
+
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = Some(voice.into());
+        self
+    }
+
+    pub fn line_id(&self) -> String {
+        if self.id.is_empty() {
+            format!("{}: {}", self.speaker, self.text)
+        } else {
+            self.id.clone()
+        }
+    }
+}
+
+// What applied effects ask of the rest of the game, beyond changing facts
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum EffectOutput {
+    Say(DialogueLine),
+    RollCredits,
+    SetTimeScale(TimeScale, Duration),
+    UnlockAchievement(String),
+    Rumble {
+        intensity: RumbleIntensity,
+        duration: Duration,
+    },
+    ShowTutorial {
+        id: String,
+        text: String,
+        anchor_to: Option<String>,
+    },
+    ChangeAffinity {
+        character: String,
+        amount: i32,
+    },
+    HireCrew(CrewMember),
+    DismissCrew(String),
+    OpenShop(String),
+    OpenMap,
+    SetWeather {
+        weather: Weather,
+        hold: Option<Duration>,
+    },
+    TravelTo(String),
+    TweenFact {
+        key: String,
+        to: i32,
+        duration: Duration,
+        easing: Easing,
+    },
+    StartMiniGame(String),
+    EmitSignal(String),
+    Spawn(String),
+    CameraShake(ShakeTrauma),
+    ChangeScene {
+        level: String,
+        transition: SceneTransitionKind,
+    },
+    ParticleBurst {
+        archetype: String,
+        at: Option<WorldPoint>,
+    },
+    PlaySound {
+        sound: String,
+        at: Option<WorldPoint>,
+        caption: Option<String>,
+    },
 }
 
 impl Effect {
-    pub fn apply(&self, fact_store: &mut FactsOfTheWorld) {
+    pub fn rename_facts(&mut self, rename: &mut impl FnMut(&mut String)) {
+        match self {
+            Effect::SetFact(fact) => rename(fact.key_mut()),
+            Effect::Tag { fact_name, .. } => rename(fact_name),
+            Effect::TweenFact { key, .. } => rename(key),
+            Effect::OneOf(effects) => {
+                for effect in effects {
+                    effect.rename_facts(rename);
+                }
+            }
+            Effect::Script(_)
+            | Effect::ClearTag(_)
+            | Effect::Say(_)
+            | Effect::RollCredits
+            | Effect::SetTimeScale(..)
+            | Effect::UnlockAchievement(_)
+            | Effect::Rumble { .. }
+            | Effect::ShowTutorial { .. }
+            | Effect::ChangeAffinity { .. }
+            | Effect::HireCrew(_)
+            | Effect::DismissCrew(_)
+            | Effect::OpenShop(_)
+            | Effect::OpenMap
+            | Effect::SetWeather { .. }
+            | Effect::TravelTo(_)
+            | Effect::StartMiniGame(_)
+            | Effect::EmitSignal(_)
+            | Effect::Spawn(_)
+            | Effect::CameraShake(_)
+            | Effect::ChangeScene { .. }
+            | Effect::ParticleBurst { .. }
+            | Effect::PlaySound { .. } => {}
+        }
+    }
+
+    // The facts this effect writes. Scripts are opaque and report none, and so do effects the
+    // game's own systems carry out, whose facts the story lint knows about.
+    pub fn written_facts(&self) -> Vec<String> {
+        match self {
+            Effect::SetFact(fact) => vec![fact.key().to_string()],
+            Effect::TweenFact { key, .. } => vec![key.clone()],
+            Effect::OneOf(effects) => effects.iter().flat_map(Effect::written_facts).collect(),
+            Effect::Script(_)
+            | Effect::ChangeAffinity { .. }
+            | Effect::HireCrew(_)
+            | Effect::DismissCrew(_)
+            | Effect::SetWeather { .. }
+            | Effect::StartMiniGame(_)
+            | Effect::Tag { .. }
+            | Effect::ClearTag(_)
+            | Effect::Say(_)
+            | Effect::RollCredits
+            | Effect::SetTimeScale(..)
+            | Effect::UnlockAchievement(_)
+            | Effect::Rumble { .. }
+            | Effect::ShowTutorial { .. }
+            | Effect::OpenShop(_)
+            | Effect::OpenMap
+            | Effect::TravelTo(_)
+            | Effect::EmitSignal(_)
+            | Effect::Spawn(_)
+            | Effect::CameraShake(_)
+            | Effect::ChangeScene { .. }
+            | Effect::ParticleBurst { .. }
+            | Effect::PlaySound { .. } => Vec::new(),
+        }
+    }
+
+    // Changes the facts and returns whatever else the effect asks for, for the caller to pass on
+    pub fn apply<S: FactStorage>(
+        &self,
+        fact_store: &mut S,
+        rng: &mut StoryRng,
+    ) -> Result<Vec<EffectOutput>, FactError> {
+        let mut outputs = Vec::new();
         match self {
             Effect::SetFact(fact) => {
                 match fact {
-                    Fact::Int(name, value) => fact_store.store_int(name.clone(), *value),
-                    Fact::String(name, value) => fact_store.store_string(name.clone(), value.clone()),
-                    Fact::Bool(name, value) => fact_store.store_bool(name.clone(), *value),
                     Fact::StringList(name, values) => {
+                        fact_store.check_type(fact)?;
+                        fact_store.check_writable(name)?;
                         for value in &values.0 {
                             fact_store.add_to_list(name.clone(), value.clone());
                         }
                     },
+                    _ => fact_store.try_set(fact.clone())?,
+                }
+            }
+            Effect::Script(script) => scripting::run_script(script, fact_store)?,
+            Effect::OneOf(effects) => {
+                if !effects.is_empty() {
+                    let picked = rng.below(effects.len());
+                    outputs.extend(effects[picked].apply(fact_store, rng)?);
                 }
             }
+            Effect::Tag { fact_name, tag } => {
+                let key = fact_store.resolve(fact_name).to_string();
+                fact_store.tag(&key, tag);
+            }
+            // Cleared together, a constant among the tagged facts leaves all of them as they were
+            Effect::ClearTag(tag) => {
+                let cleared = fact_store
+                    .facts_with_tag(tag)
+                    .map(|fact| FactMutation::from_fact(fact.cleared()))
+                    .collect();
+                fact_store.apply_batch(cleared)?;
+            }
+            Effect::Say(line) => outputs.push(EffectOutput::Say(line.clone())),
+            Effect::RollCredits => outputs.push(EffectOutput::RollCredits),
+            Effect::SetTimeScale(scale, duration) => {
+                outputs.push(EffectOutput::SetTimeScale(*scale, *duration))
+            }
+            Effect::UnlockAchievement(id) => {
+                outputs.push(EffectOutput::UnlockAchievement(id.clone()))
+            }
+            Effect::Rumble {
+                intensity,
+                duration,
+            } => outputs.push(EffectOutput::Rumble {
+                intensity: *intensity,
+                duration: *duration,
+            }),
+            Effect::ShowTutorial {
+                id,
+                text,
+                anchor_to,
+            } => outputs.push(EffectOutput::ShowTutorial {
+                id: id.clone(),
+                text: text.clone(),
+                anchor_to: anchor_to.clone(),
+            }),
+            Effect::ChangeAffinity { character, amount } => {
+                outputs.push(EffectOutput::ChangeAffinity {
+                    character: character.clone(),
+                    amount: *amount,
+                })
+            }
+            Effect::HireCrew(member) => outputs.push(EffectOutput::HireCrew(member.clone())),
+            Effect::DismissCrew(name) => outputs.push(EffectOutput::DismissCrew(name.clone())),
+            Effect::OpenShop(name) => outputs.push(EffectOutput::OpenShop(name.clone())),
+            Effect::OpenMap => outputs.push(EffectOutput::OpenMap),
+            Effect::SetWeather { weather, hold } => outputs.push(EffectOutput::SetWeather {
+                weather: *weather,
+                hold: *hold,
+            }),
+            Effect::TravelTo(location) => outputs.push(EffectOutput::TravelTo(location.clone())),
+            Effect::TweenFact {
+                key,
+                to,
+                duration,
+                easing,
+            } => outputs.push(EffectOutput::TweenFact {
+                key: key.clone(),
+                to: *to,
+                duration: *duration,
+                easing: *easing,
+            }),
+            Effect::StartMiniGame(id) => outputs.push(EffectOutput::StartMiniGame(id.clone())),
+            Effect::EmitSignal(topic) => outputs.push(EffectOutput::EmitSignal(topic.clone())),
+            Effect::Spawn(archetype) => outputs.push(EffectOutput::Spawn(archetype.clone())),
+            Effect::CameraShake(trauma) => outputs.push(EffectOutput::CameraShake(*trauma)),
+            Effect::ChangeScene { level, transition } => {
+                outputs.push(EffectOutput::ChangeScene {
+                    level: level.clone(),
+                    transition: *transition,
+                })
+            }
+            Effect::ParticleBurst { archetype, at } => {
+                outputs.push(EffectOutput::ParticleBurst {
+                    archetype: archetype.clone(),
+                    at: *at,
+                })
+            }
+            Effect::PlaySound { sound, at, caption } => outputs.push(EffectOutput::PlaySound {
+                sound: sound.clone(),
+                at: *at,
+                caption: caption.clone(),
+            }),
         }
+        Ok(outputs)
     }
 }
-
-
-
-
@@ -0,0 +1,154 @@
+use crate::beats::choices::PresentChoices;
+use crate::beats::data::{
+    ConditionResult, EvaluationContext, FactsOfTheWorld, StoryBeatFinished, StoryEngine,
+};
+use crate::beats::rng::StoryRng;
+use crate::beats::story_time::StoryTime;
+use crate::dialogue::transcript::ExportTranscript;
+use crate::ui::photo::TakePhoto;
+use bevy::prelude::*;
+
+// Commands for poking at the story engine while developing
+#[derive(Event, Debug, Clone)]
+pub enum DebugCommand {
+    // Floods the store with throwaway int facts, to see how evaluation copes with volume
+    Stress { facts: usize },
+    // Finishes the beat and everything before it. With apply_effects off the
+    // effects of those beats are skipped too.
+    FinishBeat {
+        story: String,
+        beat: String,
+        apply_effects: bool,
+    },
+    // Makes the beat the active one, finishing the beats passed over on the way
+    JumpToBeat {
+        story: String,
+        beat: String,
+        apply_effects: bool,
+    },
+    // Saves a screenshot with the UI hidden
+    Photo,
+    // Writes the dialogue, choices and finished beats so far to a Markdown file
+    ExportTranscript,
+    // Reads stories, data tables and mods from disk again, keeping the facts and story progress
+    ReloadContent,
+}
+
+pub const STRESS_FACT_COUNT: usize = 10_000;
+
+pub fn debug_command_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut debug_commands: EventWriter<DebugCommand>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        debug_commands.send(DebugCommand::Stress {
+            facts: STRESS_FACT_COUNT,
+        });
+    }
+    if keyboard_input.just_pressed(KeyCode::F10) {
+        debug_commands.send(DebugCommand::ReloadContent);
+    }
+}
+
+pub fn debug_command_system(
+    mut debug_commands: EventReader<DebugCommand>,
+    mut storage: ResMut<FactsOfTheWorld>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut story_beat_writer: EventWriter<StoryBeatFinished>,
+    mut present_choices: EventWriter<PresentChoices>,
+    mut photos: EventWriter<TakePhoto>,
+    mut transcripts: EventWriter<ExportTranscript>,
+) {
+    for command in debug_commands.read() {
+        match command {
+            DebugCommand::FinishBeat {
+                story,
+                beat,
+                apply_effects,
+            }
+            | DebugCommand::JumpToBeat {
+                story,
+                beat,
+                apply_effects,
+            } => {
+                let finished = if matches!(command, DebugCommand::FinishBeat { .. }) {
+                    story_engine.force_finish_beat(story, beat)
+                } else {
+                    story_engine.jump_to_beat(story, beat)
+                };
+                let Some(finished) = finished else {
+                    warn!("No beat {} in story {}", beat, story);
+                    continue;
+                };
+                info!("Skipped {} beats of {}", finished.len(), story);
+                // The effect applier picks these up like any other finished beat
+                if *apply_effects {
+                    story_beat_writer.send_batch(finished);
+                }
+                if let Some(choices) = story_engine
+                    .stories
+                    .iter()
+                    .find(|s| &s.name == story)
+                    .and_then(PresentChoices::for_active_beat)
+                {
+                    present_choices.send(choices);
+                }
+            }
+            DebugCommand::Photo => {
+                photos.send(TakePhoto);
+            }
+            DebugCommand::ExportTranscript => {
+                transcripts.send(ExportTranscript);
+            }
+            // Handled by the content reload, which waits for the files to come back
+            DebugCommand::ReloadContent => {}
+            DebugCommand::Stress { facts } => {
+                info!("Stressing the fact store with {} facts", facts);
+                for i in 0..*facts {
+                    if let Err(error) = storage.add_to_int(format!("stress.{}", i), 1) {
+                        warn!("Stress write failed: {}", error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Asks the engine how each condition of the named rule currently evaluates
+#[derive(Event, Debug, Clone)]
+pub struct RequestRuleExplanation(pub String);
+
+#[derive(Event, Debug, Clone)]
+pub struct RuleExplanationReady {
+    pub rule: String,
+    // None when no story has a rule with that name
+    pub conditions: Option<Vec<ConditionResult>>,
+}
+
+impl RuleExplanationReady {
+    pub fn is_satisfied(&self) -> bool {
+        self.conditions
+            .as_ref()
+            .is_some_and(|conditions| conditions.iter().all(|result| result.holds))
+    }
+}
+
+pub fn explain_rules(
+    mut requests: EventReader<RequestRuleExplanation>,
+    mut explanations: EventWriter<RuleExplanationReady>,
+    story_engine: Res<StoryEngine>,
+    storage: Res<FactsOfTheWorld>,
+    story_time: Res<StoryTime>,
+) {
+    for RequestRuleExplanation(name) in requests.read() {
+        // A throwaway rng, explaining a rule mustn't shift the story's random rolls
+        let mut rng = StoryRng::new(0);
+        let mut context =
+            EvaluationContext::new(story_time.elapsed_seconds(), &mut rng, &*storage);
+        let evaluation = story_engine.evaluate_single(name, &mut context);
+        explanations.send(RuleExplanationReady {
+            rule: name.clone(),
+            conditions: evaluation.map(|evaluation| evaluation.conditions),
+        });
+    }
+}
@@ -0,0 +1,247 @@
+use crate::settings::Settings;
+use crate::ui::animation::UiAnimation;
+use crate::ui::layers::UiLayer;
+use crate::ui::theme::UiTheme;
+use bevy::prelude::*;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, Once};
+
+// Something went wrong with story content or a save. These are reported and shown on screen
+// instead of taking the whole game down.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    StoryParse {
+        path: String,
+        message: String,
+    },
+    // A data table under assets/data couldn't be read
+    DataParse {
+        path: String,
+        message: String,
+    },
+    InvalidStory {
+        story: String,
+        message: String,
+    },
+    Effect {
+        story: String,
+        beat: String,
+        message: String,
+    },
+    SaveLoad {
+        path: String,
+        message: String,
+    },
+    // The save couldn't be read but the previous one could, so that was loaded instead
+    SaveCorrupted {
+        path: String,
+        message: String,
+    },
+    // A content pack in the mods folder couldn't be read, it is left out
+    ModLoad {
+        path: String,
+        message: String,
+    },
+    // Something panicked outside of the guarded paths, e.g. in an asset loader task
+    Panic {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::StoryParse { path, message } => {
+                write!(f, "Could not parse story {}: {}", path, message)
+            }
+            EngineError::DataParse { path, message } => {
+                write!(f, "Could not parse data table {}: {}", path, message)
+            }
+            EngineError::InvalidStory { story, message } => {
+                write!(f, "Story {} is invalid: {}", story, message)
+            }
+            EngineError::Effect {
+                story,
+                beat,
+                message,
+            } => write!(
+                f,
+                "Effect of beat {} in {} failed: {}",
+                beat, story, message
+            ),
+            EngineError::SaveLoad { path, message } => {
+                write!(f, "Could not load save {}: {}", path, message)
+            }
+            EngineError::SaveCorrupted { path, message } => write!(
+                f,
+                "Save {} is corrupted ({}), loaded the previous save instead",
+                path, message
+            ),
+            EngineError::ModLoad { path, message } => {
+                write!(f, "Could not load mod {}: {}", path, message)
+            }
+            EngineError::Panic { message } => write!(f, "Something went wrong: {}", message),
+        }
+    }
+}
+
+// Panics seen by the hook, waiting to be reported
+static PANICS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static PANIC_HOOK: Once = Once::new();
+
+thread_local! {
+    // Set while `recover` runs, its caller reports the panic with more context
+    static RECOVERING: Cell<bool> = const { Cell::new(false) };
+}
+
+// Runs parsing, effects or save loading, turning a panic into an error message so bad content
+// doesn't take the whole game down. Panics can't be caught on wasm, see `install_panic_hook`.
+pub fn recover<T>(run: impl FnOnce() -> T) -> Result<T, String> {
+    let was_recovering = RECOVERING.with(|recovering| recovering.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(run));
+    RECOVERING.with(|recovering| recovering.set(was_recovering));
+    result.map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panicked".to_string())
+    })
+}
+
+// Passes panics on to `report_panics` after whatever hook was set before, e.g. the one logging
+// to the browser console. A wasm build stops at a panic, so there the message is also put over
+// the canvas, as the error screen won't get another frame to show it.
+pub fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            if RECOVERING.with(Cell::get) {
+                return;
+            }
+            #[cfg(target_arch = "wasm32")]
+            show_panic_in_page(&info.to_string());
+            if let Ok(mut panics) = PANICS.lock() {
+                panics.push(info.to_string());
+            }
+        }));
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn show_panic_in_page(message: &str) {
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let (Ok(screen), Some(body)) = (document.create_element("div"), document.body()) else {
+        return;
+    };
+    screen.set_text_content(Some(&format!("Something went wrong: {}", message)));
+    let _ = screen.set_attribute(
+        "style",
+        "position: fixed; top: 0; left: 0; right: 0; padding: 12px; background: #7a1f1f; \
+         color: white; font: 18px sans-serif; white-space: pre-wrap; z-index: 1000",
+    );
+    let _ = body.append_child(&screen);
+}
+
+pub fn report_panics(mut errors: EventWriter<EngineError>) {
+    let Ok(mut panics) = PANICS.lock() else {
+        return;
+    };
+    for message in panics.drain(..) {
+        errors.send(EngineError::Panic { message });
+    }
+}
+
+// Errors currently listed on the error screen
+#[derive(Resource, Default)]
+pub struct ErrorLog {
+    pub messages: Vec<String>,
+}
+
+#[derive(Component)]
+pub struct ErrorScreen;
+
+pub fn collect_engine_errors(mut errors: EventReader<EngineError>, mut log: ResMut<ErrorLog>) {
+    for engine_error in errors.read() {
+        error!("{}", engine_error);
+        log.messages.push(engine_error.to_string());
+    }
+}
+
+const ERROR_SCREEN_FADE_SECONDS: f32 = 0.25;
+
+pub fn show_error_screen(
+    mut commands: Commands,
+    log: Res<ErrorLog>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    screens: Query<Entity, With<ErrorScreen>>,
+) {
+    if !log.is_changed() {
+        return;
+    }
+    if log.messages.is_empty() {
+        for entity in screens.iter() {
+            commands
+                .entity(entity)
+                .insert(UiAnimation::fade_out(ERROR_SCREEN_FADE_SECONDS));
+        }
+        return;
+    }
+    // Only fade in when the screen first opens, not on every new message
+    let opening = screens.is_empty();
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let mut screen = commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.),
+                width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.)),
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            background_color: theme.palette(settings.palette).negative_background.into(),
+            z_index: UiLayer::Modal.z_index(),
+            ..default()
+        },
+        ErrorScreen,
+        UiLayer::Modal,
+    ));
+    if opening {
+        screen.insert(UiAnimation::fade_in(ERROR_SCREEN_FADE_SECONDS));
+    }
+    screen.with_children(|parent| {
+        for message in log.messages.iter() {
+            parent.spawn(TextBundle::from_section(
+                message.clone(),
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        }
+        parent.spawn(TextBundle::from_section(
+            "Press Escape to dismiss",
+            TextStyle {
+                font_size: 14.0,
+                color: Color::rgb(0.8, 0.8, 0.8),
+                ..default()
+            },
+        ));
+    });
+}
+
+pub fn dismiss_error_screen(keyboard_input: Res<ButtonInput<KeyCode>>, mut log: ResMut<ErrorLog>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) && !log.messages.is_empty() {
+        log.messages.clear();
+    }
+}
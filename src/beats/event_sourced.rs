@@ -0,0 +1,199 @@
+use crate::beats::data::{
+    Fact, FactAliasUsed, FactError, FactMutation, FactRemoved, FactUpdated, FactWriteDenied,
+    FactsOfTheWorld, StringHashSet,
+};
+use crate::beats::storage::FactStorage;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_COMPACTION_THRESHOLD: usize = 1024;
+
+// A fact store whose state is derived from an append-only log of mutations.
+// The log is folded into a snapshot once it grows past the compaction threshold,
+// so undo and replay only reach back to the last compaction.
+#[derive(Resource, Deserialize, Serialize)]
+pub struct EventSourcedFactStore {
+    snapshot: HashMap<String, Fact>,
+    log: Vec<FactMutation>,
+    compaction_threshold: usize,
+    state: FactsOfTheWorld,
+}
+
+impl EventSourcedFactStore {
+    pub fn new() -> Self {
+        Self::with_compaction_threshold(DEFAULT_COMPACTION_THRESHOLD)
+    }
+
+    pub fn with_compaction_threshold(compaction_threshold: usize) -> Self {
+        EventSourcedFactStore {
+            snapshot: HashMap::new(),
+            log: Vec::new(),
+            compaction_threshold,
+            state: FactsOfTheWorld::new(),
+        }
+    }
+
+    // Applies the mutation and appends it to the log. Writes to constants and writes of the wrong
+    // type are refused and stay out of the log, replay doesn't know which facts are constant.
+    pub fn record(&mut self, mut mutation: FactMutation) -> Result<(), FactError> {
+        // The log only ever holds canonical keys, aliases are resolved on the way in
+        *mutation.key_mut() = self.state.resolve_write(mutation.key().to_string());
+        mutation.try_apply(&mut self.state)?;
+        self.log.push(mutation);
+        if self.log.len() > self.compaction_threshold {
+            self.compact();
+        }
+        Ok(())
+    }
+
+    pub fn store_int(&mut self, key: String, value: i32) -> Result<(), FactError> {
+        self.record(FactMutation::StoreInt(key, value))
+    }
+
+    pub fn store_string(&mut self, key: String, value: String) -> Result<(), FactError> {
+        self.record(FactMutation::StoreString(key, value))
+    }
+
+    pub fn store_bool(&mut self, key: String, value: bool) -> Result<(), FactError> {
+        self.record(FactMutation::StoreBool(key, value))
+    }
+
+    pub fn add_to_list(&mut self, key: String, value: String) -> Result<(), FactError> {
+        self.record(FactMutation::AddToList(key, value))
+    }
+
+    pub fn remove_from_list(&mut self, key: String, value: String) -> Result<(), FactError> {
+        self.record(FactMutation::RemoveFromList(key, value))
+    }
+
+    // The log has no mutation for a removal, so it is folded into the snapshot first and the
+    // removal can't be undone
+    pub fn remove(&mut self, key: &str) -> Option<Fact> {
+        let key = self.state.resolve(key).to_string();
+        self.state.facts.get(&key)?;
+        self.compact();
+        self.snapshot.remove(&key);
+        self.state.remove(&key)
+    }
+
+    // Folds the log into the snapshot. Mutations before this point can no longer be undone.
+    pub fn compact(&mut self) {
+        self.snapshot = self.state.facts.clone();
+        self.log.clear();
+    }
+
+    // Reverts the most recent mutation since the last compaction, if any. The fact goes back to
+    // the value the rest of the log gives it, reported like any other write, or is removed if
+    // the undone mutation created it.
+    pub fn undo(&mut self) -> Option<FactMutation> {
+        let undone = self.log.pop()?;
+        let mut replayed = Self::replay(&self.snapshot, &self.log);
+        // Only the undone fact is written, tags and constants stay as they are
+        match replayed.facts.remove(undone.key()) {
+            Some(fact) => {
+                if let Err(error) = FactStorage::try_set(&mut self.state, fact) {
+                    warn!("Could not undo {}: {}", undone.key(), error);
+                }
+            }
+            None => {
+                self.state.remove(undone.key());
+            }
+        }
+        Some(undone)
+    }
+
+    // Rebuilds a fact store by applying the log on top of the snapshot, with the same checks
+    // the mutations passed when they were recorded
+    pub fn replay(snapshot: &HashMap<String, Fact>, log: &[FactMutation]) -> FactsOfTheWorld {
+        let mut state = FactsOfTheWorld::new();
+        state.facts = snapshot.clone();
+        for mutation in log {
+            if let Err(error) = mutation.try_apply(&mut state) {
+                warn!("Skipped {:?} while replaying: {}", mutation, error);
+            }
+        }
+        state.updated_facts.clear();
+        state.previous_facts.clear();
+        state
+    }
+
+    pub fn log(&self) -> &[FactMutation] {
+        &self.log
+    }
+
+    pub fn facts(&self) -> &HashMap<String, Fact> {
+        &self.state.facts
+    }
+
+    pub fn has_updates(&self) -> bool {
+        self.state.has_updates()
+    }
+
+    pub fn drain_updated(&mut self) -> Vec<FactUpdated> {
+        self.state.drain_updated()
+    }
+
+    pub fn drain_removed(&mut self) -> Vec<FactRemoved> {
+        self.state.drain_removed()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.state.revision()
+    }
+
+    // Constants are stored beside the log, so they survive undo and compaction
+    pub fn store_constant(&mut self, fact: Fact) {
+        self.state.store_constant(fact.clone());
+        self.snapshot.insert(fact.key().to_string(), fact);
+    }
+
+    pub fn is_constant(&self, key: &str) -> bool {
+        self.state.is_constant(key)
+    }
+
+    pub fn alias(&mut self, alias: impl Into<String>, target: impl Into<String>) {
+        self.state.alias(alias, target);
+    }
+
+    pub fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        self.state.resolve(key)
+    }
+
+    pub fn aliases(&self) -> &HashMap<String, String> {
+        &self.state.aliases
+    }
+
+    pub fn drain_alias_hits(&mut self) -> Vec<FactAliasUsed> {
+        self.state.drain_alias_hits()
+    }
+
+    pub fn drain_denied(&mut self) -> Vec<FactWriteDenied> {
+        self.state.drain_denied()
+    }
+
+    // Tags live beside the log rather than in it, undo leaves them alone
+    pub fn tag(&mut self, key: &str, tag: &str) {
+        self.state.tag(key, tag);
+    }
+
+    pub fn tagged_keys(&self, tag: &str) -> Vec<String> {
+        self.state.tagged_keys(tag)
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<&i32> {
+        self.state.get_int(key)
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<&String> {
+        self.state.get_string(key)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<&bool> {
+        self.state.get_bool(key)
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<&StringHashSet> {
+        self.state.get_list(key)
+    }
+}
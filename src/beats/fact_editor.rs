@@ -0,0 +1,114 @@
+use crate::beats::data::{Fact, FactMutation, FactsOfTheWorld};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+// State of the fact editor window between frames
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FactEditor {
+    // Only facts whose key contains this are listed
+    pub filter: String,
+    // Text being typed into string facts, written to the store when the field loses focus
+    drafts: HashMap<String, String>,
+    // Item being typed for each string list, added when Enter is pressed
+    new_items: HashMap<String, String>,
+}
+
+// Lists every fact with an editor for its type. Edits go through the store as one batch,
+// so they fire FactUpdated and rules react as if the game had written them.
+pub fn fact_editor_window(
+    mut contexts: EguiContexts,
+    mut editor: ResMut<FactEditor>,
+    mut storage: ResMut<FactsOfTheWorld>,
+) {
+    let editor = editor.as_mut();
+    let mut edits = Vec::new();
+    egui::Window::new("Facts")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter");
+                ui.text_edit_singleline(&mut editor.filter);
+            });
+            let mut keys: Vec<&String> = storage
+                .facts
+                .keys()
+                .filter(|key| key.contains(editor.filter.as_str()))
+                .collect();
+            keys.sort();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("fact_editor").striped(true).show(ui, |ui| {
+                    for key in keys {
+                        ui.monospace(key.as_str());
+                        ui.add_enabled_ui(!storage.is_constant(key), |ui| {
+                            if let Some(edit) = fact_widget(ui, editor, &storage.facts[key]) {
+                                edits.push(edit);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    // Only touched when something was edited, so the store isn't flagged changed every frame
+    if !edits.is_empty() {
+        if let Err(error) = storage.apply_batch(edits) {
+            warn!("Could not apply fact edit: {}", error);
+        }
+    }
+}
+
+fn fact_widget(ui: &mut egui::Ui, editor: &mut FactEditor, fact: &Fact) -> Option<FactMutation> {
+    match fact {
+        Fact::Int(key, value) => {
+            let mut value = *value;
+            ui.add(egui::DragValue::new(&mut value))
+                .changed()
+                .then(|| FactMutation::StoreInt(key.clone(), value))
+        }
+        Fact::Bool(key, value) => {
+            let mut value = *value;
+            ui.checkbox(&mut value, "")
+                .changed()
+                .then(|| FactMutation::StoreBool(key.clone(), value))
+        }
+        Fact::String(key, value) => {
+            let draft = editor
+                .drafts
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+            let response = ui.text_edit_singleline(draft);
+            if response.has_focus() {
+                return None;
+            }
+            // Not being typed into, so the draft follows the store again
+            let draft = editor.drafts.remove(key).unwrap_or_default();
+            (response.lost_focus() && draft != *value)
+                .then(|| FactMutation::StoreString(key.clone(), draft))
+        }
+        Fact::StringList(key, values) => {
+            let mut edit = None;
+            ui.horizontal_wrapped(|ui| {
+                let mut items: Vec<&String> = values.0.iter().collect();
+                items.sort();
+                for item in items {
+                    if ui.small_button(format!("{} x", item)).clicked() {
+                        edit = Some(FactMutation::RemoveFromList(key.clone(), item.clone()));
+                    }
+                }
+                let new_item = editor.new_items.entry(key.clone()).or_default();
+                let response = ui.add(egui::TextEdit::singleline(new_item).desired_width(80.));
+                let submitted =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if submitted && !new_item.is_empty() {
+                    edit = Some(FactMutation::AddToList(
+                        key.clone(),
+                        std::mem::take(new_item),
+                    ));
+                }
+            });
+            edit
+        }
+    }
+}
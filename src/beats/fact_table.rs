@@ -0,0 +1,206 @@
+use crate::beats::data::{Fact, FactsOfTheWorld};
+use crate::beats::errors::EngineError;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+
+// Spreadsheet data bundled with the game, relative to the assets folder
+pub const FACT_TABLE_FILES: &[&str] = &["data/ports.csv"];
+
+// Rows of a CSV file as facts. The first column names the row, the header names the other
+// columns, and each cell becomes `<file>.<row>.<column>`, e.g. `ports.saltmere.berths`.
+// Cells that read as a whole number are ints, `true`/`false` are bools, the rest strings.
+#[derive(Asset, TypePath, Debug, Clone, PartialEq)]
+pub struct FactTable {
+    pub facts: Vec<Fact>,
+}
+
+#[derive(Debug)]
+pub enum FactTableLoadError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl std::fmt::Display for FactTableLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FactTableLoadError::Io(error) => write!(f, "could not read data file: {}", error),
+            FactTableLoadError::Parse { line, message } => {
+                write!(f, "line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FactTableLoadError {}
+
+impl From<std::io::Error> for FactTableLoadError {
+    fn from(error: std::io::Error) -> Self {
+        FactTableLoadError::Io(error)
+    }
+}
+
+pub fn parse_fact_table(namespace: &str, source: &str) -> Result<FactTable, FactTableLoadError> {
+    let mut rows = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((header_line, header)) = rows.next() else {
+        return Ok(FactTable { facts: Vec::new() });
+    };
+    let columns = split_row(header).map_err(|message| FactTableLoadError::Parse {
+        line: header_line,
+        message,
+    })?;
+    let mut facts = Vec::new();
+    for (line, row) in rows {
+        let cells =
+            split_row(row).map_err(|message| FactTableLoadError::Parse { line, message })?;
+        if cells.len() != columns.len() {
+            return Err(FactTableLoadError::Parse {
+                line,
+                message: format!("expected {} cells, found {}", columns.len(), cells.len()),
+            });
+        }
+        let id = &cells[0];
+        if id.is_empty() {
+            return Err(FactTableLoadError::Parse {
+                line,
+                message: "the first cell names the row and can't be empty".to_string(),
+            });
+        }
+        for (column, cell) in columns.iter().zip(cells.iter()).skip(1) {
+            let key = format!("{}.{}.{}", namespace, id, column);
+            facts.push(cell_fact(key, cell));
+        }
+    }
+    Ok(FactTable { facts })
+}
+
+fn cell_fact(key: String, cell: &str) -> Fact {
+    if let Ok(value) = cell.parse::<i32>() {
+        return Fact::Int(key, value);
+    }
+    match cell {
+        "true" => Fact::Bool(key, true),
+        "false" => Fact::Bool(key, false),
+        _ => Fact::String(key, cell.to_string()),
+    }
+}
+
+// Comma separated cells, trimmed. A cell in double quotes may hold commas, and `""` inside
+// quotes stands for one quote.
+fn split_row(row: &str) -> Result<Vec<String>, String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            (',', false) => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err("unclosed quote".to_string());
+    }
+    cells.push(cell.trim().to_string());
+    Ok(cells)
+}
+
+#[derive(Default)]
+pub struct FactTableLoader;
+
+impl AssetLoader for FactTableLoader {
+    type Asset = FactTable;
+    type Settings = ();
+    type Error = FactTableLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            // `data/ports.csv` fills the `ports.` namespace
+            let namespace = load_context
+                .path()
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            parse_fact_table(&namespace, &source)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+}
+
+// Keeps the data files loaded
+#[derive(Resource, Default)]
+pub struct FactTableFiles(pub Vec<Handle<FactTable>>);
+
+pub fn load_fact_tables(asset_server: Res<AssetServer>, mut files: ResMut<FactTableFiles>) {
+    for path in FACT_TABLE_FILES {
+        files.0.push(asset_server.load(*path));
+    }
+}
+
+// Tables are stored as constants, so stories read them but nothing in the game overwrites
+// them. An edited file is stored again when it reloads.
+pub fn store_fact_tables(
+    mut asset_events: EventReader<AssetEvent<FactTable>>,
+    tables: Res<Assets<FactTable>>,
+    mut storage: ResMut<FactsOfTheWorld>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if let Some(table) = tables.get(*id) {
+            store_table(&mut storage, table);
+        }
+    }
+}
+
+// Chapter starts and loaded saves replace the facts wholesale, so the tables go back in
+// whenever a story starts
+pub fn restore_fact_tables(tables: Res<Assets<FactTable>>, mut storage: ResMut<FactsOfTheWorld>) {
+    for (_, table) in tables.iter() {
+        store_table(&mut storage, table);
+    }
+}
+
+fn store_table(storage: &mut FactsOfTheWorld, table: &FactTable) {
+    for fact in table.facts.iter() {
+        storage.store_constant(fact.clone());
+    }
+}
+
+pub fn report_fact_table_load_failures(
+    mut failures: EventReader<AssetLoadFailedEvent<FactTable>>,
+    mut errors: EventWriter<EngineError>,
+) {
+    for failure in failures.read() {
+        errors.send(EngineError::DataParse {
+            path: failure.path.to_string(),
+            message: failure.error.to_string(),
+        });
+    }
+}
@@ -0,0 +1,32 @@
+use crate::beats::StoryCorePlugin;
+use crate::GameState;
+use bevy::app::PluginGroupBuilder;
+use bevy::asset::AssetPlugin;
+use bevy::core::{FrameCountPlugin, TaskPoolPlugin, TypeRegistrationPlugin};
+use bevy::prelude::*;
+use bevy::time::TimePlugin;
+
+// Everything needed to run stories without a window, renderer or audio, e.g. in integration
+// tests. The app starts out in `GameState::Story`; call `App::update` to step it a frame.
+pub struct MinimalStoryPlugins;
+
+impl PluginGroup for MinimalStoryPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(TaskPoolPlugin::default())
+            .add(TypeRegistrationPlugin)
+            .add(FrameCountPlugin)
+            .add(TimePlugin)
+            .add(AssetPlugin::default())
+            .add(StartInStory)
+            .add(StoryCorePlugin)
+    }
+}
+
+struct StartInStory;
+
+impl Plugin for StartInStory {
+    fn build(&self, app: &mut App) {
+        app.insert_state(GameState::Story);
+    }
+}
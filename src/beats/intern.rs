@@ -0,0 +1,97 @@
+use bevy::utils::HashSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Every distinct string interned so far. Entries are never dropped, this only holds the
+// literals story files compare facts against, which is a small and fixed set per game.
+static INTERNED: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+
+// A shared, immutable string. Equal literals across all loaded rules point at one allocation,
+// so thousands of conditions checking for the same value don't each own a copy.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    pub fn new(value: &str) -> Self {
+        let mut interned = INTERNED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(existing) = interned.get(value) {
+            return Interned(existing.clone());
+        }
+        let value: Arc<str> = Arc::from(value);
+        interned.insert(value.clone());
+        Interned(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Interned {
+    fn from(value: &str) -> Self {
+        Interned::new(value)
+    }
+}
+
+impl From<String> for Interned {
+    fn from(value: String) -> Self {
+        Interned::new(&value)
+    }
+}
+
+impl PartialEq<str> for Interned {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<String> for Interned {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+// Shown like a plain string, so debug output and explanations read the same as before
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Interned::new(&value))
+    }
+}
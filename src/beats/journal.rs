@@ -0,0 +1,138 @@
+use crate::beats::choices::ChoiceMade;
+use crate::beats::data::{Fact, FactUpdated, StoryBeatFinished, StoryEngine};
+use crate::beats::save_location::SaveLocation;
+use crate::beats::story_time::StoryTime;
+use bevy::prelude::*;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+pub const JOURNAL_EXPORT_FILE: &str = "journal.json";
+// Oldest entries are dropped past this, a long session changes a lot of facts
+const MAX_JOURNAL_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JournalEntry {
+    // Story time in seconds
+    pub at: f64,
+    #[serde(flatten)]
+    pub kind: JournalKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum JournalKind {
+    FactChanged {
+        fact: Fact,
+    },
+    BeatFinished {
+        story: String,
+        beat: String,
+    },
+    ChoiceMade {
+        story: String,
+        beat: String,
+        choice: usize,
+        label: String,
+    },
+}
+
+impl JournalKind {
+    pub fn describe(&self) -> String {
+        match self {
+            JournalKind::FactChanged { fact } => format!("{:?}", fact),
+            JournalKind::BeatFinished { story, beat } => format!("{}: {} finished", story, beat),
+            JournalKind::ChoiceMade {
+                story, beat, label, ..
+            } => format!("{}: {} chose \"{}\"", story, beat, label),
+        }
+    }
+}
+
+// Everything that happened to the story this session, in order, for the timeline and for
+// sharing a session as JSON
+#[derive(Resource, Debug, Clone, Default, Serialize)]
+pub struct SessionJournal {
+    pub entries: VecDeque<JournalEntry>,
+}
+
+impl SessionJournal {
+    pub fn push(&mut self, at: f64, kind: JournalKind) {
+        self.entries.push_back(JournalEntry { at, kind });
+        while self.entries.len() > MAX_JOURNAL_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+// Writes the journal to `journal.json` in the save directory
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ExportSessionJournal;
+
+pub fn record_session_journal(
+    story_time: Res<StoryTime>,
+    story_engine: Res<StoryEngine>,
+    mut fact_updates: EventReader<FactUpdated>,
+    mut story_beat_finished: EventReader<StoryBeatFinished>,
+    mut choices_made: EventReader<ChoiceMade>,
+    mut journal: ResMut<SessionJournal>,
+) {
+    let now = story_time.elapsed_seconds();
+    for update in fact_updates.read() {
+        journal.push(
+            now,
+            JournalKind::FactChanged {
+                fact: update.fact.clone(),
+            },
+        );
+    }
+    for finished in story_beat_finished.read() {
+        journal.push(
+            now,
+            JournalKind::BeatFinished {
+                story: finished.story.name.clone(),
+                beat: finished.beat.name.clone(),
+            },
+        );
+    }
+    for made in choices_made.read() {
+        let label = story_engine
+            .stories
+            .iter()
+            .find(|story| story.name == made.story)
+            .and_then(|story| story.beats.iter().find(|beat| beat.name == made.beat))
+            .and_then(|beat| beat.choices.get(made.index))
+            .map(|choice| choice.label.clone())
+            .unwrap_or_default();
+        journal.push(
+            now,
+            JournalKind::ChoiceMade {
+                story: made.story.clone(),
+                beat: made.beat.clone(),
+                choice: made.index,
+                label,
+            },
+        );
+    }
+}
+
+pub fn export_session_journal(
+    mut requests: EventReader<ExportSessionJournal>,
+    journal: Res<SessionJournal>,
+    location: Res<SaveLocation>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    let exported = journal
+        .to_json()
+        .map_err(|error| error.to_string())
+        .and_then(|json| location.export(JOURNAL_EXPORT_FILE, &json));
+    match exported {
+        Ok(path) => info!("Session journal exported to {}", path),
+        Err(error) => warn!("Could not export session journal: {}", error),
+    }
+}
@@ -0,0 +1,111 @@
+use crate::beats::data::{Fact, FactUpdated, FactsOfTheWorld};
+use crate::beats::storage::FactStorage;
+use crate::beats::story_time::StoryTime;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Derived int facts summing up tagged bool facts, like deeds adding to karma. Endings and
+// rules branch on the derived fact instead of checking every deed themselves.
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KarmaConfig {
+    // Length of a game day in story time, decay is applied once per day
+    pub seconds_per_day: f64,
+    pub aggregates: Vec<KarmaAggregate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KarmaAggregate {
+    // Int fact the total is written to
+    pub fact: String,
+    // Tags of bool facts and what each one adds once it turns true
+    pub weights: Vec<(String, i32)>,
+    // Share of the total lost every game day, 0.0 keeps everything
+    #[serde(default)]
+    pub decay_per_day: f32,
+}
+
+impl KarmaAggregate {
+    // List fact of the deeds already added, saved with the facts so loading doesn't count them twice
+    pub fn counted_fact(&self) -> String {
+        format!("{}.counted", self.fact)
+    }
+}
+
+impl Default for KarmaConfig {
+    fn default() -> Self {
+        KarmaConfig {
+            seconds_per_day: 600.0,
+            aggregates: vec![KarmaAggregate {
+                fact: "karma".to_string(),
+                weights: vec![("good_deed".to_string(), 1), ("bad_deed".to_string(), -1)],
+                decay_per_day: 0.1,
+            }],
+        }
+    }
+}
+
+// Last game day decay was applied for
+#[derive(Resource, Debug, Default)]
+pub struct KarmaDecay {
+    day: Option<u64>,
+}
+
+pub fn aggregate_karma(
+    mut fact_updates: EventReader<FactUpdated>,
+    config: Res<KarmaConfig>,
+    story_time: Res<StoryTime>,
+    mut decay: ResMut<KarmaDecay>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    let day = (story_time.elapsed_seconds() / config.seconds_per_day.max(1.0)) as u64;
+    match decay.day {
+        Some(last) if day > last => {
+            for aggregate in config.aggregates.iter() {
+                let Some(total) = facts.get_int(&aggregate.fact).copied() else {
+                    continue;
+                };
+                let kept =
+                    (1.0 - aggregate.decay_per_day.clamp(0.0, 1.0)).powi((day - last) as i32);
+                let decayed = (total as f32 * kept).round() as i32;
+                if decayed != total {
+                    if let Err(error) = facts.store_int(aggregate.fact.clone(), decayed) {
+                        warn!("Karma did not decay: {}", error);
+                    }
+                }
+            }
+            decay.day = Some(day);
+        }
+        // First run, or a save from earlier in the game was loaded
+        Some(last) if day == last => {}
+        _ => decay.day = Some(day),
+    }
+
+    if fact_updates.read().count() == 0 {
+        return;
+    }
+    for aggregate in config.aggregates.iter() {
+        let counted_fact = aggregate.counted_fact();
+        let mut added = 0;
+        for (tag, weight) in aggregate.weights.iter() {
+            for key in facts.tagged_keys(tag) {
+                let done = matches!(facts.get(&key), Some(Fact::Bool(_, true)));
+                let counted = facts
+                    .get_list(&counted_fact)
+                    .is_some_and(|counted| counted.contains(&key));
+                if done && !counted {
+                    match facts.add_to_list(counted_fact.clone(), key) {
+                        Ok(()) => added += weight,
+                        Err(error) => warn!("Deed not counted towards karma: {}", error),
+                    }
+                }
+            }
+        }
+        if added != 0 {
+            let total = facts.get_int(&aggregate.fact).copied().unwrap_or_default();
+            if let Err(error) = facts.store_int(aggregate.fact.clone(), total.saturating_add(added)) {
+                warn!("Karma not updated: {}", error);
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::beats::data::{Condition, CrewMember, Effect, Story};
+use crate::beats::relationships::affinity_fact;
+use crate::beats::systems::beat_finished_at_fact;
+use crate::codex::{CODEX_UNLOCKED_FACT, CODEX_UNSEEN_FACT};
+use crate::crew::crew_fact;
+use crate::difficulty::DIFFICULTY_FACT;
+use crate::map::{discovered_fact, TRAVEL_FACT};
+use crate::mini_games::mini_game_fact;
+use crate::shop::INVENTORY_FACT;
+use crate::weather::WEATHER_FACT;
+
+// Facts read or written outside the stories, by the game's systems or the data tables. An entry
+// ending in `.` stands for the whole namespace.
+#[derive(Debug, Clone, Default)]
+pub struct KnownFacts {
+    pub written: Vec<String>,
+    pub read: Vec<String>,
+}
+
+impl KnownFacts {
+    // What the game's own systems keep up to date and look at
+    pub fn game() -> Self {
+        let both = [
+            "supplies.".to_string(),
+            "ui.".to_string(),
+            "button_pressed".to_string(),
+            TRAVEL_FACT.to_string(),
+            discovered_fact(""),
+            DIFFICULTY_FACT.to_string(),
+            format!("{}.", DIFFICULTY_FACT),
+            CODEX_UNLOCKED_FACT.to_string(),
+            CODEX_UNSEEN_FACT.to_string(),
+            INVENTORY_FACT.to_string(),
+        ];
+        KnownFacts {
+            written: both.to_vec(),
+            read: both.to_vec(),
+        }
+    }
+
+    pub fn writes(mut self, fact: impl Into<String>) -> Self {
+        self.written.push(fact.into());
+        self
+    }
+
+    pub fn reads(mut self, fact: impl Into<String>) -> Self {
+        self.read.push(fact.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoryLint {
+    // A rule of the beat reads a fact nothing writes, so the beat can't be reached that way.
+    // Prerequisites have no beat.
+    UnwrittenFact {
+        story: String,
+        beat: Option<String>,
+        rule: String,
+        fact: String,
+    },
+    // The story's effects write a fact no rule, choice or known system reads
+    UnreadFact {
+        story: String,
+        fact: String,
+    },
+}
+
+impl std::fmt::Display for StoryLint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoryLint::UnwrittenFact {
+                story,
+                beat,
+                rule,
+                fact,
+            } => {
+                match beat {
+                    Some(beat) => write!(f, "{}/{}", story, beat)?,
+                    None => write!(f, "{} prerequisites", story)?,
+                }
+                write!(f, ": rule {} reads {}, which nothing writes", rule, fact)
+            }
+            StoryLint::UnreadFact { story, fact } => {
+                write!(f, "{} writes {}, which nothing reads", story, fact)
+            }
+        }
+    }
+}
+
+// Whether the names are the same fact, or one is a namespace the other is in
+fn overlaps(a: &str, b: &str) -> bool {
+    a == b || (a.ends_with('.') && b.starts_with(a)) || (b.ends_with('.') && a.starts_with(b))
+}
+
+fn conditions(story: &Story) -> impl Iterator<Item = &Condition> {
+    let rules = story
+        .pre_requisites
+        .iter()
+        .chain(story.beats.iter().flat_map(|beat| beat.rules.iter()))
+        .chain(
+            story
+                .beats
+                .iter()
+                .flat_map(|beat| beat.transitions.iter().map(|transition| &transition.rule)),
+        );
+    rules.flat_map(|rule| rule.conditions.iter()).chain(
+        story
+            .beats
+            .iter()
+            .flat_map(|beat| beat.choices.iter())
+            .flat_map(|choice| choice.requires.iter()),
+    )
+}
+
+fn effects(story: &Story) -> impl Iterator<Item = &Effect> {
+    story.beats.iter().flat_map(|beat| {
+        beat.effects
+            .iter()
+            .chain(beat.choices.iter().flat_map(|choice| choice.effects.iter()))
+    })
+}
+
+fn condition_scripts<'a>(condition: &'a Condition, scripts: &mut Vec<&'a str>) {
+    match condition {
+        Condition::Script(script) => scripts.push(script),
+        Condition::Not(condition) | Condition::Costed { condition, .. } => {
+            condition_scripts(condition, scripts)
+        }
+        Condition::Any(conditions) | Condition::All(conditions) => {
+            for condition in conditions {
+                condition_scripts(condition, scripts);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn effect_scripts<'a>(effect: &'a Effect, scripts: &mut Vec<&'a str>) {
+    match effect {
+        Effect::Script(script) => scripts.push(script),
+        Effect::OneOf(effects) => {
+            for effect in effects {
+                effect_scripts(effect, scripts);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The facts an effect writes, with those the game's systems write for it. Effects writing a
+// person's or a mini-game's facts report their namespace, ending in `.`.
+fn written_facts(effect: &Effect) -> Vec<String> {
+    match effect {
+        Effect::OneOf(effects) => effects.iter().flat_map(written_facts).collect(),
+        Effect::ChangeAffinity { character, .. } => vec![affinity_fact(character)],
+        Effect::HireCrew(CrewMember { name, .. }) | Effect::DismissCrew(name) => {
+            vec![crew_fact(name, "")]
+        }
+        Effect::SetWeather { .. } => vec![WEATHER_FACT.to_string()],
+        Effect::StartMiniGame(id) => vec![mini_game_fact(id, "")],
+        _ => effect.written_facts(),
+    }
+}
+
+// Looks over the stories together for beats waiting on facts nothing writes and facts written
+// that nothing reads. Scripts can't be looked into, so a fact named anywhere in a script counts
+// as read, and as written if the script is an effect.
+pub fn lint_stories(stories: &[Story], known: &KnownFacts) -> Vec<StoryLint> {
+    let mut written = known.written.clone();
+    let mut read = known.read.clone();
+    let mut condition_texts = Vec::new();
+    let mut effect_texts = Vec::new();
+    for story in stories {
+        for beat in story.beats.iter() {
+            written.push(beat_finished_at_fact(&story.name, &beat.name));
+        }
+        for effect in effects(story) {
+            written.extend(written_facts(effect));
+            effect_scripts(effect, &mut effect_texts);
+        }
+        for condition in conditions(story) {
+            read.extend(condition.fact_names().into_iter().map(String::from));
+            condition_scripts(condition, &mut condition_texts);
+        }
+    }
+    let is_written = |fact: &str| {
+        written.iter().any(|name| overlaps(name, fact))
+            || effect_texts.iter().any(|script| script.contains(fact))
+    };
+    let is_read = |fact: &str| {
+        read.iter().any(|name| overlaps(name, fact))
+            || condition_texts
+                .iter()
+                .chain(effect_texts.iter())
+                .any(|script| script.contains(fact))
+    };
+
+    let mut lints = Vec::new();
+    for story in stories {
+        let rules = story.pre_requisites.iter().map(|rule| (None, rule)).chain(
+            story.beats.iter().flat_map(|beat| {
+                beat.rules
+                    .iter()
+                    .map(|rule| (Some(beat.name.clone()), rule))
+            }),
+        );
+        for (beat, rule) in rules {
+            for fact in rule
+                .conditions
+                .iter()
+                .flat_map(|condition| condition.fact_names())
+            {
+                if !is_written(fact) {
+                    lints.push(StoryLint::UnwrittenFact {
+                        story: story.name.clone(),
+                        beat: beat.clone(),
+                        rule: rule.name.clone(),
+                        fact: fact.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    for story in stories {
+        let mut reported: Vec<String> = Vec::new();
+        for fact in effects(story).flat_map(written_facts) {
+            if !is_read(&fact) && !reported.contains(&fact) {
+                reported.push(fact.clone());
+                lints.push(StoryLint::UnreadFact {
+                    story: story.name.clone(),
+                    fact,
+                });
+            }
+        }
+    }
+    lints
+}
@@ -0,0 +1,23 @@
+use bevy::log::Level;
+use bevy::prelude::*;
+
+// Target of the pipeline's log lines and spans, so `RUST_LOG=beats=debug` narrows the log down
+// to the narrative: facts changing, rules flipping, beats finishing and what their effects do
+pub const BEATS_LOG_TARGET: &str = "beats";
+
+// How much the pipeline logs, before the log filter gets a say. Debug covers facts, rules,
+// beats and effects, trace adds what each effect asked for. By default the filter decides.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeatsLogLevel(pub Level);
+
+impl Default for BeatsLogLevel {
+    fn default() -> Self {
+        BeatsLogLevel(Level::TRACE)
+    }
+}
+
+impl BeatsLogLevel {
+    pub fn logs(&self, level: Level) -> bool {
+        level <= self.0
+    }
+}
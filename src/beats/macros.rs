@@ -0,0 +1,71 @@
+// Inline story definitions that expand straight into StoryBuilder calls.
+//
+// story! {
+//     "Hero's Journey" {
+//         meta "chapter" = "1"
+//         requires "Before We Start" { Condition::IntMoreThan { .. } }
+//         beat "The Call to Adventure" {
+//             rule "Enough Presses" { Condition::IntMoreThan { .. } }
+//             effects { set_fact_bool("quest_one_complete", true) }
+//             transition "The Road of Trials" { Condition::BoolEquals { .. } }
+//             choice "Go home" { set_fact_bool("went_home", true) }
+//             meta "mood" = "tense"
+//             weight 3
+//         }
+//     }
+// }
+//
+// Evaluates to the same Result<Story, RuleBuildError> as StoryBuilder::build.
+#[macro_export]
+macro_rules! story {
+    ($name:literal { $($body:tt)* }) => {
+        $crate::story!(@story $crate::prelude::StoryBuilder::new($name); $($body)*)
+    };
+
+    (@story $builder:expr;) => {
+        $builder.build()
+    };
+    (@story $builder:expr; requires $rule:literal { $($condition:expr),* $(,)? } $($rest:tt)*) => {
+        $crate::story!(@story $builder.add_pre_requisite($rule, |rule| {
+            rule $(.with_condition($condition))*
+        }); $($rest)*)
+    };
+    (@story $builder:expr; meta $key:literal = $value:literal $($rest:tt)*) => {
+        $crate::story!(@story $builder.with_metadata($key, $value); $($rest)*)
+    };
+    (@story $builder:expr; beat $beat:literal { $($body:tt)* } $($rest:tt)*) => {
+        $crate::story!(@story $builder.add_story_beat($beat, |beat| {
+            $crate::story!(@beat beat; $($body)*)
+        }); $($rest)*)
+    };
+
+    (@beat $builder:expr;) => {
+        $builder
+    };
+    (@beat $builder:expr; meta $key:literal = $value:literal $($rest:tt)*) => {
+        $crate::story!(@beat $builder.with_metadata($key, $value); $($rest)*)
+    };
+    (@beat $builder:expr; weight $weight:literal $($rest:tt)*) => {
+        $crate::story!(@beat $builder.with_weight($weight); $($rest)*)
+    };
+    (@beat $builder:expr; rule $rule:literal { $($condition:expr),* $(,)? } $($rest:tt)*) => {
+        $crate::story!(@beat $builder.with_rule($rule, |rule| {
+            rule $(.with_condition($condition))*
+        }); $($rest)*)
+    };
+    (@beat $builder:expr; transition $target:literal { $($condition:expr),* $(,)? } $($rest:tt)*) => {
+        $crate::story!(@beat $builder.transition_to($target, |rule| {
+            rule $(.with_condition($condition))*
+        }); $($rest)*)
+    };
+    (@beat $builder:expr; effects { $($effect:ident ( $($arg:expr),* $(,)? )),* $(,)? } $($rest:tt)*) => {
+        $crate::story!(@beat $builder.with_effects(|effects| {
+            effects $(.$effect($($arg),*))*
+        }); $($rest)*)
+    };
+    (@beat $builder:expr; choice $label:literal { $($effect:ident ( $($arg:expr),* $(,)? )),* $(,)? } $($rest:tt)*) => {
+        $crate::story!(@beat $builder.choice($label, |effects| {
+            effects $(.$effect($($arg),*))*
+        }); $($rest)*)
+    };
+}
@@ -1,10 +1,46 @@
+use crate::beats::achievements::{load_achievements, unlock_achievements, AchievementBackends, AchievementUnlocked, Achievements};
 use crate::beats::data::*;
+use crate::beats::choices::*;
+use crate::beats::debug::*;
+use crate::beats::errors::*;
+use crate::beats::fact_editor::{fact_editor_window, FactEditor};
+use crate::beats::fact_table::{load_fact_tables, report_fact_table_load_failures, restore_fact_tables, store_fact_tables, FactTable, FactTableFiles, FactTableLoader};
+use crate::beats::journal::{export_session_journal, record_session_journal, ExportSessionJournal, SessionJournal};
+use crate::beats::karma::{aggregate_karma, KarmaConfig, KarmaDecay};
+use crate::beats::logging::BeatsLogLevel;
+use crate::beats::new_game_plus::{start_new_game_plus, NewGamePlusPolicy, StartNewGamePlus};
+use crate::beats::relationships::{apply_affinity_changes, mirror_affinity_facts, Relationships};
+use crate::beats::rng::{apply_run_seed, RunSeed, StoryRng, StoryRngSeeded};
+use crate::beats::save::*;
+use crate::beats::save_location::SaveLocation;
+use crate::beats::signals::{signal_effects, signal_finished_beats, signal_rule_flips, Signals};
+use crate::beats::story_asset::*;
+use crate::beats::story_graph::story_graph_window;
+use crate::beats::story_time::{tick_story_time, StoryTime};
+use crate::beats::time_scale::{apply_time_scale, reset_time_scale, SlowMotion};
+use crate::beats::telemetry::record_story_telemetry;
+use crate::beats::watch::*;
+use crate::mini_games::resuming_from_mini_game;
+use crate::settings::{self, Settings};
 use crate::beats::systems::*;
 use crate::GameState;
-use bevy::app::{App, Plugin, Update};
-use bevy::prelude::{in_state, Component, IntoSystemConfigs, OnEnter, Commands, not, any_with_component, Query, Entity, With, Res, Time, PositionType, Val, Color};
+use bevy::app::{App, Plugin, Startup, Update};
+use bevy::asset::AssetApp;
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::{in_state, Component, SystemSet, IntoSystemConfigs, OnEnter, Commands, not, any_with_component, OnExit, Query, Entity, With, Res, Time, PositionType, Val, Color};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use crate::ui::animation;
+use crate::ui::confirm;
+use crate::ui::diagnostics_overlay;
+use crate::ui::announcements;
 use crate::ui::fps_widget;
+use crate::ui::photo::{self, photo_mode_inactive};
+use crate::ui::quest_log;
+use crate::ui::relationships_panel;
+use crate::ui::timeline;
+use crate::ui::toasts;
+use crate::ui::theme;
+use crate::ui::tutorial;
 use sickle_ui::{
     ui_builder::{UiBuilderExt, UiRoot},
     ui_commands::SetTextExt,
@@ -20,34 +56,239 @@ use sickle_ui::{
 use crate::ui::banner_widget::{BannerWidget, BannerWidgetCommands, BannerWidgetConfig, UiBannerWidgetExt};
 use crate::ui::fps_widget::{FpsWidget, UiFPSWidgetExt};
 
+pub mod achievements;
 pub mod data;
 pub mod systems;
-mod builders;
+pub mod builders;
+pub mod choices;
+pub mod debug;
+pub mod errors;
+pub mod macros;
+pub mod event_sourced;
+pub mod fact_editor;
+pub mod fact_table;
+pub mod headless;
+pub mod intern;
+pub mod journal;
+pub mod karma;
+pub mod lint;
+pub mod logging;
+pub mod new_game_plus;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub mod net_tcp;
+pub mod relationships;
+pub mod rng;
+pub mod rumble;
+pub mod save;
+pub mod save_format;
+pub mod save_location;
+pub mod scripting;
+pub mod signals;
+pub mod simulator;
+pub mod sorted;
+pub mod story_asset;
+pub mod story_graph;
+pub mod story_time;
+pub mod storage;
+pub mod telemetry;
+pub mod time_scale;
+pub mod watch;
 
+// The story engine on its own, without anything that needs a window, renderer or audio.
+// Headless apps and tests use this, the game adds it through StoryPlugin.
+pub struct StoryCorePlugin;
+
+// The story engine along with its UI and debug tools
 pub struct StoryPlugin;
 
-impl Plugin for StoryPlugin {
+// The systems that advance stories and apply beat effects
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoryProgression;
+
+// One pass of story progression: broadcast changed facts, evaluate stories, apply the effects
+// of finished beats. Run up to `StoryCascade::max_passes_per_frame` times a frame.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoryProgressionPass;
+
+// The part of a progression pass that evaluates stories and applies effects. Gated where the
+// game isn't the story authority, while changed facts are still broadcast.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoryEvaluation;
+
+impl Plugin for StoryCorePlugin {
     fn build(&self, app: &mut App) {
+        install_panic_hook();
         app.insert_resource(FactsOfTheWorld::new())
-            .add_plugins(WorldInspectorPlugin::new())
-            .add_plugins(fps_widget::plugin)
+            .init_resource::<Settings>()
+            .init_resource::<StoryRng>()
+            .init_resource::<RunSeed>()
+            .init_resource::<StoryRngSeeded>()
+            .init_resource::<StoryTime>()
+            .init_resource::<SaveMigrations>()
+            .init_resource::<SaveLocation>()
+            .init_resource::<Achievements>()
+            .init_resource::<Relationships>()
+            .init_resource::<KarmaConfig>()
+            .init_resource::<KarmaDecay>()
+            .init_resource::<SessionJournal>()
+            .init_resource::<NewGamePlusPolicy>()
+            .init_resource::<AchievementBackends>()
+            .init_resource::<StoryCascade>()
+            .init_resource::<Signals>()
+            .init_resource::<BeatsLogLevel>()
             .insert_resource(StoryEngine::new())
             .add_event::<FactUpdated>()
+            .add_event::<FactRemoved>()
+            .add_event::<FactWriteDenied>()
+            .add_event::<FactAliasUsed>()
             .add_event::<RuleUpdated>()
             .add_event::<StoryBeatFinished>()
+            .add_event::<NarrativeCycleDetected>()
+            .add_event::<RequestRuleExplanation>()
+            .add_event::<RuleExplanationReady>()
+            .add_event::<EngineError>()
+            .add_event::<EffectOutput>()
+            .add_event::<PresentChoices>()
+            .add_event::<ChoiceMade>()
+            .add_event::<SaveGameRequest>()
+            .add_event::<LoadGameRequest>()
+            .add_event::<AchievementUnlocked>()
+            .add_event::<StartNewGamePlus>()
+            .add_event::<ExportSessionJournal>()
+            .init_resource::<ErrorLog>()
+            .init_resource::<StoryFiles>()
+            .init_asset::<StoryAsset>()
+            .init_asset_loader::<StoryAssetLoader>()
+            .init_resource::<FactTableFiles>()
+            .init_asset::<FactTable>()
+            .init_asset_loader::<FactTableLoader>()
+            .init_schedule(StoryProgressionPass)
+            // Chained so beats finished in a pass have their effects applied in that same
+            // pass, in the order their stories were declared
+            .add_systems(
+                StoryProgressionPass,
+                (
+                    fact_update_event_broadcaster::<FactsOfTheWorld>,
+                    (
+                        story_evaluator::<FactsOfTheWorld>,
+                        story_beat_effect_applier::<FactsOfTheWorld>,
+                        apply_choices::<FactsOfTheWorld>,
+                    )
+                        .chain()
+                        .in_set(StoryEvaluation),
+                )
+                    .chain(),
+            )
+            // Effects carried out outside the engine write their facts after evaluation, so the
+            // next pass of the frame sees them
+            .add_systems(
+                StoryProgressionPass,
+                apply_affinity_changes.after(StoryEvaluation),
+            )
+            .add_systems(Startup, (load_story_files, load_achievements, load_fact_tables))
+            .add_systems(OnEnter(GameState::Story), restore_fact_tables)
+            .add_systems(
+                Update,
+                (
+                    register_loaded_stories,
+                    report_story_load_failures,
+                    store_fact_tables,
+                    report_fact_table_load_failures,
+                    report_panics.before(collect_engine_errors),
+                    collect_engine_errors,
+                    explain_rules,
+                    unlock_achievements,
+                    mirror_affinity_facts,
+                    start_new_game_plus,
+                    export_session_journal,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    tick_story_time.before(StoryProgression),
+                    apply_fact_aliases::<FactsOfTheWorld>
+                        .after(register_loaded_stories)
+                        .before(StoryProgression),
+                    // Before progression, while the rule that finishes a beat is still watched
+                    signal_rule_flips.before(StoryProgression),
+                    run_story_progression::<FactsOfTheWorld>.in_set(StoryProgression),
+                    aggregate_karma.after(StoryProgression),
+                    (signal_finished_beats, signal_effects).after(StoryProgression),
+                    record_story_telemetry,
+                    record_session_journal,
+                    save_game,
+                    load_game,
+                )
+                    .run_if(in_state(GameState::Story)),
+            );
+
+        #[cfg(feature = "net")]
+        {
+            app.add_plugins(net::ReplicationPlugin);
+        }
+        #[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+        {
+            app.add_plugins(net_tcp::TcpTransportPlugin);
+        }
+    }
+}
+
+impl Plugin for StoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(StoryCorePlugin)
+            .init_resource::<SlowMotion>()
+            .init_resource::<FactWatches>()
+            .init_resource::<FactEditor>()
+            .add_plugins(WorldInspectorPlugin::new().run_if(photo_mode_inactive))
+            .add_plugins(fps_widget::plugin)
+            .add_plugins(diagnostics_overlay::plugin)
+            .add_plugins(animation::plugin)
+            .add_plugins(theme::plugin)
+            .add_plugins(announcements::plugin)
+            .add_plugins(toasts::plugin)
+            .add_plugins(confirm::plugin)
+            .add_plugins(photo::plugin)
+            .add_plugins(tutorial::plugin)
+            .add_plugins(relationships_panel::plugin)
+            .add_plugins(quest_log::plugin)
+            .add_plugins(timeline::plugin)
+            .add_plugins(settings::plugin)
+            .add_event::<DebugCommand>()
+            .add_systems(
+                Update,
+                (
+                    show_error_screen,
+                    dismiss_error_screen,
+                    fact_watch_window.run_if(photo_mode_inactive),
+                    fact_editor_window.run_if(photo_mode_inactive),
+                    story_graph_window.run_if(photo_mode_inactive),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::Story),
+                setup_stories.run_if(not(resuming_from_mini_game)), //setup, spawn_layout, 
+            )
             .add_systems(
                 OnEnter(GameState::Story),
-                (setup_stories), //setup, spawn_layout, 
+                apply_run_seed.run_if(not(resuming_from_mini_game)),
             )
+            .add_systems(OnExit(GameState::Story), reset_time_scale)
             .add_systems(
                 Update,
                 (
-                    fact_update_event_broadcaster,
                     fact_event_system,
                     rule_event_system,
                     button_system,
-                    story_evaluator,
-                    story_beat_effect_applier
+                    spawn_choice_panel,
+                    choice_button_system,
+                    close_choice_panel,
+                    debug_command_system,
+                    ui_animation_facts,
+                    apply_time_scale.after(StoryProgression),
+                    save_load_keys,
                 )
                     .run_if(in_state(GameState::Story)),
             )
@@ -60,6 +301,21 @@ impl Plugin for StoryPlugin {
                     move_banner_example,
                 ).run_if(in_state(GameState::Story)))
         ;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.add_systems(
+                Update,
+                rumble::rumble_gamepads
+                    .after(StoryProgression)
+                    .run_if(in_state(GameState::Story)),
+            );
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            app.add_systems(Update, debug_command_keys.run_if(in_state(GameState::Story)));
+        }
     }
 }
 #[derive(Component)]
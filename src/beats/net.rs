@@ -0,0 +1,276 @@
+use crate::beats::builders::RESERVED_FACT_PREFIXES;
+use crate::beats::data::{
+    Fact, FactError, FactMutation, FactRemoved, FactUpdated, FactsOfTheWorld, StoryEngine,
+};
+use crate::beats::save::StoryProgress;
+use crate::beats::storage::{write_facts, FactStorage};
+use crate::beats::{StoryEvaluation, StoryProgressionPass};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+// Replicates fact mutations and story progress between a host and its clients.
+// The host is the authority: clients never evaluate stories themselves, they send
+// mutation requests and mirror whatever the host broadcasts.
+// Moving messages over the wire is left to a transport, which drains `ReplicationOutbox`,
+// fills `ReplicationInbox` and reports peers joining and leaving. `TcpTransportPlugin` is
+// the one shipped for native builds.
+pub struct ReplicationPlugin;
+
+impl Plugin for ReplicationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplicationOutbox>()
+            .init_resource::<ReplicationInbox>()
+            .add_event::<PeerConnected>()
+            .add_event::<PeerDisconnected>()
+            .add_event::<MutationRequestDenied>()
+            // Clients still run the rest of the pass, so the facts the host sends are drained
+            // and reported like any other change
+            .configure_sets(
+                StoryProgressionPass,
+                StoryEvaluation.run_if(has_story_authority),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        host_send_snapshots::<FactsOfTheWorld>,
+                        host_apply_mutation_requests::<FactsOfTheWorld>,
+                        host_broadcast_changes,
+                    )
+                        .chain()
+                        .run_if(resource_exists_and_equals(NetworkRole::Host)),
+                    client_apply_replication::<FactsOfTheWorld>
+                        .run_if(resource_exists_and_equals(NetworkRole::Client)),
+                )
+                    .run_if(in_state(GameState::Story)),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkRole {
+    Host,
+    Client,
+}
+
+// Identifies one end of the session. Clients only ever talk to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PeerId(pub u64);
+
+impl PeerId {
+    pub const HOST: PeerId = PeerId(0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    Everyone,
+    Peer(PeerId),
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerConnected(pub PeerId);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerDisconnected(pub PeerId);
+
+// Sent on a client when the host refused one of its mutation requests
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct MutationRequestDenied {
+    pub mutation: FactMutation,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReplicationMessage {
+    // Host to clients
+    FactChanged(Fact),
+    FactRemoved(String),
+    // Same progress a save keeps, finished beats included
+    StoryProgress(StoryProgress),
+    MutationDenied {
+        mutation: FactMutation,
+        reason: String,
+    },
+    // Client to host
+    RequestMutation(FactMutation),
+}
+
+impl ReplicationMessage {
+    pub fn encode(&self) -> Result<Vec<u8>, ron::Error> {
+        ron::to_string(self).map(String::into_bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ron::de::SpannedError> {
+        ron::de::from_bytes(bytes)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ReplicationOutbox(pub Vec<(Recipient, ReplicationMessage)>);
+
+impl ReplicationOutbox {
+    // What clients call instead of touching the store directly
+    pub fn request(&mut self, mutation: FactMutation) {
+        self.send(
+            Recipient::Peer(PeerId::HOST),
+            ReplicationMessage::RequestMutation(mutation),
+        );
+    }
+
+    pub fn send(&mut self, recipient: Recipient, message: ReplicationMessage) {
+        self.0.push((recipient, message));
+    }
+
+    pub fn broadcast(&mut self, message: ReplicationMessage) {
+        self.send(Recipient::Everyone, message);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ReplicationInbox(pub Vec<(PeerId, ReplicationMessage)>);
+
+impl ReplicationInbox {
+    pub fn receive(&mut self, from: PeerId, message: ReplicationMessage) {
+        self.0.push((from, message));
+    }
+}
+
+// Stories only progress where there is no network session, or on the host
+pub fn has_story_authority(role: Option<Res<NetworkRole>>) -> bool {
+    !matches!(role.as_deref(), Some(NetworkRole::Client))
+}
+
+// Catches a client that joins mid-session up with every fact and story
+pub fn host_send_snapshots<S: FactStorage + Resource>(
+    mut connected: EventReader<PeerConnected>,
+    storage: Res<S>,
+    story_engine: Res<StoryEngine>,
+    mut outbox: ResMut<ReplicationOutbox>,
+) {
+    for PeerConnected(peer) in connected.read() {
+        let recipient = Recipient::Peer(*peer);
+        for fact in storage.iter() {
+            outbox.send(recipient, ReplicationMessage::FactChanged(fact.clone()));
+        }
+        for story in story_engine.stories.iter() {
+            outbox.send(
+                recipient,
+                ReplicationMessage::StoryProgress(StoryProgress::capture(story)),
+            );
+        }
+    }
+}
+
+// Clients may only write facts the story could write itself: constants and facts under the
+// engine's reserved prefixes are refused, as are writes of the wrong type.
+pub fn check_requested_mutation<S: FactStorage>(
+    storage: &mut S,
+    mutation: FactMutation,
+) -> Result<(), FactError> {
+    let key = storage.resolve(mutation.key());
+    if RESERVED_FACT_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+    {
+        return Err(FactError::Reserved {
+            key: mutation.key().to_string(),
+        });
+    }
+    storage.apply_batch(vec![mutation])
+}
+
+pub fn host_apply_mutation_requests<S: FactStorage + Resource>(
+    mut inbox: ResMut<ReplicationInbox>,
+    mut storage: ResMut<S>,
+    mut outbox: ResMut<ReplicationOutbox>,
+) {
+    for (peer, message) in inbox.0.drain(..) {
+        match message {
+            ReplicationMessage::RequestMutation(mutation) => {
+                let checked = write_facts(&mut storage, |storage| {
+                    check_requested_mutation(storage, mutation.clone())
+                });
+                if let Err(error) = checked {
+                    warn!("Denied a mutation requested by {:?}: {}", peer, error);
+                    outbox.send(
+                        Recipient::Peer(peer),
+                        ReplicationMessage::MutationDenied {
+                            mutation,
+                            reason: error.to_string(),
+                        },
+                    );
+                }
+            }
+            other => warn!("Host ignoring unexpected replication message {:?}", other),
+        }
+    }
+}
+
+// Progress is compared with what was sent last rather than following finished beats, so
+// loading a save, New Game Plus and chapter select reach the clients too
+pub fn host_broadcast_changes(
+    mut fact_updated: EventReader<FactUpdated>,
+    mut fact_removed: EventReader<FactRemoved>,
+    story_engine: Res<StoryEngine>,
+    mut sent_progress: Local<HashMap<String, StoryProgress>>,
+    mut outbox: ResMut<ReplicationOutbox>,
+) {
+    for event in fact_updated.read() {
+        outbox.broadcast(ReplicationMessage::FactChanged(event.fact.clone()));
+    }
+    for event in fact_removed.read() {
+        outbox.broadcast(ReplicationMessage::FactRemoved(
+            event.previous.key().to_string(),
+        ));
+    }
+    for story in story_engine.stories.iter() {
+        let progress = StoryProgress::capture(story);
+        if sent_progress.get(&story.name) != Some(&progress) {
+            sent_progress.insert(story.name.clone(), progress.clone());
+            outbox.broadcast(ReplicationMessage::StoryProgress(progress));
+        }
+    }
+}
+
+pub fn client_apply_replication<S: FactStorage + Resource>(
+    mut inbox: ResMut<ReplicationInbox>,
+    mut storage: ResMut<S>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut denied: EventWriter<MutationRequestDenied>,
+) {
+    for (_, message) in inbox.0.drain(..) {
+        match message {
+            ReplicationMessage::FactChanged(fact) => {
+                // The host's value wins, even over a fact of another type the client still has
+                if storage
+                    .get(fact.key())
+                    .is_some_and(|stored| stored.type_name() != fact.type_name())
+                {
+                    storage.remove(fact.key());
+                }
+                storage.set(fact);
+            }
+            ReplicationMessage::FactRemoved(key) => {
+                storage.remove(&key);
+            }
+            ReplicationMessage::StoryProgress(progress) => {
+                if let Some(story) = story_engine
+                    .stories
+                    .iter_mut()
+                    .find(|story| story.name == progress.name)
+                {
+                    progress.restore(story);
+                } else {
+                    warn!("Host reported progress for unknown story {}", progress.name);
+                }
+            }
+            ReplicationMessage::MutationDenied { mutation, reason } => {
+                warn!("Host denied mutation of {}: {}", mutation.key(), reason);
+                denied.send(MutationRequestDenied { mutation, reason });
+            }
+            other => warn!("Client ignoring unexpected replication message {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::beats::net::{
+    NetworkRole, PeerConnected, PeerDisconnected, PeerId, Recipient, ReplicationInbox,
+    ReplicationMessage, ReplicationOutbox,
+};
+use bevy::prelude::*;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+// Carries replication messages over plain TCP on native builds. Each message is framed as a
+// big-endian u32 length followed by the RON-encoded message. The host accepts any number of
+// clients; a client is connected to exactly one host, which it knows as `PeerId::HOST`.
+// Written by hand rather than on renet or matchbox so the `net` feature adds no dependencies
+// and the replication plugin stays transport agnostic; either can be added as another plugin
+// filling the same inbox and outbox. There is no reconnect: a dropped client inserts a new
+// `TcpTransport::connect` and the host sends it a fresh snapshot.
+pub struct TcpTransportPlugin;
+
+impl Plugin for TcpTransportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            receive_tcp_messages.run_if(resource_exists::<TcpTransport>),
+        )
+        .add_systems(
+            PostUpdate,
+            send_tcp_messages.run_if(resource_exists::<TcpTransport>),
+        );
+    }
+}
+
+// Frames larger than this are treated as a broken connection rather than allocated, and are
+// never sent, so one oversized fact can't get a client dropped on every connect
+const MAX_FRAME_LEN: usize = 1 << 20;
+
+struct TcpPeer {
+    id: PeerId,
+    stream: TcpStream,
+    received: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl TcpPeer {
+    fn new(id: PeerId, stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(TcpPeer {
+            id,
+            stream,
+            received: Vec::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    fn queue(&mut self, frame: &[u8]) {
+        self.pending
+            .extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        self.pending.extend_from_slice(frame);
+    }
+
+    // Writes as much of the queued data as the socket takes without blocking
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            match self.stream.write(&self.pending) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.pending.drain(..written);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    // Reads whatever arrived and returns the messages that are now complete
+    fn receive(&mut self) -> io::Result<Vec<ReplicationMessage>> {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(read) => self.received.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+        let mut messages = Vec::new();
+        while self.received.len() >= 4 {
+            let len = u32::from_be_bytes([
+                self.received[0],
+                self.received[1],
+                self.received[2],
+                self.received[3],
+            ]) as usize;
+            if len > MAX_FRAME_LEN {
+                return Err(io::Error::new(ErrorKind::InvalidData, "frame too large"));
+            }
+            if self.received.len() < 4 + len {
+                break;
+            }
+            let frame: Vec<u8> = self.received.drain(..4 + len).skip(4).collect();
+            match ReplicationMessage::decode(&frame) {
+                Ok(message) => messages.push(message),
+                Err(error) => warn!("Dropped an unreadable message from {:?}: {}", self.id, error),
+            }
+        }
+        Ok(messages)
+    }
+}
+
+#[derive(Resource)]
+pub struct TcpTransport {
+    listener: Option<TcpListener>,
+    peers: Vec<TcpPeer>,
+    next_peer: u64,
+}
+
+impl TcpTransport {
+    // Listens for clients on the address. Insert it together with `NetworkRole::Host`.
+    pub fn host(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(TcpTransport {
+            listener: Some(listener),
+            peers: Vec::new(),
+            next_peer: PeerId::HOST.0 + 1,
+        })
+    }
+
+    // Connects to a host. Insert it together with `NetworkRole::Client`.
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        Ok(TcpTransport {
+            listener: None,
+            peers: vec![TcpPeer::new(PeerId::HOST, stream)?],
+            next_peer: PeerId::HOST.0 + 1,
+        })
+    }
+
+    // Where the host is listening, useful when it was bound to port 0
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = PeerId> + '_ {
+        self.peers.iter().map(|peer| peer.id)
+    }
+}
+
+pub fn receive_tcp_messages(
+    mut transport: ResMut<TcpTransport>,
+    role: Option<Res<NetworkRole>>,
+    mut inbox: ResMut<ReplicationInbox>,
+    mut connected: EventWriter<PeerConnected>,
+    mut disconnected: EventWriter<PeerDisconnected>,
+) {
+    let transport = transport.as_mut();
+    if let Some(listener) = transport.listener.as_ref() {
+        loop {
+            match listener.accept() {
+                Ok((stream, address)) => {
+                    let id = PeerId(transport.next_peer);
+                    match TcpPeer::new(id, stream) {
+                        Ok(peer) => {
+                            transport.next_peer += 1;
+                            info!("Peer {:?} connected from {}", id, address);
+                            transport.peers.push(peer);
+                            connected.send(PeerConnected(id));
+                        }
+                        Err(error) => warn!("Could not accept {}: {}", address, error),
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    warn!("Could not accept a peer: {}", error);
+                    break;
+                }
+            }
+        }
+    }
+    let is_host = matches!(role.as_deref(), Some(NetworkRole::Host));
+    transport.peers.retain_mut(|peer| match peer.receive() {
+        Ok(messages) => {
+            for message in messages {
+                // Only the host may tell anyone what the facts are
+                if !is_host || matches!(message, ReplicationMessage::RequestMutation(_)) {
+                    inbox.receive(peer.id, message);
+                } else {
+                    warn!("Dropped a host-only message from {:?}", peer.id);
+                }
+            }
+            true
+        }
+        Err(error) => {
+            info!("Peer {:?} disconnected: {}", peer.id, error);
+            disconnected.send(PeerDisconnected(peer.id));
+            false
+        }
+    });
+}
+
+pub fn send_tcp_messages(
+    mut transport: ResMut<TcpTransport>,
+    mut outbox: ResMut<ReplicationOutbox>,
+    mut disconnected: EventWriter<PeerDisconnected>,
+) {
+    for (recipient, message) in outbox.0.drain(..) {
+        let frame = match message.encode() {
+            Ok(frame) => frame,
+            Err(error) => {
+                warn!("Could not encode {:?}: {}", message, error);
+                continue;
+            }
+        };
+        if frame.len() > MAX_FRAME_LEN {
+            warn!(
+                "Dropped a message of {} bytes, more than the {} a peer accepts",
+                frame.len(),
+                MAX_FRAME_LEN
+            );
+            continue;
+        }
+        for peer in transport.peers.iter_mut() {
+            if recipient == Recipient::Everyone || recipient == Recipient::Peer(peer.id) {
+                peer.queue(&frame);
+            }
+        }
+    }
+    transport.peers.retain_mut(|peer| match peer.flush() {
+        Ok(()) => true,
+        Err(error) => {
+            info!("Peer {:?} disconnected: {}", peer.id, error);
+            disconnected.send(PeerDisconnected(peer.id));
+            false
+        }
+    });
+}
@@ -0,0 +1,112 @@
+use crate::beats::data::{Fact, FactsOfTheWorld, StoryEngine};
+use crate::beats::rng::{RunSeed, StoryRng, StoryRngSeeded};
+use crate::beats::storage::FactStorage;
+use crate::beats::story_time::StoryTime;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// What survives into a fresh run when the player starts over after an ending. Achievements
+// live outside the facts and always carry over, everything not listed here starts over.
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NewGamePlusPolicy {
+    // Facts kept as they are
+    pub keep_facts: Vec<String>,
+    // Every fact with one of these tags is kept, unlocks by default
+    pub keep_tags: Vec<String>,
+    // Stories that keep their progress instead of starting from the first beat
+    pub keep_stories: Vec<String>,
+    // Int fact counting the runs started this way, so stories can react to a second playthrough
+    pub cycle_fact: Option<String>,
+}
+
+impl Default for NewGamePlusPolicy {
+    fn default() -> Self {
+        NewGamePlusPolicy {
+            keep_facts: Vec::new(),
+            keep_tags: vec!["unlock".to_string()],
+            keep_stories: Vec::new(),
+            cycle_fact: Some("new_game_plus".to_string()),
+        }
+    }
+}
+
+impl NewGamePlusPolicy {
+    pub fn keeps_fact(&self, facts: &FactsOfTheWorld, key: &str) -> bool {
+        self.keep_facts.iter().any(|kept| kept == key)
+            || self.cycle_fact.as_deref() == Some(key)
+            // Constants are set up by the game, not earned during a run
+            || facts.is_constant(key)
+            || self.keep_tags.iter().any(|tag| {
+                facts
+                    .tags
+                    .get(tag)
+                    .is_some_and(|keys| keys.contains(key))
+            })
+    }
+
+    // Clears progress the policy doesn't keep. Facts are reset to the empty value of their
+    // type, the same way `Effect::ClearTag` does, so rules and UI see the change.
+    pub fn apply(
+        &self,
+        facts: &mut FactsOfTheWorld,
+        story_engine: &mut StoryEngine,
+        time: &mut StoryTime,
+    ) {
+        let cleared: Vec<Fact> = facts
+            .facts
+            .values()
+            .filter(|fact| !self.keeps_fact(facts, fact.key()))
+            .map(Fact::cleared)
+            .collect();
+        for fact in cleared {
+            facts.set(fact);
+        }
+        if let Some(cycle_fact) = &self.cycle_fact {
+            let cycle = facts.get_int(cycle_fact).copied().unwrap_or_default();
+            if let Err(error) = facts.store_int(cycle_fact.clone(), cycle.saturating_add(1)) {
+                warn!("New Game Plus cycle not counted: {}", error);
+            }
+        }
+
+        for story in story_engine
+            .stories
+            .iter_mut()
+            .filter(|story| !self.keep_stories.contains(&story.name))
+        {
+            story.is_started = false;
+            story.active_beat_index = 0;
+            for beat in story.beats.iter_mut() {
+                beat.finished = false;
+            }
+        }
+
+        // The clock starts over too
+        time.replace_keeping_pause(StoryTime::default());
+    }
+}
+
+// Starts a fresh run that keeps what the `NewGamePlusPolicy` allows. Sent by the menu once an
+// ending was reached; the sender also switches to the story state.
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct StartNewGamePlus;
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_new_game_plus(
+    mut requests: EventReader<StartNewGamePlus>,
+    policy: Res<NewGamePlusPolicy>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut story_time: ResMut<StoryTime>,
+    run_seed: Res<RunSeed>,
+    mut rng: ResMut<StoryRng>,
+    mut seeded: ResMut<StoryRngSeeded>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    policy.apply(&mut facts, &mut story_engine, &mut story_time);
+    // The new run rolls from the seed shown in the menu, not where the last one left off
+    *rng = run_seed.rng();
+    seeded.0 = true;
+}
@@ -0,0 +1,65 @@
+use crate::beats::data::{EffectOutput, Fact, FactUpdated, FactsOfTheWorld};
+use crate::beats::storage::{write_facts, FactStorage};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+// Affinity is stored as an int fact per character, so rules can gate branches on it
+pub const AFFINITY_FACT_PREFIX: &str = "affinity.";
+pub const MIN_AFFINITY: i32 = -100;
+pub const MAX_AFFINITY: i32 = 100;
+
+pub fn affinity_fact(character: &str) -> String {
+    format!("{}{}", AFFINITY_FACT_PREFIX, character)
+}
+
+// How much the player is liked by each named character, kept in step with the affinity facts
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Relationships {
+    pub affinity: HashMap<String, i32>,
+}
+
+impl Relationships {
+    // Characters the player hasn't dealt with yet are neutral
+    pub fn affinity(&self, character: &str) -> i32 {
+        self.affinity.get(character).copied().unwrap_or_default()
+    }
+}
+
+pub fn mirror_affinity_facts(
+    mut fact_updates: EventReader<FactUpdated>,
+    mut relationships: ResMut<Relationships>,
+) {
+    for update in fact_updates.read() {
+        if let Fact::Int(key, value) = &update.fact {
+            if let Some(character) = key.strip_prefix(AFFINITY_FACT_PREFIX) {
+                relationships.affinity.insert(character.to_string(), *value);
+            }
+        }
+    }
+}
+
+// Carries out `Effect::ChangeAffinity`, keeping affinity within MIN_AFFINITY..=MAX_AFFINITY
+pub fn apply_affinity_changes(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::ChangeAffinity { character, amount } = output else {
+            continue;
+        };
+        let key = affinity_fact(character);
+        let current = match facts.get(&key) {
+            Some(Fact::Int(_, value)) => *value,
+            _ => 0,
+        };
+        let fact = Fact::Int(
+            key,
+            current
+                .saturating_add(*amount)
+                .clamp(MIN_AFFINITY, MAX_AFFINITY),
+        );
+        if let Err(error) = write_facts(&mut facts, |facts| facts.try_set(fact)) {
+            warn!("Could not change how {} feels: {}", character, error);
+        }
+    }
+}
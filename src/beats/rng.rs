@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// The one source of randomness for stories. SplitMix64, so the whole state is a single number
+// that can be saved and restored to make random outcomes reproducible across save/load.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StoryRng {
+    seed: u64,
+    state: u64,
+}
+
+impl StoryRng {
+    pub fn new(seed: u64) -> Self {
+        StoryRng { seed, state: seed }
+    }
+
+    pub fn from_entropy() -> Self {
+        Self::new(rand::random())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Uniform in [0, bound), bound must not be zero
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
+
+impl Default for StoryRng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+const SEED_ADJECTIVES: &[&str] = &[
+    "salty", "brave", "sleepy", "crusty", "jolly", "misty", "rusty", "sunny", "stormy", "quiet",
+    "lucky", "grumpy", "tidal", "briny", "sandy", "windy",
+];
+const SEED_NOUNS: &[&str] = &[
+    "barnacle", "gull", "anchor", "harbour", "lantern", "crab", "kelp", "buoy", "oyster", "net",
+    "trawler", "pier", "squid", "rope", "tide", "whale",
+];
+
+// The seed of a run as words players can read out and share. Any text works as a seed, so a
+// friend's seed can be typed in to play the same run.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RunSeed {
+    pub text: String,
+}
+
+impl RunSeed {
+    pub fn new(text: impl Into<String>) -> Self {
+        RunSeed { text: text.into() }
+    }
+
+    // Something like "salty-barnacle-42"
+    pub fn generate() -> Self {
+        let mut rng = StoryRng::from_entropy();
+        RunSeed::new(format!(
+            "{}-{}-{}",
+            SEED_ADJECTIVES[rng.below(SEED_ADJECTIVES.len())],
+            SEED_NOUNS[rng.below(SEED_NOUNS.len())],
+            rng.below(100)
+        ))
+    }
+
+    // FNV-1a over the trimmed, lowercased text. The std hasher isn't stable between Rust
+    // versions, and the same text has to give the same run everywhere.
+    pub fn seed(&self) -> u64 {
+        self.text
+            .trim()
+            .to_lowercase()
+            .bytes()
+            .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+            })
+    }
+
+    pub fn rng(&self) -> StoryRng {
+        StoryRng::new(self.seed())
+    }
+}
+
+impl Default for RunSeed {
+    fn default() -> Self {
+        Self::generate()
+    }
+}
+
+// Set once the rng of the current run is in place, whether from the run seed, a save, a chapter
+// start or New Game Plus, so coming back to the story doesn't roll it again
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoryRngSeeded(pub bool);
+
+// New games start from the run seed, saves bring their own rng state
+pub fn apply_run_seed(
+    run_seed: Res<RunSeed>,
+    mut rng: ResMut<StoryRng>,
+    mut seeded: ResMut<StoryRngSeeded>,
+) {
+    if seeded.0 {
+        return;
+    }
+    *rng = run_seed.rng();
+    seeded.0 = true;
+}
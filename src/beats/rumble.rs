@@ -0,0 +1,31 @@
+use crate::beats::data::{EffectOutput, RumbleIntensity};
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+
+// Passes `Effect::Rumble` on to every connected gamepad. Browsers can't rumble, so this
+// isn't added on the web.
+pub fn rumble_gamepads(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+    gamepads: Res<Gamepads>,
+) {
+    for output in effect_outputs.read() {
+        if let EffectOutput::Rumble {
+            intensity: RumbleIntensity(intensity),
+            duration,
+        } = output
+        {
+            let intensity = intensity.clamp(0.0, 1.0);
+            for gamepad in gamepads.iter() {
+                rumble_requests.send(GamepadRumbleRequest::Add {
+                    gamepad,
+                    duration: *duration,
+                    intensity: GamepadRumbleIntensity {
+                        strong_motor: intensity,
+                        weak_motor: intensity,
+                    },
+                });
+            }
+        }
+    }
+}
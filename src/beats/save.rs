@@ -0,0 +1,296 @@
+use crate::beats::data::{default_story_version, Fact, FactsOfTheWorld, Story, StoryEngine};
+use crate::beats::errors::{recover, EngineError};
+use crate::beats::rng::{StoryRng, StoryRngSeeded};
+use crate::beats::save_location::{backup_name, SaveLocation};
+use crate::beats::sorted;
+use crate::beats::story_time::StoryTime;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+pub const SAVE_FILE: &str = "save.ron";
+
+// How far a story has come. Story definitions come from code and story files, so only progress is saved.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StoryProgress {
+    pub name: String,
+    // Version of the story definition the progress was saved against
+    #[serde(default = "default_story_version")]
+    pub version: u32,
+    pub is_started: bool,
+    pub active_beat_index: usize,
+    // Preferred over the index when restoring, so reordered beats don't desync
+    #[serde(default)]
+    pub active_beat: Option<String>,
+    // Beats finished on the way here. Saves from before this was stored count the beats ahead
+    // of the active one as finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_beats: Option<Vec<String>>,
+}
+
+impl StoryProgress {
+    pub fn capture(story: &Story) -> Self {
+        StoryProgress {
+            name: story.name.clone(),
+            version: story.version,
+            is_started: story.is_started,
+            active_beat_index: story.active_beat_index,
+            active_beat: story.active_beat().map(|beat| beat.name.clone()),
+            finished_beats: Some(
+                story
+                    .beats
+                    .iter()
+                    .filter(|beat| beat.finished)
+                    .map(|beat| beat.name.clone())
+                    .collect(),
+            ),
+        }
+    }
+
+    // Puts the story where the progress says, the story must have the same name
+    pub fn restore(&self, story: &mut Story) {
+        story.is_started = self.is_started;
+        story.active_beat_index = match self
+            .active_beat
+            .as_deref()
+            .and_then(|beat| story.beat_index(beat))
+        {
+            Some(index) => index,
+            None => self.active_beat_index.min(story.beats.len()),
+        };
+        let active_beat_index = story.active_beat_index;
+        for (index, beat) in story.beats.iter_mut().enumerate() {
+            beat.finished = match self.finished_beats.as_ref() {
+                Some(finished) => finished.contains(&beat.name),
+                None => index < active_beat_index,
+            };
+        }
+    }
+}
+
+// Saves written before versioning count as the first version
+fn default_save_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SaveGame {
+    #[serde(default = "default_save_version")]
+    pub version: u32,
+    #[serde(serialize_with = "sorted::map")]
+    pub facts: HashMap<String, Fact>,
+    pub stories: Vec<StoryProgress>,
+    #[serde(default)]
+    pub rng: StoryRng,
+    #[serde(default)]
+    pub time: StoryTime,
+}
+
+impl SaveGame {
+    pub fn capture(
+        version: u32,
+        facts: &FactsOfTheWorld,
+        story_engine: &StoryEngine,
+        rng: &StoryRng,
+        time: &StoryTime,
+    ) -> Self {
+        SaveGame {
+            version,
+            facts: facts.facts.clone(),
+            stories: story_engine
+                .stories
+                .iter()
+                .map(StoryProgress::capture)
+                .collect(),
+            rng: rng.clone(),
+            time: time.clone(),
+        }
+    }
+
+    pub fn restore(
+        self,
+        facts: &mut FactsOfTheWorld,
+        story_engine: &mut StoryEngine,
+        rng: &mut StoryRng,
+        time: &mut StoryTime,
+    ) {
+        facts.replace_all(self.facts);
+        *rng = self.rng;
+        time.replace_keeping_pause(self.time);
+        for progress in self.stories {
+            if let Some(story) = story_engine
+                .stories
+                .iter_mut()
+                .find(|story| story.name == progress.name)
+            {
+                if story.version != progress.version {
+                    warn!(
+                        "Story {} was saved at version {} but is now version {}",
+                        story.name, progress.version, story.version
+                    );
+                }
+                progress.restore(story);
+            } else {
+                warn!("Save contains progress for unknown story {}", progress.name);
+            }
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, ron::de::SpannedError> {
+        ron::from_str(source)
+    }
+}
+
+type Migration = Box<dyn Fn(&mut SaveGame) + Send + Sync>;
+
+// Upgrades older saves step by step until they match `current_version`. Games bump the
+// version when beats or facts change between releases and register how to get there.
+#[derive(Resource)]
+pub struct SaveMigrations {
+    pub current_version: u32,
+    migrations: HashMap<u32, (u32, Migration)>,
+}
+
+impl Default for SaveMigrations {
+    fn default() -> Self {
+        SaveMigrations {
+            current_version: default_save_version(),
+            migrations: HashMap::new(),
+        }
+    }
+}
+
+impl SaveMigrations {
+    pub fn register_migration(
+        &mut self,
+        from: u32,
+        to: u32,
+        migrate: impl Fn(&mut SaveGame) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.migrations.insert(from, (to, Box::new(migrate)));
+        self
+    }
+
+    pub fn migrate(&self, save: &mut SaveGame) -> Result<(), String> {
+        while save.version < self.current_version {
+            let Some((to, migrate)) = self.migrations.get(&save.version) else {
+                return Err(format!(
+                    "no migration from save version {} towards {}",
+                    save.version, self.current_version
+                ));
+            };
+            if *to <= save.version {
+                return Err(format!(
+                    "migration from save version {} goes back to {}",
+                    save.version, to
+                ));
+            }
+            migrate(save);
+            save.version = *to;
+        }
+        if save.version > self.current_version {
+            return Err(format!(
+                "save version {} is newer than this game's version {}",
+                save.version, self.current_version
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Event)]
+pub struct SaveGameRequest;
+
+#[derive(Event)]
+pub struct LoadGameRequest;
+
+pub fn save_load_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut save_requests: EventWriter<SaveGameRequest>,
+    mut load_requests: EventWriter<LoadGameRequest>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        save_requests.send(SaveGameRequest);
+    }
+    if keyboard_input.just_pressed(KeyCode::F8) {
+        load_requests.send(LoadGameRequest);
+    }
+}
+
+pub fn save_game(
+    mut requests: EventReader<SaveGameRequest>,
+    facts: Res<FactsOfTheWorld>,
+    story_engine: Res<StoryEngine>,
+    rng: Res<StoryRng>,
+    story_time: Res<StoryTime>,
+    migrations: Res<SaveMigrations>,
+    location: Res<SaveLocation>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    let save = SaveGame::capture(
+        migrations.current_version,
+        &facts,
+        &story_engine,
+        &rng,
+        &story_time,
+    );
+    match save.to_ron() {
+        Ok(source) => location.write(SAVE_FILE, &source),
+        Err(error) => warn!("Could not serialize save: {}", error),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn load_game(
+    mut requests: EventReader<LoadGameRequest>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut rng: ResMut<StoryRng>,
+    mut seeded: ResMut<StoryRngSeeded>,
+    mut story_time: ResMut<StoryTime>,
+    migrations: Res<SaveMigrations>,
+    location: Res<SaveLocation>,
+    mut errors: EventWriter<EngineError>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    // Migrations are game code run on old data, a panic in one counts as a bad save
+    let load = |name: &str| {
+        location.read(name).and_then(|source| {
+            recover(|| {
+                let mut save = SaveGame::from_ron(&source).map_err(|error| error.to_string())?;
+                migrations.migrate(&mut save)?;
+                Ok(save)
+            })?
+        })
+    };
+    let save = match load(SAVE_FILE) {
+        Ok(save) => save,
+        // Fall back to the save that was replaced last time, if there is one
+        Err(message) => match load(&backup_name(SAVE_FILE)) {
+            Ok(save) => {
+                errors.send(EngineError::SaveCorrupted {
+                    path: SAVE_FILE.to_string(),
+                    message,
+                });
+                save
+            }
+            Err(_) => {
+                errors.send(EngineError::SaveLoad {
+                    path: SAVE_FILE.to_string(),
+                    message,
+                });
+                return;
+            }
+        },
+    };
+    save.restore(&mut facts, &mut story_engine, &mut rng, &mut story_time);
+    seeded.0 = true;
+}
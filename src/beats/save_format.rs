@@ -0,0 +1,103 @@
+// On-disk layout of save files: the RON payload, deflated when the `compressed-saves`
+// feature is on, followed by a footer of magic bytes, flags and a CRC32 of the payload.
+// Files without the footer are read as plain RON so saves from older builds still load.
+
+const MAGIC: &[u8; 4] = b"BBSV";
+const FLAG_COMPRESSED: u8 = 1;
+const FOOTER_LEN: usize = MAGIC.len() + 1 + 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveFormatError {
+    ChecksumMismatch { expected: u32, actual: u32 },
+    UnknownFlags(u8),
+    Decompress(String),
+    NotUtf8,
+}
+
+impl std::fmt::Display for SaveFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveFormatError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "save corrupted, checksum {:08x} does not match {:08x}",
+                actual, expected
+            ),
+            SaveFormatError::UnknownFlags(flags) => {
+                write!(f, "save written with unsupported flags {:#04x}", flags)
+            }
+            SaveFormatError::Decompress(message) => {
+                write!(f, "save corrupted, could not decompress: {}", message)
+            }
+            SaveFormatError::NotUtf8 => write!(f, "save corrupted, contents are not text"),
+        }
+    }
+}
+
+pub fn encode(source: &str) -> Vec<u8> {
+    let (mut bytes, flags) = match compress(source.as_bytes()) {
+        Some(compressed) => (compressed, FLAG_COMPRESSED),
+        None => (source.as_bytes().to_vec(), 0),
+    };
+    let checksum = crc32fast::hash(&bytes);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(flags);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<String, SaveFormatError> {
+    let Some((payload, footer)) = split_footer(bytes) else {
+        return String::from_utf8(bytes.to_vec()).map_err(|_| SaveFormatError::NotUtf8);
+    };
+    let flags = footer[MAGIC.len()];
+    let expected = u32::from_le_bytes(footer[MAGIC.len() + 1..].try_into().unwrap());
+    let actual = crc32fast::hash(payload);
+    if actual != expected {
+        return Err(SaveFormatError::ChecksumMismatch { expected, actual });
+    }
+    let payload = match flags {
+        0 => payload.to_vec(),
+        FLAG_COMPRESSED => decompress(payload)?,
+        other => return Err(SaveFormatError::UnknownFlags(other)),
+    };
+    String::from_utf8(payload).map_err(|_| SaveFormatError::NotUtf8)
+}
+
+fn split_footer(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    if bytes.len() < FOOTER_LEN {
+        return None;
+    }
+    let (payload, footer) = bytes.split_at(bytes.len() - FOOTER_LEN);
+    footer.starts_with(MAGIC).then_some((payload, footer))
+}
+
+#[cfg(feature = "compressed-saves")]
+fn compress(bytes: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
+    let mut encoder =
+        flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).ok()?;
+    encoder.finish().ok()
+}
+
+#[cfg(not(feature = "compressed-saves"))]
+fn compress(_bytes: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(feature = "compressed-saves")]
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>, SaveFormatError> {
+    use std::io::Read;
+    let mut decompressed = Vec::new();
+    flate2::read::DeflateDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(|error| SaveFormatError::Decompress(error.to_string()))?;
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "compressed-saves"))]
+fn decompress(_bytes: &[u8]) -> Result<Vec<u8>, SaveFormatError> {
+    Err(SaveFormatError::Decompress(
+        "built without the compressed-saves feature".to_string(),
+    ))
+}
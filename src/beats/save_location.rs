@@ -0,0 +1,190 @@
+use crate::beats::save_format;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+// Where save files end up. Natively that's the per-user data directory for the game, so
+// installs in read-only or synced folders (itch, Steam) keep working; browsers use local storage.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SaveLocation {
+    pub qualifier: String,
+    pub organization: String,
+    pub application: String,
+    // Replaces the data directory, e.g. to point saves at a synced folder
+    pub path: Option<String>,
+    // Put in front of every local storage key on the web
+    pub web_key_prefix: String,
+    // Where saves were kept before they moved to the data directory. A file missing from the
+    // save directory is moved over from here the first time it's read.
+    pub legacy_path: Option<String>,
+}
+
+impl Default for SaveLocation {
+    fn default() -> Self {
+        SaveLocation {
+            qualifier: "com".to_string(),
+            organization: "lavaeater".to_string(),
+            application: "Barnacle Beats".to_string(),
+            path: None,
+            web_key_prefix: "barnacle_beats/".to_string(),
+            legacy_path: Some(".".to_string()),
+        }
+    }
+}
+
+// Where the previous version of a save is kept when it gets overwritten
+pub fn backup_name(name: &str) -> String {
+    format!("{}.bak", name)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveLocation {
+    pub fn directory(&self) -> PathBuf {
+        if let Some(path) = &self.path {
+            return PathBuf::from(path);
+        }
+        match directories::ProjectDirs::from(&self.qualifier, &self.organization, &self.application)
+        {
+            Some(dirs) => dirs.data_dir().to_path_buf(),
+            // No home directory to speak of, keep saves next to the game
+            None => PathBuf::from("."),
+        }
+    }
+
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.directory().join(name)
+    }
+
+    pub fn write(&self, name: &str, source: &str) {
+        let path = self.file(name);
+        if let Err(error) = std::fs::create_dir_all(self.directory()) {
+            warn!("Could not create save directory for {}: {}", name, error);
+        }
+        // Keep the previous save around in case this one ends up truncated or corrupted, one
+        // left in the legacy location included
+        self.find(name);
+        if path.exists() {
+            if let Err(error) = std::fs::rename(&path, self.file(&backup_name(name))) {
+                warn!("Could not back up save {}: {}", path.display(), error);
+            }
+        }
+        if let Err(error) = std::fs::write(&path, save_format::encode(source)) {
+            warn!("Could not write save {}: {}", path.display(), error);
+        }
+    }
+
+    // Writes plain text meant for people rather than the game, like reports to share.
+    // Returns where it ended up.
+    pub fn export(&self, name: &str, contents: &str) -> Result<String, String> {
+        let path = self.file(name);
+        std::fs::create_dir_all(self.directory()).map_err(|error| error.to_string())?;
+        std::fs::write(&path, contents).map_err(|error| error.to_string())?;
+        Ok(path.display().to_string())
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.find(name).exists()
+    }
+
+    pub fn read(&self, name: &str) -> Result<String, String> {
+        let bytes = std::fs::read(self.find(name)).map_err(|error| error.to_string())?;
+        save_format::decode(&bytes).map_err(|error| error.to_string())
+    }
+
+    // The file to read, moving one left in the legacy location into the save directory if the
+    // directory has none. A file that can't be moved is read where it is.
+    fn find(&self, name: &str) -> PathBuf {
+        let path = self.file(name);
+        let Some(legacy) = self
+            .legacy_path
+            .as_ref()
+            .map(|dir| PathBuf::from(dir).join(name))
+        else {
+            return path;
+        };
+        if path.exists() || !legacy.is_file() {
+            return path;
+        }
+        let moved = std::fs::create_dir_all(self.directory())
+            .and_then(|_| std::fs::copy(&legacy, &path))
+            .and_then(|_| std::fs::remove_file(&legacy));
+        match moved {
+            Ok(()) => {
+                info!("Moved {} to {}", legacy.display(), path.display());
+                path
+            }
+            Err(error) => {
+                warn!(
+                    "Could not move {} to {}: {}",
+                    legacy.display(),
+                    path.display(),
+                    error
+                );
+                legacy
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SaveLocation {
+    pub fn key(&self, name: &str) -> String {
+        format!("{}{}", self.web_key_prefix, name)
+    }
+
+    fn storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| "local storage is not available".to_string())
+    }
+
+    pub fn write(&self, name: &str, source: &str) {
+        let result = Self::storage().and_then(|storage| {
+            if let Ok(Some(previous)) = storage.get_item(&self.key(name)) {
+                let _ = storage.set_item(&self.key(&backup_name(name)), &previous);
+            }
+            // Local storage only holds strings, so the encoded bytes are stored as hex
+            let bytes = save_format::encode(source);
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            storage
+                .set_item(&self.key(name), &hex)
+                .map_err(|_| "local storage is full".to_string())
+        });
+        if let Err(error) = result {
+            warn!("Could not write save {}: {}", self.key(name), error);
+        }
+    }
+
+    // Writes plain text meant for people rather than the game, like reports to share.
+    // Returns where it ended up.
+    pub fn export(&self, name: &str, contents: &str) -> Result<String, String> {
+        Self::storage()?
+            .set_item(&self.key(name), contents)
+            .map_err(|_| "local storage is full".to_string())?;
+        Ok(self.key(name))
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        Self::storage()
+            .is_ok_and(|storage| matches!(storage.get_item(&self.key(name)), Ok(Some(_))))
+    }
+
+    pub fn read(&self, name: &str) -> Result<String, String> {
+        let hex = Self::storage()?
+            .get_item(&self.key(name))
+            .ok()
+            .flatten()
+            .ok_or_else(|| format!("no save stored under {}", self.key(name)))?;
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|index| {
+                hex.get(index..index + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| "save corrupted, stored data is not hex".to_string())?;
+        save_format::decode(&bytes).map_err(|error| error.to_string())
+    }
+}
@@ -0,0 +1,190 @@
+use crate::beats::data::{EvaluationContext, Fact, FactError};
+use crate::beats::storage::FactStorage;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+
+// Scripts see every fact whose key is a plain identifier as a variable of the same name,
+// and all facts through the `facts` map (`facts["player.health"]`). Condition scripts also
+// get the story clock in seconds as `now`.
+// Effect scripts write back any variable they changed or introduced with `let`, all in one
+// batch, so a script that fails or writes a bad fact leaves the store as it was.
+
+#[cfg(feature = "scripting")]
+mod backend {
+    use super::*;
+    use crate::beats::data::{FactMutation, StringHashSet};
+    use rhai::{Array, Dynamic, Engine, Map, Scope};
+
+    // Keeps runaway scripts (`loop {}`) from hanging the frame
+    const MAX_OPERATIONS: u64 = 10_000;
+    const FACTS_VARIABLE: &str = "facts";
+    const NOW_VARIABLE: &str = "now";
+
+    thread_local! {
+        static ENGINE: Engine = {
+            let mut engine = Engine::new();
+            engine
+                .set_max_operations(MAX_OPERATIONS)
+                .set_max_expr_depths(32, 32)
+                .set_max_string_size(4096)
+                .set_max_array_size(1024)
+                .on_print(|text| info!("[story script] {}", text));
+            engine
+        };
+    }
+
+    fn is_identifier(key: &str) -> bool {
+        let mut chars = key.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn to_dynamic(fact: &Fact) -> Dynamic {
+        match fact {
+            Fact::Int(_, value) => Dynamic::from(*value as rhai::INT),
+            Fact::String(_, value) => Dynamic::from(value.clone()),
+            Fact::Bool(_, value) => Dynamic::from(*value),
+            Fact::StringList(_, values) => {
+                let mut sorted: Vec<&String> = values.0.iter().collect();
+                sorted.sort();
+                let array: Array = sorted
+                    .into_iter()
+                    .map(|v| Dynamic::from(v.clone()))
+                    .collect();
+                Dynamic::from(array)
+            }
+        }
+    }
+
+    fn to_fact(key: &str, value: Dynamic) -> Option<Fact> {
+        if let Ok(value) = value.as_int() {
+            return i32::try_from(value)
+                .ok()
+                .map(|v| Fact::Int(key.to_string(), v));
+        }
+        if let Ok(value) = value.as_bool() {
+            return Some(Fact::Bool(key.to_string(), value));
+        }
+        if value.is_string() {
+            return value
+                .into_string()
+                .ok()
+                .map(|v| Fact::String(key.to_string(), v));
+        }
+        if value.is_array() {
+            let mut list = StringHashSet::new();
+            for item in value.into_array().ok()? {
+                list.insert(item.into_string().ok()?);
+            }
+            return Some(Fact::StringList(key.to_string(), list));
+        }
+        None
+    }
+
+    fn scope_for(facts: &HashMap<String, Fact>) -> Scope<'static> {
+        scope_with(Scope::new(), facts)
+    }
+
+    // Facts pushed after what's already in the scope, so a fact shadows a variable of the same name
+    fn scope_with(mut scope: Scope<'static>, facts: &HashMap<String, Fact>) -> Scope<'static> {
+        let mut all = Map::new();
+        for (key, fact) in facts {
+            let value = to_dynamic(fact);
+            if is_identifier(key) && key != FACTS_VARIABLE {
+                scope.push(key.clone(), value.clone());
+            }
+            all.insert(key.as_str().into(), value);
+        }
+        scope.push_constant(FACTS_VARIABLE, all);
+        scope
+    }
+
+    pub fn evaluate(script: &str, context: &EvaluationContext) -> bool {
+        // Conditions can compare against the story clock through `now`
+        let mut scope = Scope::new();
+        scope.push_constant(NOW_VARIABLE, context.now as rhai::FLOAT);
+        let mut scope = scope_with(scope, context.facts);
+        ENGINE.with(
+            |engine| match engine.eval_expression_with_scope::<bool>(&mut scope, script) {
+                Ok(result) => result,
+                Err(error) => {
+                    warn!("Condition script `{}` failed: {}", script, error);
+                    false
+                }
+            },
+        )
+    }
+
+    pub fn watch(script: &str, facts: &HashMap<String, Fact>) -> Result<String, String> {
+        let mut scope = scope_for(facts);
+        ENGINE
+            .with(|engine| engine.eval_expression_with_scope::<Dynamic>(&mut scope, script))
+            .map(|value| value.to_string())
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn run<S: FactStorage>(script: &str, fact_store: &mut S) -> Result<(), FactError> {
+        let mut scope = scope_for(fact_store.facts());
+        ENGINE
+            .with(|engine| engine.run_with_scope(&mut scope, script))
+            .map_err(|error| FactError::Script {
+                script: script.to_string(),
+                message: error.to_string(),
+            })?;
+        let changed: Vec<FactMutation> = scope
+            .iter()
+            .filter(|(name, is_constant, _)| !is_constant && *name != FACTS_VARIABLE)
+            .filter_map(|(name, _, value)| to_fact(name, value))
+            .filter(|fact| fact_store.facts().get(fact.key()) != Some(fact))
+            .map(FactMutation::from_fact)
+            .collect();
+        fact_store.apply_batch(changed)
+    }
+}
+
+#[cfg(feature = "scripting")]
+pub fn evaluate_script(script: &str, context: &EvaluationContext) -> bool {
+    backend::evaluate(script, context)
+}
+
+// Evaluates an expression for display, e.g. a watch in the inspector
+#[cfg(feature = "scripting")]
+pub fn watch_script(script: &str, facts: &HashMap<String, Fact>) -> Result<String, String> {
+    backend::watch(script, facts)
+}
+
+#[cfg(feature = "scripting")]
+pub fn run_script<S: FactStorage>(script: &str, fact_store: &mut S) -> Result<(), FactError> {
+    backend::run(script, fact_store)
+}
+
+#[cfg(not(feature = "scripting"))]
+thread_local! {
+    // Rules are evaluated every frame, one warning per script is enough
+    static IGNORED_SCRIPTS: std::cell::RefCell<bevy::utils::HashSet<String>> =
+        std::cell::RefCell::new(bevy::utils::HashSet::new());
+}
+
+#[cfg(not(feature = "scripting"))]
+pub fn evaluate_script(script: &str, _context: &EvaluationContext) -> bool {
+    if IGNORED_SCRIPTS.with(|ignored| ignored.borrow_mut().insert(script.to_string())) {
+        warn!(
+            "Condition script `{}` ignored, build with the `scripting` feature to run it",
+            script
+        );
+    }
+    false
+}
+
+#[cfg(not(feature = "scripting"))]
+pub fn watch_script(_script: &str, _facts: &HashMap<String, Fact>) -> Result<String, String> {
+    Err("build with the `scripting` feature to evaluate watches".to_string())
+}
+
+#[cfg(not(feature = "scripting"))]
+pub fn run_script<S: FactStorage>(script: &str, _fact_store: &mut S) -> Result<(), FactError> {
+    Err(FactError::Script {
+        script: script.to_string(),
+        message: "build with the `scripting` feature to run it".to_string(),
+    })
+}
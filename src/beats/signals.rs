@@ -0,0 +1,153 @@
+use crate::beats::data::{
+    EffectOutput, FactsOfTheWorld, Rule, StoryBeatFinished, StoryEngine,
+};
+use crate::beats::logging::{BeatsLogLevel, BEATS_LOG_TARGET};
+use bevy::log::Level;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use std::collections::VecDeque;
+
+// Signals waiting for a subscriber past this are dropped oldest first, so a system that stopped
+// reading doesn't hold on to every signal of the session
+const MAX_QUEUED_SIGNALS: usize = 256;
+
+// Topics are dot separated like fact names. In a subscription a `*` segment matches any one
+// segment, and as the last segment anything below it, so `story.*` gets every finished beat.
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    let mut topic_segments = topic.split('.');
+    let mut pattern_segments = pattern.split('.').peekable();
+    while let Some(segment) = pattern_segments.next() {
+        let Some(topic_segment) = topic_segments.next() else {
+            return false;
+        };
+        if segment == "*" && pattern_segments.peek().is_none() {
+            return true;
+        }
+        if segment != "*" && segment != topic_segment {
+            return false;
+        }
+    }
+    topic_segments.next().is_none()
+}
+
+// Sent when a beat finishes, e.g. `story.harbour.arrival.finished`
+pub fn beat_signal(story: &str, beat: &str) -> String {
+    format!("story.{}.{}.finished", story, beat)
+}
+
+// Sent when a rule of an active beat, or a story's prerequisite, starts or stops holding
+pub fn rule_signal(rule: &str, holds: bool) -> String {
+    format!("rule.{}.{}", rule, if holds { "holds" } else { "broken" })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+#[derive(Debug)]
+struct Subscription {
+    id: SubscriptionId,
+    pattern: String,
+    queue: VecDeque<String>,
+}
+
+// String topics anything can emit to and subscribe to, for extensions that shouldn't need an
+// event type of their own. A subscriber keeps its id in a `Local` and reads its queue each frame:
+// `let id = *subscription.get_or_insert_with(|| signals.subscribe("story.*"));`
+#[derive(Resource, Debug, Default)]
+pub struct Signals {
+    subscriptions: Vec<Subscription>,
+    next_id: u64,
+}
+
+impl Signals {
+    pub fn subscribe(&mut self, pattern: impl Into<String>) -> SubscriptionId {
+        self.next_id += 1;
+        let id = SubscriptionId(self.next_id);
+        self.subscriptions.push(Subscription {
+            id,
+            pattern: pattern.into(),
+            queue: VecDeque::new(),
+        });
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.subscriptions
+            .retain(|subscription| subscription.id != id);
+    }
+
+    // Queues the topic for every subscription matching it
+    pub fn emit(&mut self, topic: impl Into<String>) {
+        let topic = topic.into();
+        for subscription in self
+            .subscriptions
+            .iter_mut()
+            .filter(|subscription| topic_matches(&subscription.pattern, &topic))
+        {
+            if subscription.queue.len() >= MAX_QUEUED_SIGNALS {
+                subscription.queue.pop_front();
+            }
+            subscription.queue.push_back(topic.clone());
+        }
+    }
+
+    // Topics emitted since the subscription was last read, oldest first
+    pub fn read(&mut self, id: SubscriptionId) -> Vec<String> {
+        self.subscriptions
+            .iter_mut()
+            .find(|subscription| subscription.id == id)
+            .map(|subscription| subscription.queue.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+pub fn signal_effects(mut effect_outputs: EventReader<EffectOutput>, mut signals: ResMut<Signals>) {
+    for output in effect_outputs.read() {
+        if let EffectOutput::EmitSignal(topic) = output {
+            signals.emit(topic.clone());
+        }
+    }
+}
+
+pub fn signal_finished_beats(
+    mut story_beat_finished: EventReader<StoryBeatFinished>,
+    mut signals: ResMut<Signals>,
+) {
+    for finished in story_beat_finished.read() {
+        signals.emit(beat_signal(&finished.story.name, &finished.beat.name));
+    }
+}
+
+// Watches the rules that could move a story on next. A rule's first evaluation only records
+// where it stands, a signal goes out when that changes.
+pub fn signal_rule_flips(
+    facts: Res<FactsOfTheWorld>,
+    story_engine: Res<StoryEngine>,
+    mut signals: ResMut<Signals>,
+    mut holding: Local<HashMap<String, bool>>,
+    log_level: Res<BeatsLogLevel>,
+) {
+    if !facts.is_changed() && !story_engine.is_changed() {
+        return;
+    }
+    let mut watched: Vec<&Rule> = Vec::new();
+    for story in story_engine.stories.iter() {
+        if !story.is_started {
+            watched.extend(story.pre_requisites.iter());
+        } else if let Some(beat) = story.active_beat() {
+            watched.extend(beat.rules.iter());
+        }
+    }
+    let mut now = HashMap::new();
+    for rule in watched {
+        let holds = rule.evaluate(&*facts);
+        if holding.get(&rule.name).is_some_and(|held| *held != holds) {
+            if log_level.logs(Level::DEBUG) {
+                debug!(target: BEATS_LOG_TARGET, rule = %rule.name, holds, "Rule flipped");
+            }
+            signals.emit(rule_signal(&rule.name, holds));
+        }
+        now.insert(rule.name.clone(), holds);
+    }
+    *holding = now;
+}
@@ -0,0 +1,302 @@
+use crate::beats::data::{
+    EffectOutput, EvaluationContext, FactMutation, FactsOfTheWorld, Rule, StoryEngine,
+};
+use crate::beats::rng::StoryRng;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Steps through stories without an App, one batch of fact mutations per step.
+// Beat effects are applied straight away and evaluation repeats until nothing moves,
+// so a whole playthrough can be scripted as a list of steps.
+pub struct Simulation {
+    pub facts: FactsOfTheWorld,
+    pub story_engine: StoryEngine,
+    pub rng: StoryRng,
+    // Story time in seconds seen by conditions, only moves when a script sets it
+    pub now: f64,
+    pub coverage: Coverage,
+    // Everything the applied effects asked for, in order
+    pub outputs: Vec<EffectOutput>,
+}
+
+// Upper bound on evaluation passes per step, in case effects keep re-triggering each other
+const MAX_PASSES_PER_STEP: usize = 64;
+
+impl Simulation {
+    pub fn new(story_engine: StoryEngine, seed: u64) -> Self {
+        let mut coverage = Coverage::default();
+        coverage.register(&story_engine);
+        Simulation {
+            facts: FactsOfTheWorld::new(),
+            story_engine,
+            rng: StoryRng::new(seed),
+            now: 0.0,
+            coverage,
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn step(&mut self, mutations: Vec<FactMutation>) {
+        if let Err(error) = self.facts.apply_batch(mutations) {
+            self.coverage.errors.push(error.to_string());
+        }
+        for _ in 0..MAX_PASSES_PER_STEP {
+            if !self.evaluate_once() {
+                return;
+            }
+        }
+        self.coverage.errors.push(format!(
+            "evaluation still moving after {} passes",
+            MAX_PASSES_PER_STEP
+        ));
+    }
+
+    pub fn run(&mut self, script: Vec<Vec<FactMutation>>) {
+        for mutations in script {
+            self.step(mutations);
+        }
+    }
+
+    // One pass over all stories, returns whether any of them moved
+    fn evaluate_once(&mut self) -> bool {
+        let mut progressed = false;
+        let mut effects = Vec::new();
+        for story in self.story_engine.stories.iter_mut() {
+            if !story.is_started {
+                for rule in story.pre_requisites.iter() {
+                    self.coverage.record_rule(&story.name, rule, &self.facts);
+                }
+                if !story.start_if_possible(&mut EvaluationContext::new(
+                    self.now,
+                    &mut self.rng,
+                    &self.facts,
+                )) {
+                    continue;
+                }
+                progressed = true;
+            }
+            let Some(beat) = story.active_beat() else {
+                continue;
+            };
+            for rule in beat
+                .rules
+                .iter()
+                .chain(beat.transitions.iter().map(|transition| &transition.rule))
+            {
+                self.coverage.record_rule(&story.name, rule, &self.facts);
+            }
+            let mut context = EvaluationContext::new(self.now, &mut self.rng, &self.facts);
+            let transition = beat
+                .transitions
+                .iter()
+                .find(|transition| transition.rule.evaluate_with(&mut context))
+                .map(|transition| transition.target.clone());
+            if let Some(finished) = story.evaluate_active_beat(&mut context) {
+                progressed = true;
+                if let Some(target) = transition {
+                    self.coverage.transitions_taken.insert((
+                        story.name.clone(),
+                        finished.name.clone(),
+                        target,
+                    ));
+                }
+                self.coverage
+                    .beats_finished
+                    .insert((story.name.clone(), finished.name.clone()));
+                effects.extend(finished.effects);
+            }
+        }
+        for effect in effects {
+            match effect.apply(&mut self.facts, &mut self.rng) {
+                Ok(outputs) => self.outputs.extend(outputs),
+                Err(error) => self.coverage.errors.push(error.to_string()),
+            }
+        }
+        self.facts.drain_updated();
+        progressed
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConditionOutcomes {
+    pub held: bool,
+    pub failed: bool,
+}
+
+// What a set of simulated runs exercised. Merge the coverage of every run in a suite
+// before building the report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    // Keyed by story, rule and the condition's position in the rule
+    pub conditions: BTreeMap<(String, String, usize), ConditionOutcomes>,
+    pub beats: BTreeSet<(String, String)>,
+    pub beats_finished: BTreeSet<(String, String)>,
+    pub transitions: BTreeSet<(String, String, String)>,
+    pub transitions_taken: BTreeSet<(String, String, String)>,
+    pub errors: Vec<String>,
+}
+
+impl Coverage {
+    // Lists everything the stories contain, so content that is never reached still shows up
+    pub fn register(&mut self, story_engine: &StoryEngine) {
+        for story in story_engine.stories.iter() {
+            let rules = story
+                .pre_requisites
+                .iter()
+                .chain(story.beats.iter().flat_map(|beat| {
+                    beat.rules
+                        .iter()
+                        .chain(beat.transitions.iter().map(|transition| &transition.rule))
+                }));
+            for rule in rules {
+                for index in 0..rule.conditions.len() {
+                    self.conditions
+                        .entry((story.name.clone(), rule.name.clone(), index))
+                        .or_default();
+                }
+            }
+            for beat in story.beats.iter() {
+                self.beats.insert((story.name.clone(), beat.name.clone()));
+                for transition in beat.transitions.iter() {
+                    self.transitions.insert((
+                        story.name.clone(),
+                        beat.name.clone(),
+                        transition.target.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn record_rule(&mut self, story: &str, rule: &Rule, facts: &FactsOfTheWorld) {
+        for (index, condition) in rule.conditions.iter().enumerate() {
+            let outcomes = self
+                .conditions
+                .entry((story.to_string(), rule.name.clone(), index))
+                .or_default();
+            if condition.evaluate(facts) {
+                outcomes.held = true;
+            } else {
+                outcomes.failed = true;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: Coverage) {
+        for (key, outcomes) in other.conditions {
+            let merged = self.conditions.entry(key).or_default();
+            merged.held |= outcomes.held;
+            merged.failed |= outcomes.failed;
+        }
+        self.beats.extend(other.beats);
+        self.beats_finished.extend(other.beats_finished);
+        self.transitions.extend(other.transitions);
+        self.transitions_taken.extend(other.transitions_taken);
+        self.errors.extend(other.errors);
+    }
+
+    pub fn report(&self) -> CoverageReport {
+        CoverageReport {
+            conditions_never_held: self
+                .conditions
+                .iter()
+                .filter(|(_, outcomes)| !outcomes.held)
+                .map(|(key, _)| key.clone())
+                .collect(),
+            conditions_never_failed: self
+                .conditions
+                .iter()
+                .filter(|(_, outcomes)| !outcomes.failed)
+                .map(|(key, _)| key.clone())
+                .collect(),
+            beats_never_finished: self
+                .beats
+                .difference(&self.beats_finished)
+                .cloned()
+                .collect(),
+            transitions_never_taken: self
+                .transitions
+                .difference(&self.transitions_taken)
+                .cloned()
+                .collect(),
+            conditions: self.conditions.len(),
+            beats: self.beats.len(),
+            transitions: self.transitions.len(),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub conditions_never_held: Vec<(String, String, usize)>,
+    pub conditions_never_failed: Vec<(String, String, usize)>,
+    pub beats_never_finished: Vec<(String, String)>,
+    pub transitions_never_taken: Vec<(String, String, String)>,
+    pub conditions: usize,
+    pub beats: usize,
+    pub transitions: usize,
+    pub errors: Vec<String>,
+}
+
+impl CoverageReport {
+    pub fn is_complete(&self) -> bool {
+        self.conditions_never_held.is_empty()
+            && self.beats_never_finished.is_empty()
+            && self.transitions_never_taken.is_empty()
+    }
+}
+
+impl std::fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "beats finished: {}/{}",
+            self.beats - self.beats_never_finished.len(),
+            self.beats
+        )?;
+        for (story, beat) in self.beats_never_finished.iter() {
+            writeln!(f, "  never finished: {} / {}", story, beat)?;
+        }
+        writeln!(
+            f,
+            "transitions taken: {}/{}",
+            self.transitions - self.transitions_never_taken.len(),
+            self.transitions
+        )?;
+        for (story, from, to) in self.transitions_never_taken.iter() {
+            writeln!(f, "  never taken: {} / {} -> {}", story, from, to)?;
+        }
+        writeln!(
+            f,
+            "conditions held: {}/{}",
+            self.conditions - self.conditions_never_held.len(),
+            self.conditions
+        )?;
+        for (story, rule, index) in self.conditions_never_held.iter() {
+            writeln!(f, "  never held: {} / {} #{}", story, rule, index)?;
+        }
+        for (story, rule, index) in self.conditions_never_failed.iter() {
+            writeln!(f, "  never failed: {} / {} #{}", story, rule, index)?;
+        }
+        for error in self.errors.iter() {
+            writeln!(f, "error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+// Runs every script from a fresh copy of the stories and merges what they covered
+pub fn simulate_suite(
+    story_engine: &StoryEngine,
+    seed: u64,
+    scripts: Vec<Vec<Vec<FactMutation>>>,
+) -> CoverageReport {
+    let mut coverage = Coverage::default();
+    coverage.register(story_engine);
+    for script in scripts {
+        let mut simulation = Simulation::new(story_engine.clone(), seed);
+        simulation.run(script);
+        coverage.merge(simulation.coverage);
+    }
+    coverage.report()
+}
@@ -0,0 +1,55 @@
+use crate::beats::data::Fact;
+use bevy::utils::hashbrown::{HashMap, HashSet};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::BuildHasher;
+
+// `serialize_with` helpers that write hash maps and sets in order, so saves and other
+// exports come out byte for byte the same every run and diff cleanly in version control.
+
+pub fn map<K, V, H, S>(map: &HashMap<K, V, H>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    H: BuildHasher,
+    S: Serializer,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+pub fn set<T, H, S>(set: &HashSet<T, H>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Ord + Serialize,
+    H: BuildHasher,
+    S: Serializer,
+{
+    set.iter().collect::<BTreeSet<_>>().serialize(serializer)
+}
+
+pub fn map_of_sets<K, T, H, I, S>(
+    map: &HashMap<K, HashSet<T, I>, H>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    T: Ord + Serialize,
+    H: BuildHasher,
+    I: BuildHasher,
+    S: Serializer,
+{
+    map.iter()
+        .map(|(key, values)| (key, values.iter().collect::<BTreeSet<_>>()))
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+// Facts aren't ordered themselves, their keys are
+pub fn facts<H, S>(facts: &HashSet<Fact, H>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    H: BuildHasher,
+    S: Serializer,
+{
+    let mut sorted: Vec<&Fact> = facts.iter().collect();
+    sorted.sort_by(|a, b| (a.key(), a.type_name()).cmp(&(b.key(), b.type_name())));
+    sorted.serialize(serializer)
+}
@@ -0,0 +1,314 @@
+use crate::beats::data::{
+    Fact, FactAliasUsed, FactError, FactMutation, FactRemoved, FactUpdated, FactWriteDenied,
+    FactsOfTheWorld,
+};
+use crate::beats::event_sourced::EventSourcedFactStore;
+use bevy::log::warn;
+use bevy::prelude::{DetectChangesMut, ResMut, Resource};
+use bevy::utils::hashbrown::HashMap;
+
+// The operations the story systems need from a fact store, so backends can be swapped
+// without touching the systems themselves.
+pub trait FactStorage {
+    fn get(&self, key: &str) -> Option<&Fact>;
+
+    // Stores the fact under its own key, replacing any previous value. A fact of another type
+    // under the key is kept and the write only logged, `try_set` reports it instead.
+    fn set(&mut self, fact: Fact);
+
+    fn add_to_list(&mut self, key: String, value: String);
+
+    fn remove_from_list(&mut self, key: String, value: String);
+
+    // Takes the fact out of the store, reported as a FactRemoved
+    fn remove(&mut self, key: &str) -> Option<Fact>;
+
+    fn facts(&self) -> &HashMap<String, Fact>;
+
+    // Whether any fact changed or was removed since the last drain
+    fn has_updates(&self) -> bool;
+
+    // Takes the facts that changed since the last drain
+    fn drain_updated(&mut self) -> Vec<FactUpdated>;
+
+    // Takes the facts removed since the last drain
+    fn drain_removed(&mut self) -> Vec<FactRemoved>;
+
+    // Moves on with every write that changed a fact
+    fn revision(&self) -> u64;
+
+    // Takes the writes to constant facts that were refused since the last drain
+    fn drain_denied(&mut self) -> Vec<FactWriteDenied>;
+
+    fn is_constant(&self, key: &str) -> bool;
+
+    // The name a fact is actually stored under, following aliases
+    fn resolve<'a>(&'a self, key: &'a str) -> &'a str;
+
+    // Old fact names mapped to the names they were renamed to
+    fn aliases(&self) -> &HashMap<String, String>;
+
+    // Takes the aliased writes made since the last drain
+    fn drain_alias_hits(&mut self) -> Vec<FactAliasUsed>;
+
+    // Fails if the fact, or the fact an alias points to, was stored as a constant
+    fn check_writable(&self, key: &str) -> Result<(), FactError> {
+        if self.is_constant(self.resolve(key)) {
+            Err(FactError::ReadOnly {
+                key: key.to_string(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn tag(&mut self, key: &str, tag: &str);
+
+    fn tagged_keys(&self, tag: &str) -> Vec<String>;
+
+    fn facts_with_tag(&self, tag: &str) -> impl Iterator<Item = &Fact> {
+        self.tagged_keys(tag)
+            .into_iter()
+            .filter_map(|key| self.get(&key))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Fact> {
+        self.facts().values()
+    }
+
+    // Fails if a fact with the same key is already stored with a different type
+    fn check_type(&self, fact: &Fact) -> Result<(), FactError> {
+        match self.get(fact.key()) {
+            Some(stored) if std::mem::discriminant(stored) != std::mem::discriminant(fact) => {
+                Err(FactError::TypeMismatch {
+                    key: fact.key().to_string(),
+                    stored: stored.type_name(),
+                    written: fact.type_name(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Applies every mutation or none of them. Keys are resolved through aliases and checked up
+    // front, for constants and for types against the store and earlier writes in the same
+    // batch, so a bad mutation returns an error before anything is written.
+    fn apply_batch(&mut self, mutations: Vec<FactMutation>) -> Result<(), FactError>
+    where
+        Self: Sized,
+    {
+        let mut batch_types: HashMap<String, &'static str> = HashMap::new();
+        for mutation in mutations.iter() {
+            let key = self.resolve(mutation.key());
+            self.check_writable(key)?;
+            let stored = batch_types
+                .get(key)
+                .copied()
+                .or_else(|| self.get(key).map(|fact| fact.type_name()));
+            // List edits need a list, the other mutations replace the fact with their own type
+            let written = mutation.type_name().unwrap_or("string list");
+            if let Some(stored) = stored.filter(|stored| *stored != written) {
+                return Err(FactError::TypeMismatch {
+                    key: mutation.key().to_string(),
+                    stored,
+                    written,
+                });
+            }
+            if mutation.type_name().is_some() {
+                batch_types.insert(key.to_string(), written);
+            }
+        }
+        for mutation in mutations.iter() {
+            mutation.try_apply(self)?;
+        }
+        Ok(())
+    }
+
+    // Like `set`, but a fact of another type or a constant under the key is returned as an error
+    // rather than logged, and nothing is stored. Writes to constants still go through `set` so
+    // the denial is recorded. Systems applying story content use this, so bad content ends up
+    // in the `ErrorLog`.
+    fn try_set(&mut self, fact: Fact) -> Result<(), FactError> {
+        self.check_type(&fact)?;
+        let writable = self.check_writable(fact.key());
+        self.set(fact);
+        writable
+    }
+}
+
+impl FactStorage for FactsOfTheWorld {
+    fn get(&self, key: &str) -> Option<&Fact> {
+        self.facts.get(self.resolve(key))
+    }
+
+    fn set(&mut self, fact: Fact) {
+        if let Err(error) = self.try_set(fact) {
+            warn!("Ignored a write: {}", error);
+        }
+    }
+
+    // The setters already refuse constants and facts of another type
+    fn try_set(&mut self, fact: Fact) -> Result<(), FactError> {
+        match fact {
+            Fact::Int(key, value) => self.store_int(key, value),
+            Fact::String(key, value) => self.store_string(key, value),
+            Fact::Bool(key, value) => self.store_bool(key, value),
+            Fact::StringList(key, values) => self.store_list(key, values),
+        }
+    }
+
+    fn add_to_list(&mut self, key: String, value: String) {
+        if let Err(error) = FactsOfTheWorld::add_to_list(self, key, value) {
+            warn!("Ignored a write: {}", error);
+        }
+    }
+
+    fn remove_from_list(&mut self, key: String, value: String) {
+        if let Err(error) = FactsOfTheWorld::remove_from_list(self, key, value) {
+            warn!("Ignored a write: {}", error);
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Fact> {
+        let key = self.resolve(key).to_string();
+        FactsOfTheWorld::remove(self, &key)
+    }
+
+    fn facts(&self) -> &HashMap<String, Fact> {
+        &self.facts
+    }
+
+    fn has_updates(&self) -> bool {
+        FactsOfTheWorld::has_updates(self)
+    }
+
+    fn drain_updated(&mut self) -> Vec<FactUpdated> {
+        FactsOfTheWorld::drain_updated(self)
+    }
+
+    fn drain_removed(&mut self) -> Vec<FactRemoved> {
+        FactsOfTheWorld::drain_removed(self)
+    }
+
+    fn revision(&self) -> u64 {
+        FactsOfTheWorld::revision(self)
+    }
+
+    fn drain_denied(&mut self) -> Vec<FactWriteDenied> {
+        FactsOfTheWorld::drain_denied(self)
+    }
+
+    fn is_constant(&self, key: &str) -> bool {
+        FactsOfTheWorld::is_constant(self, key)
+    }
+
+    fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        FactsOfTheWorld::resolve(self, key)
+    }
+
+    fn aliases(&self) -> &HashMap<String, String> {
+        &self.aliases
+    }
+
+    fn drain_alias_hits(&mut self) -> Vec<FactAliasUsed> {
+        FactsOfTheWorld::drain_alias_hits(self)
+    }
+
+    fn tag(&mut self, key: &str, tag: &str) {
+        FactsOfTheWorld::tag(self, key, tag);
+    }
+
+    fn tagged_keys(&self, tag: &str) -> Vec<String> {
+        FactsOfTheWorld::tagged_keys(self, tag)
+    }
+}
+
+impl FactStorage for EventSourcedFactStore {
+    fn get(&self, key: &str) -> Option<&Fact> {
+        EventSourcedFactStore::facts(self).get(self.resolve(key))
+    }
+
+    fn set(&mut self, fact: Fact) {
+        if let Err(error) = self.record(FactMutation::from_fact(fact)) {
+            warn!("Ignored a write: {}", error);
+        }
+    }
+
+    fn add_to_list(&mut self, key: String, value: String) {
+        if let Err(error) = EventSourcedFactStore::add_to_list(self, key, value) {
+            warn!("Ignored a write: {}", error);
+        }
+    }
+
+    fn remove_from_list(&mut self, key: String, value: String) {
+        if let Err(error) = EventSourcedFactStore::remove_from_list(self, key, value) {
+            warn!("Ignored a write: {}", error);
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Fact> {
+        EventSourcedFactStore::remove(self, key)
+    }
+
+    fn facts(&self) -> &HashMap<String, Fact> {
+        EventSourcedFactStore::facts(self)
+    }
+
+    fn has_updates(&self) -> bool {
+        EventSourcedFactStore::has_updates(self)
+    }
+
+    fn drain_updated(&mut self) -> Vec<FactUpdated> {
+        EventSourcedFactStore::drain_updated(self)
+    }
+
+    fn drain_removed(&mut self) -> Vec<FactRemoved> {
+        EventSourcedFactStore::drain_removed(self)
+    }
+
+    fn revision(&self) -> u64 {
+        EventSourcedFactStore::revision(self)
+    }
+
+    fn drain_denied(&mut self) -> Vec<FactWriteDenied> {
+        EventSourcedFactStore::drain_denied(self)
+    }
+
+    fn is_constant(&self, key: &str) -> bool {
+        EventSourcedFactStore::is_constant(self, key)
+    }
+
+    fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        EventSourcedFactStore::resolve(self, key)
+    }
+
+    fn aliases(&self) -> &HashMap<String, String> {
+        EventSourcedFactStore::aliases(self)
+    }
+
+    fn drain_alias_hits(&mut self) -> Vec<FactAliasUsed> {
+        EventSourcedFactStore::drain_alias_hits(self)
+    }
+
+    fn tag(&mut self, key: &str, tag: &str) {
+        EventSourcedFactStore::tag(self, key, tag);
+    }
+
+    fn tagged_keys(&self, tag: &str) -> Vec<String> {
+        EventSourcedFactStore::tagged_keys(self, tag)
+    }
+}
+
+// Runs the writes without flagging the store as changed, then flags it only if a fact actually
+// changed. Writing the value a fact already has leaves `Res::is_changed` and `Changed` alone.
+pub fn write_facts<S: FactStorage + Resource, T>(
+    storage: &mut ResMut<S>,
+    write: impl FnOnce(&mut S) -> T,
+) -> T {
+    let revision = storage.revision();
+    let written = write(storage.bypass_change_detection());
+    if storage.revision() != revision {
+        storage.set_changed();
+    }
+    written
+}
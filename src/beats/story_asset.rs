@@ -0,0 +1,110 @@
+use crate::beats::data::{Story, StoryEngine};
+use crate::beats::errors::{recover, EngineError};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+
+// Story files bundled with the game, relative to the assets folder
+pub const STORY_FILES: &[&str] = &[
+    "stories/fishing.story.ron",
+    "stories/lost_barnacle.story.ron",
+    "stories/ship_supplies.story.ron",
+];
+
+#[derive(Asset, TypePath, Debug)]
+pub struct StoryAsset(pub Story);
+
+#[derive(Debug)]
+pub enum StoryLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+    // Parsing panicked rather than failing
+    Panic(String),
+}
+
+impl std::fmt::Display for StoryLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoryLoadError::Io(error) => write!(f, "could not read story file: {}", error),
+            StoryLoadError::Parse(error) => write!(f, "{}", error),
+            StoryLoadError::Panic(message) => write!(f, "parsing panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for StoryLoadError {}
+
+impl From<std::io::Error> for StoryLoadError {
+    fn from(error: std::io::Error) -> Self {
+        StoryLoadError::Io(error)
+    }
+}
+
+pub fn parse_story(source: &str) -> Result<Story, StoryLoadError> {
+    recover(|| ron::from_str(source))
+        .map_err(StoryLoadError::Panic)?
+        .map_err(StoryLoadError::Parse)
+}
+
+#[derive(Default)]
+pub struct StoryAssetLoader;
+
+impl AssetLoader for StoryAssetLoader {
+    type Asset = StoryAsset;
+    type Settings = ();
+    type Error = StoryLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            parse_story(&source).map(StoryAsset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["story.ron"]
+    }
+}
+
+// Keeps the story files loaded
+#[derive(Resource, Default)]
+pub struct StoryFiles(pub Vec<Handle<StoryAsset>>);
+
+pub fn load_story_files(asset_server: Res<AssetServer>, mut story_files: ResMut<StoryFiles>) {
+    for path in STORY_FILES {
+        story_files.0.push(asset_server.load(*path));
+    }
+}
+
+pub fn register_loaded_stories(
+    mut asset_events: EventReader<AssetEvent<StoryAsset>>,
+    stories: Res<Assets<StoryAsset>>,
+    mut story_engine: ResMut<StoryEngine>,
+) {
+    for event in asset_events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } = event {
+            if let Some(StoryAsset(story)) = stories.get(*id) {
+                story_engine.add_or_replace_story(story.clone());
+            }
+        }
+    }
+}
+
+pub fn report_story_load_failures(
+    mut failures: EventReader<AssetLoadFailedEvent<StoryAsset>>,
+    mut errors: EventWriter<EngineError>,
+) {
+    for failure in failures.read() {
+        errors.send(EngineError::StoryParse {
+            path: failure.path.to_string(),
+            message: failure.error.to_string(),
+        });
+    }
+}
@@ -0,0 +1,146 @@
+use crate::beats::data::{EvaluationContext, FactsOfTheWorld, Story, StoryEngine};
+use crate::beats::debug::DebugCommand;
+use crate::beats::lint::{lint_stories, KnownFacts, StoryLint};
+use crate::beats::rng::StoryRng;
+use crate::beats::story_time::StoryTime;
+use crate::beats::watch::egui_color;
+use crate::settings::Settings;
+use crate::ui::theme::UiTheme;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+// Every loaded story as a list of its beats and where they lead. The active beat is
+// highlighted with the conditions still holding it back, and any beat can be finished or
+// jumped to from here. Lint findings for the stories are listed above them.
+#[allow(clippy::too_many_arguments)]
+pub fn story_graph_window(
+    mut contexts: EguiContexts,
+    story_engine: Res<StoryEngine>,
+    storage: Res<FactsOfTheWorld>,
+    story_time: Res<StoryTime>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    mut debug_commands: EventWriter<DebugCommand>,
+    mut lints: Local<Option<Vec<StoryLint>>>,
+) {
+    // Facts in the store count as written, whatever wrote them
+    if lints.is_none() || story_engine.is_changed() {
+        let known = storage
+            .facts
+            .keys()
+            .fold(KnownFacts::game(), |known, key| known.writes(key.clone()));
+        *lints = Some(lint_stories(&story_engine.stories, &known));
+    }
+    let palette = theme.palette(settings.palette);
+    // A throwaway rng, looking at rules mustn't shift the story's random rolls
+    let mut rng = StoryRng::new(0);
+    let mut context =
+        EvaluationContext::new(story_time.elapsed_seconds(), &mut rng, &*storage);
+    egui::Window::new("Story graph")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let lints = lints.as_deref().unwrap_or_default();
+                ui.collapsing(format!("Lint ({})", lints.len()), |ui| {
+                    for lint in lints {
+                        ui.colored_label(egui_color(palette.negative), lint.to_string());
+                    }
+                });
+                for story in story_engine.stories.iter() {
+                    ui.collapsing(story_heading(story), |ui| {
+                        if !story.is_started {
+                            blocking_rules(
+                                ui,
+                                &story_engine,
+                                story.pre_requisites.iter().map(|rule| rule.name.as_str()),
+                                &mut context,
+                                palette.negative,
+                            );
+                        }
+                        for (index, beat) in story.beats.iter().enumerate() {
+                            let active = story.is_started && index == story.active_beat_index;
+                            ui.horizontal(|ui| {
+                                let marker = if active {
+                                    ">"
+                                } else if beat.finished {
+                                    "+"
+                                } else {
+                                    " "
+                                };
+                                let label =
+                                    egui::RichText::new(format!("{} {}", marker, beat.name))
+                                        .monospace();
+                                let label = if active {
+                                    label.strong().color(egui_color(palette.positive))
+                                } else {
+                                    label
+                                };
+                                ui.label(label);
+                                if ui.small_button("Finish").clicked() {
+                                    debug_commands.send(DebugCommand::FinishBeat {
+                                        story: story.name.clone(),
+                                        beat: beat.name.clone(),
+                                        apply_effects: true,
+                                    });
+                                }
+                                if ui.small_button("Jump").clicked() {
+                                    debug_commands.send(DebugCommand::JumpToBeat {
+                                        story: story.name.clone(),
+                                        beat: beat.name.clone(),
+                                        apply_effects: false,
+                                    });
+                                }
+                            });
+                            ui.indent(&beat.name, |ui| {
+                                for transition in beat.transitions.iter() {
+                                    ui.monospace(format!("-> {}", transition.target));
+                                }
+                                if active {
+                                    blocking_rules(
+                                        ui,
+                                        &story_engine,
+                                        beat.rules.iter().map(|rule| rule.name.as_str()),
+                                        &mut context,
+                                        palette.negative,
+                                    );
+                                }
+                            });
+                        }
+                    });
+                }
+            });
+        });
+}
+
+fn story_heading(story: &Story) -> String {
+    let state = if story.is_finished() {
+        "finished"
+    } else if story.is_started {
+        "playing"
+    } else {
+        "waiting"
+    };
+    format!("{} ({})", story.name, state)
+}
+
+// Lists the conditions that don't hold yet for each of the named rules
+fn blocking_rules<'a>(
+    ui: &mut egui::Ui,
+    story_engine: &StoryEngine,
+    rules: impl Iterator<Item = &'a str>,
+    context: &mut EvaluationContext,
+    color: Color,
+) {
+    for rule in rules {
+        let Some(evaluation) = story_engine.evaluate_single(rule, context) else {
+            continue;
+        };
+        for result in evaluation.conditions.iter().filter(|result| !result.holds) {
+            ui.colored_label(
+                egui_color(color),
+                format!("{} waits on {:?}", evaluation.rule, result.condition),
+            );
+        }
+    }
+}
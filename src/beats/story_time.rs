@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Clock for everything timed in stories. It only runs while stories are being played and
+// nothing paused it, so menus and pauses don't eat into timed objectives. Saved with the game.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct StoryTime {
+    elapsed: f64,
+    #[serde(skip)]
+    paused: bool,
+}
+
+impl StoryTime {
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn advance(&mut self, seconds: f64) {
+        if !self.paused {
+            self.elapsed += seconds;
+        }
+    }
+
+    // Takes the clock from `other`, like a loaded save or a fresh run, but keeps pausing as it
+    // was, pausing belongs to whatever is on screen rather than to the story
+    pub fn replace_keeping_pause(&mut self, other: StoryTime) {
+        let paused = self.paused;
+        *self = other;
+        self.paused = paused;
+    }
+
+    // Jumps ahead by time that passes in the story without being played, like a journey.
+    // Works while paused too.
+    pub fn skip(&mut self, seconds: f64) {
+        self.elapsed += seconds.max(0.);
+    }
+}
+
+// Follows the virtual clock, so it also stops when that is paused
+pub fn tick_story_time(time: Res<Time>, mut story_time: ResMut<StoryTime>) {
+    story_time.advance(time.delta_seconds_f64());
+}
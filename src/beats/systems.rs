@@ -1,11 +1,25 @@
-use crate::beats::data::{Condition, FactsOfTheWorld, FactUpdated, Rule, RuleUpdated, StoryBeatFinished, StoryEngine};
-use crate::beats::TextComponent;
+use crate::beats::data::{Condition, EffectOutput, EvaluationContext, Fact, FactAliasUsed, FactRemoved, FactsOfTheWorld, FactUpdated, FactWriteDenied, Rule, RuleUpdated, StoryBeatFinished, StoryEngine};
+use crate::beats::choices::{ChoiceButton, PresentChoices};
+use crate::beats::errors::{recover, EngineError};
+use crate::beats::logging::{BeatsLogLevel, BEATS_LOG_TARGET};
+use crate::beats::rng::StoryRng;
+use crate::beats::storage::{write_facts, FactStorage};
+use crate::beats::story_time::StoryTime;
+use crate::beats::{StoryProgressionPass, TextComponent};
+use crate::dialogue::auto_advance::AutoAdvanceButton;
+use crate::settings::Settings;
+use crate::ui::theme::UiTheme;
 use bevy::asset::{AssetServer, Assets, Handle};
 use bevy::hierarchy::{ChildBuilder, Children};
+use bevy::ecs::change_detection::DetectChangesMut;
+use bevy::log::{debug, debug_span, trace, warn, Level};
 use bevy::math::Vec2;
-use bevy::prelude::{default, AlignItems, BackgroundColor, BorderColor, BuildChildren, Button, ButtonBundle, Changed, Color, ColorMaterial, Commands, Display, EventReader, EventWriter, Font, GridPlacement, GridTrack, Interaction, JustifyContent, JustifyItems, Mesh, NodeBundle, PositionType, Query, RepeatedGridTrack, Res, ResMut, Style, Text, TextBundle, TextStyle, Transform, Triangle2d, UiRect, Val, Visibility, With, JustifyText};
+use bevy::prelude::{default, AlignItems, BackgroundColor, BorderColor, BuildChildren, Button, ButtonBundle, Changed, Color, ColorMaterial, Commands, Display, EventReader, EventWriter, Font, GridPlacement, GridTrack, Interaction, JustifyContent, JustifyItems, Mesh, NodeBundle, PositionType, Query, RepeatedGridTrack, Res, ResMut, Resource, Style, World, Text, TextBundle, TextStyle, Time, Transform, Triangle2d, UiRect, Val, Visibility, With, Without, JustifyText};
 use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
-use crate::beats::builders::StoryBuilder;
+use bevy::ecs::event::{Events, ManualEventReader};
+use bevy::prelude::{Event, Local};
+use bevy::utils::HashSet;
+use crate::ui::animation::UiAnimationFinished;
 use crate::ui::builders::{add_button, NodeBundleBuilder};
 
 pub fn spawn_layout(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -215,7 +229,6 @@ pub fn text_bundle(builder: &mut ChildBuilder, font: Handle<Font>, text: &str, f
 
 pub(crate) const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
 pub fn fact_event_system(
     mut query: Query<&mut Text, With<TextComponent>>,
@@ -235,12 +248,69 @@ pub fn fact_event_system(
     }
 }
 
-pub fn fact_update_event_broadcaster(
+pub fn fact_update_event_broadcaster<S: FactStorage + Resource>(
     mut event_writer: EventWriter<FactUpdated>,
-    mut storage: ResMut<FactsOfTheWorld>,
+    mut removed_writer: EventWriter<FactRemoved>,
+    mut denied_writer: EventWriter<FactWriteDenied>,
+    mut alias_writer: EventWriter<FactAliasUsed>,
+    mut storage: ResMut<S>,
+    log_level: Res<BeatsLogLevel>,
 ) {
-    for fact in storage.updated_facts.drain() {
-        event_writer.send(FactUpdated { fact });
+    // Draining is bookkeeping, the resource only counts as changed when a fact was written
+    let storage = storage.bypass_change_detection();
+    for fact_updated in storage.drain_updated() {
+        if log_level.logs(Level::DEBUG) {
+            debug!(
+                target: BEATS_LOG_TARGET,
+                fact = fact_updated.fact.key(),
+                value = ?fact_updated.fact,
+                previous = ?fact_updated.previous,
+                "Fact changed"
+            );
+        }
+        event_writer.send(fact_updated);
+    }
+    for removed in storage.drain_removed() {
+        if log_level.logs(Level::DEBUG) {
+            debug!(target: BEATS_LOG_TARGET, fact = removed.previous.key(), "Fact removed");
+        }
+        removed_writer.send(removed);
+    }
+    for denied in storage.drain_denied() {
+        warn!("Refused to change constant fact {}", denied.fact.key());
+        denied_writer.send(denied);
+    }
+    for alias_used in storage.drain_alias_hits() {
+        warn!("Fact {} is an alias for {}", alias_used.alias, alias_used.target);
+        alias_writer.send(alias_used);
+    }
+}
+
+// Points stories written against old fact names at the current ones and reports each use. Runs
+// once for stories that were just added or loaded, evaluation follows aliases on its own.
+pub fn apply_fact_aliases<S: FactStorage + Resource>(
+    storage: Res<S>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut alias_writer: EventWriter<FactAliasUsed>,
+) {
+    if !story_engine.aliases_pending {
+        return;
+    }
+    let story_engine = story_engine.bypass_change_detection();
+    story_engine.aliases_pending = false;
+    for story in story_engine.stories.iter_mut() {
+        story.rename_facts(&mut |fact_name: &mut String| {
+            let target = storage.resolve(fact_name);
+            if target == fact_name.as_str() {
+                return;
+            }
+            let target = target.to_string();
+            warn!("A story uses fact {}, an alias for {}", fact_name, target);
+            alias_writer.send(FactAliasUsed {
+                alias: std::mem::replace(fact_name, target.clone()),
+                target,
+            });
+        });
     }
 }
 
@@ -263,19 +333,23 @@ pub fn button_system(
             &mut BorderColor,
             &Children,
         ),
-        (Changed<Interaction>, With<Button>),
+        (Changed<Interaction>, With<Button>, Without<ChoiceButton>, Without<AutoAdvanceButton>),
     >,
     mut text_query: Query<&mut Text>,
     mut storage: ResMut<FactsOfTheWorld>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
 ) {
     for (interaction, mut color, mut border_color, children) in &mut interaction_query {
         let mut text = text_query.get_mut(children[0]).unwrap();
         match *interaction {
             Interaction::Pressed => {
-                storage.add_to_int("button_pressed".to_string(), 1);
+                if let Err(error) = storage.add_to_int("button_pressed".to_string(), 1) {
+                    warn!("Button press not counted: {}", error);
+                }
                 text.sections[0].value = "Press".to_string();
-                *color = PRESSED_BUTTON.into();
-                border_color.0 = Color::RED;
+                *color = theme.palette(settings.palette).positive.into();
+                border_color.0 = theme.palette(settings.palette).negative;
             }
             Interaction::Hovered => {
                 text.sections[0].value =
@@ -325,45 +399,217 @@ pub fn setup(
     }
 }
 
-pub fn story_evaluator(
+// How far beat effects may cascade within a frame. When a beat's effects satisfy the next
+// beat's rules, that beat finishes in the same frame instead of the next one.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct StoryCascade {
+    // 1 evaluates once a frame, effects are then seen by rules on the frame after
+    pub max_passes_per_frame: usize,
+}
+
+impl Default for StoryCascade {
+    fn default() -> Self {
+        StoryCascade {
+            max_passes_per_frame: 8,
+        }
+    }
+}
+
+// A beat finished more than once in a single frame, its effects keep satisfying rules that
+// lead back to it. Cascading stops for the frame when this is sent.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct NarrativeCycleDetected {
+    // Story and beat names, in the order they finished this frame, ending with the repeat
+    pub chain: Vec<(String, String)>,
+}
+
+impl NarrativeCycleDetected {
+    pub fn describe(&self) -> String {
+        self.chain
+            .iter()
+            .map(|(story, beat)| format!("{}: {}", story, beat))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+}
+
+// Runs progression passes until the facts stop changing, a beat finishes twice or the cap is
+// reached. Changes left over after the last pass are picked up on the next frame.
+pub fn run_story_progression<S: FactStorage + Resource>(
+    world: &mut World,
+    mut finished_beats: Local<ManualEventReader<StoryBeatFinished>>,
+) {
+    let max_passes = world.resource::<StoryCascade>().max_passes_per_frame.max(1);
+    let log_level = *world.resource::<BeatsLogLevel>();
+    // Skip beats finished outside of progression since the last frame, e.g. by debug commands
+    finished_beats.clear(world.resource::<Events<StoryBeatFinished>>());
+    let mut chain: Vec<(String, String)> = Vec::new();
+    for pass in 0..max_passes {
+        // Everything logged during the pass, facts, beats and effects, is nested under it
+        let _pass = log_level
+            .logs(Level::DEBUG)
+            .then(|| debug_span!(target: BEATS_LOG_TARGET, "progression", pass).entered());
+        world.run_schedule(StoryProgressionPass);
+        let events = world.resource::<Events<StoryBeatFinished>>();
+        for finished in finished_beats.read(events) {
+            let beat = (finished.story.name.clone(), finished.beat.name.clone());
+            let repeated = chain.contains(&beat);
+            chain.push(beat);
+            if repeated {
+                let cycle = NarrativeCycleDetected { chain };
+                warn!("Narrative cycle detected: {}", cycle.describe());
+                world.send_event(cycle);
+                return;
+            }
+        }
+        if !world.resource::<S>().has_updates() {
+            return;
+        }
+    }
+    if max_passes > 1 {
+        warn!(
+            "Story progression still changing facts after {} passes, continuing next frame",
+            max_passes
+        );
+    }
+}
+
+pub fn story_evaluator<S: FactStorage + Resource>(
     mut fact_updated: EventReader<FactUpdated>,
+    mut fact_removed: EventReader<FactRemoved>,
     mut story_engine: ResMut<StoryEngine>,
-    cool_fact_store: Res<FactsOfTheWorld>,
+    cool_fact_store: Res<S>,
+    story_time: Res<StoryTime>,
+    mut rng: ResMut<StoryRng>,
     mut story_beat_writer: EventWriter<StoryBeatFinished>,
+    mut present_choices: EventWriter<PresentChoices>,
 ) {
-    if !fact_updated.is_empty() {
+    if !fact_updated.is_empty() || !fact_removed.is_empty() {
         fact_updated.clear();
+        fact_removed.clear();
+        let mut context = EvaluationContext::new(
+            story_time.elapsed_seconds(),
+            &mut rng,
+            &*cool_fact_store,
+        );
         for story in &mut story_engine.stories.iter_mut().filter(|s| !s.is_started) {
-            story.start_if_possible(&cool_fact_store.facts);
+            if story.start_if_possible(&mut context) {
+                if let Some(choices) = PresentChoices::for_active_beat(story) {
+                    present_choices.send(choices);
+                }
+            }
         }
 
         for story in &mut story_engine.stories.iter_mut().filter(|s| s.is_started && !s.is_finished()) {
-            match story.evaluate_active_beat(&cool_fact_store.facts) {
+            match story.evaluate_active_beat(&mut context) {
                 None => {}
                 Some(story_beat) => {
                     story_beat_writer.send(StoryBeatFinished {
                         story: story.clone(),
                         beat: story_beat.clone(),
                     });
+                    if let Some(choices) = PresentChoices::for_active_beat(story) {
+                        present_choices.send(choices);
+                    }
                 }
             }
         }
     }
 }
 
-pub fn story_beat_effect_applier(
-    mut story_beat_reader: EventReader<StoryBeatFinished>,
-    mut cool_fact_store: ResMut<FactsOfTheWorld>,
+// Lets rules wait on named UI animations through `ui.<name>.finished`
+pub fn ui_animation_facts(
+    mut finished: EventReader<UiAnimationFinished>,
+    mut storage: ResMut<FactsOfTheWorld>,
 ) {
-    for event in story_beat_reader.read() {
-        for effect in event.beat.effects.iter() {
-            effect.apply(&mut cool_fact_store);
+    for event in finished.read() {
+        if let Some(name) = &event.name {
+            if let Err(error) = storage.store_bool(format!("ui.{}.finished", name), true) {
+                warn!("UI animation {} not recorded: {}", name, error);
+            }
         }
     }
 }
 
+// Name of the fact holding the story-clock second a beat finished at
+pub fn beat_finished_at_fact(story: &str, beat: &str) -> String {
+    format!("beat.{}.{}.finished_at", story, beat)
+}
+
+pub fn story_beat_effect_applier<S: FactStorage + Resource>(
+    mut story_beat_reader: EventReader<StoryBeatFinished>,
+    mut cool_fact_store: ResMut<S>,
+    mut rng: ResMut<StoryRng>,
+    story_time: Res<StoryTime>,
+    mut effect_outputs: EventWriter<EffectOutput>,
+    mut errors: EventWriter<EngineError>,
+    log_level: Res<BeatsLogLevel>,
+) {
+    // Effects often write what a fact already holds, that shouldn't count as a change
+    write_facts(&mut cool_fact_store, |cool_fact_store| {
+        // A beat's effects are applied once, even if it was reported finished twice in one frame
+        let mut applied = HashSet::new();
+        for event in story_beat_reader.read() {
+            if !applied.insert((event.story.name.as_str(), event.beat.name.as_str())) {
+                warn!(
+                    "Beat {} of {} finished twice in one frame, applying its effects once",
+                    event.beat.name, event.story.name
+                );
+                continue;
+            }
+            let _beat = log_level.logs(Level::DEBUG).then(|| {
+                debug_span!(
+                    target: BEATS_LOG_TARGET,
+                    "beat",
+                    story = %event.story.name,
+                    beat = %event.beat.name
+                )
+                .entered()
+            });
+            if log_level.logs(Level::DEBUG) {
+                debug!(target: BEATS_LOG_TARGET, "Beat finished");
+            }
+            let finished_at = Fact::Int(
+                beat_finished_at_fact(&event.story.name, &event.beat.name),
+                story_time.elapsed_seconds() as i32,
+            );
+            if let Err(error) = cool_fact_store.try_set(finished_at) {
+                errors.send(EngineError::Effect {
+                    story: event.story.name.clone(),
+                    beat: event.beat.name.clone(),
+                    message: error.to_string(),
+                });
+            }
+            for effect in event.beat.effects.iter() {
+                if log_level.logs(Level::DEBUG) {
+                    debug!(target: BEATS_LOG_TARGET, ?effect, "Applying effect");
+                }
+                // Catches what the effect's own checks don't, e.g. a panicking script
+                let applied = recover(|| effect.apply(&mut *cool_fact_store, &mut rng))
+                    .and_then(|applied| applied.map_err(|error| error.to_string()));
+                match applied {
+                    Ok(outputs) => {
+                        if log_level.logs(Level::TRACE) && !outputs.is_empty() {
+                            trace!(target: BEATS_LOG_TARGET, ?outputs, "Effect asked for");
+                        }
+                        effect_outputs.send_batch(outputs);
+                    }
+                    Err(message) => {
+                        errors.send(EngineError::Effect {
+                            story: event.story.name.clone(),
+                            beat: event.beat.name.clone(),
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+    });
+}
+
 pub fn setup_stories(
     mut story_engine: ResMut<StoryEngine>,
+    mut errors: EventWriter<EngineError>,
 ) {
     /*
     Let's imagine two stories. One that simply requires that the button is pressed three times.
@@ -374,36 +620,43 @@ pub fn setup_stories(
     This could be a simple case of enum variants to be used for this.
 
      */
-    let story = StoryBuilder::new("Hero's Journey")
-        .add_pre_requisite("Before We Start", |pre_req| {
-            pre_req.with_condition(Condition::IntMoreThan {
-                fact_name: "button_pressed".to_string(),
-                expected_value: 1,
-            })
-        })
-        .add_story_beat("The Call to Adventure", |beat| {
-            beat.with_rule("Enough Presses", |rule| {
-                rule.with_condition(Condition::IntMoreThan {
-                    fact_name: "button_pressed".to_string(),
-                    expected_value: 3,
-                })
-            })
-                .with_effects(|effects| {
-                    effects.set_fact_bool("quest_one_complete", true)
-                })
-        })
-        .add_story_beat("The Road of Trials", |beat| {
-            beat.with_rule("DefeatedEnemies", |rule| {
-                rule.with_condition(Condition::IntMoreThan {
+    let story = crate::story! {
+        "Hero's Journey" {
+            requires "Before We Start" {
+                Condition::IntMoreThan {
                     fact_name: "button_pressed".to_string(),
-                    expected_value: 5,
-                })
-            })
-                .with_effects(|effects| {
-                    effects.set_fact_bool("quest_two_complete", true)
-                })
-        })
-        .build();
+                    expected_value: 1,
+                }
+            }
+            beat "The Call to Adventure" {
+                rule "Enough Presses" {
+                    Condition::IntMoreThan {
+                        fact_name: "button_pressed".to_string(),
+                        expected_value: 3,
+                    }
+                }
+                effects { set_fact_bool("quest_one_complete", true) }
+            }
+            beat "The Road of Trials" {
+                rule "DefeatedEnemies" {
+                    Condition::IntMoreThan {
+                        fact_name: "button_pressed".to_string(),
+                        expected_value: 5,
+                    }
+                }
+                effects { set_fact_bool("quest_two_complete", true) }
+            }
+        }
+    };
 
-    story_engine.add_story(story);
+    match story {
+        // Entered again after the menu or a restore, keep the progress instead of a second copy
+        Ok(story) => story_engine.add_or_replace_story(story),
+        Err(error) => {
+            errors.send(EngineError::InvalidStory {
+                story: "Hero's Journey".to_string(),
+                message: error.to_string(),
+            });
+        }
+    }
 }
@@ -0,0 +1,198 @@
+use crate::beats::choices::ChoiceMade;
+use crate::beats::data::StoryBeatFinished;
+use crate::beats::save_location::SaveLocation;
+use crate::beats::story_time::StoryTime;
+use crate::settings::Settings;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TelemetryEvent {
+    // Story time in seconds, which stops while the story is paused and carries over saves
+    pub at: f64,
+    #[serde(flatten)]
+    pub kind: TelemetryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum TelemetryKind {
+    BeatFinished {
+        story: String,
+        beat: String,
+        // Time since the previous beat of the same story finished, or since the player opted in
+        seconds_spent: f64,
+    },
+    ChoiceMade {
+        story: String,
+        beat: String,
+        choice: usize,
+    },
+}
+
+// Where telemetry events end up. Swap in your own to send them somewhere else.
+pub trait TelemetrySink: Send + Sync {
+    fn record(&mut self, event: &TelemetryEvent);
+
+    fn flush(&mut self) {}
+}
+
+pub const TELEMETRY_FILE: &str = "telemetry.jsonl";
+
+// Appends one JSON object per line to a file, or logs them on wasm
+pub struct JsonlTelemetrySink {
+    #[cfg(not(target_arch = "wasm32"))]
+    writer: Option<std::io::BufWriter<std::fs::File>>,
+}
+
+impl JsonlTelemetrySink {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(path: impl AsRef<std::path::Path>) -> Self {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref());
+        let writer = match file {
+            Ok(file) => Some(std::io::BufWriter::new(file)),
+            Err(error) => {
+                warn!("Could not open telemetry file {:?}: {}", path.as_ref(), error);
+                None
+            }
+        };
+        JsonlTelemetrySink { writer }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(_path: impl AsRef<std::path::Path>) -> Self {
+        JsonlTelemetrySink {}
+    }
+
+    // Appends to the named file next to the saves
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn in_location(location: &SaveLocation, name: &str) -> Self {
+        if let Err(error) = std::fs::create_dir_all(location.directory()) {
+            warn!("Could not create directory for {}: {}", name, error);
+        }
+        JsonlTelemetrySink::new(location.file(name))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn in_location(_location: &SaveLocation, name: &str) -> Self {
+        JsonlTelemetrySink::new(name)
+    }
+}
+
+impl TelemetrySink for JsonlTelemetrySink {
+    fn record(&mut self, event: &TelemetryEvent) {
+        let line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(error) => {
+                warn!("Could not serialize telemetry event: {}", error);
+                return;
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(writer) = self.writer.as_mut() {
+            use std::io::Write;
+            if let Err(error) = writeln!(writer, "{}", line) {
+                warn!("Could not write telemetry event: {}", error);
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        info!("telemetry {}", line);
+    }
+
+    fn flush(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(writer) = self.writer.as_mut() {
+            use std::io::Write;
+            let _ = writer.flush();
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Telemetry {
+    sink: Box<dyn TelemetrySink>,
+    // When the player opted in, what the first beat of each story is timed from
+    recording_since: Option<f64>,
+    last_progress_at: HashMap<String, f64>,
+}
+
+impl Telemetry {
+    pub fn new(sink: impl TelemetrySink + 'static) -> Self {
+        Telemetry {
+            sink: Box::new(sink),
+            recording_since: None,
+            last_progress_at: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: TelemetryEvent) {
+        self.sink.record(&event);
+    }
+}
+
+pub fn record_story_telemetry(
+    settings: Res<Settings>,
+    telemetry: Option<ResMut<Telemetry>>,
+    location: Res<SaveLocation>,
+    mut commands: Commands,
+    story_time: Res<StoryTime>,
+    mut story_beat_finished: EventReader<StoryBeatFinished>,
+    mut choices_made: EventReader<ChoiceMade>,
+) {
+    let now = story_time.elapsed_seconds();
+    if !settings.telemetry_enabled {
+        story_beat_finished.clear();
+        choices_made.clear();
+        // Opting in again starts the timing over
+        if let Some(mut telemetry) = telemetry {
+            if telemetry.recording_since.is_some() {
+                telemetry.recording_since = None;
+                telemetry.last_progress_at.clear();
+            }
+        }
+        return;
+    }
+    // The sink is only created once the player opts in, so no file appears otherwise
+    let Some(mut telemetry) = telemetry else {
+        let mut telemetry =
+            Telemetry::new(JsonlTelemetrySink::in_location(&location, TELEMETRY_FILE));
+        telemetry.recording_since = Some(now);
+        commands.insert_resource(telemetry);
+        return;
+    };
+    let opted_in_at = *telemetry.recording_since.get_or_insert(now);
+    let mut recorded = false;
+    for event in story_beat_finished.read() {
+        let since = telemetry
+            .last_progress_at
+            .insert(event.story.name.clone(), now)
+            .unwrap_or(opted_in_at);
+        telemetry.record(TelemetryEvent {
+            at: now,
+            kind: TelemetryKind::BeatFinished {
+                story: event.story.name.clone(),
+                beat: event.beat.name.clone(),
+                seconds_spent: now - since,
+            },
+        });
+        recorded = true;
+    }
+    for made in choices_made.read() {
+        telemetry.record(TelemetryEvent {
+            at: now,
+            kind: TelemetryKind::ChoiceMade {
+                story: made.story.clone(),
+                beat: made.beat.clone(),
+                choice: made.index,
+            },
+        });
+        recorded = true;
+    }
+    if recorded {
+        telemetry.sink.flush();
+    }
+}
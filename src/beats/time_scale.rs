@@ -0,0 +1,73 @@
+use crate::beats::data::{EffectOutput, TimeScale};
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use std::time::Duration;
+
+// Slowest the clock is run at. Zero would freeze the story until the slow motion ends, and the
+// virtual clock can't run backwards.
+pub const MIN_TIME_SCALE: f32 = 0.01;
+
+// A running `Effect::SetTimeScale`. Counted down in real time, since the virtual clock is the
+// one being scaled.
+#[derive(Resource, Debug)]
+pub struct SlowMotion {
+    remaining: Option<Duration>,
+    // Pitch audio along with the clock
+    pub scale_audio: bool,
+}
+
+impl Default for SlowMotion {
+    fn default() -> Self {
+        SlowMotion {
+            remaining: None,
+            scale_audio: true,
+        }
+    }
+}
+
+impl SlowMotion {
+    pub fn is_active(&self) -> bool {
+        self.remaining.is_some()
+    }
+}
+
+fn set_time_scale(scale: f32, slow_motion: &SlowMotion, time: &mut Time<Virtual>, audio: &Audio) {
+    time.set_relative_speed(scale);
+    if slow_motion.scale_audio {
+        audio.set_playback_rate(scale as f64);
+    }
+}
+
+pub fn apply_time_scale(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut slow_motion: ResMut<SlowMotion>,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    audio: Res<Audio>,
+) {
+    for output in effect_outputs.read() {
+        if let EffectOutput::SetTimeScale(TimeScale(scale), duration) = output {
+            let scale = scale.max(MIN_TIME_SCALE);
+            set_time_scale(scale, &slow_motion, &mut virtual_time, &audio);
+            slow_motion.remaining = Some(*duration);
+        }
+    }
+    let Some(remaining) = slow_motion.remaining else {
+        return;
+    };
+    match remaining.checked_sub(real_time.delta()) {
+        Some(remaining) if !remaining.is_zero() => slow_motion.remaining = Some(remaining),
+        _ => reset_time_scale(slow_motion, virtual_time, audio),
+    }
+}
+
+// Back to normal speed, also when leaving the story in the middle of a slow motion moment
+pub fn reset_time_scale(
+    mut slow_motion: ResMut<SlowMotion>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    audio: Res<Audio>,
+) {
+    if slow_motion.remaining.take().is_some() {
+        set_time_scale(1.0, &slow_motion, &mut virtual_time, &audio);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::beats::data::{FactsOfTheWorld, StoryEngine};
+use crate::beats::scripting;
+use crate::settings::Settings;
+use crate::ui::theme::UiTheme;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+// Expressions pinned next to the inspector, e.g. `coins + gems * 10 > 50`.
+// They are evaluated against the fact store every frame while the window is drawn.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FactWatches {
+    pub expressions: Vec<String>,
+    new_expression: String,
+}
+
+impl FactWatches {
+    pub fn pin(&mut self, expression: impl Into<String>) {
+        self.expressions.push(expression.into());
+    }
+}
+
+pub(crate) fn egui_color(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.as_rgba_u8();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+pub fn fact_watch_window(
+    mut contexts: EguiContexts,
+    mut watches: ResMut<FactWatches>,
+    storage: Res<FactsOfTheWorld>,
+    story_engine: Res<StoryEngine>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+) {
+    let watches = watches.as_mut();
+    let palette = theme.palette(settings.palette);
+    egui::Window::new("Fact watches").show(contexts.ctx_mut(), |ui| {
+        let mut unpinned = None;
+        egui::Grid::new("fact_watches")
+            .striped(true)
+            .show(ui, |ui| {
+                for (index, expression) in watches.expressions.iter().enumerate() {
+                    ui.monospace(expression);
+                    match scripting::watch_script(expression, &storage.facts) {
+                        Ok(value) => ui.monospace(value),
+                        Err(error) => ui.colored_label(egui_color(palette.negative), error),
+                    };
+                    if ui.small_button("x").clicked() {
+                        unpinned = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = unpinned {
+            watches.expressions.remove(index);
+        }
+        ui.horizontal(|ui| {
+            let input = ui.text_edit_singleline(&mut watches.new_expression);
+            let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (ui.button("Pin").clicked() || submitted) && !watches.new_expression.is_empty() {
+                let expression = std::mem::take(&mut watches.new_expression);
+                watches.expressions.push(expression);
+            }
+        });
+        // Whether each rule of the beats being played currently holds
+        ui.collapsing("Active rules", |ui| {
+            for story in story_engine.stories.iter().filter(|story| story.is_started) {
+                let Some(beat) = story.active_beat() else {
+                    continue;
+                };
+                for rule in beat.rules.iter() {
+                    let color = if rule.evaluate(&*storage) {
+                        palette.positive
+                    } else {
+                        palette.negative
+                    };
+                    ui.colored_label(
+                        egui_color(color),
+                        format!("{} / {} / {}", story.name, beat.name, rule.name),
+                    );
+                }
+            }
+        });
+    });
+}
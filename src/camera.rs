@@ -0,0 +1,136 @@
+use crate::beats::data::EffectOutput;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+pub struct CameraPlugin;
+
+/// The game's one 2D camera, which follows its target, stays inside the level bounds and
+/// shakes on `Effect::CameraShake`
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_camera).add_systems(
+            PostUpdate,
+            (add_shake_trauma, move_camera_rig)
+                .chain()
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct CameraRig {
+    // How quickly the camera catches up with its target, higher is snappier. Zero snaps.
+    pub follow_speed: f32,
+    // Area the view stays inside, in world units. A level smaller than the view is centred.
+    pub bounds: Option<Rect>,
+    // 0.0 to 1.0, the shake is trauma squared so small knocks stay subtle
+    pub trauma: f32,
+    // Trauma lost per second
+    pub trauma_decay: f32,
+    // Furthest the shake moves the camera, in world units, and turns it, in radians
+    pub max_shake_offset: f32,
+    pub max_shake_roll: f32,
+    // Where the camera looks before shake is added
+    pub focus: Vec2,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        CameraRig {
+            follow_speed: 5.,
+            bounds: None,
+            trauma: 0.,
+            trauma_decay: 1.5,
+            max_shake_offset: 12.,
+            max_shake_roll: 0.05,
+            focus: Vec2::ZERO,
+        }
+    }
+}
+
+impl CameraRig {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0., 1.);
+    }
+}
+
+// The entity the camera follows, if there is more than one the first found is used
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct CameraTarget;
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), CameraRig::default()));
+}
+
+fn add_shake_trauma(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut rigs: Query<&mut CameraRig>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::CameraShake(trauma) = output else {
+            continue;
+        };
+        for mut rig in rigs.iter_mut() {
+            rig.add_trauma(trauma.0);
+        }
+    }
+}
+
+pub(crate) fn move_camera_rig(
+    time: Res<Time>,
+    targets: Query<&GlobalTransform, (With<CameraTarget>, Without<CameraRig>)>,
+    mut rigs: Query<(&mut CameraRig, &mut Transform, &OrthographicProjection)>,
+) {
+    let delta = time.delta_seconds();
+    let target = targets
+        .iter()
+        .next()
+        .map(|transform| transform.translation().truncate());
+    for (mut rig, mut transform, projection) in rigs.iter_mut() {
+        if let Some(target) = target {
+            // Frame rate independent easing towards the target
+            let catch_up = if rig.follow_speed > 0. {
+                1. - (-rig.follow_speed * delta).exp()
+            } else {
+                1.
+            };
+            rig.focus = rig.focus.lerp(target, catch_up);
+        }
+        if let Some(bounds) = rig.bounds {
+            rig.focus = clamp_to_bounds(rig.focus, bounds, projection.area.half_size());
+        }
+        rig.trauma = (rig.trauma - rig.trauma_decay * delta).max(0.);
+
+        let shake = rig.trauma * rig.trauma;
+        let seconds = time.elapsed_seconds();
+        let offset = Vec2::new(wobble(seconds, 0.), wobble(seconds, 1.)) * rig.max_shake_offset;
+        transform.translation.x = rig.focus.x + offset.x * shake;
+        transform.translation.y = rig.focus.y + offset.y * shake;
+        transform.rotation =
+            Quat::from_rotation_z(wobble(seconds, 2.) * rig.max_shake_roll * shake);
+    }
+}
+
+// Keeps a view of `half_size` around `focus` inside `bounds`
+fn clamp_to_bounds(focus: Vec2, bounds: Rect, half_size: Vec2) -> Vec2 {
+    let min = bounds.min + half_size;
+    let max = bounds.max - half_size;
+    Vec2::new(
+        if min.x > max.x {
+            bounds.center().x
+        } else {
+            focus.x.clamp(min.x, max.x)
+        },
+        if min.y > max.y {
+            bounds.center().y
+        } else {
+            focus.y.clamp(min.y, max.y)
+        },
+    )
+}
+
+// Smooth noise from -1.0 to 1.0, a different curve for each seed
+fn wobble(seconds: f32, seed: f32) -> f32 {
+    let t = seconds * 25. + seed * 17.;
+    ((t.sin() + (t * 1.7 + seed).sin() * 0.5) / 1.5).clamp(-1., 1.)
+}
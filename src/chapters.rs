@@ -0,0 +1,379 @@
+use crate::beats::data::{Fact, FactsOfTheWorld, Story, StoryEngine};
+use crate::beats::rng::{StoryRng, StoryRngSeeded};
+use crate::beats::save::{SaveGame, StoryProgress};
+use crate::beats::save_location::SaveLocation;
+use crate::beats::sorted;
+use crate::beats::story_time::StoryTime;
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashSet};
+use serde::{Deserialize, Serialize};
+
+// Story metadata key marking a story as a chapter, the value orders the chapters
+pub const CHAPTER_KEY: &str = "chapter";
+pub const CHAPTER_PROGRESS_FILE: &str = "chapters.ron";
+// Chapter start snapshots bundled with the game, relative to the assets folder
+pub const CHAPTER_FILES: &[&str] = &["chapters/lost_barnacle.chapter.ron"];
+
+pub struct ChaptersPlugin;
+
+/// Chapter select, reached from the menu. A chapter unlocks once the one before it has been
+/// finished in any run, and starts from the facts in its `.chapter.ron` snapshot.
+impl Plugin for ChaptersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ChapterStart>()
+            .init_asset_loader::<ChapterStartLoader>()
+            .init_resource::<ChapterProgress>()
+            .init_resource::<ChapterFiles>()
+            .add_systems(Startup, (load_chapter_files, load_chapter_progress))
+            .add_systems(
+                Update,
+                record_finished_chapters.run_if(in_state(GameState::Story)),
+            )
+            .add_systems(OnEnter(GameState::ChapterSelect), setup_chapter_select)
+            .add_systems(
+                Update,
+                (chapter_button_system, leave_chapter_select)
+                    .run_if(in_state(GameState::ChapterSelect)),
+            )
+            .add_systems(OnExit(GameState::ChapterSelect), cleanup_chapter_select);
+    }
+}
+
+// The facts a chapter starts with, curated so it plays as if the earlier chapters happened
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct ChapterStart {
+    // Name of the chapter's story
+    pub story: String,
+    pub facts: Vec<Fact>,
+}
+
+#[derive(Debug)]
+pub enum ChapterStartLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for ChapterStartLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChapterStartLoadError::Io(error) => write!(f, "could not read chapter file: {}", error),
+            ChapterStartLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ChapterStartLoadError {}
+
+impl From<std::io::Error> for ChapterStartLoadError {
+    fn from(error: std::io::Error) -> Self {
+        ChapterStartLoadError::Io(error)
+    }
+}
+
+#[derive(Default)]
+pub struct ChapterStartLoader;
+
+impl AssetLoader for ChapterStartLoader {
+    type Asset = ChapterStart;
+    type Settings = ();
+    type Error = ChapterStartLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            ron::from_str(&source).map_err(ChapterStartLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["chapter.ron"]
+    }
+}
+
+// Keeps the chapter files loaded
+#[derive(Resource, Default)]
+pub struct ChapterFiles(pub Vec<Handle<ChapterStart>>);
+
+// Chapters finished in any run, kept apart from saves so starting over keeps them unlocked
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChapterProgress {
+    #[serde(serialize_with = "sorted::set")]
+    pub completed: HashSet<String>,
+}
+
+impl ChapterProgress {
+    // The first chapter is always open, the others once the chapter before has been finished
+    pub fn is_unlocked(&self, chapters: &[&Story], index: usize) -> bool {
+        index == 0
+            || chapters
+                .get(index - 1)
+                .is_some_and(|previous| self.completed.contains(&previous.name))
+    }
+}
+
+// Stories marked as chapters, in chapter order
+pub fn chapters(story_engine: &StoryEngine) -> Vec<&Story> {
+    let mut chapters: Vec<(i32, &Story)> = story_engine
+        .stories
+        .iter()
+        .filter_map(|story| {
+            let order = story.metadata.get(CHAPTER_KEY)?;
+            Some((order.trim().parse().unwrap_or_default(), story))
+        })
+        .collect();
+    chapters.sort_by_key(|(order, _)| *order);
+    chapters.into_iter().map(|(_, story)| story).collect()
+}
+
+#[derive(Component)]
+struct ChapterSelectScreen;
+
+#[derive(Component)]
+enum ChapterButton {
+    Start(String),
+    Back,
+}
+
+const CHAPTER_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_CHAPTER_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const LOCKED_CHAPTER_BUTTON: Color = Color::rgb(0.08, 0.08, 0.08);
+
+fn load_chapter_files(asset_server: Res<AssetServer>, mut chapter_files: ResMut<ChapterFiles>) {
+    for path in CHAPTER_FILES {
+        chapter_files.0.push(asset_server.load(*path));
+    }
+}
+
+fn load_chapter_progress(mut progress: ResMut<ChapterProgress>, location: Res<SaveLocation>) {
+    let Ok(source) = location.read(CHAPTER_PROGRESS_FILE) else {
+        return;
+    };
+    match ron::from_str(&source) {
+        Ok(loaded) => *progress = loaded,
+        Err(error) => warn!("Could not read {}: {}", CHAPTER_PROGRESS_FILE, error),
+    }
+}
+
+fn record_finished_chapters(
+    story_engine: Res<StoryEngine>,
+    mut progress: ResMut<ChapterProgress>,
+    location: Res<SaveLocation>,
+) {
+    if !story_engine.is_changed() {
+        return;
+    }
+    let finished: Vec<String> = chapters(&story_engine)
+        .into_iter()
+        .filter(|story| story.is_finished() && !progress.completed.contains(&story.name))
+        .map(|story| story.name.clone())
+        .collect();
+    if finished.is_empty() {
+        return;
+    }
+    progress.completed.extend(finished);
+    match ron::ser::to_string_pretty(&*progress, ron::ser::PrettyConfig::default()) {
+        Ok(source) => location.write(CHAPTER_PROGRESS_FILE, &source),
+        Err(error) => warn!("Could not serialize chapter progress: {}", error),
+    }
+}
+
+fn setup_chapter_select(
+    mut commands: Commands,
+    story_engine: Res<StoryEngine>,
+    progress: Res<ChapterProgress>,
+) {
+    let chapters = chapters(&story_engine);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            ChapterSelectScreen,
+            UiLayer::Modal,
+        ))
+        .with_children(|screen| {
+            screen.spawn(chapter_text("Chapters", 40.0));
+            for (index, story) in chapters.iter().enumerate() {
+                let unlocked = progress.is_unlocked(&chapters, index);
+                let label = if unlocked {
+                    format!("{}. {}", index + 1, story.name)
+                } else {
+                    format!("{}. Locked", index + 1)
+                };
+                let mut button = screen.spawn(ButtonBundle {
+                    style: chapter_button_style(),
+                    background_color: if unlocked {
+                        CHAPTER_BUTTON
+                    } else {
+                        LOCKED_CHAPTER_BUTTON
+                    }
+                    .into(),
+                    ..default()
+                });
+                // Locked chapters are shown but can't be picked
+                if unlocked {
+                    button.insert(ChapterButton::Start(story.name.clone()));
+                }
+                button.with_children(|parent| {
+                    parent.spawn(chapter_text(&label, 24.0));
+                });
+            }
+            screen
+                .spawn((
+                    ButtonBundle {
+                        style: chapter_button_style(),
+                        background_color: CHAPTER_BUTTON.into(),
+                        ..default()
+                    },
+                    ChapterButton::Back,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(chapter_text("Back", 24.0));
+                });
+        });
+}
+
+fn chapter_button_style() -> Style {
+    Style {
+        width: Val::Px(320.0),
+        height: Val::Px(44.0),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+fn chapter_text(text: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn chapter_button_system(
+    mut buttons: Query<(&Interaction, &ChapterButton, &mut BackgroundColor), Changed<Interaction>>,
+    chapter_starts: Res<Assets<ChapterStart>>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut rng: ResMut<StoryRng>,
+    mut seeded: ResMut<StoryRngSeeded>,
+    mut story_time: ResMut<StoryTime>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match button {
+                ChapterButton::Start(story) => {
+                    start_chapter(
+                        story,
+                        &chapter_starts,
+                        &mut facts,
+                        &mut story_engine,
+                        &mut rng,
+                        &mut story_time,
+                    );
+                    seeded.0 = true;
+                    next_state.set(GameState::Story);
+                }
+                ChapterButton::Back => next_state.set(GameState::Menu),
+            },
+            Interaction::Hovered => *color = HOVERED_CHAPTER_BUTTON.into(),
+            Interaction::None => *color = CHAPTER_BUTTON.into(),
+        }
+    }
+}
+
+// Replaces the facts with the chapter's snapshot and puts every story where it would be at the
+// start of the chapter: earlier chapters finished, the rest not started. Goes through
+// `SaveGame::restore` so rules and UI catch up the same way they do after loading.
+fn start_chapter(
+    story: &str,
+    chapter_starts: &Assets<ChapterStart>,
+    facts: &mut FactsOfTheWorld,
+    story_engine: &mut StoryEngine,
+    rng: &mut StoryRng,
+    story_time: &mut StoryTime,
+) {
+    let snapshot = chapter_starts
+        .iter()
+        .find(|(_, start)| start.story == story)
+        .map(|(_, start)| start.facts.clone())
+        .unwrap_or_else(|| {
+            warn!("No chapter start for {}, starting without facts", story);
+            Vec::new()
+        });
+    let chapters = chapters(story_engine);
+    let position = chapters.iter().position(|chapter| chapter.name == story);
+    let earlier: Vec<String> = chapters[..position.unwrap_or_default()]
+        .iter()
+        .map(|chapter| chapter.name.clone())
+        .collect();
+    let save = SaveGame {
+        version: 1,
+        facts: snapshot
+            .into_iter()
+            .map(|fact| (fact.key().to_string(), fact))
+            .collect(),
+        stories: story_engine
+            .stories
+            .iter()
+            .map(|story| {
+                let finished = earlier.contains(&story.name);
+                StoryProgress {
+                    name: story.name.clone(),
+                    version: story.version,
+                    is_started: finished,
+                    active_beat_index: if finished { story.beats.len() } else { 0 },
+                    active_beat: None,
+                    finished_beats: None,
+                }
+            })
+            .collect(),
+        rng: rng.clone(),
+        time: StoryTime::default(),
+    };
+    save.restore(facts, story_engine, rng, story_time);
+}
+
+fn leave_chapter_select(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn cleanup_chapter_select(
+    mut commands: Commands,
+    screens: Query<Entity, With<ChapterSelectScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,305 @@
+use crate::beats::data::{Condition, FactMutation, FactsOfTheWorld};
+use crate::ui::layers::UiLayer;
+use crate::ui::toasts::ShowToast;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+pub const CODEX_FILE: &str = "codex.ron";
+// List fact of the entry ids unlocked so far, saved with the other facts
+pub const CODEX_UNLOCKED_FACT: &str = "codex.unlocked";
+// Int fact counting entries unlocked since the codex was last opened, for the menu badge
+pub const CODEX_UNSEEN_FACT: &str = "codex.unseen";
+
+pub struct CodexPlugin;
+
+/// Lore entries from `assets/codex.ron` that unlock when their condition passes against the
+/// facts. Each unlock shows a toast and bumps `codex.unseen` until the codex screen is opened.
+impl Plugin for CodexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<CodexEntries>()
+            .init_asset_loader::<CodexEntriesLoader>()
+            .add_event::<CodexEntryUnlocked>()
+            .add_systems(Startup, load_codex)
+            .add_systems(Update, unlock_codex_entries)
+            .add_systems(OnEnter(GameState::Codex), setup_codex)
+            .add_systems(
+                Update,
+                (codex_button_system, leave_codex).run_if(in_state(GameState::Codex)),
+            )
+            .add_systems(OnExit(GameState::Codex), cleanup_codex);
+    }
+}
+
+// (
+//     entries: [
+//         (id: "harbour", title: "The Harbour", text: "..", unlock: BoolEquals(fact_name: "arrived", expected_value: true)),
+//     ],
+// )
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct CodexEntries {
+    pub entries: Vec<CodexEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CodexEntry {
+    pub id: String,
+    pub title: String,
+    pub text: String,
+    // Entries without a condition are there from the start
+    #[serde(default)]
+    pub unlock: Option<Condition>,
+}
+
+impl CodexEntries {
+    // Entries whose condition passes but that haven't been recorded as unlocked yet
+    pub fn newly_unlocked(&self, facts: &FactsOfTheWorld) -> Vec<&CodexEntry> {
+        let unlocked = facts.get_list(CODEX_UNLOCKED_FACT);
+        self.entries
+            .iter()
+            .filter(|entry| !unlocked.is_some_and(|unlocked| unlocked.contains(&entry.id)))
+            .filter(|entry| {
+                entry
+                    .unlock
+                    .as_ref()
+                    .is_none_or(|condition| condition.evaluate(&*facts))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum CodexLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for CodexLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodexLoadError::Io(error) => write!(f, "could not read codex: {}", error),
+            CodexLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CodexLoadError {}
+
+#[derive(Default)]
+pub struct CodexEntriesLoader;
+
+impl AssetLoader for CodexEntriesLoader {
+    type Asset = CodexEntries;
+    type Settings = ();
+    type Error = CodexLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(CodexLoadError::Io)?;
+            ron::from_str(&source).map_err(CodexLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["codex.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct CodexHandle(pub Handle<CodexEntries>);
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CodexEntryUnlocked {
+    pub id: String,
+}
+
+// Number of entries unlocked since the codex was last opened
+pub fn unseen_codex_entries(facts: &FactsOfTheWorld) -> i32 {
+    facts
+        .get_int(CODEX_UNSEEN_FACT)
+        .copied()
+        .unwrap_or_default()
+}
+
+#[derive(Component)]
+struct CodexScreen;
+
+#[derive(Component)]
+struct CodexBackButton;
+
+const CODEX_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_CODEX_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+
+fn load_codex(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CodexHandle(asset_server.load(CODEX_FILE)));
+}
+
+fn unlock_codex_entries(
+    mut facts: ResMut<FactsOfTheWorld>,
+    handle: Res<CodexHandle>,
+    codex: Res<Assets<CodexEntries>>,
+    mut checked: Local<bool>,
+    mut unlocked: EventWriter<CodexEntryUnlocked>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let Some(codex) = codex.get(&handle.0) else {
+        return;
+    };
+    // Facts written by this system itself don't count as a change the next time it runs
+    if *checked && !facts.is_changed() {
+        return;
+    }
+    *checked = true;
+    let entries: Vec<(String, String)> = codex
+        .newly_unlocked(&facts)
+        .into_iter()
+        .map(|entry| (entry.id.clone(), entry.title.clone()))
+        .collect();
+    if !entries.is_empty() {
+        let mut mutations: Vec<FactMutation> = entries
+            .iter()
+            .map(|(id, _)| FactMutation::AddToList(CODEX_UNLOCKED_FACT.to_string(), id.clone()))
+            .collect();
+        mutations.push(FactMutation::StoreInt(
+            CODEX_UNSEEN_FACT.to_string(),
+            unseen_codex_entries(&facts) + entries.len() as i32,
+        ));
+        match facts.apply_batch(mutations) {
+            Ok(()) => {
+                // Lore carries over into new game plus like other unlocks
+                facts.tag(CODEX_UNLOCKED_FACT, "unlock");
+                for (id, title) in entries {
+                    toasts.send(ShowToast(format!("Codex entry unlocked: {}", title)));
+                    unlocked.send(CodexEntryUnlocked { id });
+                }
+            }
+            Err(error) => warn!("Could not record codex unlocks: {}", error),
+        }
+    }
+}
+
+fn setup_codex(
+    mut commands: Commands,
+    mut facts: ResMut<FactsOfTheWorld>,
+    handle: Res<CodexHandle>,
+    codex: Res<Assets<CodexEntries>>,
+) {
+    if unseen_codex_entries(&facts) != 0 {
+        if let Err(error) = facts.apply_batch(vec![FactMutation::StoreInt(
+            CODEX_UNSEEN_FACT.to_string(),
+            0,
+        )]) {
+            warn!("Could not clear {}: {}", CODEX_UNSEEN_FACT, error);
+        }
+    }
+    let unlocked = facts.get_list(CODEX_UNLOCKED_FACT);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            CodexScreen,
+            UiLayer::Modal,
+        ))
+        .with_children(|screen| {
+            screen.spawn(codex_text("Codex", 40.0));
+            for entry in codex
+                .get(&handle.0)
+                .map(|codex| codex.entries.as_slice())
+                .unwrap_or_default()
+            {
+                // Locked entries keep their place so the player can see how much is left
+                if unlocked.is_some_and(|unlocked| unlocked.contains(&entry.id)) {
+                    screen.spawn(codex_text(&entry.title, 28.0));
+                    screen.spawn(codex_text(&entry.text, 20.0).with_style(Style {
+                        max_width: Val::Px(640.0),
+                        ..default()
+                    }));
+                } else {
+                    screen.spawn(codex_text("???", 28.0));
+                }
+            }
+            screen
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(320.0),
+                            height: Val::Px(44.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: CODEX_BUTTON.into(),
+                        ..default()
+                    },
+                    CodexBackButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(codex_text("Back", 24.0));
+                });
+        });
+}
+
+fn codex_text(text: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+fn codex_button_system(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<CodexBackButton>),
+    >,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => next_state.set(GameState::Menu),
+            Interaction::Hovered => *color = HOVERED_CODEX_BUTTON.into(),
+            Interaction::None => *color = CODEX_BUTTON.into(),
+        }
+    }
+}
+
+fn leave_codex(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn cleanup_codex(mut commands: Commands, screens: Query<Entity, With<CodexScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,54 @@
+use crate::beats::save_location::SaveLocation;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const GAME_CONFIG_FILE: &str = "assets/game.ron";
+
+// Window setup read from `assets/game.ron`, so forks can rename the game without code changes.
+// The window is created before the asset server runs, so this is read directly.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub title: String,
+    // Relative to the assets folder, only used on windows and X11
+    pub icon: Option<String>,
+    // Selector of the canvas to draw into on the web
+    pub canvas: Option<String>,
+    pub saves: SaveLocation,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            title: "Barnacle Beats".to_string(),
+            icon: None,
+            canvas: Some("#bevy".to_string()),
+            saves: SaveLocation::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    // The file next to the game wins, the copy built into the binary covers the web
+    pub fn load() -> Self {
+        let source =
+            read_config().unwrap_or_else(|| include_str!("../assets/game.ron").to_string());
+        match ron::from_str(&source) {
+            Ok(config) => config,
+            Err(error) => {
+                warn!("Could not read {}: {}", GAME_CONFIG_FILE, error);
+                GameConfig::default()
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_config() -> Option<String> {
+    std::fs::read_to_string(GAME_CONFIG_FILE).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_config() -> Option<String> {
+    None
+}
@@ -0,0 +1,289 @@
+use crate::beats::data::{Fact, Rule, Story, StoryEngine};
+use crate::beats::debug::DebugCommand;
+use crate::beats::fact_table::{store_fact_tables, FactTable, FactTableFiles};
+use crate::beats::story_asset::{register_loaded_stories, StoryAsset, StoryFiles};
+use crate::mods::{ModIndex, ModPack, Mods};
+use crate::ui::theme::UiTheme;
+use crate::ui::toasts::ShowToast;
+use bevy::asset::{AssetLoadFailedEvent, UntypedAssetId};
+use bevy::prelude::*;
+use bevy::utils::hashbrown::{HashMap, HashSet};
+
+pub struct ContentReloadPlugin;
+
+/// Reloads the narrative content in one go with `DebugCommand::ReloadContent`, F10 in debug
+/// builds. Story files, data tables and mods are read from disk again and the mods merged on
+/// top, while the fact store and story progress stay as they were. A toast says what changed.
+impl Plugin for ContentReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ContentReload>().add_systems(
+            Update,
+            (
+                start_content_reload,
+                track_reloaded::<StoryAsset>,
+                track_reloaded::<FactTable>,
+                track_reloaded::<ModIndex>,
+                track_reloaded::<ModPack>,
+                finish_content_reload,
+            )
+                .chain()
+                .after(register_loaded_stories)
+                .after(store_fact_tables),
+        );
+    }
+}
+
+// The content a reload can change, taken before and after to tell what did
+#[derive(Debug, Clone, Default)]
+pub struct ContentSnapshot {
+    pub stories: Vec<Story>,
+    // From every data table, the packs' included
+    pub table_facts: Vec<Fact>,
+    pub theme: Option<UiTheme>,
+}
+
+impl ContentSnapshot {
+    pub fn take(
+        story_engine: &StoryEngine,
+        tables: &Assets<FactTable>,
+        theme: Option<&UiTheme>,
+    ) -> Self {
+        ContentSnapshot {
+            stories: story_engine.stories.clone(),
+            table_facts: tables
+                .iter()
+                .flat_map(|(_, table)| table.facts.iter().cloned())
+                .collect(),
+            theme: theme.cloned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentChanges {
+    pub stories_added: usize,
+    pub stories_changed: usize,
+    // Prerequisites and beat rules added, changed or gone
+    pub rules_changed: usize,
+    pub facts_changed: usize,
+    pub theme_changed: bool,
+}
+
+// A story as written, without how far the player got in it
+fn definition(story: &Story) -> Story {
+    let mut story = story.clone();
+    story.is_started = false;
+    story.active_beat_index = 0;
+    for beat in story.beats.iter_mut() {
+        beat.finished = false;
+    }
+    story
+}
+
+fn rules(stories: &[Story]) -> HashMap<(&str, &str), &Rule> {
+    stories
+        .iter()
+        .flat_map(|story| {
+            story
+                .pre_requisites
+                .iter()
+                .chain(story.beats.iter().flat_map(|beat| beat.rules.iter()))
+                .map(|rule| ((story.name.as_str(), rule.name.as_str()), rule))
+        })
+        .collect()
+}
+
+// How many of `after` differ from `before` or are new, plus how many of `before` are gone
+fn changed<K: Eq + std::hash::Hash, V: PartialEq>(
+    before: &HashMap<K, V>,
+    after: &HashMap<K, V>,
+) -> usize {
+    let differing = after
+        .iter()
+        .filter(|(key, value)| before.get(*key) != Some(*value))
+        .count();
+    differing
+        + before
+            .keys()
+            .filter(|key| !after.contains_key(*key))
+            .count()
+}
+
+impl ContentChanges {
+    pub fn between(before: &ContentSnapshot, after: &ContentSnapshot) -> Self {
+        let stories_before: HashMap<&str, Story> = before
+            .stories
+            .iter()
+            .map(|story| (story.name.as_str(), definition(story)))
+            .collect();
+        let mut changes = ContentChanges::default();
+        for story in after.stories.iter() {
+            match stories_before.get(story.name.as_str()) {
+                None => changes.stories_added += 1,
+                Some(previous) if *previous != definition(story) => changes.stories_changed += 1,
+                Some(_) => {}
+            }
+        }
+        changes.rules_changed = changed(&rules(&before.stories), &rules(&after.stories));
+        let facts = |snapshot: &ContentSnapshot| -> HashMap<String, Fact> {
+            snapshot
+                .table_facts
+                .iter()
+                .map(|fact| (fact.key().to_string(), fact.clone()))
+                .collect()
+        };
+        changes.facts_changed = changed(&facts(before), &facts(after));
+        changes.theme_changed = before.theme != after.theme;
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == ContentChanges::default()
+    }
+}
+
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+impl std::fmt::Display for ContentChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "Reloaded content, nothing changed");
+        }
+        let mut parts = Vec::new();
+        if self.stories_added > 0 {
+            parts.push(format!(
+                "{} added",
+                count(self.stories_added, "story", "stories")
+            ));
+        }
+        if self.stories_changed > 0 {
+            parts.push(format!(
+                "{} changed",
+                count(self.stories_changed, "story", "stories")
+            ));
+        }
+        if self.rules_changed > 0 {
+            parts.push(format!(
+                "{} changed",
+                count(self.rules_changed, "rule", "rules")
+            ));
+        }
+        if self.facts_changed > 0 {
+            parts.push(format!(
+                "{} changed",
+                count(self.facts_changed, "fact", "facts")
+            ));
+        }
+        if self.theme_changed {
+            parts.push("theme changed".to_string());
+        }
+        write!(f, "Reloaded content: {}", parts.join(", "))
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct ContentReload {
+    // Set while a reload is underway
+    before: Option<ContentSnapshot>,
+    // Files asked to reload that haven't come back yet
+    pending: HashSet<UntypedAssetId>,
+    // The files are back and the mods are merging again
+    merging: bool,
+}
+
+impl ContentReload {
+    pub fn is_reloading(&self) -> bool {
+        self.before.is_some()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_content_reload(
+    mut debug_commands: EventReader<DebugCommand>,
+    mut reload: ResMut<ContentReload>,
+    asset_server: Res<AssetServer>,
+    story_files: Res<StoryFiles>,
+    table_files: Res<FactTableFiles>,
+    mods: Option<Res<Mods>>,
+    story_engine: Res<StoryEngine>,
+    tables: Res<Assets<FactTable>>,
+    theme: Option<Res<UiTheme>>,
+) {
+    let requested = debug_commands
+        .read()
+        .any(|command| matches!(command, DebugCommand::ReloadContent));
+    if !requested {
+        return;
+    }
+    if reload.is_reloading() {
+        info!("Content is already reloading");
+        return;
+    }
+    reload.before = Some(ContentSnapshot::take(
+        &story_engine,
+        &tables,
+        theme.as_deref(),
+    ));
+    reload.merging = false;
+    // Reloading keeps the asset's load state, so the files are tracked by their events instead
+    let files = story_files
+        .0
+        .iter()
+        .map(|handle| handle.id().untyped())
+        .chain(table_files.0.iter().map(|handle| handle.id().untyped()))
+        .chain(mods.iter().flat_map(|mods| mods.files()));
+    for id in files {
+        if let Some(path) = asset_server.get_path(id) {
+            asset_server.reload(path);
+            reload.pending.insert(id);
+        }
+    }
+    info!("Reloading {} content files", reload.pending.len());
+}
+
+fn track_reloaded<A: Asset>(
+    mut reload: ResMut<ContentReload>,
+    mut asset_events: EventReader<AssetEvent<A>>,
+    mut failures: EventReader<AssetLoadFailedEvent<A>>,
+) {
+    for event in asset_events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } = event {
+            reload.pending.remove(&id.untyped());
+        }
+    }
+    // Failures are reported where the files are loaded
+    for failure in failures.read() {
+        reload.pending.remove(&failure.id.untyped());
+    }
+}
+
+fn finish_content_reload(
+    mut reload: ResMut<ContentReload>,
+    mut mods: Option<ResMut<Mods>>,
+    story_engine: Res<StoryEngine>,
+    tables: Res<Assets<FactTable>>,
+    theme: Option<Res<UiTheme>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !reload.is_reloading() || !reload.pending.is_empty() {
+        return;
+    }
+    if !reload.merging {
+        reload.merging = true;
+        if let Some(mods) = mods.as_mut() {
+            mods.merge_again();
+        }
+    }
+    if mods.is_some_and(|mods| !mods.is_merged()) {
+        return;
+    }
+    let Some(before) = reload.before.take() else {
+        return;
+    };
+    let after = ContentSnapshot::take(&story_engine, &tables, theme.as_deref());
+    let changes = ContentChanges::between(&before, &after);
+    info!("{}", changes);
+    toasts.send(ShowToast(changes.to_string()));
+}
@@ -0,0 +1,220 @@
+use crate::beats::data::EffectOutput;
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+pub const CREDITS_FILE: &str = "credits.ron";
+// Pixels per second
+const CREDITS_SCROLL_SPEED: f32 = 60.0;
+
+pub struct CreditsPlugin;
+
+/// Rolls the credits from `assets/credits.ron`, from the menu or when a story runs `Effect::RollCredits`
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Credits>()
+            .init_asset_loader::<CreditsLoader>()
+            .add_systems(Startup, load_credits)
+            .add_systems(Update, roll_credits.run_if(in_state(GameState::Story)))
+            .add_systems(OnEnter(GameState::Credits), setup_credits)
+            .add_systems(
+                Update,
+                (scroll_credits, leave_credits).run_if(in_state(GameState::Credits)),
+            )
+            .add_systems(OnExit(GameState::Credits), cleanup_credits);
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct Credits {
+    pub title: String,
+    pub sections: Vec<CreditsSection>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreditsSection {
+    pub heading: String,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum CreditsLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for CreditsLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CreditsLoadError::Io(error) => write!(f, "could not read credits: {}", error),
+            CreditsLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CreditsLoadError {}
+
+#[derive(Default)]
+pub struct CreditsLoader;
+
+impl AssetLoader for CreditsLoader {
+    type Asset = Credits;
+    type Settings = ();
+    type Error = CreditsLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(CreditsLoadError::Io)?;
+            ron::from_str(&source).map_err(CreditsLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["credits.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct CreditsHandle(pub Handle<Credits>);
+
+#[derive(Component)]
+struct CreditsScreen;
+
+#[derive(Component)]
+struct CreditsRoll;
+
+fn load_credits(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CreditsHandle(asset_server.load(CREDITS_FILE)));
+}
+
+fn roll_credits(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if effect_outputs
+        .read()
+        .any(|output| matches!(output, EffectOutput::RollCredits))
+    {
+        next_state.set(GameState::Credits);
+    }
+}
+
+fn setup_credits(
+    mut commands: Commands,
+    handle: Res<CreditsHandle>,
+    credits: Res<Assets<Credits>>,
+    windows: Query<&Window>,
+) {
+    let start = windows.get_single().map(|w| w.height()).unwrap_or(720.);
+    let Some(credits) = credits.get(&handle.0) else {
+        warn!("Credits are not loaded, check {}", CREDITS_FILE);
+        return;
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                // Covers whatever the story left on screen
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            CreditsScreen,
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(start),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(8.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    CreditsRoll,
+                ))
+                .with_children(|roll| {
+                    roll.spawn(credits_text(&credits.title, 48.0));
+                    for section in credits.sections.iter() {
+                        roll.spawn(NodeBundle {
+                            style: Style {
+                                height: Val::Px(32.),
+                                ..default()
+                            },
+                            ..default()
+                        });
+                        roll.spawn(credits_text(&section.heading, 28.0));
+                        for name in section.names.iter() {
+                            roll.spawn(credits_text(name, 20.0));
+                        }
+                    }
+                });
+        });
+}
+
+fn credits_text(text: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+fn scroll_credits(
+    time: Res<Time>,
+    mut rolls: Query<(&mut Style, &Node), With<CreditsRoll>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (mut style, node) in rolls.iter_mut() {
+        let Val::Px(top) = style.top else {
+            continue;
+        };
+        let top = top - CREDITS_SCROLL_SPEED * time.delta_seconds();
+        style.top = Val::Px(top);
+        // Back to the menu once the last line has scrolled out
+        if top < -node.size().y {
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+fn leave_credits(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::Space, KeyCode::Enter]) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn cleanup_credits(mut commands: Commands, screens: Query<Entity, With<CreditsScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
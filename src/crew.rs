@@ -0,0 +1,388 @@
+pub use crate::beats::data::CrewMember;
+use crate::beats::data::{
+    EffectOutput, Fact, FactError, FactMutation, FactsOfTheWorld, StringHashSet,
+};
+use crate::beats::storage::{write_facts, FactStorage};
+use crate::beats::{StoryEvaluation, StoryProgressionPass};
+use crate::settings::Settings;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use crate::ui::theme::UiTheme;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use std::collections::BTreeMap;
+
+// List fact of the names of everyone on the crew
+pub const CREW_ROSTER_FACT: &str = "crew.roster";
+// List fact of every trait someone on the crew has
+pub const CREW_TRAITS_FACT: &str = "crew.traits";
+// Int facts `crew.best.<skill>` hold the highest level of a skill on the crew, so a rule can
+// ask for "a crew member with carpentry > 2". Nobody can be hired under this name.
+pub const CREW_BEST: &str = "best";
+const CREW_PREFIX: &str = "crew";
+
+pub struct CrewPlugin;
+
+/// The ship's crew. Each member's skills and traits are facts under `crew.<name>.*`, written by
+/// `Effect::HireCrew` and cleared by `Effect::DismissCrew`. The roster, opened with R during a
+/// story, lists who is aboard.
+impl Plugin for CrewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CrewRoster>()
+            .add_event::<ToggleCrewRoster>()
+            .add_systems(
+                Update,
+                (
+                    mirror_crew_facts,
+                    crew_roster_keys,
+                    toggle_crew_roster,
+                    refresh_crew_roster,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            )
+            // In the pass, so rules see the new crew before the frame is out
+            .add_systems(
+                StoryProgressionPass,
+                apply_crew_effects.after(StoryEvaluation),
+            )
+            .add_systems(OnExit(GameState::Story), close_crew_roster);
+    }
+}
+
+impl CrewMember {
+    pub fn new(name: impl Into<String>) -> Self {
+        CrewMember {
+            name: name.into(),
+            skills: BTreeMap::new(),
+            traits: Vec::new(),
+        }
+    }
+
+    pub fn with_skill(mut self, skill: impl Into<String>, level: i32) -> Self {
+        self.skills.insert(skill.into(), level);
+        self
+    }
+
+    pub fn with_trait(mut self, name: impl Into<String>) -> Self {
+        self.traits.push(name.into());
+        self
+    }
+}
+
+pub fn crew_fact(name: &str, skill_or_trait: &str) -> String {
+    format!("{}.{}.{}", CREW_PREFIX, name, skill_or_trait)
+}
+
+pub fn best_skill_fact(skill: &str) -> String {
+    crew_fact(CREW_BEST, skill)
+}
+
+// Every fact of a crew member carries this tag, so dismissing them can clear the lot
+fn crew_tag(name: &str) -> String {
+    format!("{}.{}", CREW_PREFIX, name)
+}
+
+// Writes the member's facts and puts them on the roster. Hiring someone already aboard
+// replaces their skills and traits.
+pub fn hire<S: FactStorage>(fact_store: &mut S, member: &CrewMember) -> Result<(), FactError> {
+    if member.name == CREW_BEST {
+        warn!("Can't hire a crew member named {}", CREW_BEST);
+        return Ok(());
+    }
+    if is_aboard(fact_store, &member.name) {
+        clear_member(fact_store, &member.name);
+    }
+    let mut mutations = vec![FactMutation::AddToList(
+        CREW_ROSTER_FACT.to_string(),
+        member.name.clone(),
+    )];
+    for (skill, level) in member.skills.iter() {
+        mutations.push(FactMutation::StoreInt(
+            crew_fact(&member.name, skill),
+            *level,
+        ));
+    }
+    for name in member.traits.iter() {
+        mutations.push(FactMutation::StoreBool(crew_fact(&member.name, name), true));
+    }
+    fact_store.apply_batch(mutations)?;
+    let tag = crew_tag(&member.name);
+    for key in member.skills.keys().chain(member.traits.iter()) {
+        fact_store.tag(&crew_fact(&member.name, key), &tag);
+    }
+    refresh_crew_facts(fact_store)
+}
+
+// Takes the member off the roster and clears their facts
+pub fn dismiss<S: FactStorage>(fact_store: &mut S, name: &str) -> Result<(), FactError> {
+    if !is_aboard(fact_store, name) {
+        warn!("{} is not on the crew", name);
+        return Ok(());
+    }
+    fact_store.apply_batch(vec![FactMutation::RemoveFromList(
+        CREW_ROSTER_FACT.to_string(),
+        name.to_string(),
+    )])?;
+    clear_member(fact_store, name);
+    refresh_crew_facts(fact_store)
+}
+
+// Carries out `Effect::HireCrew` and `Effect::DismissCrew`
+pub fn apply_crew_effects(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    write_facts(&mut facts, |facts| {
+        for output in effect_outputs.read() {
+            let changed = match output {
+                EffectOutput::HireCrew(member) => hire(facts, member),
+                EffectOutput::DismissCrew(name) => dismiss(facts, name),
+                _ => continue,
+            };
+            if let Err(error) = changed {
+                warn!("Could not change the crew: {}", error);
+            }
+        }
+    });
+}
+
+fn is_aboard<S: FactStorage>(fact_store: &S, name: &str) -> bool {
+    matches!(
+        fact_store.get(CREW_ROSTER_FACT),
+        Some(Fact::StringList(_, names)) if names.contains(&name.to_string())
+    )
+}
+
+fn clear_member<S: FactStorage>(fact_store: &mut S, name: &str) {
+    for key in fact_store.tagged_keys(&crew_tag(name)) {
+        if let Some(cleared) = fact_store.get(&key).map(Fact::cleared) {
+            fact_store.set(cleared);
+        }
+    }
+}
+
+// Rewrites the facts summing up the whole crew. Skills nobody has any more drop to 0.
+fn refresh_crew_facts<S: FactStorage>(fact_store: &mut S) -> Result<(), FactError> {
+    let roster = CrewRoster::from_facts(fact_store.facts());
+    let best_prefix = format!("{}.", crew_tag(CREW_BEST));
+    let mut best: BTreeMap<String, i32> = fact_store
+        .facts()
+        .keys()
+        .filter_map(|key| key.strip_prefix(&best_prefix))
+        .map(|skill| (skill.to_string(), 0))
+        .collect();
+    let mut traits = Vec::new();
+    for member in roster.members.iter() {
+        for (skill, level) in member.skills.iter() {
+            let highest = best.entry(skill.clone()).or_insert(*level);
+            *highest = (*highest).max(*level);
+        }
+        traits.extend(member.traits.iter().cloned());
+    }
+    let mut mutations: Vec<FactMutation> = best
+        .into_iter()
+        .map(|(skill, level)| FactMutation::StoreInt(best_skill_fact(&skill), level))
+        .collect();
+    mutations.push(FactMutation::StoreList(
+        CREW_TRAITS_FACT.to_string(),
+        StringHashSet::new(),
+    ));
+    mutations.extend(
+        traits
+            .into_iter()
+            .map(|name| FactMutation::AddToList(CREW_TRAITS_FACT.to_string(), name)),
+    );
+    fact_store.apply_batch(mutations)
+}
+
+// Who is aboard, read back from the crew facts
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct CrewRoster {
+    pub members: Vec<CrewMember>,
+}
+
+impl CrewRoster {
+    pub fn from_facts(facts: &HashMap<String, Fact>) -> Self {
+        let Some(Fact::StringList(_, names)) = facts.get(CREW_ROSTER_FACT) else {
+            return CrewRoster::default();
+        };
+        let mut names: Vec<&String> = names.0.iter().collect();
+        names.sort();
+        let members = names
+            .into_iter()
+            .map(|name| {
+                let prefix = format!("{}.", crew_tag(name));
+                let mut member = CrewMember::new(name.clone());
+                for fact in facts.values() {
+                    let Some(key) = fact.key().strip_prefix(&prefix) else {
+                        continue;
+                    };
+                    match fact {
+                        Fact::Int(_, level) => {
+                            member.skills.insert(key.to_string(), *level);
+                        }
+                        Fact::Bool(_, true) => member.traits.push(key.to_string()),
+                        _ => {}
+                    }
+                }
+                member.traits.sort();
+                member
+            })
+            .collect();
+        CrewRoster { members }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CrewMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+// Opens the crew roster, or closes it
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ToggleCrewRoster;
+
+#[derive(Component)]
+pub struct CrewRosterPanel;
+
+#[derive(Component)]
+struct CrewRosterList;
+
+fn mirror_crew_facts(
+    facts: Res<FactsOfTheWorld>,
+    mut roster: ResMut<CrewRoster>,
+) {
+    if !facts.is_changed() {
+        return;
+    }
+    let current = CrewRoster::from_facts(&facts.facts);
+    // Only touch the resource when someone joined, left or changed, so the panel isn't rebuilt
+    if *roster != current {
+        *roster = current;
+    }
+}
+
+fn crew_roster_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut toggle: EventWriter<ToggleCrewRoster>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        toggle.send(ToggleCrewRoster);
+    }
+}
+
+fn toggle_crew_roster(
+    mut commands: Commands,
+    mut toggles: EventReader<ToggleCrewRoster>,
+    roster: Res<CrewRoster>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    panels: Query<Entity, With<CrewRosterPanel>>,
+) {
+    if toggles.read().count().is_multiple_of(2) {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in panels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.top_left(40.))
+                .on_layer(UiLayer::Modal)
+                .build(),
+            UiLayer::Modal,
+            CrewRosterPanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(6.),
+                            padding: UiRect::all(Val::Px(12.)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+                        ..default()
+                    },
+                    CrewRosterList,
+                ))
+                .with_children(|list| {
+                    spawn_crew_rows(list, &roster, &theme, &settings);
+                });
+        });
+}
+
+// Rebuilds the rows while the roster is open and the crew changes
+fn refresh_crew_roster(
+    mut commands: Commands,
+    roster: Res<CrewRoster>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    lists: Query<Entity, With<CrewRosterList>>,
+) {
+    if !roster.is_changed() {
+        return;
+    }
+    for entity in lists.iter() {
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|list| {
+                spawn_crew_rows(list, &roster, &theme, &settings);
+            });
+    }
+}
+
+fn spawn_crew_rows(
+    list: &mut ChildBuilder,
+    roster: &CrewRoster,
+    theme: &UiTheme,
+    settings: &Settings,
+) {
+    let palette = theme.palette(settings.palette);
+    let name_style = TextStyle {
+        font_size: 20.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    let detail_style = TextStyle {
+        font_size: 16.0,
+        color: palette.positive,
+        ..default()
+    };
+    if roster.members.is_empty() {
+        list.spawn(TextBundle::from_section("Nobody has signed on", name_style));
+        return;
+    }
+    for member in roster.members.iter() {
+        list.spawn(TextBundle::from_section(
+            member.name.clone(),
+            name_style.clone(),
+        ));
+        let mut details: Vec<String> = member
+            .skills
+            .iter()
+            .map(|(skill, level)| format!("{} {}", skill, level))
+            .collect();
+        details.extend(member.traits.iter().cloned());
+        if !details.is_empty() {
+            list.spawn(TextBundle::from_section(
+                details.join(", "),
+                detail_style.clone(),
+            ));
+        }
+    }
+}
+
+fn close_crew_roster(mut commands: Commands, panels: Query<Entity, With<CrewRosterPanel>>) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
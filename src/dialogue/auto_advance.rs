@@ -0,0 +1,86 @@
+use crate::settings::Settings;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use crate::ui::theme::UiTheme;
+use bevy::prelude::*;
+
+// Seconds the current line has been fully shown
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct AutoAdvanceTimer(pub f32);
+
+#[derive(Component)]
+pub struct AutoAdvanceButton;
+
+const AUTO_ADVANCE_OFF: Color = Color::rgb(0.15, 0.15, 0.25);
+
+fn auto_advance_label(on: bool) -> String {
+    format!("Auto: {}", if on { "on" } else { "off" })
+}
+
+fn auto_advance_color(settings: &Settings, theme: &UiTheme) -> Color {
+    if settings.auto_advance {
+        theme.palette(settings.palette).positive
+    } else {
+        AUTO_ADVANCE_OFF
+    }
+}
+
+pub fn spawn_auto_advance_button(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    theme: Res<UiTheme>,
+) {
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.bottom_right(16.))
+                .on_layer(UiLayer::Hud)
+                .build(),
+            UiLayer::Hud,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                            ..default()
+                        },
+                        background_color: auto_advance_color(&settings, &theme).into(),
+                        ..default()
+                    },
+                    AutoAdvanceButton,
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        auto_advance_label(settings.auto_advance),
+                        TextStyle {
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+pub fn auto_advance_button_system(
+    mut settings: ResMut<Settings>,
+    theme: Res<UiTheme>,
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor, &Children),
+        (Changed<Interaction>, With<AutoAdvanceButton>),
+    >,
+    mut texts: Query<&mut Text>,
+) {
+    for (interaction, mut color, children) in buttons.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        settings.auto_advance = !settings.auto_advance;
+        *color = auto_advance_color(&settings, &theme).into();
+        if let Ok(mut text) = texts.get_mut(children[0]) {
+            text.sections[0].value = auto_advance_label(settings.auto_advance);
+        }
+    }
+}
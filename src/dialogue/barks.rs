@@ -0,0 +1,225 @@
+use crate::beats::data::{FactsOfTheWorld, Rule};
+use crate::beats::story_time::StoryTime;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+
+// Ambient lines a character says by themselves when the facts are right, loaded from
+// `.barks.ron` files:
+//
+// (
+//     speaker: "Dock Worker",
+//     barks: [
+//         (text: "Tide's coming in.", rule: (name: "Tide", conditions: [..]), cooldown: 30.0),
+//         (text: "Seen a barnacle?", rule: (name: "Lost", conditions: [..]), priority: 1),
+//     ],
+// )
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct BarkSet {
+    pub speaker: String,
+    pub barks: Vec<Bark>,
+    // Seconds the speaker stays quiet after any bark
+    #[serde(default = "default_quiet_time")]
+    pub quiet_time: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bark {
+    pub text: String,
+    pub rule: Rule,
+    // Higher priorities win over more specific rules
+    #[serde(default)]
+    pub priority: i32,
+    // Seconds before this bark can be said again
+    #[serde(default = "default_bark_cooldown")]
+    pub cooldown: f32,
+}
+
+fn default_quiet_time() -> f32 {
+    5.0
+}
+
+fn default_bark_cooldown() -> f32 {
+    30.0
+}
+
+impl BarkSet {
+    // Best match among the barks whose rules pass and that aren't cooling down: highest
+    // priority first, then the rule with the most conditions, so specific lines beat generic ones
+    pub fn best_match(
+        &self,
+        facts: &FactsOfTheWorld,
+        cooldowns: &BarkCooldowns,
+        now: f64,
+    ) -> Option<usize> {
+        self.barks
+            .iter()
+            .enumerate()
+            .filter(|(index, bark)| {
+                cooldowns
+                    .said_at
+                    .get(index)
+                    .map_or(true, |said_at| now - said_at >= bark.cooldown as f64)
+            })
+            .filter(|(_, bark)| bark.rule.evaluate(&*facts))
+            .max_by_key(|(index, bark)| {
+                // Earlier barks win ties
+                (
+                    bark.priority,
+                    bark.rule.conditions.len(),
+                    std::cmp::Reverse(*index),
+                )
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+#[derive(Debug)]
+pub enum BarkSetLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for BarkSetLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BarkSetLoadError::Io(error) => write!(f, "could not read bark file: {}", error),
+            BarkSetLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for BarkSetLoadError {}
+
+impl From<std::io::Error> for BarkSetLoadError {
+    fn from(error: std::io::Error) -> Self {
+        BarkSetLoadError::Io(error)
+    }
+}
+
+#[derive(Default)]
+pub struct BarkSetLoader;
+
+impl AssetLoader for BarkSetLoader {
+    type Asset = BarkSet;
+    type Settings = ();
+    type Error = BarkSetLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            ron::from_str(&source).map_err(BarkSetLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["barks.ron"]
+    }
+}
+
+// Put on an entity with a Transform to have it bark the lines of the set
+#[derive(Component, Debug, Clone)]
+pub struct Barker {
+    pub barks: Handle<BarkSet>,
+    // Where the bubble sits, relative to the entity
+    pub bubble_offset: Vec3,
+}
+
+impl Barker {
+    pub fn new(barks: Handle<BarkSet>) -> Self {
+        Barker {
+            barks,
+            bubble_offset: Vec3::new(0., 48., 0.),
+        }
+    }
+}
+
+// When each bark of a Barker was last said, in seconds of story time, so pausing doesn't
+// count towards cooldowns
+#[derive(Component, Debug, Clone, Default)]
+pub struct BarkCooldowns {
+    pub said_at: HashMap<usize, f64>,
+    pub quiet_until: f64,
+}
+
+#[derive(Component, Debug)]
+pub struct BarkBubble {
+    remaining: Timer,
+}
+
+// How long a bubble stays up
+const BARK_BUBBLE_SECONDS: f32 = 3.0;
+
+pub fn choose_barks(
+    mut commands: Commands,
+    mut barkers: Query<(
+        Entity,
+        &Barker,
+        Option<&mut BarkCooldowns>,
+        Option<&Children>,
+    )>,
+    bubbles: Query<(), With<BarkBubble>>,
+    bark_sets: Res<Assets<BarkSet>>,
+    facts: Res<FactsOfTheWorld>,
+    story_time: Res<StoryTime>,
+) {
+    let now = story_time.elapsed_seconds();
+    for (entity, barker, cooldowns, children) in barkers.iter_mut() {
+        let Some(mut cooldowns) = cooldowns else {
+            commands.entity(entity).insert(BarkCooldowns::default());
+            continue;
+        };
+        let speaking =
+            children.is_some_and(|children| children.iter().any(|child| bubbles.contains(*child)));
+        if speaking || now < cooldowns.quiet_until {
+            continue;
+        }
+        let Some(bark_set) = bark_sets.get(&barker.barks) else {
+            continue;
+        };
+        let Some(index) = bark_set.best_match(&facts, &cooldowns, now) else {
+            continue;
+        };
+        cooldowns.said_at.insert(index, now);
+        cooldowns.quiet_until = now + (BARK_BUBBLE_SECONDS + bark_set.quiet_time) as f64;
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        bark_set.barks[index].text.clone(),
+                        TextStyle {
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_translation(barker.bubble_offset),
+                    ..default()
+                },
+                BarkBubble {
+                    remaining: Timer::from_seconds(BARK_BUBBLE_SECONDS, TimerMode::Once),
+                },
+            ));
+        });
+    }
+}
+
+pub fn expire_bark_bubbles(
+    mut commands: Commands,
+    mut bubbles: Query<(Entity, &mut BarkBubble)>,
+    time: Res<Time>,
+) {
+    for (entity, mut bubble) in bubbles.iter_mut() {
+        if bubble.remaining.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::beats::choices::ChoiceMade;
+use crate::beats::data::{DialogueLine, StoryEngine};
+use crate::dialogue::DialogueLineFinished;
+use crate::settings::Settings;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialogueHistoryEntry {
+    Line(DialogueLine),
+    Choice { label: String },
+}
+
+// Everything said and chosen so far, newest last
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DialogueHistory {
+    pub entries: VecDeque<DialogueHistoryEntry>,
+}
+
+impl DialogueHistory {
+    pub fn push(&mut self, entry: DialogueHistoryEntry, max_len: usize) {
+        self.entries.push_back(entry);
+        while self.entries.len() > max_len {
+            self.entries.pop_front();
+        }
+    }
+}
+
+// Opens the backlog, or closes it when already open. Sent by whatever menu offers it.
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ToggleDialogueHistory;
+
+#[derive(Component)]
+pub struct DialogueHistoryPanel;
+
+#[derive(Component, Default)]
+pub struct DialogueHistoryList {
+    position: f32,
+}
+
+const SCROLL_LINE_HEIGHT: f32 = 24.0;
+
+pub fn record_dialogue_history(
+    mut lines_finished: EventReader<DialogueLineFinished>,
+    mut choices_made: EventReader<ChoiceMade>,
+    story_engine: Res<StoryEngine>,
+    settings: Res<Settings>,
+    mut history: ResMut<DialogueHistory>,
+) {
+    for DialogueLineFinished(line) in lines_finished.read() {
+        history.push(
+            DialogueHistoryEntry::Line(line.clone()),
+            settings.dialogue_history_len,
+        );
+    }
+    for made in choices_made.read() {
+        let label = story_engine
+            .stories
+            .iter()
+            .find(|story| story.name == made.story)
+            .and_then(|story| story.beats.iter().find(|beat| beat.name == made.beat))
+            .and_then(|beat| beat.choices.get(made.index))
+            .map(|choice| choice.label.clone());
+        if let Some(label) = label {
+            history.push(
+                DialogueHistoryEntry::Choice { label },
+                settings.dialogue_history_len,
+            );
+        }
+    }
+}
+
+pub fn dialogue_history_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut toggle: EventWriter<ToggleDialogueHistory>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyH) {
+        toggle.send(ToggleDialogueHistory);
+    }
+}
+
+pub fn toggle_dialogue_history(
+    mut commands: Commands,
+    mut toggles: EventReader<ToggleDialogueHistory>,
+    history: Res<DialogueHistory>,
+    panels: Query<Entity, With<DialogueHistoryPanel>>,
+) {
+    if toggles.read().count() % 2 == 0 {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in panels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.top_center(40.))
+                .on_layer(UiLayer::Modal)
+                .build(),
+            UiLayer::Modal,
+            DialogueHistoryPanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(640.),
+                        height: Val::Px(480.),
+                        padding: UiRect::all(Val::Px(16.)),
+                        overflow: Overflow::clip_y(),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+                    ..default()
+                })
+                .with_children(|frame| {
+                    frame
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(8.),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            DialogueHistoryList::default(),
+                        ))
+                        .with_children(|list| {
+                            for entry in history.entries.iter() {
+                                let (text, color) = match entry {
+                                    DialogueHistoryEntry::Line(line) => {
+                                        (format!("{}: {}", line.speaker, line.text), Color::WHITE)
+                                    }
+                                    DialogueHistoryEntry::Choice { label } => {
+                                        (format!("> {}", label), Color::rgb(0.6, 0.8, 1.0))
+                                    }
+                                };
+                                list.spawn(TextBundle::from_section(
+                                    text,
+                                    TextStyle {
+                                        font_size: 18.0,
+                                        color,
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+                });
+        });
+}
+
+pub fn scroll_dialogue_history(
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut lists: Query<(&mut DialogueHistoryList, &mut Style, &Parent, &Node)>,
+    frames: Query<&Node>,
+) {
+    for wheel in mouse_wheel.read() {
+        for (mut list, mut style, parent, node) in lists.iter_mut() {
+            let Ok(frame) = frames.get(parent.get()) else {
+                continue;
+            };
+            let max_scroll = (node.size().y - frame.size().y).max(0.);
+            let delta = match wheel.unit {
+                MouseScrollUnit::Line => wheel.y * SCROLL_LINE_HEIGHT,
+                MouseScrollUnit::Pixel => wheel.y,
+            };
+            list.position = (list.position + delta).clamp(-max_scroll, 0.);
+            style.top = Val::Px(list.position);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+pub mod auto_advance;
+pub mod barks;
+pub mod history;
+pub mod portraits;
+pub mod skip;
+pub mod subtitles;
+pub mod transcript;
+pub mod typewriter;
+
+use crate::beats::data::{DialogueLine, EffectOutput};
+use crate::dialogue::auto_advance::{
+    auto_advance_button_system, spawn_auto_advance_button, AutoAdvanceTimer,
+};
+use crate::dialogue::barks::{choose_barks, expire_bark_bubbles, BarkSet, BarkSetLoader};
+use crate::dialogue::history::{
+    dialogue_history_keys, record_dialogue_history, scroll_dialogue_history,
+    toggle_dialogue_history, DialogueHistory, DialogueHistoryPanel, ToggleDialogueHistory,
+};
+use crate::dialogue::portraits::PortraitRegistry;
+use crate::dialogue::skip::{
+    fast_forward_keys, load_seen_dialogue, mark_dialogue_seen, store_seen_dialogue, FastForward,
+    SeenDialogue,
+};
+use crate::dialogue::subtitles::{
+    despawn_subtitle_panel, expire_subtitles, show_subtitles, spawn_subtitle_panel,
+    voice_dialogue_lines, ShowSubtitle,
+};
+use crate::dialogue::transcript::{export_transcript, transcript_keys, ExportTranscript};
+use crate::dialogue::typewriter::{typewriter_system, Typewriter};
+use crate::settings::Settings;
+use crate::ui::animation::{Easing, UiAnimation};
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::asset::AssetApp;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// Shows the lines stories `Say`, one at a time in a box at the bottom of the screen
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DialogueQueue>()
+            .init_resource::<PortraitRegistry>()
+            .init_resource::<DialogueHistory>()
+            .init_resource::<SeenDialogue>()
+            .init_resource::<FastForward>()
+            .add_event::<DialogueLineFinished>()
+            .add_event::<AdvanceDialogue>()
+            .add_event::<ToggleDialogueHistory>()
+            .add_event::<ExportTranscript>()
+            .add_event::<ShowSubtitle>()
+            .init_asset::<BarkSet>()
+            .init_asset_loader::<BarkSetLoader>()
+            .add_systems(Startup, load_seen_dialogue)
+            .add_systems(
+                OnEnter(GameState::Story),
+                (spawn_auto_advance_button, spawn_subtitle_panel),
+            )
+            .add_systems(OnExit(GameState::Story), despawn_subtitle_panel)
+            .add_systems(Update, store_seen_dialogue)
+            .add_systems(
+                Update,
+                (
+                    fast_forward_keys,
+                    auto_advance_button_system,
+                    queue_dialogue,
+                    show_next_line,
+                    typewriter_system,
+                    dialogue_keys,
+                    advance_dialogue.run_if(not(any_with_component::<DialogueHistoryPanel>)),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            )
+            .add_systems(
+                Update,
+                (
+                    mark_dialogue_seen,
+                    record_dialogue_history,
+                    dialogue_history_keys,
+                    toggle_dialogue_history,
+                    scroll_dialogue_history,
+                    transcript_keys,
+                    export_transcript,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            )
+            .add_systems(
+                Update,
+                (choose_barks, expire_bark_bubbles).run_if(in_state(GameState::Story)),
+            )
+            .add_systems(
+                Update,
+                (voice_dialogue_lines, show_subtitles, expire_subtitles)
+                    .chain()
+                    .after(show_next_line)
+                    .run_if(in_state(GameState::Story)),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DialogueQueue(pub VecDeque<DialogueLine>);
+
+// The player moved on from a line
+#[derive(Event, Debug, Clone)]
+pub struct DialogueLineFinished(pub DialogueLine);
+
+#[derive(Component, Debug, Clone)]
+pub struct DialogueBox(pub DialogueLine);
+
+// The player asked to move on, from the keyboard, a click or a replayed recording
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct AdvanceDialogue;
+
+pub fn queue_dialogue(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut queue: ResMut<DialogueQueue>,
+) {
+    for output in effect_outputs.read() {
+        if let EffectOutput::Say(line) = output {
+            queue.0.push_back(line.clone());
+        }
+    }
+}
+
+pub fn show_next_line(
+    mut commands: Commands,
+    mut queue: ResMut<DialogueQueue>,
+    portraits: Res<PortraitRegistry>,
+    boxes: Query<(), With<DialogueBox>>,
+) {
+    if !boxes.is_empty() {
+        return;
+    }
+    let Some(line) = queue.0.pop_front() else {
+        return;
+    };
+    let portrait = portraits.get(&line.speaker);
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.bottom_center(24.))
+                .on_layer(UiLayer::Dialogue)
+                .build(),
+            UiLayer::Dialogue,
+            UiAnimation::slide_in_from(Vec2::new(0., 30.), 0.25).with_easing(Easing::EaseOut),
+            DialogueBox(line.clone()),
+            AutoAdvanceTimer::default(),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(70.),
+                        padding: UiRect::all(Val::Px(16.)),
+                        column_gap: Val::Px(16.),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.05, 0.05, 0.1, 0.9).into(),
+                    ..default()
+                })
+                .with_children(|panel| {
+                    if let Some(image) = &portrait.image {
+                        panel.spawn(ImageBundle {
+                            style: Style {
+                                width: Val::Px(96.),
+                                height: Val::Px(96.),
+                                ..default()
+                            },
+                            image: UiImage::new(image.clone()),
+                            ..default()
+                        });
+                    }
+                    panel
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(6.),
+                                flex_grow: 1.,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|column| {
+                            column.spawn(TextBundle::from_section(
+                                line.speaker.clone(),
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: portrait.name_color,
+                                    ..default()
+                                },
+                            ));
+                            column.spawn((
+                                TextBundle::from_section(
+                                    "",
+                                    TextStyle {
+                                        font_size: 22.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                ),
+                                Typewriter::new(line.speaker.clone(), line.text.clone()),
+                            ));
+                        });
+                });
+        });
+}
+
+pub fn dialogue_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut advances: EventWriter<AdvanceDialogue>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Space, KeyCode::Enter])
+        || mouse_input.just_pressed(MouseButton::Left)
+    {
+        advances.send(AdvanceDialogue);
+    }
+}
+
+// Advancing first reveals the whole line, then moves on to the next one.
+// Seen lines are passed straight through while fast forwarding, and with auto advance on
+// a shown line moves on after a delay that grows with its length.
+pub fn advance_dialogue(
+    mut commands: Commands,
+    mut advances: EventReader<AdvanceDialogue>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    fast_forward: Res<FastForward>,
+    seen: Res<SeenDialogue>,
+    mut boxes: Query<(Entity, &DialogueBox, &mut AutoAdvanceTimer)>,
+    mut typewriters: Query<&mut Typewriter>,
+    mut finished: EventWriter<DialogueLineFinished>,
+) {
+    let Ok((entity, dialogue_box, mut timer)) = boxes.get_single_mut() else {
+        return;
+    };
+    if typewriters.iter().all(|typewriter| typewriter.is_done()) {
+        timer.0 += time.delta_seconds();
+    }
+    let skipping = fast_forward.0 && seen.contains(&dialogue_box.0.line_id());
+    let auto =
+        settings.auto_advance && timer.0 >= settings.auto_advance_delay_for(&dialogue_box.0.text);
+    let pressed = advances.read().count() > 0;
+    if !pressed && !skipping && !auto {
+        return;
+    }
+    let mut typing = false;
+    for mut typewriter in typewriters.iter_mut() {
+        if !typewriter.is_done() {
+            typewriter.finish();
+            typing = true;
+        }
+    }
+    if !typing || skipping {
+        finished.send(DialogueLineFinished(dialogue_box.0.clone()));
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_kira_audio::AudioSource;
+
+// How a speaker looks and sounds in the dialogue box
+#[derive(Debug, Clone)]
+pub struct Portrait {
+    pub name_color: Color,
+    pub image: Option<Handle<Image>>,
+    // Played while their text is revealed, no sound when unset
+    pub blip: Option<Handle<AudioSource>>,
+    // Playback rate picked at random from this range for every blip
+    pub pitch_range: (f64, f64),
+    // A blip every this many revealed characters
+    pub blip_every: usize,
+}
+
+impl Default for Portrait {
+    fn default() -> Self {
+        Portrait {
+            name_color: Color::rgb(0.9, 0.8, 0.4),
+            image: None,
+            blip: None,
+            pitch_range: (0.9, 1.1),
+            blip_every: 2,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct PortraitRegistry {
+    portraits: HashMap<String, Portrait>,
+    fallback: Portrait,
+}
+
+impl PortraitRegistry {
+    pub fn register(&mut self, speaker: impl Into<String>, portrait: Portrait) -> &mut Self {
+        self.portraits.insert(speaker.into(), portrait);
+        self
+    }
+
+    // Unknown speakers get the fallback portrait
+    pub fn get(&self, speaker: &str) -> &Portrait {
+        self.portraits.get(speaker).unwrap_or(&self.fallback)
+    }
+}
@@ -0,0 +1,73 @@
+use crate::beats::save::SaveGameRequest;
+use crate::beats::save_location::SaveLocation;
+use crate::beats::sorted;
+use crate::dialogue::DialogueLineFinished;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+// Kept apart from save slots so lines stay seen across playthroughs
+pub const SEEN_DIALOGUE_FILE: &str = "seen_dialogue.ron";
+
+// Ids of every line the player has read, see `DialogueLine::line_id`
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SeenDialogue {
+    #[serde(serialize_with = "sorted::set")]
+    pub lines: HashSet<String>,
+}
+
+impl SeenDialogue {
+    pub fn contains(&self, line_id: &str) -> bool {
+        self.lines.contains(line_id)
+    }
+}
+
+// While on, lines the player has already seen advance by themselves
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FastForward(pub bool);
+
+pub fn load_seen_dialogue(mut seen: ResMut<SeenDialogue>, location: Res<SaveLocation>) {
+    // No file just means nothing has been seen yet
+    let Ok(source) = location.read(SEEN_DIALOGUE_FILE) else {
+        return;
+    };
+    match ron::from_str(&source) {
+        Ok(loaded) => *seen = loaded,
+        Err(error) => warn!("Could not read {}: {}", SEEN_DIALOGUE_FILE, error),
+    }
+}
+
+pub fn mark_dialogue_seen(
+    mut lines_finished: EventReader<DialogueLineFinished>,
+    mut seen: ResMut<SeenDialogue>,
+) {
+    for DialogueLineFinished(line) in lines_finished.read() {
+        seen.lines.insert(line.line_id());
+    }
+}
+
+// Written alongside every save and when the game closes
+pub fn store_seen_dialogue(
+    mut save_requests: EventReader<SaveGameRequest>,
+    mut exits: EventReader<AppExit>,
+    seen: Res<SeenDialogue>,
+    location: Res<SaveLocation>,
+) {
+    if save_requests.read().count() + exits.read().count() == 0 {
+        return;
+    }
+    match ron::ser::to_string_pretty(seen.as_ref(), ron::ser::PrettyConfig::default()) {
+        Ok(source) => location.write(SEEN_DIALOGUE_FILE, &source),
+        Err(error) => warn!("Could not serialize seen dialogue: {}", error),
+    }
+}
+
+pub fn fast_forward_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut fast_forward: ResMut<FastForward>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        fast_forward.0 = !fast_forward.0;
+    }
+}
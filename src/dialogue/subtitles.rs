@@ -0,0 +1,116 @@
+use crate::dialogue::DialogueBox;
+use crate::settings::Settings;
+use crate::sfx::PlaySfx;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// Captions on screen at once, the oldest goes when another comes in
+const MAX_SUBTITLES: usize = 3;
+
+// A caption for something the player hears. Only shown with subtitles turned on, for as long
+// as the line takes to read.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ShowSubtitle(pub String);
+
+#[derive(Component)]
+pub struct SubtitlePanel;
+
+#[derive(Component, Debug)]
+pub struct SubtitleLine {
+    remaining: Timer,
+}
+
+pub fn spawn_subtitle_panel(mut commands: Commands) {
+    commands.spawn((
+        NodeBundleBuilder::new()
+            .with_style(|style| style.bottom_center(180.).flex_column().row_gap_px(4.))
+            .on_layer(UiLayer::Subtitles)
+            .build(),
+        UiLayer::Subtitles,
+        SubtitlePanel,
+    ));
+}
+
+pub fn despawn_subtitle_panel(mut commands: Commands, panels: Query<Entity, With<SubtitlePanel>>) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Plays the recording of each line as its box comes up, captioned with who is speaking
+pub fn voice_dialogue_lines(
+    boxes: Query<&DialogueBox, Added<DialogueBox>>,
+    asset_server: Res<AssetServer>,
+    mut sfx: EventWriter<PlaySfx>,
+    mut subtitles: EventWriter<ShowSubtitle>,
+) {
+    for dialogue_box in boxes.iter() {
+        let line = &dialogue_box.0;
+        let Some(voice) = &line.voice else {
+            continue;
+        };
+        sfx.send(PlaySfx::new(asset_server.load(voice.clone())));
+        subtitles.send(ShowSubtitle(format!("{}: {}", line.speaker, line.text)));
+    }
+}
+
+pub fn show_subtitles(
+    mut commands: Commands,
+    mut requests: EventReader<ShowSubtitle>,
+    settings: Res<Settings>,
+    panels: Query<(Entity, Option<&Children>), With<SubtitlePanel>>,
+) {
+    if !settings.subtitles {
+        requests.clear();
+        return;
+    }
+    let Some((panel, children)) = panels.iter().next() else {
+        requests.clear();
+        return;
+    };
+    let mut shown: VecDeque<Entity> = children
+        .map(|children| children.iter().copied().collect())
+        .unwrap_or_default();
+    for ShowSubtitle(text) in requests.read() {
+        let line = commands
+            .spawn((
+                TextBundle::from_section(
+                    text.clone(),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::rgba(0., 0., 0., 0.7)),
+                SubtitleLine {
+                    remaining: Timer::from_seconds(
+                        settings.auto_advance_delay_for(text),
+                        TimerMode::Once,
+                    ),
+                },
+            ))
+            .id();
+        commands.entity(panel).add_child(line);
+        shown.push_back(line);
+    }
+    while shown.len() > MAX_SUBTITLES {
+        if let Some(oldest) = shown.pop_front() {
+            commands.entity(oldest).despawn_recursive();
+        }
+    }
+}
+
+pub fn expire_subtitles(
+    mut commands: Commands,
+    mut lines: Query<(Entity, &mut SubtitleLine)>,
+    time: Res<Time>,
+) {
+    for (entity, mut line) in lines.iter_mut() {
+        if line.remaining.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::beats::journal::{JournalKind, SessionJournal};
+use crate::beats::save_location::SaveLocation;
+use crate::dialogue::history::{DialogueHistory, DialogueHistoryEntry};
+use bevy::prelude::*;
+use std::fmt::Write;
+
+pub const TRANSCRIPT_FILE: &str = "transcript.md";
+
+// Writes what was said, chosen and finished so far to `transcript.md` in the save directory,
+// for narrative review and bug reports
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ExportTranscript;
+
+// Markdown with the conversation in order, choices inline, followed by the beats finished
+pub fn render_transcript(history: &DialogueHistory, journal: &SessionJournal) -> String {
+    let mut transcript = String::from("# Playthrough transcript\n\n## Dialogue\n\n");
+    if history.entries.is_empty() {
+        transcript.push_str("_Nothing was said._\n");
+    }
+    for entry in history.entries.iter() {
+        // Writing to a String can't fail
+        let _ = match entry {
+            DialogueHistoryEntry::Line(line) if line.speaker.is_empty() => {
+                writeln!(transcript, "{}\n", line.text)
+            }
+            DialogueHistoryEntry::Line(line) => {
+                writeln!(transcript, "**{}:** {}\n", line.speaker, line.text)
+            }
+            DialogueHistoryEntry::Choice { label } => {
+                writeln!(transcript, "> Chose: {}\n", label)
+            }
+        };
+    }
+
+    transcript.push_str("\n## Beats completed\n\n");
+    let mut finished_any = false;
+    for entry in journal.entries.iter() {
+        if let JournalKind::BeatFinished { story, beat } = &entry.kind {
+            let _ = writeln!(transcript, "- {:.1}s: {} / {}", entry.at, story, beat);
+            finished_any = true;
+        }
+    }
+    if !finished_any {
+        transcript.push_str("_No beats finished._\n");
+    }
+    transcript
+}
+
+pub fn transcript_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut exports: EventWriter<ExportTranscript>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        exports.send(ExportTranscript);
+    }
+}
+
+pub fn export_transcript(
+    mut requests: EventReader<ExportTranscript>,
+    history: Res<DialogueHistory>,
+    journal: Res<SessionJournal>,
+    location: Res<SaveLocation>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    match location.export(TRANSCRIPT_FILE, &render_transcript(&history, &journal)) {
+        Ok(path) => info!("Transcript exported to {}", path),
+        Err(error) => warn!("Could not export transcript: {}", error),
+    }
+}
@@ -0,0 +1,89 @@
+use crate::dialogue::portraits::PortraitRegistry;
+use crate::settings::Settings;
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use rand::Rng;
+
+pub const DEFAULT_CHARS_PER_SECOND: f32 = 40.0;
+// Extra wait after punctuation, so sentences breathe
+const PUNCTUATION_PAUSE: f32 = 0.15;
+
+// Reveals the text of a line a character at a time, into the first section of the entity's Text
+#[derive(Component, Debug, Clone)]
+pub struct Typewriter {
+    pub speaker: String,
+    pub text: String,
+    pub chars_per_second: f32,
+    revealed: usize,
+    wait: f32,
+    blips: usize,
+}
+
+impl Typewriter {
+    pub fn new(speaker: impl Into<String>, text: impl Into<String>) -> Self {
+        Typewriter {
+            speaker: speaker.into(),
+            text: text.into(),
+            chars_per_second: DEFAULT_CHARS_PER_SECOND,
+            revealed: 0,
+            wait: 0.0,
+            blips: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.revealed >= self.text.chars().count()
+    }
+
+    pub fn finish(&mut self) {
+        self.revealed = self.text.chars().count();
+    }
+
+    pub fn revealed_text(&self) -> String {
+        self.text.chars().take(self.revealed).collect()
+    }
+}
+
+pub fn typewriter_system(
+    time: Res<Time>,
+    portraits: Res<PortraitRegistry>,
+    settings: Res<Settings>,
+    audio: Res<Audio>,
+    mut typewriters: Query<(&mut Typewriter, &mut Text)>,
+) {
+    let mut rng = rand::thread_rng();
+    for (mut typewriter, mut text) in typewriters.iter_mut() {
+        if typewriter.is_done() {
+            if text.sections[0].value.len() != typewriter.text.len() {
+                text.sections[0].value = typewriter.text.clone();
+            }
+            continue;
+        }
+        let portrait = portraits.get(&typewriter.speaker);
+        typewriter.wait -= time.delta_seconds();
+        while typewriter.wait <= 0.0 && !typewriter.is_done() {
+            let Some(next) = typewriter.text.chars().nth(typewriter.revealed) else {
+                break;
+            };
+            typewriter.revealed += 1;
+            typewriter.wait += 1.0 / (typewriter.chars_per_second * settings.text_speed).max(1.0);
+            if matches!(next, '.' | ',' | '!' | '?' | ';' | ':') {
+                typewriter.wait += PUNCTUATION_PAUSE;
+            }
+            if next.is_alphanumeric() {
+                typewriter.blips += 1;
+                let on_beat = (typewriter.blips - 1) % portrait.blip_every.max(1) == 0;
+                if let Some(blip) = portrait.blip.as_ref().filter(|_| on_beat) {
+                    let (low, high) = portrait.pitch_range;
+                    let pitch = if high > low {
+                        rng.gen_range(low..high)
+                    } else {
+                        low
+                    };
+                    audio.play(blip.clone()).with_playback_rate(pitch);
+                }
+            }
+        }
+        text.sections[0].value = typewriter.revealed_text();
+    }
+}
@@ -0,0 +1,105 @@
+use crate::beats::data::{Fact, FactMutation, FactsOfTheWorld};
+use crate::mini_games::resuming_from_mini_game;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// String fact holding the name of the chosen preset
+pub const DIFFICULTY_FACT: &str = "difficulty";
+// Int facts in percent, 100 leaves damage as it is
+pub const DAMAGE_DEALT_FACT: &str = "difficulty.damage_dealt_percent";
+pub const DAMAGE_TAKEN_FACT: &str = "difficulty.damage_taken_percent";
+
+pub struct DifficultyPlugin;
+
+/// Writes the facts of the difficulty picked in the menu when a story starts, so rules and
+/// gameplay read difficulty from the fact store like everything else
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DifficultyPresets>()
+            .add_systems(
+                OnEnter(GameState::Story),
+                apply_difficulty.run_if(not(resuming_from_mini_game)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DifficultyPreset {
+    // Stored in the `difficulty` fact
+    pub name: String,
+    // Shown in the menu
+    pub label: String,
+    pub facts: Vec<Fact>,
+}
+
+impl DifficultyPreset {
+    pub fn new(name: &str, label: &str, damage_dealt: i32, damage_taken: i32) -> Self {
+        DifficultyPreset {
+            name: name.to_string(),
+            label: label.to_string(),
+            facts: vec![
+                Fact::Int(DAMAGE_DEALT_FACT.to_string(), damage_dealt),
+                Fact::Int(DAMAGE_TAKEN_FACT.to_string(), damage_taken),
+            ],
+        }
+    }
+
+    // The preset as one batch, so a bad fact type leaves the store untouched
+    pub fn mutations(&self) -> Vec<FactMutation> {
+        std::iter::once(FactMutation::StoreString(
+            DIFFICULTY_FACT.to_string(),
+            self.name.clone(),
+        ))
+        .chain(self.facts.iter().cloned().map(FactMutation::from_fact))
+        .collect()
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DifficultyPresets {
+    pub presets: Vec<DifficultyPreset>,
+    pub selected: usize,
+}
+
+impl Default for DifficultyPresets {
+    fn default() -> Self {
+        DifficultyPresets {
+            presets: vec![
+                DifficultyPreset::new("easy", "Easy", 150, 50),
+                DifficultyPreset::new("normal", "Normal", 100, 100),
+                DifficultyPreset::new("hard", "Hard", 75, 150),
+            ],
+            selected: 1,
+        }
+    }
+}
+
+impl DifficultyPresets {
+    pub fn selected(&self) -> Option<&DifficultyPreset> {
+        self.presets.get(self.selected)
+    }
+
+    // Moves on to the next preset, wrapping around to the first
+    pub fn select_next(&mut self) {
+        if !self.presets.is_empty() {
+            self.selected = (self.selected + 1) % self.presets.len();
+        }
+    }
+}
+
+// Multiplier from one of the percent facts, 1.0 when no difficulty has been applied
+pub fn difficulty_multiplier(facts: &FactsOfTheWorld, fact: &str) -> f32 {
+    facts
+        .get_int(fact)
+        .map_or(1.0, |percent| *percent as f32 / 100.0)
+}
+
+fn apply_difficulty(presets: Res<DifficultyPresets>, mut facts: ResMut<FactsOfTheWorld>) {
+    let Some(preset) = presets.selected() else {
+        return;
+    };
+    if let Err(error) = facts.apply_batch(preset.mutations()) {
+        warn!("Could not apply difficulty {}: {}", preset.name, error);
+    }
+}
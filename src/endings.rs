@@ -0,0 +1,317 @@
+use crate::beats::data::{Fact, FactsOfTheWorld, Rule, StoryEngine};
+use crate::beats::storage::{write_facts, FactStorage};
+use crate::beats::StoryProgression;
+use crate::dialogue::history::{DialogueHistory, DialogueHistoryEntry};
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+pub const ENDINGS_FILE: &str = "endings.ron";
+// Name of the ending the run reached. Set, the run is over and no other ending is picked
+// until New Game Plus or a chapter start clears it.
+pub const ENDING_FACT: &str = "ending.reached";
+// Recorded when no ending matched and the plain epilogue was shown
+pub const NO_ENDING: &str = "none";
+
+pub struct EndingsPlugin;
+
+/// Picks an ending from `assets/endings.ron` once every story is finished and shows the
+/// epilogue before rolling the credits
+impl Plugin for EndingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EndingDefinitions>()
+            .init_asset_loader::<EndingDefinitionsLoader>()
+            .add_event::<EndingReached>()
+            .add_systems(Startup, load_endings)
+            .add_systems(
+                Update,
+                choose_ending
+                    .after(StoryProgression)
+                    .run_if(in_state(GameState::Story)),
+            )
+            .add_systems(OnEnter(GameState::Epilogue), setup_epilogue)
+            .add_systems(Update, leave_epilogue.run_if(in_state(GameState::Epilogue)))
+            .add_systems(OnExit(GameState::Epilogue), cleanup_epilogue);
+    }
+}
+
+// (
+//     endings: [
+//         (name: "saint", title: "Harbour Saint", text: "..", rule: (name: "Saint", conditions: [..]), priority: 1),
+//         (name: "drifter", title: "Drifter", text: "..", rule: (name: "Drifter", conditions: [])),
+//     ],
+//     summary: [(label: "Karma", fact: "karma")],
+// )
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct EndingDefinitions {
+    pub endings: Vec<Ending>,
+    // Facts listed on the epilogue screen
+    #[serde(default)]
+    pub summary: Vec<EpilogueFact>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ending {
+    pub name: String,
+    pub title: String,
+    pub text: String,
+    pub rule: Rule,
+    // Higher priorities win over more specific rules
+    #[serde(default)]
+    pub priority: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EpilogueFact {
+    pub label: String,
+    pub fact: String,
+}
+
+impl EndingDefinitions {
+    // Same ordering as barks: highest priority, then the most conditions, then the earliest ending
+    pub fn choose(&self, facts: &FactsOfTheWorld) -> Option<&Ending> {
+        self.endings
+            .iter()
+            .enumerate()
+            .filter(|(_, ending)| ending.rule.evaluate(&*facts))
+            .max_by_key(|(index, ending)| {
+                (
+                    ending.priority,
+                    ending.rule.conditions.len(),
+                    std::cmp::Reverse(*index),
+                )
+            })
+            .map(|(_, ending)| ending)
+    }
+}
+
+#[derive(Debug)]
+pub enum EndingsLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for EndingsLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndingsLoadError::Io(error) => write!(f, "could not read endings: {}", error),
+            EndingsLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for EndingsLoadError {}
+
+#[derive(Default)]
+pub struct EndingDefinitionsLoader;
+
+impl AssetLoader for EndingDefinitionsLoader {
+    type Asset = EndingDefinitions;
+    type Settings = ();
+    type Error = EndingsLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(EndingsLoadError::Io)?;
+            ron::from_str(&source).map_err(EndingsLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["endings.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct EndingDefinitionsHandle(pub Handle<EndingDefinitions>);
+
+// Sent with the name of the ending once the run is over
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct EndingReached {
+    pub ending: String,
+}
+
+// The ending of the run, shown by the epilogue
+#[derive(Resource, Debug, Clone)]
+pub struct ChosenEnding(pub Ending);
+
+#[derive(Component)]
+struct EpilogueScreen;
+
+fn load_endings(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(EndingDefinitionsHandle(asset_server.load(ENDINGS_FILE)));
+}
+
+fn choose_ending(
+    mut commands: Commands,
+    story_engine: Res<StoryEngine>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    handle: Res<EndingDefinitionsHandle>,
+    definitions: Res<Assets<EndingDefinitions>>,
+    mut endings_reached: EventWriter<EndingReached>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Nothing to end before the stories have loaded
+    if story_engine.stories.is_empty() || !story_engine.all_stories_finished() {
+        return;
+    }
+    // Coming back to the finished stories from the menu doesn't end the run again
+    if facts
+        .get_string(ENDING_FACT)
+        .is_some_and(|ending| !ending.is_empty())
+    {
+        return;
+    }
+    let Some(definitions) = definitions.get(&handle.0) else {
+        warn!("Endings are not loaded, check {}", ENDINGS_FILE);
+        return;
+    };
+    let reached = match definitions.choose(&facts) {
+        Some(ending) => {
+            endings_reached.send(EndingReached {
+                ending: ending.name.clone(),
+            });
+            commands.insert_resource(ChosenEnding(ending.clone()));
+            ending.name.clone()
+        }
+        None => {
+            warn!("No ending matches the facts, add one without conditions as a fallback");
+            commands.remove_resource::<ChosenEnding>();
+            NO_ENDING.to_string()
+        }
+    };
+    if let Err(error) = write_facts(&mut facts, |facts| {
+        facts.store_string(ENDING_FACT.to_string(), reached)
+    }) {
+        warn!("Reached ending not recorded: {}", error);
+    }
+    next_state.set(GameState::Epilogue);
+}
+
+fn setup_epilogue(
+    mut commands: Commands,
+    chosen: Option<Res<ChosenEnding>>,
+    handle: Res<EndingDefinitionsHandle>,
+    definitions: Res<Assets<EndingDefinitions>>,
+    facts: Res<FactsOfTheWorld>,
+    history: Res<DialogueHistory>,
+) {
+    let (title, text) = chosen.map_or(("The End".to_string(), String::new()), |chosen| {
+        (chosen.0.title.clone(), chosen.0.text.clone())
+    });
+    let summary: Vec<String> = definitions
+        .get(&handle.0)
+        .map(|definitions| {
+            definitions
+                .summary
+                .iter()
+                .filter_map(|line| {
+                    let value = fact_value(facts.get(&line.fact)?);
+                    Some(format!("{}: {}", line.label, value))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let choices: Vec<&str> = history
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            DialogueHistoryEntry::Choice { label } => Some(label.as_str()),
+            DialogueHistoryEntry::Line(_) => None,
+        })
+        .collect();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(8.),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            EpilogueScreen,
+            UiLayer::Modal,
+        ))
+        .with_children(|screen| {
+            screen.spawn(epilogue_text(&title, 48.0));
+            if !text.is_empty() {
+                screen.spawn(epilogue_text(&text, 22.0));
+            }
+            if !summary.is_empty() {
+                screen.spawn(epilogue_text("", 16.0));
+                screen.spawn(epilogue_text("Your journey", 28.0));
+                for line in summary.iter() {
+                    screen.spawn(epilogue_text(line, 20.0));
+                }
+            }
+            if !choices.is_empty() {
+                screen.spawn(epilogue_text("", 16.0));
+                screen.spawn(epilogue_text("Choices made", 28.0));
+                for label in choices {
+                    screen.spawn(epilogue_text(label, 20.0));
+                }
+            }
+            screen.spawn(epilogue_text("", 16.0));
+            screen.spawn(epilogue_text("Press Enter to continue", 16.0));
+        });
+}
+
+fn fact_value(fact: &Fact) -> String {
+    match fact {
+        Fact::Int(_, value) => value.to_string(),
+        Fact::String(_, value) => value.clone(),
+        Fact::Bool(_, value) => if *value { "yes" } else { "no" }.to_string(),
+        Fact::StringList(_, values) => {
+            let mut values: Vec<&str> = values.0.iter().map(String::as_str).collect();
+            values.sort_unstable();
+            values.join(", ")
+        }
+    }
+}
+
+fn epilogue_text(text: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+fn leave_epilogue(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::Space, KeyCode::Enter]) {
+        next_state.set(GameState::Credits);
+    }
+}
+
+fn cleanup_epilogue(mut commands: Commands, screens: Query<Entity, With<EpilogueScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,355 @@
+use crate::beats::data::{FactMutation, FactValue, FactsOfTheWorld};
+use crate::mini_games::{in_mini_game, MiniGameAppExt, MiniGameFinished};
+use crate::shop::INVENTORY_FACT;
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub const FISHING_ID: &str = "fishing";
+// Result of a run where the fish got off the hook
+pub const FISH_GOT_AWAY: &str = "got_away";
+// Beats to listen to before the first one that counts
+const LEAD_IN_BEATS: u64 = 2;
+
+pub struct FishingPlugin;
+
+/// A timing mini-game: reel with Space on the beat until the fish is landed, or miss too often
+/// and it gets away. The fish caught goes into the `inventory` fact and its id is the result,
+/// in `minigame.fishing.result`.
+impl Plugin for FishingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_mini_game(FISHING_ID)
+            .init_resource::<FishingSettings>()
+            .add_systems(
+                OnEnter(GameState::MiniGame(FISHING_ID.to_string())),
+                start_fishing,
+            )
+            .add_systems(
+                Update,
+                (reel_on_beat, pulse_beat_marker, update_fishing_status)
+                    .chain()
+                    .run_if(in_mini_game(FISHING_ID)),
+            )
+            .add_systems(
+                OnExit(GameState::MiniGame(FISHING_ID.to_string())),
+                cleanup_fishing,
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct FishingSettings {
+    pub bpm: f64,
+    // Reels on the beat it takes to land the fish
+    pub reels_to_land: u32,
+    // The fish gets away on the miss after this many
+    pub misses_allowed: u32,
+    // Seconds either side of a beat a reel counts as perfect, or as good
+    pub perfect_window: f64,
+    pub good_window: f64,
+    // The fish landed with at least this many perfect reels, best first
+    pub catches: Vec<(u32, String)>,
+}
+
+impl Default for FishingSettings {
+    fn default() -> Self {
+        FishingSettings {
+            bpm: 90.,
+            reels_to_land: 8,
+            misses_allowed: 3,
+            perfect_window: 0.06,
+            good_window: 0.15,
+            catches: vec![
+                (7, "fish.marlin".to_string()),
+                (4, "fish.mackerel".to_string()),
+                (0, "fish.sprat".to_string()),
+            ],
+        }
+    }
+}
+
+impl FishingSettings {
+    pub fn catch_for(&self, perfect: u32) -> Option<&str> {
+        self.catches
+            .iter()
+            .find(|(needed, _)| perfect >= *needed)
+            .map(|(_, item)| item.as_str())
+    }
+}
+
+// Keeps time with the music. Beat 0 falls on `started_at`, in seconds of app time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conductor {
+    pub bpm: f64,
+    pub started_at: f64,
+}
+
+impl Conductor {
+    pub fn seconds_per_beat(&self) -> f64 {
+        60. / self.bpm
+    }
+
+    // How far into the song `now` is, in beats
+    pub fn position(&self, now: f64) -> f64 {
+        (now - self.started_at) / self.seconds_per_beat()
+    }
+
+    pub fn beat_time(&self, beat: u64) -> f64 {
+        self.started_at + beat as f64 * self.seconds_per_beat()
+    }
+
+    // The beat closest to `now` and how many seconds off it `now` is, early being negative
+    pub fn nearest_beat(&self, now: f64) -> (u64, f64) {
+        let beat = self.position(now).round().max(0.) as u64;
+        (beat, now - self.beat_time(beat))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Judgment {
+    Perfect,
+    Good,
+    Miss,
+}
+
+impl Judgment {
+    pub fn of(offset: f64, settings: &FishingSettings) -> Judgment {
+        let offset = offset.abs();
+        if offset <= settings.perfect_window {
+            Judgment::Perfect
+        } else if offset <= settings.good_window {
+            Judgment::Good
+        } else {
+            Judgment::Miss
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Judgment::Perfect => "Perfect!",
+            Judgment::Good => "Good",
+            Judgment::Miss => "Miss",
+        }
+    }
+}
+
+// One go at landing a fish
+#[derive(Resource, Debug, Clone)]
+pub struct FishingRun {
+    pub conductor: Conductor,
+    pub perfect: u32,
+    pub good: u32,
+    pub misses: u32,
+    pub last_judgment: Option<Judgment>,
+    // Set once the result has been sent, until the game leaves the mini-game
+    pub finished: bool,
+    // First beat that hasn't been reeled on or missed yet
+    next_beat: u64,
+}
+
+impl FishingRun {
+    pub fn new(conductor: Conductor) -> Self {
+        FishingRun {
+            conductor,
+            perfect: 0,
+            good: 0,
+            misses: 0,
+            last_judgment: None,
+            finished: false,
+            next_beat: LEAD_IN_BEATS,
+        }
+    }
+
+    pub fn landed(&self) -> u32 {
+        self.perfect + self.good
+    }
+
+    // Judges a reel at `now`. Reeling twice on a beat, or off the beat, is a miss.
+    pub fn reel(&mut self, now: f64, settings: &FishingSettings) -> Judgment {
+        let (beat, offset) = self.conductor.nearest_beat(now);
+        let judgment = if beat < self.next_beat {
+            Judgment::Miss
+        } else {
+            Judgment::of(offset, settings)
+        };
+        match judgment {
+            Judgment::Perfect => self.perfect += 1,
+            Judgment::Good => self.good += 1,
+            Judgment::Miss => self.misses += 1,
+        }
+        if judgment != Judgment::Miss {
+            self.next_beat = beat + 1;
+        }
+        self.last_judgment = Some(judgment);
+        judgment
+    }
+
+    // Counts the beats that went by without a reel as misses
+    pub fn pass_time(&mut self, now: f64, settings: &FishingSettings) {
+        while now > self.conductor.beat_time(self.next_beat) + settings.good_window {
+            self.misses += 1;
+            self.next_beat += 1;
+            self.last_judgment = Some(Judgment::Miss);
+        }
+    }
+
+    // What came of the run once it's over: the fish landed, or that it got away
+    pub fn outcome<'a>(&self, settings: &'a FishingSettings) -> Option<&'a str> {
+        if self.misses > settings.misses_allowed {
+            Some(FISH_GOT_AWAY)
+        } else if self.landed() >= settings.reels_to_land {
+            Some(settings.catch_for(self.perfect).unwrap_or(FISH_GOT_AWAY))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Component)]
+struct FishingScreen;
+
+#[derive(Component)]
+struct BeatMarker;
+
+#[derive(Component)]
+struct FishingStatus;
+
+fn start_fishing(mut commands: Commands, time: Res<Time>, settings: Res<FishingSettings>) {
+    commands.insert_resource(FishingRun::new(Conductor {
+        bpm: settings.bpm,
+        started_at: time.elapsed_seconds_f64(),
+    }));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(16.),
+                    ..default()
+                },
+                background_color: Color::rgb(0.05, 0.12, 0.2).into(),
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            FishingScreen,
+            UiLayer::Modal,
+        ))
+        .with_children(|screen| {
+            screen.spawn(fishing_text("Reel on the beat! (Space)", 32.0));
+            screen.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(80.0),
+                        height: Val::Px(80.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.9, 0.8, 0.3).into(),
+                    ..default()
+                },
+                BeatMarker,
+            ));
+            screen.spawn((fishing_text("", 24.0), FishingStatus));
+            screen.spawn(fishing_text("Escape to let it go", 18.0));
+        });
+}
+
+fn fishing_text(text: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+fn reel_on_beat(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<FishingSettings>,
+    mut run: ResMut<FishingRun>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut finished: EventWriter<MiniGameFinished>,
+) {
+    if run.finished {
+        return;
+    }
+    let now = time.elapsed_seconds_f64();
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        run.reel(now, &settings);
+    }
+    run.pass_time(now, &settings);
+    let outcome = if keyboard_input.just_pressed(KeyCode::Escape) {
+        Some(FISH_GOT_AWAY)
+    } else {
+        run.outcome(&settings)
+    };
+    let Some(outcome) = outcome else {
+        return;
+    };
+    run.finished = true;
+    if outcome != FISH_GOT_AWAY {
+        if let Err(error) = facts.apply_batch(vec![FactMutation::AddToList(
+            INVENTORY_FACT.to_string(),
+            outcome.to_string(),
+        )]) {
+            warn!("Could not put {} in the inventory: {}", outcome, error);
+        }
+    }
+    finished.send(MiniGameFinished::new(
+        FISHING_ID,
+        FactValue::String(outcome.to_string()),
+    ));
+}
+
+// Shrinks the marker between beats, so it's biggest on the beat
+fn pulse_beat_marker(
+    time: Res<Time>,
+    run: Res<FishingRun>,
+    mut markers: Query<&mut Transform, With<BeatMarker>>,
+) {
+    let phase = run
+        .conductor
+        .position(time.elapsed_seconds_f64())
+        .rem_euclid(1.);
+    let scale = 1. - 0.4 * phase as f32;
+    for mut transform in markers.iter_mut() {
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+fn update_fishing_status(
+    run: Res<FishingRun>,
+    settings: Res<FishingSettings>,
+    mut texts: Query<&mut Text, With<FishingStatus>>,
+) {
+    if !run.is_changed() {
+        return;
+    }
+    let judgment = run
+        .last_judgment
+        .map(|judgment| judgment.label())
+        .unwrap_or("");
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!(
+            "Reeled {}/{}   Misses {}/{}   {}",
+            run.landed(),
+            settings.reels_to_land,
+            run.misses,
+            settings.misses_allowed,
+            judgment
+        );
+    }
+}
+
+fn cleanup_fishing(mut commands: Commands, screens: Query<Entity, With<FishingScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<FishingRun>();
+}
@@ -1,30 +1,79 @@
 #![allow(clippy::type_complexity)]
 
-mod actions;
+pub mod actions;
+mod archetypes;
 mod audio;
 mod beats;
-mod loading;
+mod camera;
+mod chapters;
+pub mod codex;
+pub mod config;
+pub mod content_reload;
+pub mod credits;
+pub mod crew;
+pub mod dialogue;
+pub mod difficulty;
+pub mod endings;
+pub mod fishing;
+pub mod loading;
+pub mod map;
 mod menu;
+pub mod mini_games;
+pub mod mods;
+pub mod parallax;
+mod particles;
 mod player;
-mod ui;
+pub mod prelude;
+pub mod settings;
+mod sfx;
+mod shop;
+pub mod sprite_animation;
+mod supplies;
+mod transitions;
+pub mod tween;
+pub mod ui;
+pub mod weather;
 
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
 use crate::loading::LoadingPlugin;
+use crate::map::MapPlugin;
 use crate::menu::MenuPlugin;
+use crate::mini_games::MiniGamesPlugin;
+use crate::mods::ModsPlugin;
+use crate::parallax::ParallaxPlugin;
+use crate::particles::ParticlePlugin;
 use crate::player::PlayerPlugin;
 
+use crate::archetypes::ArchetypePlugin;
 use crate::beats::StoryPlugin;
+use crate::camera::CameraPlugin;
+use crate::chapters::ChaptersPlugin;
+use crate::codex::CodexPlugin;
+use crate::content_reload::ContentReloadPlugin;
+use crate::credits::CreditsPlugin;
+use crate::crew::CrewPlugin;
+use crate::dialogue::DialoguePlugin;
+use crate::difficulty::DifficultyPlugin;
+use crate::endings::EndingsPlugin;
+use crate::fishing::FishingPlugin;
+use crate::sfx::SfxPlugin;
+use crate::shop::ShopPlugin;
+use crate::sprite_animation::SpriteAnimationPlugin;
+use crate::supplies::SuppliesPlugin;
+use crate::transitions::TransitionPlugin;
+use crate::tween::TweenPlugin;
+use crate::weather::WeatherPlugin;
 use bevy::app::App;
 #[cfg(debug_assertions)]
-use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::diagnostic::LogDiagnosticsPlugin;
 use bevy::prelude::*;
 
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
 // Or https://github.com/bevyengine/bevy/blob/main/examples/ecs/state.rs
 #[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
-enum GameState {
+pub enum GameState {
     // During the loading State the LoadingPlugin will load our assets
     #[default]
     Loading,
@@ -33,6 +82,15 @@ enum GameState {
     Story,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // Picking a chapter to start from, reached from the menu
+    ChapterSelect,
+    // Lore entries unlocked so far, reached from the menu
+    Codex,
+    // A registered mini-game with this id, started by a story effect
+    MiniGame(String),
+    // Summary of the run after the stories end, before the credits
+    Epilogue,
+    Credits,
 }
 
 pub struct GamePlugin;
@@ -46,11 +104,39 @@ impl Plugin for GamePlugin {
             InternalAudioPlugin,
             PlayerPlugin,
             StoryPlugin,
+            DialoguePlugin,
+            ChaptersPlugin,
+            CreditsPlugin,
+            DifficultyPlugin,
+            EndingsPlugin,
+            ShopPlugin,
+            CodexPlugin,
+            MapPlugin,
+        ));
+        app.add_plugins((MiniGamesPlugin, FishingPlugin));
+        // Community content, merged on top of the game's own
+        app.add_plugins(ModsPlugin);
+        // Stories, data and mods read from disk again on request while developing
+        app.add_plugins(ContentReloadPlugin);
+        // Running the ship
+        app.add_plugins((SuppliesPlugin, CrewPlugin));
+        // What story scenes show on screen
+        app.add_plugins((
+            CameraPlugin,
+            ParallaxPlugin,
+            TransitionPlugin,
+            ArchetypePlugin,
+            SpriteAnimationPlugin,
+            ParticlePlugin,
+            SfxPlugin,
+            WeatherPlugin,
+            TweenPlugin,
         ));
 
         #[cfg(debug_assertions)]
         {
-            app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
+            // Frame time diagnostics are added by the diagnostics overlay
+            app.add_plugins(LogDiagnosticsPlugin::default());
         }
     }
 }
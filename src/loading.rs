@@ -1,13 +1,32 @@
+use crate::beats::data::{Condition, EvaluationContext, Fact, FactsOfTheWorld};
+use crate::beats::rng::StoryRng;
+use crate::beats::save::{SaveGame, SaveMigrations, SAVE_FILE};
+use crate::beats::save_location::SaveLocation;
+use crate::ui::builders::NodeBundleBuilder;
 use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use bevy::utils::BoxedFuture;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
+use rand::Rng;
+use serde::Deserialize;
+
+pub const LOADING_TIPS_FILE: &str = "tips.ron";
+// Seconds each tip stays up before the next one
+pub const TIP_SECONDS: f32 = 6.;
 
 pub struct LoadingPlugin;
 
 /// This plugin loads all assets using [`AssetLoader`] from a third party bevy plugin
 /// Alternatively you can write the logic to load assets yourself
 /// If interested, take a look at <https://bevy-cheatbook.github.io/features/assets.html>
+///
+/// While loading, tips from `assets/tips.ron` rotate at the bottom of the screen. Each tip can
+/// have conditions, checked against the facts of the last save so only hints for what the
+/// player has already unlocked come up.
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
         app.add_loading_state(
@@ -15,7 +34,14 @@ impl Plugin for LoadingPlugin {
                 .continue_to_state(GameState::Menu)
                 .load_collection::<AudioAssets>()
                 .load_collection::<TextureAssets>(),
-        );
+        )
+        .init_asset::<LoadingTips>()
+        .init_asset_loader::<LoadingTipsLoader>()
+        .init_resource::<TipFacts>()
+        .add_systems(Startup, (load_loading_tips, load_tip_facts))
+        .add_systems(OnEnter(GameState::Loading), setup_loading_screen)
+        .add_systems(Update, rotate_tips.run_if(in_state(GameState::Loading)))
+        .add_systems(OnExit(GameState::Loading), cleanup_loading_screen);
     }
 }
 
@@ -35,3 +61,207 @@ pub struct TextureAssets {
     #[asset(path = "textures/github.png")]
     pub github: Handle<Image>,
 }
+
+// (
+//     tips: [
+//         (text: "Press F5 to save"),
+//         (text: "The lighthouse keeper ..", conditions: [BoolEquals(fact_name: "met_keeper", expected_value: true)]),
+//     ],
+// )
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct LoadingTips {
+    pub tips: Vec<LoadingTip>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadingTip {
+    pub text: String,
+    // Only shown while these pass
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+impl LoadingTip {
+    // The facts come straight from a save, tips name them as they are stored, without aliases
+    pub fn is_unlocked(&self, facts: &HashMap<String, Fact>) -> bool {
+        let aliases = HashMap::new();
+        let mut rng = StoryRng::new(0);
+        let mut context = EvaluationContext {
+            now: 0.0,
+            rng: &mut rng,
+            facts,
+            aliases: &aliases,
+        };
+        self.conditions
+            .iter()
+            .all(|condition| condition.evaluate_with(&mut context))
+    }
+}
+
+impl LoadingTips {
+    // Indices of the tips that can be shown with these facts
+    pub fn unlocked(&self, facts: &HashMap<String, Fact>) -> Vec<usize> {
+        self.tips
+            .iter()
+            .enumerate()
+            .filter(|(_, tip)| tip.is_unlocked(facts))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadingTipsLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for LoadingTipsLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadingTipsLoadError::Io(error) => write!(f, "could not read tips: {}", error),
+            LoadingTipsLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoadingTipsLoadError {}
+
+#[derive(Default)]
+pub struct LoadingTipsLoader;
+
+impl AssetLoader for LoadingTipsLoader {
+    type Asset = LoadingTips;
+    type Settings = ();
+    type Error = LoadingTipsLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(LoadingTipsLoadError::Io)?;
+            ron::from_str(&source).map_err(LoadingTipsLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tips.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct LoadingTipsHandle(pub Handle<LoadingTips>);
+
+// Facts from the last save. Nothing has been loaded into the fact store yet while the game
+// starts, so this is what the player has unlocked so far.
+#[derive(Resource, Debug, Default)]
+pub struct TipFacts(pub HashMap<String, Fact>);
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct TipText {
+    // Index into the tips of the tip shown, none until the tips have loaded
+    shown: Option<usize>,
+    remaining: Timer,
+}
+
+fn load_loading_tips(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LoadingTipsHandle(asset_server.load(LOADING_TIPS_FILE)));
+}
+
+fn load_tip_facts(
+    mut tip_facts: ResMut<TipFacts>,
+    location: Res<SaveLocation>,
+    migrations: Res<SaveMigrations>,
+) {
+    // No save yet is a first run, which only gets the tips without conditions
+    let Ok(source) = location.read(SAVE_FILE) else {
+        return;
+    };
+    let save = SaveGame::from_ron(&source)
+        .map_err(|error| error.to_string())
+        .and_then(|mut save| migrations.migrate(&mut save).map(|_| save));
+    match save {
+        Ok(save) => tip_facts.0 = save.facts,
+        Err(error) => warn!("Could not read {} for loading tips: {}", SAVE_FILE, error),
+    }
+}
+
+fn setup_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.bottom_center(40.))
+                .build(),
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                TipText {
+                    shown: None,
+                    remaining: Timer::from_seconds(TIP_SECONDS, TimerMode::Repeating),
+                },
+            ));
+        });
+}
+
+fn rotate_tips(
+    time: Res<Time>,
+    handle: Res<LoadingTipsHandle>,
+    loading_tips: Res<Assets<LoadingTips>>,
+    tip_facts: Res<TipFacts>,
+    facts: Res<FactsOfTheWorld>,
+    mut texts: Query<(&mut Text, &mut TipText)>,
+) {
+    let Some(loading_tips) = loading_tips.get(&handle.0) else {
+        return;
+    };
+    for (mut text, mut tip_text) in texts.iter_mut() {
+        let due = tip_text.remaining.tick(time.delta()).just_finished();
+        if tip_text.shown.is_some() && !due {
+            continue;
+        }
+        // Facts set this session win over the saved ones
+        let mut known = tip_facts.0.clone();
+        known.extend(
+            facts
+                .facts
+                .iter()
+                .map(|(key, fact)| (key.clone(), fact.clone())),
+        );
+        let mut unlocked = loading_tips.unlocked(&known);
+        if unlocked.len() > 1 {
+            unlocked.retain(|index| Some(*index) != tip_text.shown);
+        }
+        if unlocked.is_empty() {
+            continue;
+        }
+        // Looks only, so this doesn't draw from the story rng
+        let index = unlocked[rand::thread_rng().gen_range(0..unlocked.len())];
+        tip_text.shown = Some(index);
+        text.sections[0].value = loading_tips.tips[index].text.clone();
+    }
+}
+
+fn cleanup_loading_screen(mut commands: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,51 +1,64 @@
 // disable console on windows for release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use barnacle_beats::config::GameConfig;
+use barnacle_beats::mods::{mods_asset_source, MODS_SOURCE};
 use barnacle_beats::GamePlugin;
 use bevy::asset::AssetMetaCheck;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
-use bevy::DefaultPlugins; // ToDo: Replace bevy_game with your new crate name.
+use bevy::DefaultPlugins;
+use winit::window::Icon;
 
 fn main() {
+    let config = GameConfig::load();
     App::new()
         .insert_resource(Msaa::Off)
         .insert_resource(AssetMetaCheck::Never)
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
+        // Registered before the asset plugin, which only picks up sources that exist by then
+        .register_asset_source(MODS_SOURCE, mods_asset_source())
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                title: "Barnacle Beats".to_string(), // ToDo
+                title: config.title.clone(),
                 // Bind to canvas included in `index.html`
-                canvas: Some("#bevy".to_owned()),
+                canvas: config.canvas.clone(),
                 // Tells wasm not to override default event handling, like F5 and Ctrl+R
                 prevent_default_event_handling: false,
                 ..default()
             }),
             ..default()
         }))
+        .insert_resource(config.saves.clone())
+        .insert_resource(config)
         .add_plugins(GamePlugin)
-        // .add_systems(Startup, set_window_icon)
+        .add_systems(Startup, set_window_icon)
         .run();
 }
 
 // Sets the icon on windows and X11
 fn set_window_icon(
+    config: Res<GameConfig>,
     windows: NonSend<WinitWindows>,
     primary_window: Query<Entity, With<PrimaryWindow>>,
 ) {
+    let Some(icon_path) = &config.icon else {
+        return;
+    };
     let primary_entity = primary_window.single();
-    let Some(_primary) = windows.get_window(primary_entity) else {
+    let Some(primary) = windows.get_window(primary_entity) else {
+        return;
+    };
+    let Ok(image) = image::open(format!("assets/{}", icon_path)) else {
+        warn!("Could not load window icon {}", icon_path);
         return;
     };
-    // let icon_buf = Cursor::new(include_bytes!(
-    //     "../build/macos/AppIcon.iconset/icon_256x256.png"
-    // ));
-    // if let Ok(image) = image::load(icon_buf, image::ImageFormat::Png) {
-    //     let image = image.into_rgba8();
-    //     let (width, height) = image.dimensions();
-    //     let rgba = image.into_raw();
-    //     let icon = Icon::from_rgba(rgba, width, height).unwrap();
-    //     primary.set_window_icon(Some(icon));
-    // };
+    let image = image.into_rgba8();
+    let (width, height) = image.dimensions();
+    let rgba = image.into_raw();
+    match Icon::from_rgba(rgba, width, height) {
+        Ok(icon) => primary.set_window_icon(Some(icon)),
+        Err(error) => warn!("Invalid window icon {}: {}", icon_path, error),
+    }
 }
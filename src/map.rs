@@ -0,0 +1,467 @@
+use crate::beats::data::{
+    Condition, Effect, EffectOutput, FactMutation, FactsOfTheWorld, SceneTransitionKind,
+};
+use crate::beats::rng::StoryRng;
+use crate::beats::story_time::StoryTime;
+use crate::ui::confirm::{ConfirmationAnswered, RequestConfirmation};
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+pub const MAP_FILE: &str = "map.ron";
+// Bool facts named `discovered.<location>` put a location's pin on the map
+pub const DISCOVERED_PREFIX: &str = "discovered";
+// String fact set to the id of the location picked on the map, for stories to react to
+pub const TRAVEL_FACT: &str = "travel.destination";
+// Id of the question asked before `Effect::TravelTo` moves the player
+pub const TRAVEL_CONFIRMATION: &str = "travel";
+
+pub struct MapPlugin;
+
+/// The world map, opened with M or `Effect::OpenMap` during a story. Pins show up for
+/// discovered locations, and clicking one travels there by setting `travel.destination`
+/// and running the location's effects. `Effect::TravelTo` travels the same way once the
+/// player confirms it.
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WorldMap>()
+            .init_asset_loader::<WorldMapLoader>()
+            .init_state::<MapScreen>()
+            .init_resource::<PendingTravel>()
+            .add_systems(Startup, load_map)
+            .add_systems(
+                Update,
+                (open_map, request_travel, confirm_travel).run_if(in_state(GameState::Story)),
+            )
+            .add_systems(OnEnter(MapScreen::Open), setup_map)
+            .add_systems(
+                Update,
+                (map_pin_system, close_map_keys).run_if(in_state(MapScreen::Open)),
+            )
+            .add_systems(OnExit(MapScreen::Open), cleanup_map)
+            .add_systems(OnExit(GameState::Story), close_map);
+    }
+}
+
+// Whether the map is up. Kept apart from the game state so the story stays loaded underneath.
+#[derive(States, Default, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum MapScreen {
+    #[default]
+    Closed,
+    Open,
+}
+
+// (
+//     locations: [
+//         (id: "harbour", label: "Harbour", position: (30., 60.)),
+//         (id: "lighthouse", label: "Lighthouse", position: (75., 20.), requires: [..], effects: [..]),
+//     ],
+// )
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct WorldMap {
+    pub locations: Vec<MapLocation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MapLocation {
+    pub id: String,
+    pub label: String,
+    // Percent of the map's width and height, from the top left
+    pub position: (f32, f32),
+    // A discovered location is shown but can't be travelled to until these pass
+    #[serde(default)]
+    pub requires: Vec<Condition>,
+    // Level shown once the player arrives, faded in by a scene transition
+    #[serde(default)]
+    pub level: Option<String>,
+    // Seconds the story clock moves on by while travelling there
+    #[serde(default)]
+    pub travel_time: f64,
+    // Run after the travel fact is set
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+}
+
+pub fn discovered_fact(location: &str) -> String {
+    format!("{}.{}", DISCOVERED_PREFIX, location)
+}
+
+impl MapLocation {
+    pub fn is_discovered(&self, facts: &FactsOfTheWorld) -> bool {
+        facts
+            .get_bool(&discovered_fact(&self.id))
+            .is_some_and(|discovered| *discovered)
+    }
+
+    pub fn is_reachable(&self, facts: &FactsOfTheWorld) -> bool {
+        self.requires
+            .iter()
+            .all(|condition| condition.evaluate(&*facts))
+    }
+}
+
+impl WorldMap {
+    pub fn discovered<'a>(
+        &'a self,
+        facts: &'a FactsOfTheWorld,
+    ) -> impl Iterator<Item = &'a MapLocation> + 'a {
+        self.locations
+            .iter()
+            .filter(|location| location.is_discovered(facts))
+    }
+
+    pub fn find(&self, id: &str) -> Option<&MapLocation> {
+        self.locations.iter().find(|location| location.id == id)
+    }
+}
+
+#[derive(Debug)]
+pub enum WorldMapLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for WorldMapLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorldMapLoadError::Io(error) => write!(f, "could not read map: {}", error),
+            WorldMapLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for WorldMapLoadError {}
+
+#[derive(Default)]
+pub struct WorldMapLoader;
+
+impl AssetLoader for WorldMapLoader {
+    type Asset = WorldMap;
+    type Settings = ();
+    type Error = WorldMapLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(WorldMapLoadError::Io)?;
+            ron::from_str(&source).map_err(WorldMapLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct WorldMapHandle(pub Handle<WorldMap>);
+
+// Where `Effect::TravelTo` is waiting to go until the player answers
+#[derive(Resource, Debug, Default)]
+pub struct PendingTravel(pub Option<String>);
+
+#[derive(Component)]
+struct MapPanel;
+
+#[derive(Component)]
+enum MapButton {
+    Travel(String),
+    Close,
+}
+
+const MAP_BACKGROUND: Color = Color::rgb(0.12, 0.2, 0.28);
+const PIN_BUTTON: Color = Color::rgb(0.15, 0.15, 0.25);
+const HOVERED_PIN_BUTTON: Color = Color::rgb(0.25, 0.25, 0.4);
+const CURRENT_PIN_BUTTON: Color = Color::rgb(0.3, 0.45, 0.3);
+const LOCKED_PIN_BUTTON: Color = Color::rgb(0.2, 0.2, 0.2);
+const LOCKED_PIN_TEXT: Color = Color::rgb(0.5, 0.5, 0.5);
+
+fn load_map(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WorldMapHandle(asset_server.load(MAP_FILE)));
+}
+
+fn open_map(
+    mut effect_outputs: EventReader<EffectOutput>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    map_screen: Res<State<MapScreen>>,
+    mut next_map_screen: ResMut<NextState<MapScreen>>,
+) {
+    let requested = effect_outputs
+        .read()
+        .filter(|output| matches!(output, EffectOutput::OpenMap))
+        .count()
+        > 0;
+    if *map_screen.get() == MapScreen::Closed
+        && (requested || keyboard_input.just_pressed(KeyCode::KeyM))
+    {
+        next_map_screen.set(MapScreen::Open);
+    }
+}
+
+fn setup_map(
+    mut commands: Commands,
+    handle: Res<WorldMapHandle>,
+    maps: Res<Assets<WorldMap>>,
+    facts: Res<FactsOfTheWorld>,
+) {
+    let current = facts.get_string(TRAVEL_FACT);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            MapPanel,
+            UiLayer::Modal,
+        ))
+        .with_children(|screen| {
+            screen.spawn(map_text("Map", 40.0, Color::rgb(0.9, 0.9, 0.9)));
+            screen
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(800.0),
+                        height: Val::Px(500.0),
+                        ..default()
+                    },
+                    background_color: MAP_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|map| {
+                    let Some(world_map) = maps.get(&handle.0) else {
+                        warn!("The map is not loaded, check {}", MAP_FILE);
+                        return;
+                    };
+                    for location in world_map.discovered(&facts) {
+                        let reachable = location.is_reachable(&facts);
+                        let here = current.is_some_and(|current| *current == location.id);
+                        let mut pin = map.spawn(ButtonBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Percent(location.position.0),
+                                top: Val::Percent(location.position.1),
+                                padding: UiRect::axes(Val::Px(8.), Val::Px(4.)),
+                                ..default()
+                            },
+                            background_color: pin_color(reachable, here).into(),
+                            ..default()
+                        });
+                        // Locked pins are shown but can't be picked
+                        if reachable && !here {
+                            pin.insert(MapButton::Travel(location.id.clone()));
+                        }
+                        pin.with_children(|parent| {
+                            let color = if reachable {
+                                Color::rgb(0.9, 0.9, 0.9)
+                            } else {
+                                LOCKED_PIN_TEXT
+                            };
+                            parent.spawn(map_text(&location.label, 20.0, color));
+                        });
+                    }
+                });
+            screen
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(320.0),
+                            height: Val::Px(44.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: PIN_BUTTON.into(),
+                        ..default()
+                    },
+                    MapButton::Close,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(map_text("Close", 24.0, Color::rgb(0.9, 0.9, 0.9)));
+                });
+        });
+}
+
+fn pin_color(reachable: bool, here: bool) -> Color {
+    if here {
+        CURRENT_PIN_BUTTON
+    } else if reachable {
+        PIN_BUTTON
+    } else {
+        LOCKED_PIN_BUTTON
+    }
+}
+
+fn map_text(text: &str, font_size: f32, color: Color) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color,
+            ..default()
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn map_pin_system(
+    mut buttons: Query<(&Interaction, &MapButton, &mut BackgroundColor), Changed<Interaction>>,
+    handle: Res<WorldMapHandle>,
+    maps: Res<Assets<WorldMap>>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut rng: ResMut<StoryRng>,
+    mut story_time: ResMut<StoryTime>,
+    mut effect_outputs: EventWriter<EffectOutput>,
+    mut next_map_screen: ResMut<NextState<MapScreen>>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                if let MapButton::Travel(id) = button {
+                    if let Some(location) = maps.get(&handle.0).and_then(|map| map.find(id)) {
+                        effect_outputs.send_batch(travel(
+                            location,
+                            &mut facts,
+                            &mut rng,
+                            &mut story_time,
+                        ));
+                    }
+                }
+                next_map_screen.set(MapScreen::Closed);
+            }
+            Interaction::Hovered => *color = HOVERED_PIN_BUTTON.into(),
+            Interaction::None => *color = PIN_BUTTON.into(),
+        }
+    }
+}
+
+// Moves the player to the location: the facts saying where they are change together, the
+// clock moves on by the travel time and the location's level comes in behind a transition.
+// Returns what the location's effects ask for, for the caller to send on.
+pub fn travel(
+    location: &MapLocation,
+    facts: &mut FactsOfTheWorld,
+    rng: &mut StoryRng,
+    story_time: &mut StoryTime,
+) -> Vec<EffectOutput> {
+    let mut outputs = Vec::new();
+    if let Err(error) = facts.apply_batch(vec![
+        FactMutation::StoreString(TRAVEL_FACT.to_string(), location.id.clone()),
+        // Going somewhere is as good as finding it
+        FactMutation::StoreBool(discovered_fact(&location.id), true),
+    ]) {
+        warn!("Could not travel to {}: {}", location.id, error);
+        return outputs;
+    }
+    story_time.skip(location.travel_time);
+    if let Some(level) = &location.level {
+        outputs.push(EffectOutput::ChangeScene {
+            level: level.clone(),
+            transition: SceneTransitionKind::Fade,
+        });
+    }
+    for effect in location.effects.iter() {
+        match effect.apply(facts, rng) {
+            Ok(effect_outputs) => outputs.extend(effect_outputs),
+            Err(error) => warn!("Travelling to {} failed: {}", location.id, error),
+        }
+    }
+    outputs
+}
+
+fn request_travel(
+    mut effect_outputs: EventReader<EffectOutput>,
+    handle: Res<WorldMapHandle>,
+    maps: Res<Assets<WorldMap>>,
+    mut pending: ResMut<PendingTravel>,
+    mut confirmations: EventWriter<RequestConfirmation>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::TravelTo(id) = output else {
+            continue;
+        };
+        let Some(location) = maps.get(&handle.0).and_then(|map| map.find(id)) else {
+            warn!("No location named {} is on the map", id);
+            continue;
+        };
+        if let Some(waiting) = &pending.0 {
+            warn!("Already asking to travel to {}, not to {}", waiting, id);
+            continue;
+        }
+        pending.0 = Some(id.clone());
+        confirmations.send(RequestConfirmation::new(
+            TRAVEL_CONFIRMATION,
+            format!("Travel to {}?", location.label),
+        ));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn confirm_travel(
+    mut answers: EventReader<ConfirmationAnswered>,
+    handle: Res<WorldMapHandle>,
+    maps: Res<Assets<WorldMap>>,
+    mut pending: ResMut<PendingTravel>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut rng: ResMut<StoryRng>,
+    mut story_time: ResMut<StoryTime>,
+    mut effect_outputs: EventWriter<EffectOutput>,
+) {
+    for answer in answers.read() {
+        if answer.id != TRAVEL_CONFIRMATION {
+            continue;
+        }
+        let Some(id) = pending.0.take() else {
+            continue;
+        };
+        if !answer.accepted {
+            continue;
+        }
+        if let Some(location) = maps.get(&handle.0).and_then(|map| map.find(&id)) {
+            effect_outputs.send_batch(travel(location, &mut facts, &mut rng, &mut story_time));
+        }
+    }
+}
+
+fn close_map_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_map_screen: ResMut<NextState<MapScreen>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::KeyM]) {
+        next_map_screen.set(MapScreen::Closed);
+    }
+}
+
+fn close_map(
+    mut next_map_screen: ResMut<NextState<MapScreen>>,
+    mut pending: ResMut<PendingTravel>,
+) {
+    next_map_screen.set(MapScreen::Closed);
+    pending.0 = None;
+}
+
+fn cleanup_map(mut commands: Commands, panels: Query<Entity, With<MapPanel>>) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,3 +1,9 @@
+use crate::beats::data::FactsOfTheWorld;
+use crate::beats::new_game_plus::StartNewGamePlus;
+use crate::beats::rng::RunSeed;
+use crate::codex::unseen_codex_entries;
+use crate::difficulty::DifficultyPresets;
+use crate::endings::ENDING_FACT;
 use crate::loading::TextureAssets;
 use crate::GameState;
 use bevy::prelude::*;
@@ -8,8 +14,19 @@ pub struct MenuPlugin;
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Menu), setup_menu)
-            .add_systems(Update, click_play_button.run_if(in_state(GameState::Menu)))
+        app.init_resource::<SeedInput>()
+            .add_systems(OnEnter(GameState::Menu), setup_menu)
+            .add_systems(
+                Update,
+                (
+                    click_play_button,
+                    type_seed,
+                    update_difficulty_label,
+                    update_seed_label,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            )
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
     }
 }
@@ -32,9 +49,14 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
+fn setup_menu(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    difficulty: Res<DifficultyPresets>,
+    run_seed: Res<RunSeed>,
+    facts: Res<FactsOfTheWorld>,
+) {
     info!("menu");
-    commands.spawn(Camera2dBundle::default());
     commands
         .spawn((
             NodeBundle {
@@ -107,6 +129,233 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>) {
                         },
                     ));
                 });
+
+            // New Game Plus button, once a run has reached its ending
+            if facts
+                .get_string(ENDING_FACT)
+                .is_some_and(|ending| !ending.is_empty())
+            {
+                let button_colors = ButtonColors::default();
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(240.0),
+                                height: Val::Px(50.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: button_colors.normal.into(),
+                            ..Default::default()
+                        },
+                        button_colors,
+                        NewGamePlusButton,
+                        ChangeState(GameState::Story),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "New Game+",
+                            TextStyle {
+                                font_size: 40.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+
+            // Chapter select button
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(240.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::ChapterSelect),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Chapters",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+
+            // Codex button, badged with the entries unlocked since it was last opened
+            let codex_label = match unseen_codex_entries(&facts) {
+                0 => "Codex".to_string(),
+                unseen => format!("Codex ({} new)", unseen),
+            };
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(240.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::Codex),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        codex_label,
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+
+            // Difficulty button, cycles through the presets
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(240.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    CycleDifficulty,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            difficulty_label(&difficulty),
+                            TextStyle {
+                                font_size: 28.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        DifficultyLabel,
+                    ));
+                });
+
+            // Seed of the next run, click to type a friend's seed
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    let button_colors = ButtonColors::default();
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                min_width: Val::Px(240.0),
+                                height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::horizontal(Val::Px(8.)),
+                                ..Default::default()
+                            },
+                            background_color: button_colors.normal.into(),
+                            ..Default::default()
+                        },
+                        button_colors,
+                        EditSeed,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                seed_label(&run_seed, false),
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                    ..default()
+                                },
+                            ),
+                            SeedLabel,
+                        ));
+                    });
+                    let button_colors = ButtonColors::default();
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::horizontal(Val::Px(8.)),
+                                ..Default::default()
+                            },
+                            background_color: button_colors.normal.into(),
+                            ..Default::default()
+                        },
+                        button_colors,
+                        RerollSeed,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "New seed",
+                            TextStyle {
+                                font_size: 20.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                    });
+                });
+
+            // Credits button
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(140.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::Credits),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Credits",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
         });
     commands
         .spawn((
@@ -210,6 +459,45 @@ struct ChangeState(GameState);
 #[derive(Component)]
 struct OpenLink(&'static str);
 
+#[derive(Component)]
+struct NewGamePlusButton;
+
+#[derive(Component)]
+struct CycleDifficulty;
+
+#[derive(Component)]
+struct DifficultyLabel;
+
+#[derive(Component)]
+struct EditSeed;
+
+#[derive(Component)]
+struct RerollSeed;
+
+#[derive(Component)]
+struct SeedLabel;
+
+// Whether keys typed in the menu go to the seed
+#[derive(Resource, Default)]
+struct SeedInput {
+    editing: bool,
+}
+
+// Longest seed that can be typed in
+const MAX_SEED_LENGTH: usize = 32;
+
+fn seed_label(run_seed: &RunSeed, editing: bool) -> String {
+    let cursor = if editing { "_" } else { "" };
+    format!("Seed: {}{}", run_seed.text, cursor)
+}
+
+fn difficulty_label(difficulty: &DifficultyPresets) -> String {
+    let label = difficulty
+        .selected()
+        .map_or("None", |preset| preset.label.as_str());
+    format!("Difficulty: {}", label)
+}
+
 fn click_play_button(
     mut next_state: ResMut<NextState<GameState>>,
     mut interaction_query: Query<
@@ -219,15 +507,40 @@ fn click_play_button(
             &ButtonColors,
             Option<&ChangeState>,
             Option<&OpenLink>,
+            Has<CycleDifficulty>,
+            (Has<EditSeed>, Has<RerollSeed>, Has<NewGamePlusButton>),
         ),
         (Changed<Interaction>, With<Button>),
     >,
+    mut difficulty: ResMut<DifficultyPresets>,
+    mut run_seed: ResMut<RunSeed>,
+    mut seed_input: ResMut<SeedInput>,
+    mut new_game_plus: EventWriter<StartNewGamePlus>,
 ) {
-    for (interaction, mut color, button_colors, change_state, open_link) in &mut interaction_query {
+    for (
+        interaction,
+        mut color,
+        button_colors,
+        change_state,
+        open_link,
+        cycle_difficulty,
+        (edit_seed, reroll_seed, start_new_game_plus),
+    ) in &mut interaction_query
+    {
         match *interaction {
             Interaction::Pressed => {
+                // Clicking anywhere else stops typing
+                seed_input.editing = edit_seed;
+                if reroll_seed {
+                    *run_seed = RunSeed::generate();
+                }
+                if start_new_game_plus {
+                    new_game_plus.send(StartNewGamePlus);
+                }
                 if let Some(state) = change_state {
                     next_state.set(state.0.clone());
+                } else if cycle_difficulty {
+                    difficulty.select_next();
                 } else if let Some(link) = open_link {
                     if let Err(error) = webbrowser::open(link.0) {
                         warn!("Failed to open link {error:?}");
@@ -244,6 +557,64 @@ fn click_play_button(
     }
 }
 
+fn type_seed(
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut seed_input: ResMut<SeedInput>,
+    mut run_seed: ResMut<RunSeed>,
+) {
+    if !seed_input.editing {
+        characters.clear();
+        return;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::Escape]) {
+        seed_input.editing = false;
+        characters.clear();
+        // An empty seed can't be shared, roll a new one instead
+        if run_seed.text.trim().is_empty() {
+            *run_seed = RunSeed::generate();
+        }
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        run_seed.text.pop();
+    }
+    for typed in characters.read() {
+        for character in typed.char.chars() {
+            if (character.is_alphanumeric() || character == '-')
+                && run_seed.text.chars().count() < MAX_SEED_LENGTH
+            {
+                run_seed.text.push(character);
+            }
+        }
+    }
+}
+
+fn update_seed_label(
+    run_seed: Res<RunSeed>,
+    seed_input: Res<SeedInput>,
+    mut labels: Query<&mut Text, With<SeedLabel>>,
+) {
+    if !run_seed.is_changed() && !seed_input.is_changed() {
+        return;
+    }
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = seed_label(&run_seed, seed_input.editing);
+    }
+}
+
+fn update_difficulty_label(
+    difficulty: Res<DifficultyPresets>,
+    mut labels: Query<&mut Text, With<DifficultyLabel>>,
+) {
+    if !difficulty.is_changed() {
+        return;
+    }
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = difficulty_label(&difficulty);
+    }
+}
+
 fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<Menu>>) {
     for entity in menu.iter() {
         commands.entity(entity).despawn_recursive();
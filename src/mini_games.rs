@@ -0,0 +1,165 @@
+use crate::beats::data::{EffectOutput, FactMutation, FactValue, FactsOfTheWorld};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+// Facts written when a mini-game ends live under `minigame.<id>.*`
+pub const MINI_GAME_PREFIX: &str = "minigame";
+
+pub struct MiniGamesPlugin;
+
+/// Runs mini-games in `GameState::MiniGame(id)`. `Effect::StartMiniGame` switches to the
+/// mini-game's state, and once its plugin sends `MiniGameFinished` the result is written to
+/// `minigame.<id>.result` and the game goes back to the state it came from.
+impl Plugin for MiniGamesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MiniGames>()
+            .init_resource::<MiniGameSession>()
+            .add_event::<MiniGameFinished>()
+            .add_systems(
+                Update,
+                (
+                    start_mini_games.run_if(in_state(GameState::Story)),
+                    finish_mini_games,
+                    settle_after_mini_game,
+                ),
+            );
+    }
+}
+
+// Lets a mini-game plugin register itself from its `build`
+pub trait MiniGameAppExt {
+    fn register_mini_game(&mut self, id: impl Into<String>) -> &mut Self;
+}
+
+impl MiniGameAppExt for App {
+    fn register_mini_game(&mut self, id: impl Into<String>) -> &mut Self {
+        self.init_resource::<MiniGames>();
+        self.world.resource_mut::<MiniGames>().0.insert(id.into());
+        self
+    }
+}
+
+// Ids of the mini-games that have a plugin to run them
+#[derive(Resource, Debug, Default)]
+pub struct MiniGames(pub HashSet<String>);
+
+impl MiniGames {
+    pub fn is_registered(&self, id: &str) -> bool {
+        self.0.contains(id)
+    }
+}
+
+// Sent by a mini-game when it's over, with what came of it
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct MiniGameFinished {
+    pub id: String,
+    pub result: FactValue,
+}
+
+impl MiniGameFinished {
+    pub fn new(id: impl Into<String>, result: FactValue) -> Self {
+        MiniGameFinished {
+            id: id.into(),
+            result,
+        }
+    }
+}
+
+// Where to go back to once the running mini-game finishes
+#[derive(Resource, Debug, Default)]
+pub struct MiniGameSession {
+    return_to: Option<GameState>,
+    // Set for the frame the game comes back, so a story resumes instead of starting over
+    resuming: bool,
+}
+
+pub fn mini_game_fact(id: &str, key: &str) -> String {
+    format!("{}.{}.{}", MINI_GAME_PREFIX, id, key)
+}
+
+pub fn mini_game_result_fact(id: &str) -> String {
+    mini_game_fact(id, "result")
+}
+
+// Run condition for a mini-game plugin's systems
+pub fn in_mini_game(id: impl Into<String>) -> impl FnMut(Option<Res<State<GameState>>>) -> bool {
+    in_state(GameState::MiniGame(id.into()))
+}
+
+// Run condition for systems that set up a fresh story on entering `GameState::Story`, which
+// shouldn't run again when coming back from a mini-game
+pub fn resuming_from_mini_game(session: Option<Res<MiniGameSession>>) -> bool {
+    session.is_some_and(|session| session.resuming)
+}
+
+// The facts a finished mini-game writes: its result, and how many times it has been played
+pub fn mini_game_mutations(
+    finished: &MiniGameFinished,
+    facts: &FactsOfTheWorld,
+) -> Vec<FactMutation> {
+    let key = mini_game_result_fact(&finished.id);
+    let result = match &finished.result {
+        FactValue::Bool(value) => FactMutation::StoreBool(key, *value),
+        FactValue::Int(value) => FactMutation::StoreInt(key, *value),
+        FactValue::String(value) => FactMutation::StoreString(key, value.clone()),
+    };
+    let plays_fact = mini_game_fact(&finished.id, "plays");
+    let plays = facts.get_int(&plays_fact).copied().unwrap_or_default();
+    vec![result, FactMutation::StoreInt(plays_fact, plays + 1)]
+}
+
+fn start_mini_games(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mini_games: Res<MiniGames>,
+    state: Res<State<GameState>>,
+    mut session: ResMut<MiniGameSession>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::StartMiniGame(id) = output else {
+            continue;
+        };
+        if !mini_games.is_registered(id) {
+            warn!("No mini-game named {} is registered", id);
+            continue;
+        }
+        if session.return_to.is_some() {
+            warn!("A mini-game is already starting, not starting {}", id);
+            continue;
+        }
+        session.return_to = Some(state.get().clone());
+        next_state.set(GameState::MiniGame(id.clone()));
+    }
+}
+
+fn finish_mini_games(
+    mut finished: EventReader<MiniGameFinished>,
+    state: Res<State<GameState>>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut session: ResMut<MiniGameSession>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for finished in finished.read() {
+        if *state.get() != GameState::MiniGame(finished.id.clone()) {
+            warn!("Mini-game {} finished but isn't running", finished.id);
+            continue;
+        }
+        let Some(return_to) = session.return_to.take() else {
+            continue;
+        };
+        let mutations = mini_game_mutations(finished, &facts);
+        if let Err(error) = facts.apply_batch(mutations) {
+            warn!("Could not store the result of {}: {}", finished.id, error);
+        }
+        session.resuming = true;
+        next_state.set(return_to);
+    }
+}
+
+// The state the game went back to has been entered by now
+fn settle_after_mini_game(state: Res<State<GameState>>, mut session: ResMut<MiniGameSession>) {
+    if session.resuming && !matches!(state.get(), GameState::MiniGame(_)) {
+        session.resuming = false;
+    }
+}
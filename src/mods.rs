@@ -0,0 +1,785 @@
+use crate::archetypes::{Archetype, ArchetypeFiles};
+use crate::beats::data::{FactsOfTheWorld, Story, StoryEngine};
+use crate::beats::errors::EngineError;
+use crate::beats::fact_table::{parse_fact_table, FactTable, FactTableFiles};
+use crate::beats::story_asset::{parse_story, StoryFiles};
+use crate::ui::theme::{Palette, UiTheme};
+use bevy::asset::io::{AssetReaderError, AssetSource, AssetSourceBuilder, AssetSourceId, Reader};
+use bevy::asset::{
+    AssetLoadError, AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext, LoadState,
+    RecursiveDependencyLoadState, UntypedAssetId,
+};
+use bevy::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+use bevy::utils::{BoxedFuture, CowArc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Asset source for the `mods` folder next to the game, or the `mods` bundle served next to it on
+// the web. Paths in it look like `mods://harbour_tales/pack.ron`.
+pub const MODS_SOURCE: &str = "mods";
+// Which packs in the mods folder to load, by folder name. Packs that load later win conflicts.
+pub const MOD_INDEX_FILE: &str = "mods://mods.ron";
+// What a pack adds, in the pack's own folder
+pub const MOD_PACK_FILE: &str = "pack.ron";
+// Owner of content that came with the game, in conflict reports
+pub const BASE_CONTENT: &str = "base game";
+
+// Asset sources have to be registered before `DefaultPlugins`, so the game does this in `main`
+pub fn mods_asset_source() -> AssetSourceBuilder {
+    AssetSource::build().with_reader(AssetSource::get_default_reader(MODS_SOURCE.to_string()))
+}
+
+pub struct ModsPlugin;
+
+/// Community content packs from the `mods` folder. Once the game's own stories, data tables
+/// and archetypes have loaded, the packs listed in `mods/mods.ron` are merged on top of them,
+/// dependencies first and then by load order. Anything a pack replaces is reported in
+/// `Mods::conflicts`, packs that can't be loaded in `Mods::rejected`.
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ModIndex>()
+            .init_asset_loader::<ModIndexLoader>()
+            .init_asset::<ModPack>()
+            .init_asset_loader::<ModPackLoader>()
+            .init_resource::<Mods>()
+            .add_systems(Startup, load_mod_index)
+            .add_systems(
+                Update,
+                (load_mod_packs, merge_mods, report_mod_load_failures).chain(),
+            );
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Clone, Default, Deserialize)]
+pub struct ModIndex {
+    pub packs: Vec<String>,
+}
+
+// A pack's `pack.ron`, with paths relative to the pack's folder:
+//
+//     (
+//         id: "smugglers_cove",
+//         version: "1.2.0",
+//         depends_on: {"harbour_tales": "1.0"},
+//         load_order: 10,
+//         stories: ["stories/smugglers.story.ron"],
+//         fact_tables: ["data/ports.csv"],
+//         archetypes: ["archetypes/lantern.archetype.ron"],
+//         theme: Some((dyslexia_font: Some("fonts/Readable.otf"))),
+//     )
+#[derive(Debug, Clone, Deserialize)]
+struct ModPackSource {
+    id: String,
+    version: PackVersion,
+    #[serde(default)]
+    depends_on: BTreeMap<String, PackVersion>,
+    #[serde(default)]
+    load_order: i32,
+    #[serde(default)]
+    stories: Vec<String>,
+    #[serde(default)]
+    fact_tables: Vec<String>,
+    #[serde(default)]
+    archetypes: Vec<String>,
+    #[serde(default)]
+    theme: Option<ModTheme>,
+}
+
+// `major.minor.patch`, parts left out count as 0, so "1.2" is 1.2.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PackVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl std::str::FromStr for PackVersion {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let mut parts = [0; 3];
+        for (index, part) in version.split('.').enumerate() {
+            let Some(slot) = parts.get_mut(index) else {
+                return Err(format!("{} has more than three parts", version));
+            };
+            *slot = part
+                .parse()
+                .map_err(|_| format!("{} is not a version like 1.2.0", version))?;
+        }
+        Ok(PackVersion {
+            major: parts[0],
+            minor: parts[1],
+            patch: parts[2],
+        })
+    }
+}
+
+impl TryFrom<String> for PackVersion {
+    type Error = String;
+
+    fn try_from(version: String) -> Result<Self, Self::Error> {
+        version.parse()
+    }
+}
+
+impl std::fmt::Display for PackVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+// Who a pack is and where it goes among the others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackManifest {
+    pub id: String,
+    pub version: PackVersion,
+    // Packs this one builds on, with the oldest version of each that will do
+    pub depends_on: BTreeMap<String, PackVersion>,
+    // Lower loads first, e.g. DLC chapters below community packs that change them. A pack's
+    // dependencies always load before it.
+    pub load_order: i32,
+}
+
+impl PackManifest {
+    pub fn new(id: impl Into<String>, version: PackVersion) -> Self {
+        PackManifest {
+            id: id.into(),
+            version,
+            depends_on: BTreeMap::new(),
+            load_order: 0,
+        }
+    }
+
+    pub fn depending_on(mut self, id: impl Into<String>, version: PackVersion) -> Self {
+        self.depends_on.insert(id.into(), version);
+        self
+    }
+
+    pub fn with_load_order(mut self, load_order: i32) -> Self {
+        self.load_order = load_order;
+        self
+    }
+
+    // What's wrong with the manifest on its own, before it's compared with other packs
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() {
+            return Err("the pack has no id".to_string());
+        }
+        if self.depends_on.contains_key(&self.id) {
+            return Err(format!("{} depends on itself", self.id));
+        }
+        Ok(())
+    }
+
+    // Why the pack can't load next to `others`, if it can't
+    fn unmet_dependency<'a>(
+        &self,
+        others: impl Iterator<Item = &'a PackManifest> + Clone,
+    ) -> Option<String> {
+        self.depends_on.iter().find_map(|(id, needed)| {
+            match others.clone().find(|other| &other.id == id) {
+                None => Some(format!("needs {}, which isn't loaded", id)),
+                Some(other) if other.version < *needed => Some(format!(
+                    "needs {} {} or newer, found {}",
+                    id, needed, other.version
+                )),
+                Some(_) => None,
+            }
+        })
+    }
+}
+
+// A pack left out of the merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedPack {
+    pub id: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for RejectedPack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Mod {} is not loaded: {}", self.id, self.reason)
+    }
+}
+
+// Puts packs in the order to merge them, as indices into `manifests`: dependencies first, then
+// by load order, then as listed in `mods.ron`. Packs reusing an id, missing a dependency or
+// needing a newer one are left out, and so are the packs that need those.
+pub fn order_packs(manifests: &[PackManifest]) -> (Vec<usize>, Vec<RejectedPack>) {
+    let mut rejected = Vec::new();
+    let mut candidates: Vec<usize> = Vec::new();
+    for (index, manifest) in manifests.iter().enumerate() {
+        if candidates
+            .iter()
+            .any(|other| manifests[*other].id == manifest.id)
+        {
+            rejected.push(RejectedPack {
+                id: manifest.id.clone(),
+                reason: "another pack already uses this id".to_string(),
+            });
+        } else {
+            candidates.push(index);
+        }
+    }
+    // Leaving a pack out can leave its dependents short, so check until nothing changes
+    while let Some((index, reason)) = candidates.iter().find_map(|index| {
+        manifests[*index]
+            .unmet_dependency(candidates.iter().map(|other| &manifests[*other]))
+            .map(|reason| (*index, reason))
+    }) {
+        candidates.retain(|candidate| *candidate != index);
+        rejected.push(RejectedPack {
+            id: manifests[index].id.clone(),
+            reason,
+        });
+    }
+    let mut ordered: Vec<usize> = Vec::new();
+    while !candidates.is_empty() {
+        let ready = candidates
+            .iter()
+            .copied()
+            .filter(|index| {
+                manifests[*index].depends_on.keys().all(|dependency| {
+                    ordered
+                        .iter()
+                        .any(|placed| &manifests[*placed].id == dependency)
+                })
+            })
+            .min_by_key(|index| (manifests[*index].load_order, *index));
+        let Some(next) = ready else {
+            // Everything left waits on something else that's left
+            for index in candidates.drain(..) {
+                rejected.push(RejectedPack {
+                    id: manifests[index].id.clone(),
+                    reason: "its dependencies go round in a circle".to_string(),
+                });
+            }
+            break;
+        };
+        candidates.retain(|candidate| *candidate != next);
+        ordered.push(next);
+    }
+    (ordered, rejected)
+}
+
+// Parts of the UI theme a pack swaps out, anything left out stays as it was
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ModTheme {
+    pub dyslexia_font: Option<String>,
+    pub standard: Option<Palette>,
+    pub deuteranopia: Option<Palette>,
+    pub protanopia: Option<Palette>,
+}
+
+impl ModTheme {
+    // Applies the theme and returns the names of what it changed
+    pub fn apply(&self, theme: &mut UiTheme) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if let Some(font) = &self.dyslexia_font {
+            theme.dyslexia_font = font.clone();
+            changed.push("dyslexia_font");
+        }
+        for (name, palette, target) in [
+            ("standard", self.standard, &mut theme.standard),
+            ("deuteranopia", self.deuteranopia, &mut theme.deuteranopia),
+            ("protanopia", self.protanopia, &mut theme.protanopia),
+        ] {
+            if let Some(palette) = palette {
+                *target = palette;
+                changed.push(name);
+            }
+        }
+        changed
+    }
+}
+
+// Everything in one pack, read when its `pack.ron` loads
+#[derive(Asset, TypePath, Debug)]
+pub struct ModPack {
+    pub manifest: PackManifest,
+    pub stories: Vec<Story>,
+    pub fact_tables: Vec<FactTable>,
+    #[dependency]
+    pub archetypes: Vec<Handle<Archetype>>,
+    pub theme: Option<ModTheme>,
+}
+
+#[derive(Debug)]
+pub enum ModLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+    // A file the pack lists couldn't be read
+    Content { file: String, message: String },
+    Manifest(String),
+}
+
+impl std::fmt::Display for ModLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModLoadError::Io(error) => write!(f, "could not read mod file: {}", error),
+            ModLoadError::Parse(error) => write!(f, "{}", error),
+            ModLoadError::Content { file, message } => write!(f, "{}: {}", file, message),
+            ModLoadError::Manifest(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ModLoadError {}
+
+impl From<std::io::Error> for ModLoadError {
+    fn from(error: std::io::Error) -> Self {
+        ModLoadError::Io(error)
+    }
+}
+
+pub fn parse_mod_index(source: &str) -> Result<ModIndex, ModLoadError> {
+    ron::from_str(source).map_err(ModLoadError::Parse)
+}
+
+#[derive(Default)]
+pub struct ModIndexLoader;
+
+impl AssetLoader for ModIndexLoader {
+    type Asset = ModIndex;
+    type Settings = ();
+    type Error = ModLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            parse_mod_index(&source)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["mods.ron"]
+    }
+}
+
+#[derive(Default)]
+pub struct ModPackLoader;
+
+impl AssetLoader for ModPackLoader {
+    type Asset = ModPack;
+    type Settings = ();
+    type Error = ModLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            let pack: ModPackSource = ron::from_str(&source).map_err(ModLoadError::Parse)?;
+            let manifest = PackManifest {
+                id: pack.id,
+                version: pack.version,
+                depends_on: pack.depends_on,
+                load_order: pack.load_order,
+            };
+            manifest.validate().map_err(ModLoadError::Manifest)?;
+            let mut stories = Vec::new();
+            for file in pack.stories.iter() {
+                let source = read_pack_file(load_context, file).await?;
+                stories.push(parse_story(&source).map_err(|error| content_error(file, error))?);
+            }
+            let mut fact_tables = Vec::new();
+            for file in pack.fact_tables.iter() {
+                let source = read_pack_file(load_context, file).await?;
+                // Named after the file like the game's own tables, so `data/ports.csv` adds to
+                // the `ports.` namespace
+                let namespace = std::path::Path::new(file)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                fact_tables.push(
+                    parse_fact_table(&namespace, &source)
+                        .map_err(|error| content_error(file, error))?,
+                );
+            }
+            let mut archetypes = Vec::new();
+            for file in pack.archetypes.iter() {
+                archetypes.push(load_context.load(pack_path(load_context, file)?));
+            }
+            let mut theme = pack.theme;
+            if let Some(font) = theme
+                .as_mut()
+                .and_then(|theme| theme.dyslexia_font.as_mut())
+            {
+                *font = pack_path(load_context, font)?.to_string();
+            }
+            Ok(ModPack {
+                manifest,
+                stories,
+                fact_tables,
+                archetypes,
+                theme,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pack.ron"]
+    }
+}
+
+fn content_error(file: &str, error: impl std::fmt::Display) -> ModLoadError {
+    ModLoadError::Content {
+        file: file.to_string(),
+        message: error.to_string(),
+    }
+}
+
+// Where a file the pack lists is, relative to the pack's folder
+fn pack_path(
+    load_context: &LoadContext,
+    file: &str,
+) -> Result<bevy::asset::AssetPath<'static>, ModLoadError> {
+    load_context
+        .asset_path()
+        .resolve_embed(file)
+        .map_err(|error| content_error(file, error))
+}
+
+async fn read_pack_file(
+    load_context: &mut LoadContext<'_>,
+    file: &str,
+) -> Result<String, ModLoadError> {
+    let path = pack_path(load_context, file)?;
+    let bytes = load_context
+        .read_asset_bytes(path)
+        .await
+        .map_err(|error| content_error(file, error))?;
+    String::from_utf8(bytes).map_err(|error| content_error(file, error))
+}
+
+// Something a pack replaced that the game or an earlier pack had added
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModConflict {
+    pub pack: String,
+    // e.g. `story Lost Barnacle` or `fact ports.saltmere.berths`
+    pub content: String,
+    // `base game` or the pack that had it before
+    pub replaced: String,
+}
+
+impl std::fmt::Display for ModConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Mod {} replaces {} from {}",
+            self.pack, self.content, self.replaced
+        )
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Mods {
+    // Ids of the packs merged, in the order they were
+    pub loaded: Vec<String>,
+    pub rejected: Vec<RejectedPack>,
+    pub conflicts: Vec<ModConflict>,
+    // Who added each piece of content last
+    owners: HashMap<String, String>,
+    index: Option<Handle<ModIndex>>,
+    packs: Vec<Handle<ModPack>>,
+    // Keeps the packs' fact tables in the asset store, next to the game's own
+    fact_tables: Vec<Handle<FactTable>>,
+    // What the game brought before the first merge. The engine still has the packs' stories and
+    // the theme their colours after a reload, so merging again starts from these.
+    base_stories: Option<Vec<String>>,
+    base_theme: Option<UiTheme>,
+    packs_requested: bool,
+    merged: bool,
+}
+
+impl Mods {
+    // Records `owner` as where `content` comes from now. If it came from someone else before
+    // the conflict is kept and returned.
+    pub fn claim(&mut self, owner: &str, content: String) -> Option<&ModConflict> {
+        let previous = self.owners.insert(content.clone(), owner.to_string())?;
+        if previous == owner {
+            return None;
+        }
+        self.conflicts.push(ModConflict {
+            pack: owner.to_string(),
+            content,
+            replaced: previous,
+        });
+        self.conflicts.last()
+    }
+
+    pub fn is_merged(&self) -> bool {
+        self.merged
+    }
+
+    // The index and the packs it listed, for reloading them from disk
+    pub fn files(&self) -> Vec<UntypedAssetId> {
+        self.index
+            .iter()
+            .map(|handle| handle.id().untyped())
+            .chain(self.packs.iter().map(|handle| handle.id().untyped()))
+            .collect()
+    }
+
+    // Reads the index again and merges the packs it lists, for after the files were reloaded.
+    // The packs' fact tables are dropped, the merge adds them back.
+    pub fn merge_again(&mut self) {
+        if self.index.is_none() {
+            return;
+        }
+        self.loaded.clear();
+        self.rejected.clear();
+        self.conflicts.clear();
+        self.owners.clear();
+        self.fact_tables.clear();
+        self.packs_requested = false;
+        self.merged = false;
+    }
+}
+
+fn load_mod_index(asset_server: Res<AssetServer>, mut mods: ResMut<Mods>) {
+    // Builds that didn't register the source, like mobile, play without mods
+    let source = AssetSourceId::Name(CowArc::Borrowed(MODS_SOURCE));
+    if asset_server.get_source(source).is_err() {
+        mods.merged = true;
+        return;
+    }
+    mods.index = Some(asset_server.load(MOD_INDEX_FILE));
+}
+
+fn load_mod_packs(
+    asset_server: Res<AssetServer>,
+    indices: Res<Assets<ModIndex>>,
+    mut mods: ResMut<Mods>,
+) {
+    if mods.packs_requested || mods.merged {
+        return;
+    }
+    let Some(index) = &mods.index else {
+        return;
+    };
+    if asset_server.load_state(index) == LoadState::Failed {
+        mods.merged = true;
+        return;
+    }
+    let Some(index) = indices.get(index) else {
+        return;
+    };
+    let packs = index
+        .packs
+        .iter()
+        .map(|pack| asset_server.load(format!("{}://{}/{}", MODS_SOURCE, pack, MOD_PACK_FILE)))
+        .collect();
+    mods.packs = packs;
+    mods.packs_requested = true;
+}
+
+// Loaded, or failed to, along with everything it depends on
+fn settled(asset_server: &AssetServer, id: impl Into<UntypedAssetId>) -> bool {
+    matches!(
+        asset_server.get_recursive_dependency_load_state(id),
+        Some(RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed)
+    )
+}
+
+// A fact table with the facts of `replaced` taken out
+fn without_facts(table: &FactTable, replaced: &[String]) -> FactTable {
+    FactTable {
+        facts: table
+            .facts
+            .iter()
+            .filter(|fact| !replaced.iter().any(|key| key == fact.key()))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn merge_mods(
+    asset_server: Res<AssetServer>,
+    mut mods: ResMut<Mods>,
+    packs: Res<Assets<ModPack>>,
+    story_files: Res<StoryFiles>,
+    table_files: Res<FactTableFiles>,
+    archetype_files: Res<ArchetypeFiles>,
+    mut story_engine: ResMut<StoryEngine>,
+    mut tables: ResMut<Assets<FactTable>>,
+    mut archetypes: ResMut<Assets<Archetype>>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut theme: Option<ResMut<UiTheme>>,
+    mut errors: EventWriter<EngineError>,
+) {
+    if mods.merged || !mods.packs_requested {
+        return;
+    }
+    let base_settled = story_files
+        .0
+        .iter()
+        .all(|handle| settled(&asset_server, handle))
+        && table_files
+            .0
+            .iter()
+            .all(|handle| settled(&asset_server, handle))
+        && archetype_files
+            .0
+            .iter()
+            .all(|handle| settled(&asset_server, handle));
+    if !base_settled
+        || !mods
+            .packs
+            .iter()
+            .all(|handle| settled(&asset_server, handle))
+    {
+        return;
+    }
+    mods.merged = true;
+
+    // What the game brought, so replacing it is reported too. Pack archetypes are loaded by
+    // now, so only the game's own files count.
+    let base_stories = mods
+        .base_stories
+        .get_or_insert_with(|| {
+            story_engine
+                .stories
+                .iter()
+                .map(|story| story.name.clone())
+                .collect()
+        })
+        .clone();
+    for name in base_stories {
+        mods.claim(BASE_CONTENT, format!("story {}", name));
+    }
+    let mut fact_tables: HashMap<String, AssetId<FactTable>> = HashMap::new();
+    for handle in table_files.0.iter() {
+        for fact in tables
+            .get(handle)
+            .iter()
+            .flat_map(|table| table.facts.iter())
+        {
+            mods.claim(BASE_CONTENT, format!("fact {}", fact.key()));
+            fact_tables.insert(fact.key().to_string(), handle.id());
+        }
+    }
+    let mut archetype_ids: HashMap<String, AssetId<Archetype>> = HashMap::new();
+    for handle in archetype_files.0.iter() {
+        if let Some(archetype) = archetypes.get(handle) {
+            mods.claim(BASE_CONTENT, format!("archetype {}", archetype.name));
+            archetype_ids.insert(archetype.name.clone(), handle.id());
+        }
+    }
+    if let Some(theme) = theme.as_mut() {
+        let base = mods.base_theme.get_or_insert_with(|| theme.clone());
+        theme.set_if_neq(base.clone());
+        for part in ["dyslexia_font", "standard", "deuteranopia", "protanopia"] {
+            mods.claim(BASE_CONTENT, format!("theme {}", part));
+        }
+    }
+
+    // Failed packs were reported when they failed
+    let loaded: Vec<&ModPack> = mods
+        .packs
+        .iter()
+        .filter_map(|handle| packs.get(handle))
+        .collect();
+    let manifests: Vec<PackManifest> = loaded.iter().map(|pack| pack.manifest.clone()).collect();
+    let (order, rejected) = order_packs(&manifests);
+    for rejection in rejected {
+        errors.send(EngineError::ModLoad {
+            path: rejection.id.clone(),
+            message: rejection.reason.clone(),
+        });
+        mods.rejected.push(rejection);
+    }
+    for pack in order.into_iter().map(|index| loaded[index]) {
+        let owner = pack.manifest.id.as_str();
+        for story in pack.stories.iter() {
+            mods.claim(owner, format!("story {}", story.name));
+            story_engine.add_or_replace_story(story.clone());
+        }
+        for table in pack.fact_tables.iter() {
+            // The replaced facts come out of the table they were in, so reloading tables
+            // can't put them back over the pack's
+            let mut replaced: HashMap<AssetId<FactTable>, Vec<String>> = HashMap::new();
+            for fact in table.facts.iter() {
+                mods.claim(owner, format!("fact {}", fact.key()));
+                if let Some(previous) = fact_tables.get(fact.key()) {
+                    replaced
+                        .entry(*previous)
+                        .or_default()
+                        .push(fact.key().to_string());
+                }
+            }
+            for (id, keys) in replaced {
+                if let Some(previous) = tables.get_mut(id) {
+                    *previous = without_facts(previous, &keys);
+                }
+            }
+            let handle = tables.add(table.clone());
+            let id = handle.id();
+            mods.fact_tables.push(handle);
+            for fact in table.facts.iter() {
+                fact_tables.insert(fact.key().to_string(), id);
+                facts.store_constant(fact.clone());
+            }
+        }
+        for handle in pack.archetypes.iter() {
+            let Some(archetype) = archetypes.get(handle) else {
+                continue;
+            };
+            let name = archetype.name.clone();
+            mods.claim(owner, format!("archetype {}", name));
+            if let Some(previous) = archetype_ids.insert(name, handle.id()) {
+                if previous != handle.id() {
+                    archetypes.remove(previous);
+                }
+            }
+        }
+        if let (Some(pack_theme), Some(theme)) = (&pack.theme, theme.as_mut()) {
+            for part in pack_theme.apply(theme) {
+                mods.claim(owner, format!("theme {}", part));
+            }
+        }
+        info!("Loaded mod {} {}", owner, pack.manifest.version);
+        mods.loaded.push(owner.to_string());
+    }
+    for conflict in mods.conflicts.iter() {
+        warn!("{}", conflict);
+    }
+}
+
+fn report_mod_load_failures(
+    mut index_failures: EventReader<AssetLoadFailedEvent<ModIndex>>,
+    mut pack_failures: EventReader<AssetLoadFailedEvent<ModPack>>,
+    mut errors: EventWriter<EngineError>,
+) {
+    for failure in index_failures.read() {
+        // No index just means no mods are installed
+        if let AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_)) = failure.error {
+            info!("No {} found, playing without mods", MOD_INDEX_FILE);
+            continue;
+        }
+        errors.send(EngineError::ModLoad {
+            path: failure.path.to_string(),
+            message: failure.error.to_string(),
+        });
+    }
+    for failure in pack_failures.read() {
+        errors.send(EngineError::ModLoad {
+            path: failure.path.to_string(),
+            message: failure.error.to_string(),
+        });
+    }
+}
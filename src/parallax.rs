@@ -0,0 +1,254 @@
+use crate::camera::{move_camera_rig, CameraRig};
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+// Level files bundled with the game, relative to the assets folder
+pub const LEVEL_FILES: &[&str] = &["levels/harbour.level.ron"];
+// Level shown behind the story until another is picked
+pub const DEFAULT_LEVEL: &str = "harbour";
+
+pub struct ParallaxPlugin;
+
+/// Background layers for story scenes, read from `.level.ron` files, that scroll slower than
+/// the camera the further back they are
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<LevelFiles>()
+            .init_resource::<ActiveLevel>()
+            .add_systems(Startup, load_level_files)
+            .add_systems(Update, show_active_level.run_if(in_state(GameState::Story)))
+            .add_systems(OnExit(GameState::Story), despawn_level)
+            .add_systems(
+                PostUpdate,
+                scroll_parallax_layers
+                    .after(move_camera_rig)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct Level {
+    pub name: String,
+    // Area the camera stays inside while the level is shown
+    pub bounds: Option<Rect>,
+    #[dependency]
+    pub textures: Vec<Handle<Image>>,
+    pub layers: Vec<LevelLayer>,
+}
+
+// A background image. A scroll of 1.0 moves with the world, 0.0 stays put on screen, and
+// values between give the depth in between. Layers further back want lower z.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LevelLayer {
+    pub texture: String,
+    #[serde(default = "default_scroll")]
+    pub scroll: (f32, f32),
+    #[serde(default)]
+    pub offset: (f32, f32),
+    pub z: f32,
+}
+
+fn default_scroll() -> (f32, f32) {
+    (1., 1.)
+}
+
+// The file as written, before the textures are loaded
+#[derive(Deserialize)]
+struct LevelSource {
+    name: String,
+    #[serde(default)]
+    bounds: Option<((f32, f32), (f32, f32))>,
+    layers: Vec<LevelLayer>,
+}
+
+#[derive(Debug)]
+pub enum LevelLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelLoadError::Io(error) => write!(f, "could not read level: {}", error),
+            LevelLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for LevelLoadError {}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(LevelLoadError::Io)?;
+            let level: LevelSource = ron::from_str(&source).map_err(LevelLoadError::Parse)?;
+            let textures = level
+                .layers
+                .iter()
+                .map(|layer| load_context.load(layer.texture.clone()))
+                .collect();
+            Ok(Level {
+                name: level.name,
+                bounds: level
+                    .bounds
+                    .map(|((min_x, min_y), (max_x, max_y))| Rect::new(min_x, min_y, max_x, max_y)),
+                textures,
+                layers: level.layers,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+// Keeps the level files loaded
+#[derive(Resource, Default)]
+pub struct LevelFiles(pub Vec<Handle<Level>>);
+
+// The level shown behind the story. Setting another one swaps the layers over on the next
+// frame, or once the level has loaded.
+#[derive(Resource, Debug, Clone)]
+pub struct ActiveLevel {
+    name: String,
+    spawned: bool,
+}
+
+impl Default for ActiveLevel {
+    fn default() -> Self {
+        ActiveLevel {
+            name: DEFAULT_LEVEL.to_string(),
+            spawned: false,
+        }
+    }
+}
+
+impl ActiveLevel {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        if self.name != name {
+            self.name = name;
+            self.spawned = false;
+        }
+    }
+}
+
+// Placed at `origin` when the camera is at the world origin
+#[derive(Component, Debug, Clone)]
+pub struct ParallaxLayer {
+    pub scroll: Vec2,
+    pub origin: Vec2,
+}
+
+fn load_level_files(asset_server: Res<AssetServer>, mut files: ResMut<LevelFiles>) {
+    for path in LEVEL_FILES {
+        files.0.push(asset_server.load(*path));
+    }
+}
+
+pub(crate) fn show_active_level(
+    mut commands: Commands,
+    mut active_level: ResMut<ActiveLevel>,
+    levels: Res<Assets<Level>>,
+    layers: Query<Entity, With<ParallaxLayer>>,
+    mut rigs: Query<&mut CameraRig>,
+) {
+    if active_level.spawned {
+        return;
+    }
+    let Some(level) = levels
+        .iter()
+        .map(|(_, level)| level)
+        .find(|level| level.name == active_level.name)
+    else {
+        // Still loading, unless every level file is in
+        if levels.len() >= LEVEL_FILES.len() {
+            warn!("No level named {} is loaded", active_level.name);
+            active_level.spawned = true;
+        }
+        return;
+    };
+    for entity in layers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (layer, texture) in level.layers.iter().zip(level.textures.iter()) {
+        let origin = Vec2::new(layer.offset.0, layer.offset.1);
+        commands.spawn((
+            SpriteBundle {
+                texture: texture.clone(),
+                transform: Transform::from_translation(origin.extend(layer.z)),
+                ..default()
+            },
+            ParallaxLayer {
+                scroll: Vec2::new(layer.scroll.0, layer.scroll.1),
+                origin,
+            },
+        ));
+    }
+    for mut rig in rigs.iter_mut() {
+        rig.bounds = level.bounds;
+    }
+    active_level.spawned = true;
+}
+
+fn despawn_level(
+    mut commands: Commands,
+    mut active_level: ResMut<ActiveLevel>,
+    layers: Query<Entity, With<ParallaxLayer>>,
+    mut rigs: Query<&mut CameraRig>,
+) {
+    active_level.spawned = false;
+    for entity in layers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for mut rig in rigs.iter_mut() {
+        rig.bounds = None;
+    }
+}
+
+// Layers trail the camera by what they don't scroll, so a layer with scroll 0.0 keeps its
+// place on screen
+fn scroll_parallax_layers(
+    cameras: Query<&Transform, (With<CameraRig>, Without<ParallaxLayer>)>,
+    mut layers: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    let camera = camera.translation.truncate();
+    for (layer, mut transform) in layers.iter_mut() {
+        let position = layer.origin + camera * (Vec2::ONE - layer.scroll);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
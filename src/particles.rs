@@ -0,0 +1,208 @@
+use crate::archetypes::{find_archetype, spawn_archetype, Archetype};
+use crate::beats::data::EffectOutput;
+use crate::camera::CameraRig;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use rand::Rng;
+
+pub struct ParticlePlugin;
+
+/// Small coloured squares flung out of an emitter, for bursts when beats finish.
+/// `Effect::ParticleBurst` spawns an archetype with a `ParticleEmitter` component.
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ParticleEmitter>().add_systems(
+            Update,
+            (
+                burst_particles.run_if(in_state(GameState::Story)),
+                emit_particles,
+                move_particles,
+                despawn_spent_emitters,
+            )
+                .chain(),
+        );
+    }
+}
+
+// Fields left out of an archetype file keep these defaults
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component, Default)]
+pub struct ParticleEmitter {
+    // Particles in one burst
+    pub count: u32,
+    // Bursts once as soon as it's spawned
+    pub burst_on_spawn: bool,
+    // Removes the emitter once it has nothing queued and its particles are gone
+    pub despawn_when_done: bool,
+    // World units per second, each particle gets a speed between the two
+    pub min_speed: f32,
+    pub max_speed: f32,
+    // Seconds
+    pub lifetime: f32,
+    // Degrees, 90 being straight up
+    pub direction: f32,
+    // Degrees either side of the direction, 180 flings them every way
+    pub spread: f32,
+    // World units per second squared pulling particles down
+    pub gravity: f32,
+    pub size: f32,
+    // Particles start anywhere in a box this size centred on the emitter
+    pub area: Vec2,
+    // Particles go from the start colour to the end colour over their life
+    pub start_color: Color,
+    pub end_color: Color,
+    #[reflect(ignore)]
+    queued: u32,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        ParticleEmitter {
+            count: 24,
+            burst_on_spawn: true,
+            despawn_when_done: true,
+            min_speed: 60.,
+            max_speed: 180.,
+            lifetime: 0.8,
+            direction: 90.,
+            spread: 180.,
+            gravity: 200.,
+            size: 6.,
+            area: Vec2::ZERO,
+            start_color: Color::WHITE,
+            end_color: Color::rgba(1., 1., 1., 0.),
+            queued: 0,
+        }
+    }
+}
+
+impl ParticleEmitter {
+    // Queues `count` particles, sent out on the next update
+    pub fn burst(&mut self) {
+        self.queued += self.count;
+    }
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Particle {
+    pub emitter: Entity,
+    pub velocity: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+    pub gravity: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+fn burst_particles(
+    mut commands: Commands,
+    mut effect_outputs: EventReader<EffectOutput>,
+    archetypes: Res<Assets<Archetype>>,
+    rigs: Query<&CameraRig>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::ParticleBurst { archetype, at } = output else {
+            continue;
+        };
+        let Some(archetype) = find_archetype(&archetypes, archetype) else {
+            warn!("No archetype named {} is loaded", archetype);
+            continue;
+        };
+        let position = match at {
+            Some(point) => Vec2::new(point.0, point.1),
+            None => rigs.iter().next().map(|rig| rig.focus).unwrap_or_default(),
+        };
+        let mut transform = archetype.transform;
+        transform.translation = position.extend(transform.translation.z);
+        spawn_archetype(&mut commands, archetype, transform);
+    }
+}
+
+fn emit_particles(
+    mut commands: Commands,
+    mut emitters: Query<(Entity, &mut ParticleEmitter, &Transform)>,
+) {
+    // Looks only, so this doesn't draw from the story rng
+    let mut rng = rand::thread_rng();
+    for (entity, mut emitter, transform) in emitters.iter_mut() {
+        if emitter.is_added() && emitter.burst_on_spawn {
+            emitter.burst();
+        }
+        if emitter.queued == 0 {
+            continue;
+        }
+        // Emitters are spawned on their own, and the global transform lags a frame behind
+        let origin = transform.translation;
+        for _ in 0..emitter.queued {
+            let angle =
+                (emitter.direction + rng.gen_range(-1.0..=1.0) * emitter.spread).to_radians();
+            let speed = if emitter.max_speed > emitter.min_speed {
+                rng.gen_range(emitter.min_speed..emitter.max_speed)
+            } else {
+                emitter.min_speed
+            };
+            let offset = emitter.area * Vec2::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: emitter.start_color,
+                        custom_size: Some(Vec2::splat(emitter.size)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(origin + offset.extend(0.)),
+                    ..default()
+                },
+                Particle {
+                    emitter: entity,
+                    velocity: Vec2::from_angle(angle) * speed,
+                    age: 0.,
+                    lifetime: emitter.lifetime,
+                    gravity: emitter.gravity,
+                    start_color: emitter.start_color,
+                    end_color: emitter.end_color,
+                },
+            ));
+        }
+        emitter.queued = 0;
+    }
+}
+
+fn move_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        particle.velocity.y -= particle.gravity * delta;
+        transform.translation += (particle.velocity * delta).extend(0.);
+        let life = particle.age / particle.lifetime;
+        sprite.color = mix(particle.start_color, particle.end_color, life);
+    }
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let from = from.as_rgba_f32();
+    let to = to.as_rgba_f32();
+    let channel = |index: usize| from[index] + (to[index] - from[index]) * amount;
+    Color::rgba(channel(0), channel(1), channel(2), channel(3))
+}
+
+fn despawn_spent_emitters(
+    mut commands: Commands,
+    emitters: Query<(Entity, &ParticleEmitter)>,
+    particles: Query<&Particle>,
+) {
+    let emitting: HashSet<Entity> = particles.iter().map(|particle| particle.emitter).collect();
+    for (entity, emitter) in emitters.iter() {
+        if emitter.despawn_when_done && emitter.queued == 0 && !emitting.contains(&entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
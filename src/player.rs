@@ -1,4 +1,5 @@
 use crate::actions::Actions;
+use crate::camera::CameraTarget;
 use crate::loading::TextureAssets;
 use crate::GameState;
 use bevy::prelude::*;
@@ -24,7 +25,7 @@ fn spawn_player(mut commands: Commands, textures: Res<TextureAssets>) {
             transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
             ..Default::default()
         })
-        .insert(Player);
+        .insert((Player, CameraTarget));
 }
 
 fn move_player(
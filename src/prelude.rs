@@ -0,0 +1,59 @@
+//! Everything a game needs to drive stories, re-exported from one place so downstream code
+//! doesn't depend on where the types live inside the `beats` module.
+
+pub use crate::beats::achievements::{
+    AchievementBackend, AchievementBackends, AchievementUnlocked, Achievements,
+};
+#[cfg(feature = "steam")]
+pub use crate::beats::achievements::{SteamAchievements, SteamAchievementsPlugin};
+pub use crate::beats::builders::{
+    ConditionBuilder, EffectBuilder, RuleBuildError, RuleBuilder, StoryBeatBuilder, StoryBuilder,
+};
+pub use crate::beats::choices::{ChoiceMade, PresentChoices};
+pub use crate::beats::data::{
+    Choice, Condition, ConditionResult, Conditions, CrewMember, DialogueLine, Easing, Effect,
+    EffectOutput, EvaluationContext, Fact, FactAliasUsed, FactError, FactMutation, FactQuery,
+    FactRemoved, FactUpdated, FactValue, FactWriteDenied, FactsOfTheWorld, Rule, RuleEvaluation,
+    RuleUpdated, RumbleIntensity, SceneTransitionKind, ShakeTrauma, Story, StoryBeat,
+    StoryBeatFinished, StoryEngine, StringHashSet, TimeScale, Transition, Weather, WorldPoint,
+};
+pub use crate::beats::debug::{RequestRuleExplanation, RuleExplanationReady};
+pub use crate::beats::errors::{recover, EngineError, ErrorLog};
+pub use crate::beats::event_sourced::EventSourcedFactStore;
+pub use crate::beats::fact_editor::FactEditor;
+pub use crate::beats::fact_table::{parse_fact_table, FactTable};
+pub use crate::beats::headless::MinimalStoryPlugins;
+pub use crate::beats::intern::Interned;
+pub use crate::beats::journal::{ExportSessionJournal, JournalEntry, JournalKind, SessionJournal};
+pub use crate::beats::karma::{KarmaAggregate, KarmaConfig};
+pub use crate::beats::lint::{lint_stories, KnownFacts, StoryLint};
+pub use crate::beats::logging::{BeatsLogLevel, BEATS_LOG_TARGET};
+#[cfg(feature = "net")]
+pub use crate::beats::net::{
+    MutationRequestDenied, NetworkRole, PeerConnected, PeerDisconnected, PeerId, Recipient,
+    ReplicationInbox, ReplicationMessage, ReplicationOutbox,
+};
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub use crate::beats::net_tcp::{TcpTransport, TcpTransportPlugin};
+pub use crate::beats::new_game_plus::{NewGamePlusPolicy, StartNewGamePlus};
+pub use crate::beats::relationships::{affinity_fact, Relationships};
+pub use crate::beats::rng::{apply_run_seed, RunSeed, StoryRng, StoryRngSeeded};
+pub use crate::beats::save::{
+    LoadGameRequest, SaveGame, SaveGameRequest, SaveMigrations, StoryProgress,
+};
+pub use crate::beats::save_location::SaveLocation;
+pub use crate::beats::signals::{beat_signal, rule_signal, topic_matches, Signals, SubscriptionId};
+pub use crate::beats::simulator::{simulate_suite, Coverage, CoverageReport, Simulation};
+pub use crate::beats::storage::{write_facts, FactStorage};
+pub use crate::beats::story_asset::{parse_story, StoryAsset, STORY_FILES};
+pub use crate::beats::story_time::StoryTime;
+pub use crate::beats::systems::{NarrativeCycleDetected, StoryCascade};
+pub use crate::beats::telemetry::{
+    JsonlTelemetrySink, Telemetry, TelemetryEvent, TelemetryKind, TelemetrySink,
+};
+pub use crate::beats::time_scale::SlowMotion;
+pub use crate::beats::watch::FactWatches;
+pub use crate::beats::{
+    StoryCorePlugin, StoryEvaluation, StoryPlugin, StoryProgression, StoryProgressionPass,
+};
+pub use crate::GameState;
@@ -0,0 +1,124 @@
+use crate::beats::save_location::SaveLocation;
+use crate::ui::theme::PaletteMode;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Kept apart from save slots, so losing or wiping a save leaves the player's options alone.
+// On the web it gets its own local storage key.
+pub const SETTINGS_FILE: &str = "settings.ron";
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<SettingsLoadFailed>()
+        .add_systems(Startup, load_settings)
+        // Not on the first frame, loading isn't a change worth writing back
+        .add_systems(
+            Update,
+            store_settings
+                .run_if(resource_changed::<Settings>.and_then(not(resource_added::<Settings>))),
+        );
+}
+
+// Set when the settings file is there but can't be read. It's left alone for the player to fix
+// or delete instead of being overwritten with the defaults.
+#[derive(Resource, Debug, Default)]
+pub struct SettingsLoadFailed(pub bool);
+
+// Player facing options
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    // Opt-in recording of story progress for playtest analysis
+    pub telemetry_enabled: bool,
+    // How many entries the dialogue backlog keeps, oldest are dropped first
+    pub dialogue_history_len: usize,
+    // Dialogue moves on by itself once a line is fully shown and read
+    pub auto_advance: bool,
+    pub auto_advance_delay: f32,
+    pub auto_advance_delay_per_char: f32,
+    // Multiplies how fast dialogue text is revealed
+    pub text_speed: f32,
+    // Scales all UI, text included
+    pub ui_scale: f32,
+    pub dyslexia_font: bool,
+    pub palette: PaletteMode,
+    // Also log every UiAnnouncement, for checking what a screen reader would get
+    pub announce_to_console: bool,
+    // Captions for voiced lines and important sounds
+    pub subtitles: bool,
+    // Multiplies the volume of every sound, 0.0 is silent
+    pub volume: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            telemetry_enabled: false,
+            dialogue_history_len: 200,
+            auto_advance: false,
+            auto_advance_delay: 1.0,
+            auto_advance_delay_per_char: 0.04,
+            text_speed: 1.0,
+            ui_scale: 1.0,
+            dyslexia_font: false,
+            palette: PaletteMode::Standard,
+            announce_to_console: false,
+            subtitles: false,
+            volume: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    // Options missing from the file, e.g. ones added since it was written, keep their defaults
+    pub fn from_ron(source: &str) -> Result<Self, ron::de::SpannedError> {
+        ron::from_str(source)
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    // Seconds to wait on a fully shown line before auto advancing, longer lines take longer to read
+    pub fn auto_advance_delay_for(&self, text: &str) -> f32 {
+        self.auto_advance_delay + self.auto_advance_delay_per_char * text.chars().count() as f32
+    }
+}
+
+pub fn load_settings(
+    mut settings: ResMut<Settings>,
+    mut failed: ResMut<SettingsLoadFailed>,
+    location: Res<SaveLocation>,
+) {
+    // No file just means the options were never changed
+    if !location.exists(SETTINGS_FILE) {
+        return;
+    }
+    let loaded = location
+        .read(SETTINGS_FILE)
+        .and_then(|source| Settings::from_ron(&source).map_err(|error| error.to_string()));
+    match loaded {
+        Ok(loaded) => *settings = loaded,
+        Err(error) => {
+            warn!(
+                "Could not read {}, changes won't be saved: {}",
+                SETTINGS_FILE, error
+            );
+            failed.0 = true;
+        }
+    }
+}
+
+// Written whenever an option changes, so they stick even if the game doesn't close cleanly
+pub fn store_settings(
+    settings: Res<Settings>,
+    failed: Res<SettingsLoadFailed>,
+    location: Res<SaveLocation>,
+) {
+    if failed.0 {
+        return;
+    }
+    match settings.to_ron() {
+        Ok(source) => location.write(SETTINGS_FILE, &source),
+        Err(error) => warn!("Could not serialize settings: {}", error),
+    }
+}
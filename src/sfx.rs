@@ -0,0 +1,198 @@
+use crate::beats::data::EffectOutput;
+use crate::camera::CameraRig;
+use crate::dialogue::subtitles::ShowSubtitle;
+use crate::settings::Settings;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+// Distance in world units past which a positioned sound can't be heard
+pub const DEFAULT_HEARING_RANGE: f32 = 800.;
+
+pub struct SfxPlugin;
+
+/// One-shot sound effects. Sounds played at a point in the world are panned towards the side
+/// of the screen they're on and get quieter the further they are from the camera.
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .add_event::<ShowSubtitle>()
+            .add_systems(
+                Update,
+                (
+                    play_effect_sounds.run_if(in_state(GameState::Story)),
+                    play_sfx,
+                    place_positional_sounds,
+                    despawn_finished_sounds,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// Plays a sound once, sent by effects and by any game code that wants a sound at a place
+#[derive(Event, Debug, Clone)]
+pub struct PlaySfx {
+    pub sound: Handle<AudioSource>,
+    // Where the sound comes from, none for a sound that plays centred at full volume
+    pub at: Option<Vec2>,
+    pub volume: f32,
+    pub range: f32,
+    // Shown as a subtitle when the sound matters to the story
+    pub caption: Option<String>,
+}
+
+impl PlaySfx {
+    pub fn new(sound: Handle<AudioSource>) -> Self {
+        PlaySfx {
+            sound,
+            at: None,
+            volume: 1.,
+            range: DEFAULT_HEARING_RANGE,
+            caption: None,
+        }
+    }
+
+    pub fn at(mut self, position: Vec2) -> Self {
+        self.at = Some(position);
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+}
+
+// A playing sound at a place in the world, kept panned and attenuated as the camera moves
+#[derive(Component, Debug, Clone)]
+pub struct PositionalSound {
+    pub instance: Handle<AudioInstance>,
+    pub volume: f32,
+    pub range: f32,
+}
+
+// Unpositioned sounds are tracked too, so they're cleaned up the same way
+#[derive(Component, Debug, Clone)]
+struct PlayingSfx(Handle<AudioInstance>);
+
+fn play_effect_sounds(
+    mut effect_outputs: EventReader<EffectOutput>,
+    asset_server: Res<AssetServer>,
+    mut sfx: EventWriter<PlaySfx>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::PlaySound { sound, at, caption } = output else {
+            continue;
+        };
+        let mut request = PlaySfx::new(asset_server.load(sound.clone()));
+        request.at = at.map(|point| Vec2::new(point.0, point.1));
+        request.caption = caption.clone();
+        sfx.send(request);
+    }
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    mut requests: EventReader<PlaySfx>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+    listeners: Query<&Transform, With<CameraRig>>,
+    mut subtitles: EventWriter<ShowSubtitle>,
+) {
+    let listener = listeners
+        .iter()
+        .next()
+        .map(|transform| transform.translation.truncate())
+        .unwrap_or_default();
+    for request in requests.read() {
+        if let Some(caption) = &request.caption {
+            subtitles.send(ShowSubtitle(caption.clone()));
+        }
+        let Some(position) = request.at else {
+            let instance = audio
+                .play(request.sound.clone())
+                .with_volume((request.volume * settings.volume) as f64)
+                .handle();
+            commands.spawn(PlayingSfx(instance));
+            continue;
+        };
+        let (volume, panning) = heard_from(
+            listener,
+            position,
+            request.volume * settings.volume,
+            request.range,
+        );
+        let instance = audio
+            .play(request.sound.clone())
+            .with_volume(volume)
+            .with_panning(panning)
+            .handle();
+        commands.spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position.extend(0.))),
+            PositionalSound {
+                instance: instance.clone(),
+                volume: request.volume,
+                range: request.range,
+            },
+            PlayingSfx(instance),
+        ));
+    }
+}
+
+fn place_positional_sounds(
+    settings: Res<Settings>,
+    listeners: Query<&Transform, With<CameraRig>>,
+    sounds: Query<(&PositionalSound, &Transform), Without<CameraRig>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    let Some(listener) = listeners.iter().next() else {
+        return;
+    };
+    let listener = listener.translation.truncate();
+    for (sound, transform) in sounds.iter() {
+        let Some(instance) = audio_instances.get_mut(&sound.instance) else {
+            continue;
+        };
+        let (volume, panning) = heard_from(
+            listener,
+            transform.translation.truncate(),
+            sound.volume * settings.volume,
+            sound.range,
+        );
+        instance.set_volume(volume, AudioTween::default());
+        instance.set_panning(panning, AudioTween::default());
+    }
+}
+
+fn despawn_finished_sounds(
+    mut commands: Commands,
+    sounds: Query<(Entity, &PlayingSfx)>,
+    audio_instances: Res<Assets<AudioInstance>>,
+) {
+    for (entity, sound) in sounds.iter() {
+        if let Some(instance) = audio_instances.get(&sound.0) {
+            if matches!(instance.state(), PlaybackState::Stopped) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+// Volume and panning of a sound at `position` for a listener at `listener`. Volume falls off
+// linearly to nothing at `range`, panning goes from 0.0 (left) through 0.5 to 1.0 (right)
+// as the sound moves half a range to either side.
+fn heard_from(listener: Vec2, position: Vec2, volume: f32, range: f32) -> (f64, f64) {
+    if range <= 0. {
+        return (volume as f64, 0.5);
+    }
+    let offset = position - listener;
+    let falloff = (1. - offset.length() / range).clamp(0., 1.);
+    let panning = 0.5 + (offset.x / range).clamp(-0.5, 0.5);
+    ((volume * falloff) as f64, panning as f64)
+}
@@ -0,0 +1,478 @@
+use crate::beats::data::{Condition, EffectOutput, FactMutation, FactUpdated, FactsOfTheWorld};
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+// Shop files bundled with the game, relative to the assets folder
+pub const SHOP_FILES: &[&str] = &["shops/harbour_market.shop.ron"];
+// List fact items end up in unless a shop names another
+pub const INVENTORY_FACT: &str = "inventory";
+
+pub struct ShopPlugin;
+
+/// Shops opened by `Effect::OpenShop`, where items are bought with an int fact and end up in a
+/// list fact
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ShopDefinition>()
+            .init_asset_loader::<ShopDefinitionLoader>()
+            .init_resource::<ShopFiles>()
+            .add_event::<PurchaseRequest>()
+            .add_event::<ItemPurchased>()
+            .add_systems(Startup, load_shop_files)
+            .add_systems(
+                Update,
+                (
+                    open_shop,
+                    shop_button_system,
+                    purchase_items,
+                    refresh_shop_panel,
+                    close_shop_keys,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            )
+            .add_systems(OnExit(GameState::Story), close_shop);
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct ShopDefinition {
+    pub name: String,
+    // Int fact paid from
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    // List fact bought item ids are added to
+    #[serde(default = "default_inventory")]
+    pub inventory: String,
+    pub items: Vec<ShopItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShopItem {
+    pub id: String,
+    pub label: String,
+    pub price: i32,
+    // Only stocked while these pass
+    #[serde(default)]
+    pub stock: Vec<Condition>,
+}
+
+fn default_currency() -> String {
+    "coins".to_string()
+}
+
+fn default_inventory() -> String {
+    INVENTORY_FACT.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurchaseError {
+    UnknownItem(String),
+    OutOfStock(String),
+    AlreadyOwned(String),
+    CannotAfford { price: i32, funds: i32 },
+}
+
+impl std::fmt::Display for PurchaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PurchaseError::UnknownItem(item) => write!(f, "no item {} in this shop", item),
+            PurchaseError::OutOfStock(item) => write!(f, "{} is not in stock", item),
+            PurchaseError::AlreadyOwned(item) => write!(f, "{} is already owned", item),
+            PurchaseError::CannotAfford { price, funds } => {
+                write!(f, "costs {} but only {} to spend", price, funds)
+            }
+        }
+    }
+}
+
+impl ShopDefinition {
+    pub fn in_stock<'a>(
+        &'a self,
+        facts: &'a FactsOfTheWorld,
+    ) -> impl Iterator<Item = &'a ShopItem> {
+        self.items.iter().filter(|item| {
+            item.stock
+                .iter()
+                .all(|condition| condition.evaluate(&*facts))
+        })
+    }
+
+    pub fn funds(&self, facts: &FactsOfTheWorld) -> i32 {
+        facts.get_int(&self.currency).copied().unwrap_or_default()
+    }
+
+    pub fn owns(&self, facts: &FactsOfTheWorld, item: &str) -> bool {
+        facts
+            .get_list(&self.inventory)
+            .is_some_and(|inventory| inventory.contains(&item.to_string()))
+    }
+
+    // The fact changes buying the item comes down to, applied together or not at all
+    pub fn purchase(
+        &self,
+        facts: &FactsOfTheWorld,
+        item: &str,
+    ) -> Result<Vec<FactMutation>, PurchaseError> {
+        let Some(shop_item) = self.items.iter().find(|shop_item| shop_item.id == item) else {
+            return Err(PurchaseError::UnknownItem(item.to_string()));
+        };
+        if !self.in_stock(facts).any(|stocked| stocked.id == item) {
+            return Err(PurchaseError::OutOfStock(item.to_string()));
+        }
+        if self.owns(facts, item) {
+            return Err(PurchaseError::AlreadyOwned(item.to_string()));
+        }
+        let funds = self.funds(facts);
+        if funds < shop_item.price {
+            return Err(PurchaseError::CannotAfford {
+                price: shop_item.price,
+                funds,
+            });
+        }
+        Ok(vec![
+            FactMutation::StoreInt(self.currency.clone(), funds - shop_item.price),
+            FactMutation::AddToList(self.inventory.clone(), item.to_string()),
+        ])
+    }
+}
+
+#[derive(Debug)]
+pub enum ShopLoadError {
+    Io(std::io::Error),
+    Parse(ron::de::SpannedError),
+}
+
+impl std::fmt::Display for ShopLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShopLoadError::Io(error) => write!(f, "could not read shop: {}", error),
+            ShopLoadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ShopLoadError {}
+
+#[derive(Default)]
+pub struct ShopDefinitionLoader;
+
+impl AssetLoader for ShopDefinitionLoader {
+    type Asset = ShopDefinition;
+    type Settings = ();
+    type Error = ShopLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader
+                .read_to_string(&mut source)
+                .await
+                .map_err(ShopLoadError::Io)?;
+            ron::from_str(&source).map_err(ShopLoadError::Parse)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["shop.ron"]
+    }
+}
+
+// Keeps the shop files loaded
+#[derive(Resource, Default)]
+pub struct ShopFiles(pub Vec<Handle<ShopDefinition>>);
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct PurchaseRequest {
+    pub shop: String,
+    pub item: String,
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ItemPurchased {
+    pub shop: String,
+    pub item: String,
+}
+
+#[derive(Component)]
+pub struct ShopPanel {
+    pub shop: String,
+}
+
+#[derive(Component)]
+struct ShopItemList;
+
+#[derive(Component)]
+enum ShopButton {
+    Buy { shop: String, item: String },
+    Close,
+}
+
+const SHOP_BUTTON: Color = Color::rgb(0.15, 0.15, 0.25);
+const HOVERED_SHOP_BUTTON: Color = Color::rgb(0.25, 0.25, 0.4);
+const DISABLED_SHOP_BUTTON: Color = Color::rgb(0.2, 0.2, 0.2);
+
+fn load_shop_files(asset_server: Res<AssetServer>, mut shop_files: ResMut<ShopFiles>) {
+    for path in SHOP_FILES {
+        shop_files.0.push(asset_server.load(*path));
+    }
+}
+
+fn find_shop<'a>(shops: &'a Assets<ShopDefinition>, name: &str) -> Option<&'a ShopDefinition> {
+    shops
+        .iter()
+        .map(|(_, shop)| shop)
+        .find(|shop| shop.name == name)
+}
+
+fn open_shop(
+    mut commands: Commands,
+    mut effect_outputs: EventReader<EffectOutput>,
+    shops: Res<Assets<ShopDefinition>>,
+    facts: Res<FactsOfTheWorld>,
+    panels: Query<Entity, With<ShopPanel>>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::OpenShop(name) = output else {
+            continue;
+        };
+        let Some(shop) = find_shop(&shops, name) else {
+            warn!("No shop named {} is loaded", name);
+            continue;
+        };
+        for entity in panels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(40.),
+                        align_self: AlignSelf::Center,
+                        width: Val::Px(420.),
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(16.)),
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+                    z_index: UiLayer::Modal.z_index(),
+                    ..default()
+                },
+                ShopPanel {
+                    shop: shop.name.clone(),
+                },
+                UiLayer::Modal,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    shop.name.clone(),
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(6.),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        ShopItemList,
+                    ))
+                    .with_children(|list| spawn_shop_rows(list, shop, &facts));
+                spawn_shop_button(parent, "Leave", ShopButton::Close, true);
+            });
+    }
+}
+
+fn spawn_shop_rows(list: &mut ChildBuilder, shop: &ShopDefinition, facts: &FactsOfTheWorld) {
+    let funds = shop.funds(facts);
+    list.spawn(TextBundle::from_section(
+        format!("{}: {}", shop.currency, funds),
+        TextStyle {
+            font_size: 16.0,
+            color: Color::rgb(0.8, 0.8, 0.8),
+            ..default()
+        },
+    ));
+    for item in shop.in_stock(facts) {
+        let owned = shop.owns(facts, &item.id);
+        list.spawn(NodeBundle {
+            style: Style {
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(12.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn(TextBundle::from_section(
+                format!("{} ({})", item.label, item.price),
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            spawn_shop_button(
+                row,
+                if owned { "Owned" } else { "Buy" },
+                ShopButton::Buy {
+                    shop: shop.name.clone(),
+                    item: item.id.clone(),
+                },
+                !owned && funds >= item.price,
+            );
+        });
+    }
+}
+
+fn spawn_shop_button(parent: &mut ChildBuilder, label: &str, button: ShopButton, enabled: bool) {
+    let mut entity = parent.spawn(ButtonBundle {
+        style: Style {
+            padding: UiRect::axes(Val::Px(12.), Val::Px(4.)),
+            align_self: AlignSelf::FlexEnd,
+            ..default()
+        },
+        background_color: if enabled {
+            SHOP_BUTTON.into()
+        } else {
+            DISABLED_SHOP_BUTTON.into()
+        },
+        ..default()
+    });
+    // Disabled buttons are left without a ShopButton so they can't be pressed
+    if enabled {
+        entity.insert(button);
+    }
+    entity.with_children(|button| {
+        button.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    });
+}
+
+fn shop_button_system(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &ShopButton, &mut BackgroundColor), Changed<Interaction>>,
+    panels: Query<Entity, With<ShopPanel>>,
+    mut purchases: EventWriter<PurchaseRequest>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match button {
+                ShopButton::Buy { shop, item } => {
+                    purchases.send(PurchaseRequest {
+                        shop: shop.clone(),
+                        item: item.clone(),
+                    });
+                }
+                ShopButton::Close => {
+                    for entity in panels.iter() {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            },
+            Interaction::Hovered => *color = HOVERED_SHOP_BUTTON.into(),
+            Interaction::None => *color = SHOP_BUTTON.into(),
+        }
+    }
+}
+
+pub fn purchase_items(
+    mut requests: EventReader<PurchaseRequest>,
+    shops: Res<Assets<ShopDefinition>>,
+    mut facts: ResMut<FactsOfTheWorld>,
+    mut purchased: EventWriter<ItemPurchased>,
+) {
+    for request in requests.read() {
+        let Some(shop) = find_shop(&shops, &request.shop) else {
+            warn!("No shop named {}", request.shop);
+            continue;
+        };
+        let result = shop
+            .purchase(&facts, &request.item)
+            .map_err(|error| error.to_string())
+            .and_then(|mutations| {
+                facts
+                    .apply_batch(mutations)
+                    .map_err(|error| error.to_string())
+            });
+        match result {
+            Ok(()) => {
+                purchased.send(ItemPurchased {
+                    shop: request.shop.clone(),
+                    item: request.item.clone(),
+                });
+            }
+            Err(error) => warn!("Could not buy {}: {}", request.item, error),
+        }
+    }
+}
+
+// Rebuilds the item rows after purchases or other fact changes, so prices and stock stay current
+fn refresh_shop_panel(
+    mut commands: Commands,
+    mut fact_updates: EventReader<FactUpdated>,
+    facts: Res<FactsOfTheWorld>,
+    shops: Res<Assets<ShopDefinition>>,
+    panels: Query<(&ShopPanel, &Children)>,
+    lists: Query<Entity, With<ShopItemList>>,
+) {
+    if fact_updates.read().count() == 0 {
+        return;
+    }
+    for (panel, children) in panels.iter() {
+        let Some(shop) = find_shop(&shops, &panel.shop) else {
+            continue;
+        };
+        for list in children.iter().filter(|child| lists.contains(**child)) {
+            commands
+                .entity(*list)
+                .despawn_descendants()
+                .with_children(|list| spawn_shop_rows(list, shop, &facts));
+        }
+    }
+}
+
+fn close_shop_keys(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    panels: Query<Entity, With<ShopPanel>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn close_shop(mut commands: Commands, panels: Query<Entity, With<ShopPanel>>) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
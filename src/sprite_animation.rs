@@ -0,0 +1,145 @@
+use crate::beats::data::FactsOfTheWorld;
+use bevy::prelude::*;
+
+pub struct SpriteAnimationPlugin;
+
+/// Spritesheet animation, with the playing clip optionally picked by a string fact so story
+/// effects can change how characters move
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SpriteAnimation>()
+            .register_type::<SpriteClip>()
+            .register_type::<AnimationFact>()
+            .add_systems(Update, (play_clips_from_facts, animate_sprites).chain());
+    }
+}
+
+// Named runs of frames on a texture atlas, of which one plays at a time and loops
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component, Default)]
+pub struct SpriteAnimation {
+    pub fps: f32,
+    pub clips: Vec<SpriteClip>,
+    // Name of the playing clip
+    pub clip: String,
+    #[reflect(ignore)]
+    frame: usize,
+    #[reflect(ignore)]
+    elapsed: f32,
+}
+
+// Frames are indices into the entity's texture atlas layout
+#[derive(Reflect, Debug, Clone, Default, PartialEq)]
+pub struct SpriteClip {
+    pub name: String,
+    pub frames: Vec<usize>,
+}
+
+impl Default for SpriteAnimation {
+    fn default() -> Self {
+        SpriteAnimation::new(8.)
+    }
+}
+
+impl SpriteAnimation {
+    pub fn new(fps: f32) -> Self {
+        SpriteAnimation {
+            fps,
+            clips: Vec::new(),
+            clip: String::new(),
+            frame: 0,
+            elapsed: 0.,
+        }
+    }
+
+    // Adds a clip, the first one added plays until another is picked
+    pub fn with_clip(mut self, name: impl Into<String>, frames: Vec<usize>) -> Self {
+        let name = name.into();
+        if self.clips.is_empty() {
+            self.clip = name.clone();
+        }
+        self.clips.push(SpriteClip { name, frames });
+        self
+    }
+
+    pub fn has_clip(&self, name: &str) -> bool {
+        self.clips.iter().any(|clip| clip.name == name)
+    }
+
+    // Starts the clip from its first frame, unless it is already playing
+    pub fn play(&mut self, name: &str) {
+        if self.clip != name {
+            self.clip = name.to_string();
+            self.frame = 0;
+            self.elapsed = 0.;
+        }
+    }
+
+    // Moves the playing clip on by `delta` seconds and returns the atlas index to show, if a
+    // clip with frames is playing. A zero or negative fps holds the current frame.
+    pub fn advance(&mut self, delta: f32) -> Option<usize> {
+        let frames = &self
+            .clips
+            .iter()
+            .find(|clip| clip.name == self.clip)?
+            .frames;
+        if frames.is_empty() {
+            return None;
+        }
+        if self.fps > 0. {
+            let frame_time = 1. / self.fps;
+            self.elapsed += delta;
+            while self.elapsed >= frame_time {
+                self.elapsed -= frame_time;
+                self.frame += 1;
+            }
+        }
+        self.frame %= frames.len();
+        Some(frames[self.frame])
+    }
+}
+
+// Plays the clip named by a string fact, e.g. `npc.greta.anim`. The clip is picked when the
+// binding is added and whenever facts change, and a missing fact leaves the clip as it is.
+#[derive(Component, Reflect, Debug, Clone, Default)]
+#[reflect(Component)]
+pub struct AnimationFact {
+    pub fact: String,
+}
+
+fn play_clips_from_facts(
+    facts: Res<FactsOfTheWorld>,
+    mut sprites: Query<(Ref<AnimationFact>, &mut SpriteAnimation)>,
+) {
+    let facts_changed = facts.is_changed();
+    for (binding, mut animation) in sprites.iter_mut() {
+        if !facts_changed && !binding.is_changed() {
+            continue;
+        }
+        let Some(clip) = facts.get_string(&binding.fact) else {
+            continue;
+        };
+        if animation.clip == *clip {
+            continue;
+        }
+        if !animation.has_clip(clip) {
+            warn!(
+                "{} names the clip {}, which the sprite doesn't have",
+                binding.fact, clip
+            );
+            continue;
+        }
+        animation.play(clip);
+    }
+}
+
+fn animate_sprites(time: Res<Time>, mut sprites: Query<(&mut SpriteAnimation, &mut TextureAtlas)>) {
+    for (mut animation, mut atlas) in sprites.iter_mut() {
+        let Some(index) = animation.advance(time.delta_seconds()) else {
+            continue;
+        };
+        if atlas.index != index {
+            atlas.index = index;
+        }
+    }
+}
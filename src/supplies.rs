@@ -0,0 +1,225 @@
+use crate::beats::data::{FactMutation, FactsOfTheWorld};
+use crate::beats::story_time::StoryTime;
+use crate::settings::Settings;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use crate::ui::theme::UiTheme;
+use crate::GameState;
+use bevy::prelude::*;
+
+// Bool fact that starts the supplies running down, set by the story when the voyage begins
+pub const SUPPLIES_ACTIVE_FACT: &str = "supplies.active";
+
+pub struct SuppliesPlugin;
+
+/// Ship supplies kept as int facts that run down over story time while `supplies.active` is
+/// set. Warnings and running out are left to rules, see `stories/ship_supplies.story.ron`.
+impl Plugin for SuppliesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SupplySettings>()
+            .init_resource::<SupplyDecay>()
+            .add_systems(OnEnter(GameState::Story), spawn_supplies_hud)
+            .add_systems(
+                Update,
+                (stock_supplies, decay_supplies, update_supplies_hud)
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            )
+            .add_systems(OnExit(GameState::Story), despawn_supplies_hud);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Supply {
+    // Int fact holding the amount left
+    pub fact: String,
+    pub label: String,
+    pub start: i32,
+    pub max: i32,
+    // Lost per minute of story time
+    pub decay_per_minute: f32,
+}
+
+impl Supply {
+    pub fn new(fact: impl Into<String>, label: impl Into<String>, decay_per_minute: f32) -> Self {
+        Supply {
+            fact: fact.into(),
+            label: label.into(),
+            start: 100,
+            max: 100,
+            decay_per_minute,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct SupplySettings {
+    pub supplies: Vec<Supply>,
+    // The HUD shows a supply as low below this, the warning rules use the same number
+    pub low_at: i32,
+}
+
+impl Default for SupplySettings {
+    fn default() -> Self {
+        SupplySettings {
+            supplies: vec![
+                Supply::new("supplies.food", "Food", 2.),
+                Supply::new("supplies.water", "Water", 3.),
+                Supply::new("supplies.morale", "Morale", 1.),
+            ],
+            low_at: 25,
+        }
+    }
+}
+
+// What has been lost but not taken off the facts yet, which only hold whole amounts
+#[derive(Resource, Debug, Default)]
+pub struct SupplyDecay {
+    owed: Vec<f32>,
+    last_tick: Option<f64>,
+}
+
+#[derive(Component)]
+struct SuppliesHud;
+
+#[derive(Component)]
+struct SupplyText(usize);
+
+fn supplies_active(facts: &FactsOfTheWorld) -> bool {
+    facts
+        .get_bool(SUPPLIES_ACTIVE_FACT)
+        .is_some_and(|active| *active)
+}
+
+// Supplies the voyage starts without get their starting amount
+fn stock_supplies(settings: Res<SupplySettings>, mut facts: ResMut<FactsOfTheWorld>) {
+    if !supplies_active(&facts) {
+        return;
+    }
+    let missing: Vec<FactMutation> = settings
+        .supplies
+        .iter()
+        .filter(|supply| facts.get_int(&supply.fact).is_none())
+        .map(|supply| FactMutation::StoreInt(supply.fact.clone(), supply.start))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    if let Err(error) = facts.apply_batch(missing) {
+        warn!("Could not stock supplies: {}", error);
+    }
+}
+
+fn decay_supplies(
+    settings: Res<SupplySettings>,
+    story_time: Res<StoryTime>,
+    mut decay: ResMut<SupplyDecay>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    let now = story_time.elapsed_seconds();
+    let last_tick = decay.last_tick.replace(now);
+    if !supplies_active(&facts) {
+        return;
+    }
+    // Loading a save can move the clock backwards
+    let Some(elapsed) = last_tick.map(|last| (now - last).max(0.)) else {
+        return;
+    };
+    decay.owed.resize(settings.supplies.len(), 0.);
+    let mut mutations = Vec::new();
+    for (supply, owed) in settings.supplies.iter().zip(decay.owed.iter_mut()) {
+        *owed += supply.decay_per_minute * (elapsed / 60.) as f32;
+        let lost = owed.floor();
+        if lost < 1. {
+            continue;
+        }
+        *owed -= lost;
+        let Some(amount) = facts.get_int(&supply.fact) else {
+            continue;
+        };
+        let left = (amount - lost as i32).clamp(0, supply.max);
+        if left != *amount {
+            mutations.push(FactMutation::StoreInt(supply.fact.clone(), left));
+        }
+    }
+    if mutations.is_empty() {
+        return;
+    }
+    if let Err(error) = facts.apply_batch(mutations) {
+        warn!("Could not run down supplies: {}", error);
+    }
+}
+
+fn spawn_supplies_hud(mut commands: Commands, settings: Res<SupplySettings>) {
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.top_left(16.).flex_column().row_gap_px(2.))
+                .with_background_color(Color::rgba(0., 0., 0., 0.5))
+                .on_layer(UiLayer::Hud)
+                .build(),
+            UiLayer::Hud,
+            SuppliesHud,
+        ))
+        .insert(Visibility::Hidden)
+        .with_children(|hud| {
+            for (index, supply) in settings.supplies.iter().enumerate() {
+                hud.spawn((
+                    TextBundle::from_section(
+                        supply.label.clone(),
+                        TextStyle {
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                    SupplyText(index),
+                ));
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_supplies_hud(
+    facts: Res<FactsOfTheWorld>,
+    settings: Res<SupplySettings>,
+    ui_settings: Res<Settings>,
+    theme: Res<UiTheme>,
+    mut huds: Query<&mut Visibility, With<SuppliesHud>>,
+    mut texts: Query<(&mut Text, &SupplyText)>,
+    added: Query<(), Added<SupplyText>>,
+) {
+    let facts_changed = facts.is_changed();
+    let active = supplies_active(&facts);
+    for mut visibility in huds.iter_mut() {
+        *visibility = if active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    if !facts_changed && added.is_empty() {
+        return;
+    }
+    let palette = theme.palette(ui_settings.palette);
+    for (mut text, SupplyText(index)) in texts.iter_mut() {
+        let Some(supply) = settings.supplies.get(*index) else {
+            continue;
+        };
+        let amount = facts.get_int(&supply.fact).copied().unwrap_or(supply.start);
+        text.sections[0].value = format!("{}: {}", supply.label, amount);
+        text.sections[0].style.color = if amount <= 0 {
+            palette.negative
+        } else if amount < settings.low_at {
+            palette.warning
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn despawn_supplies_hud(mut commands: Commands, huds: Query<Entity, With<SuppliesHud>>) {
+    for entity in huds.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,283 @@
+use crate::beats::data::{EffectOutput, SceneTransitionKind};
+use crate::beats::StoryProgression;
+use crate::parallax::{show_active_level, ActiveLevel, ParallaxLayer};
+use crate::ui::layers::UiLayer;
+use crate::GameState;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+pub struct TransitionPlugin;
+
+/// Covers the screen between game states and when `Effect::ChangeScene` swaps the level.
+/// Stories don't progress while a transition plays, `TransitionFinished` marks when they
+/// pick up again.
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransitionSettings>()
+            .init_resource::<SceneTransition>()
+            .add_event::<TransitionFinished>()
+            .configure_sets(Update, StoryProgression.run_if(transition_idle))
+            .add_systems(
+                Update,
+                (
+                    reveal_new_states,
+                    queue_scene_changes.run_if(in_state(GameState::Story)),
+                    start_scene_changes.before(show_active_level),
+                    play_transitions.after(show_active_level),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct TransitionSettings {
+    pub color: Color,
+    // Seconds to cover the screen, and again to uncover it
+    pub half_duration: f32,
+    // Played when the game state changes. The old state's scene is gone by then, so a
+    // crossfade reveals the new state like a fade does.
+    pub on_state_change: SceneTransitionKind,
+}
+
+impl Default for TransitionSettings {
+    fn default() -> Self {
+        TransitionSettings {
+            color: Color::BLACK,
+            half_duration: 0.4,
+            on_state_change: SceneTransitionKind::Fade,
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct TransitionFinished {
+    // The level changed to, none for a change of game state
+    pub level: Option<String>,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct SceneTransition {
+    playing: Option<PlayingTransition>,
+    queued: VecDeque<(String, SceneTransitionKind)>,
+}
+
+impl SceneTransition {
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+}
+
+#[derive(Debug)]
+struct PlayingTransition {
+    kind: SceneTransitionKind,
+    phase: TransitionPhase,
+    elapsed: f32,
+    level: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransitionPhase {
+    Cover,
+    Reveal,
+}
+
+// Run condition for systems that wait for the screen to be uncovered
+pub fn transition_idle(transition: Res<SceneTransition>) -> bool {
+    !transition.is_playing()
+}
+
+#[derive(Component)]
+struct TransitionOverlay;
+
+// Background layers of the level being crossfaded away from
+#[derive(Component)]
+struct FadingOut;
+
+fn reveal_new_states(
+    mut state_changes: EventReader<StateTransitionEvent<GameState>>,
+    settings: Res<TransitionSettings>,
+    mut transition: ResMut<SceneTransition>,
+    mut active_level: ResMut<ActiveLevel>,
+) {
+    if state_changes.read().last().is_none() {
+        return;
+    }
+    // A scene change cut short still lands on its level
+    if let Some(PlayingTransition {
+        phase: TransitionPhase::Cover,
+        level: Some(level),
+        ..
+    }) = transition.playing.take()
+    {
+        active_level.set(level);
+    }
+    let kind = match settings.on_state_change {
+        SceneTransitionKind::Crossfade => SceneTransitionKind::Fade,
+        kind => kind,
+    };
+    transition.playing = Some(PlayingTransition {
+        kind,
+        phase: TransitionPhase::Reveal,
+        elapsed: 0.,
+        level: None,
+    });
+}
+
+fn queue_scene_changes(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut transition: ResMut<SceneTransition>,
+) {
+    for output in effect_outputs.read() {
+        if let EffectOutput::ChangeScene {
+            level,
+            transition: kind,
+        } = output
+        {
+            transition.queued.push_back((level.clone(), *kind));
+        }
+    }
+}
+
+// Scene changes wait for whatever is playing, so each one gets its whole transition
+fn start_scene_changes(
+    mut commands: Commands,
+    mut transition: ResMut<SceneTransition>,
+    mut active_level: ResMut<ActiveLevel>,
+    layers: Query<Entity, With<ParallaxLayer>>,
+) {
+    if transition.is_playing() {
+        return;
+    }
+    let Some((level, kind)) = transition.queued.pop_front() else {
+        return;
+    };
+    if level == active_level.name() {
+        return;
+    }
+    let phase = match kind {
+        // The new layers come in under the old ones straight away, which stop scrolling and
+        // fade out on top
+        SceneTransitionKind::Crossfade => {
+            for entity in layers.iter() {
+                commands
+                    .entity(entity)
+                    .remove::<ParallaxLayer>()
+                    .insert(FadingOut);
+            }
+            active_level.set(level.clone());
+            TransitionPhase::Reveal
+        }
+        SceneTransitionKind::Fade | SceneTransitionKind::Iris => TransitionPhase::Cover,
+    };
+    transition.playing = Some(PlayingTransition {
+        kind,
+        phase,
+        elapsed: 0.,
+        level: Some(level),
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_transitions(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<TransitionSettings>,
+    mut transition: ResMut<SceneTransition>,
+    mut active_level: ResMut<ActiveLevel>,
+    mut overlays: Query<(Entity, &mut Style, &mut BackgroundColor), With<TransitionOverlay>>,
+    mut fading_in: Query<&mut Sprite, (With<ParallaxLayer>, Without<FadingOut>)>,
+    mut fading_out: Query<(Entity, &mut Sprite), With<FadingOut>>,
+    mut finished: EventWriter<TransitionFinished>,
+) {
+    let Some(playing) = transition.playing.as_mut() else {
+        return;
+    };
+    playing.elapsed += time.delta_seconds();
+    let progress = if settings.half_duration > 0. {
+        (playing.elapsed / settings.half_duration).min(1.)
+    } else {
+        1.
+    };
+    // How much of the screen is hidden, 1.0 being all of it
+    let cover = match playing.phase {
+        TransitionPhase::Cover => progress,
+        TransitionPhase::Reveal => 1. - progress,
+    };
+
+    if playing.kind == SceneTransitionKind::Crossfade {
+        for mut sprite in fading_in.iter_mut() {
+            sprite.color.set_a(progress);
+        }
+        for (_, mut sprite) in fading_out.iter_mut() {
+            sprite.color.set_a(1. - progress);
+        }
+    } else if overlays.is_empty() {
+        commands.spawn((
+            overlay_bundle(playing.kind, cover, settings.color),
+            TransitionOverlay,
+            UiLayer::Transition,
+        ));
+    } else {
+        for (_, mut style, mut background) in overlays.iter_mut() {
+            let bundle = overlay_bundle(playing.kind, cover, settings.color);
+            *style = bundle.style;
+            *background = bundle.background_color;
+        }
+    }
+
+    if progress < 1. {
+        return;
+    }
+    match playing.phase {
+        // Fully covered, so the level is swapped where nobody sees it
+        TransitionPhase::Cover => {
+            if let Some(level) = &playing.level {
+                active_level.set(level.clone());
+            }
+            playing.phase = TransitionPhase::Reveal;
+            playing.elapsed = 0.;
+        }
+        TransitionPhase::Reveal => {
+            for (entity, _, _) in overlays.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            for (entity, _) in fading_out.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            finished.send(TransitionFinished {
+                level: playing.level.clone(),
+            });
+            transition.playing = None;
+        }
+    }
+}
+
+// A fade is a see-through sheet over the screen, an iris a border growing in from its edges
+fn overlay_bundle(kind: SceneTransitionKind, cover: f32, color: Color) -> NodeBundle {
+    let mut style = Style {
+        position_type: PositionType::Absolute,
+        width: Val::Percent(100.),
+        height: Val::Percent(100.),
+        ..default()
+    };
+    let mut bundle = NodeBundle {
+        z_index: UiLayer::Transition.z_index(),
+        ..default()
+    };
+    match kind {
+        SceneTransitionKind::Iris => {
+            style.border = UiRect::all(Val::Percent(cover * 50.));
+            bundle.border_color = color.into();
+            // Fully closed the border leaves no hole, but rounding could leave a sliver
+            if cover >= 1. {
+                bundle.background_color = color.into();
+            }
+        }
+        SceneTransitionKind::Fade | SceneTransitionKind::Crossfade => {
+            bundle.background_color = color.with_a(color.a() * cover).into();
+        }
+    }
+    bundle.style = style;
+    bundle
+}
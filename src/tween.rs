@@ -0,0 +1,279 @@
+pub use crate::beats::data::Easing;
+use crate::beats::data::{EffectOutput, FactMutation, FactsOfTheWorld};
+use crate::beats::story_time::StoryTime;
+use crate::GameState;
+use bevy::prelude::*;
+use std::time::Duration;
+
+pub struct TweenPlugin;
+
+/// Values that change gradually over a curve. `Tween` works for anything that can `Lerp`,
+/// the tween components move transforms and sprite colours, and `Effect::TweenFact` walks an
+/// int fact to a new value over story time, e.g. tension rising through a scene.
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FactTweens>()
+            .add_systems(
+                Update,
+                (tween_translations, tween_scales, tween_sprite_colors),
+            )
+            .add_systems(
+                Update,
+                (start_fact_tweens, tween_facts)
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            );
+    }
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+// Values that can be blended, `t` going from 0.0 at `self` to 1.0 at `to`
+pub trait Lerp: Copy {
+    fn lerp_to(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp_to(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp_to(self, to: Self, t: f32) -> Self {
+        self.lerp(to, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp_to(self, to: Self, t: f32) -> Self {
+        self.lerp(to, t)
+    }
+}
+
+// Blends in linear space, so halfway between two colours doesn't come out darker than both
+impl Lerp for Color {
+    fn lerp_to(self, to: Self, t: f32) -> Self {
+        let from = self.as_linear_rgba_f32();
+        let to = to.as_linear_rgba_f32();
+        let mut blended = [0.; 4];
+        for (channel, (from, to)) in blended.iter_mut().zip(from.iter().zip(to.iter())) {
+            *channel = from.lerp_to(*to, t);
+        }
+        Color::rgba_linear(blended[0], blended[1], blended[2], blended[3])
+    }
+}
+
+// A change from one value to another over `duration` seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tween<T: Lerp> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+    elapsed: f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            easing: Easing::default(),
+            elapsed: 0.,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0. {
+            1.
+        } else {
+            self.easing.apply(self.elapsed / self.duration)
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.from.lerp_to(self.to, self.progress())
+    }
+
+    // Moves the tween on and returns where it is now
+    pub fn tick(&mut self, seconds: f32) -> T {
+        self.elapsed = (self.elapsed + seconds).min(self.duration.max(0.));
+        self.value()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+// Moves the entity's translation, removed once it gets there
+#[derive(Component, Debug, Clone)]
+pub struct TranslationTween(pub Tween<Vec3>);
+
+#[derive(Component, Debug, Clone)]
+pub struct ScaleTween(pub Tween<Vec3>);
+
+#[derive(Component, Debug, Clone)]
+pub struct SpriteColorTween(pub Tween<Color>);
+
+fn tween_translations(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tweens: Query<(Entity, &mut TranslationTween, &mut Transform)>,
+) {
+    for (entity, mut tween, mut transform) in tweens.iter_mut() {
+        transform.translation = tween.0.tick(time.delta_seconds());
+        if tween.0.is_finished() {
+            commands.entity(entity).remove::<TranslationTween>();
+        }
+    }
+}
+
+fn tween_scales(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tweens: Query<(Entity, &mut ScaleTween, &mut Transform)>,
+) {
+    for (entity, mut tween, mut transform) in tweens.iter_mut() {
+        transform.scale = tween.0.tick(time.delta_seconds());
+        if tween.0.is_finished() {
+            commands.entity(entity).remove::<ScaleTween>();
+        }
+    }
+}
+
+fn tween_sprite_colors(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tweens: Query<(Entity, &mut SpriteColorTween, &mut Sprite)>,
+) {
+    for (entity, mut tween, mut sprite) in tweens.iter_mut() {
+        sprite.color = tween.0.tick(time.delta_seconds());
+        if tween.0.is_finished() {
+            commands.entity(entity).remove::<SpriteColorTween>();
+        }
+    }
+}
+
+// An int fact on its way to a new value. Whole steps are written as the curve passes them.
+#[derive(Debug, Clone)]
+pub struct FactTween {
+    pub key: String,
+    pub tween: Tween<f32>,
+}
+
+impl FactTween {
+    pub fn new(
+        key: impl Into<String>,
+        from: i32,
+        to: i32,
+        duration: Duration,
+        easing: Easing,
+    ) -> Self {
+        FactTween {
+            key: key.into(),
+            tween: Tween::new(from as f32, to as f32, duration.as_secs_f32()).with_easing(easing),
+        }
+    }
+
+    // Moves the tween on and returns the value the fact should have now
+    pub fn advance(&mut self, seconds: f32) -> i32 {
+        let value = self.tween.tick(seconds);
+        if self.tween.is_finished() {
+            self.tween.to as i32
+        } else {
+            value.round() as i32
+        }
+    }
+}
+
+// Fact tweens running, on the story clock so they hold still while the story is paused
+#[derive(Resource, Debug, Default)]
+pub struct FactTweens {
+    pub tweens: Vec<FactTween>,
+    last_tick: Option<f64>,
+}
+
+impl FactTweens {
+    // A new tween for a fact takes over from one already running
+    pub fn start(&mut self, tween: FactTween) {
+        self.tweens.retain(|running| running.key != tween.key);
+        self.tweens.push(tween);
+    }
+}
+
+fn start_fact_tweens(
+    mut effect_outputs: EventReader<EffectOutput>,
+    facts: Res<FactsOfTheWorld>,
+    mut fact_tweens: ResMut<FactTweens>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::TweenFact {
+            key,
+            to,
+            duration,
+            easing,
+        } = output
+        else {
+            continue;
+        };
+        // A fact that isn't there yet starts from 0, like an unset int
+        let from = facts.get_int(key).copied().unwrap_or_default();
+        fact_tweens.start(FactTween::new(key.clone(), from, *to, *duration, *easing));
+    }
+}
+
+fn tween_facts(
+    story_time: Res<StoryTime>,
+    mut fact_tweens: ResMut<FactTweens>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    let now = story_time.elapsed_seconds();
+    let last_tick = fact_tweens.last_tick.replace(now);
+    // Loading a save can move the clock backwards
+    let elapsed = last_tick
+        .map(|last| (now - last).max(0.))
+        .unwrap_or_default() as f32;
+    if fact_tweens.tweens.is_empty() {
+        return;
+    }
+    let mut mutations = Vec::new();
+    for tween in fact_tweens.tweens.iter_mut() {
+        let value = tween.advance(elapsed);
+        if facts.get_int(&tween.key) != Some(&value) {
+            mutations.push(FactMutation::StoreInt(tween.key.clone(), value));
+        }
+    }
+    fact_tweens
+        .tweens
+        .retain(|tween| !tween.tween.is_finished());
+    if mutations.is_empty() {
+        return;
+    }
+    if let Err(error) = facts.apply_batch(mutations) {
+        warn!("Could not tween facts: {}", error);
+    }
+}
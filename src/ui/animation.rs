@@ -0,0 +1,150 @@
+pub use crate::tween::Easing;
+use bevy::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<UiAnimationFinished>()
+        .add_systems(Update, animate_ui);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiTween {
+    // Alpha of the node's background and text
+    Fade { from: f32, to: f32 },
+    // Offset in pixels from where layout puts the node, positive y is down
+    Slide { from: Vec2, to: Vec2 },
+    Scale { from: f32, to: f32 },
+}
+
+// Tweens a UI node over `duration` seconds. Done animations are removed, or the node is
+// despawned along with its children when `despawn_when_done` is set.
+#[derive(Component, Debug, Clone)]
+pub struct UiAnimation {
+    pub tween: UiTween,
+    pub easing: Easing,
+    pub duration: f32,
+    pub despawn_when_done: bool,
+    // Reported with UiAnimationFinished so listeners can tell animations apart
+    pub name: Option<String>,
+    elapsed: f32,
+}
+
+impl UiAnimation {
+    pub fn new(tween: UiTween, duration: f32) -> Self {
+        UiAnimation {
+            tween,
+            easing: Easing::default(),
+            duration,
+            despawn_when_done: false,
+            name: None,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn fade_in(duration: f32) -> Self {
+        Self::new(UiTween::Fade { from: 0.0, to: 1.0 }, duration)
+    }
+
+    pub fn fade_out(duration: f32) -> Self {
+        Self::new(UiTween::Fade { from: 1.0, to: 0.0 }, duration).despawn_when_done()
+    }
+
+    pub fn slide_in_from(offset: Vec2, duration: f32) -> Self {
+        Self::new(
+            UiTween::Slide {
+                from: offset,
+                to: Vec2::ZERO,
+            },
+            duration,
+        )
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn despawn_when_done(mut self) -> Self {
+        self.despawn_when_done = true;
+        self
+    }
+
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            self.easing.apply(self.elapsed / self.duration)
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct UiAnimationFinished {
+    pub entity: Entity,
+    pub name: Option<String>,
+}
+
+pub fn animate_ui(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animations: Query<(
+        Entity,
+        &mut UiAnimation,
+        &mut Style,
+        &mut Transform,
+        Option<&mut BackgroundColor>,
+        Option<&mut Text>,
+    )>,
+    mut finished: EventWriter<UiAnimationFinished>,
+) {
+    for (entity, mut animation, mut style, mut transform, background, text) in animations.iter_mut()
+    {
+        animation.elapsed += time.delta_seconds();
+        let t = animation.progress();
+        match animation.tween {
+            UiTween::Fade { from, to } => {
+                let alpha = from + (to - from) * t;
+                if let Some(mut background) = background {
+                    background.0.set_a(alpha);
+                }
+                if let Some(mut text) = text {
+                    for section in text.sections.iter_mut() {
+                        section.style.color.set_a(alpha);
+                    }
+                }
+            }
+            UiTween::Slide { from, to } => {
+                // Opposite margins keep the node's size in the layout while moving it
+                let offset = from.lerp(to, t);
+                style.margin = UiRect {
+                    left: Val::Px(offset.x),
+                    right: Val::Px(-offset.x),
+                    top: Val::Px(offset.y),
+                    bottom: Val::Px(-offset.y),
+                };
+            }
+            UiTween::Scale { from, to } => {
+                transform.scale = Vec3::splat(from + (to - from) * t);
+            }
+        }
+        if animation.is_finished() {
+            finished.send(UiAnimationFinished {
+                entity,
+                name: animation.name.clone(),
+            });
+            if animation.despawn_when_done {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                commands.entity(entity).remove::<UiAnimation>();
+            }
+        }
+    }
+}
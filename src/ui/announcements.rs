@@ -0,0 +1,119 @@
+use crate::beats::choices::{ChoiceMade, PresentChoices};
+use crate::beats::data::{FactsOfTheWorld, StoryEngine};
+use crate::beats::errors::EngineError;
+use crate::dialogue::DialogueBox;
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<UiAnnouncement>().add_systems(
+        Update,
+        (
+            announce_dialogue,
+            announce_choices,
+            announce_errors,
+            print_announcements,
+        )
+            .chain(),
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnouncementKind {
+    Dialogue,
+    Choices,
+    ChoiceMade,
+    Error,
+    Toast,
+}
+
+// Plain text of something that just appeared on screen, for screen readers and other
+// tools that can't read the UI itself
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct UiAnnouncement {
+    pub kind: AnnouncementKind,
+    pub text: String,
+}
+
+impl UiAnnouncement {
+    pub fn new(kind: AnnouncementKind, text: impl Into<String>) -> Self {
+        UiAnnouncement {
+            kind,
+            text: text.into(),
+        }
+    }
+}
+
+pub fn announce_dialogue(
+    boxes: Query<&DialogueBox, Added<DialogueBox>>,
+    mut announcements: EventWriter<UiAnnouncement>,
+) {
+    for DialogueBox(line) in boxes.iter() {
+        announcements.send(UiAnnouncement::new(
+            AnnouncementKind::Dialogue,
+            format!("{}: {}", line.speaker, line.text),
+        ));
+    }
+}
+
+pub fn announce_choices(
+    mut present_choices: EventReader<PresentChoices>,
+    mut choices_made: EventReader<ChoiceMade>,
+    story_engine: Res<StoryEngine>,
+    facts: Res<FactsOfTheWorld>,
+    mut announcements: EventWriter<UiAnnouncement>,
+) {
+    for present in present_choices.read() {
+        let options: Vec<String> = present
+            .choices
+            .iter()
+            .enumerate()
+            .map(|(index, choice)| match choice.lock_reason(&*facts) {
+                Some(reason) => format!("{}. {} (locked: {})", index + 1, choice.label, reason),
+                None => format!("{}. {}", index + 1, choice.label),
+            })
+            .collect();
+        announcements.send(UiAnnouncement::new(
+            AnnouncementKind::Choices,
+            format!("Choose: {}", options.join(", ")),
+        ));
+    }
+    for made in choices_made.read() {
+        let choice = story_engine
+            .stories
+            .iter()
+            .find(|story| story.name == made.story)
+            .and_then(|story| story.beats.iter().find(|beat| beat.name == made.beat))
+            .and_then(|beat| beat.choices.get(made.index));
+        if let Some(choice) = choice {
+            announcements.send(UiAnnouncement::new(
+                AnnouncementKind::ChoiceMade,
+                format!("Chose: {}", choice.label),
+            ));
+        }
+    }
+}
+
+pub fn announce_errors(
+    mut errors: EventReader<EngineError>,
+    mut announcements: EventWriter<UiAnnouncement>,
+) {
+    for engine_error in errors.read() {
+        announcements.send(UiAnnouncement::new(
+            AnnouncementKind::Error,
+            engine_error.to_string(),
+        ));
+    }
+}
+
+// Logged under their own target, which ends up in the browser console on the web
+pub fn print_announcements(
+    settings: Res<Settings>,
+    mut announcements: EventReader<UiAnnouncement>,
+) {
+    for announcement in announcements.read() {
+        if settings.announce_to_console {
+            info!(target: "announcements", "[{:?}] {}", announcement.kind, announcement.text);
+        }
+    }
+}
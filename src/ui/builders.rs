@@ -1,8 +1,9 @@
-use bevy::prelude::{BackgroundColor, BorderColor, BuildChildren, ButtonBundle, ChildBuilder, Color, Display, GridPlacement, Handle, JustifyContent, NodeBundle, RepeatedGridTrack, Style, UiRect, Val};
+use bevy::prelude::{AlignItems, BackgroundColor, BorderColor, BuildChildren, ButtonBundle, ChildBuilder, Color, Display, FlexDirection, GridPlacement, Handle, JustifyContent, NodeBundle, PositionType, RepeatedGridTrack, Style, UiRect, Val};
 use bevy::text::Font;
 use bevy::utils::default;
 
 use crate::beats::systems::text_bundle;
+use crate::ui::layers::UiLayer;
 
 pub struct StyleBuilder {
     style: Style,
@@ -76,6 +77,63 @@ impl StyleBuilder {
         self
     }
 
+    // Anchors take the node out of the layout flow and pin it to a screen edge,
+    // `margin` pixels in from it
+    pub fn top_left(mut self, margin: f32) -> Self {
+        self.style.position_type = PositionType::Absolute;
+        self.style.top = Val::Px(margin);
+        self.style.left = Val::Px(margin);
+        self
+    }
+
+    pub fn top_right(mut self, margin: f32) -> Self {
+        self.style.position_type = PositionType::Absolute;
+        self.style.top = Val::Px(margin);
+        self.style.right = Val::Px(margin);
+        self
+    }
+
+    pub fn bottom_left(mut self, margin: f32) -> Self {
+        self.style.position_type = PositionType::Absolute;
+        self.style.bottom = Val::Px(margin);
+        self.style.left = Val::Px(margin);
+        self
+    }
+
+    pub fn bottom_right(mut self, margin: f32) -> Self {
+        self.style.position_type = PositionType::Absolute;
+        self.style.bottom = Val::Px(margin);
+        self.style.right = Val::Px(margin);
+        self
+    }
+
+    // Spans the screen width and centers the children along it
+    pub fn top_center(mut self, margin: f32) -> Self {
+        self.style.position_type = PositionType::Absolute;
+        self.style.top = Val::Px(margin);
+        self.style.width = Val::Percent(100.0);
+        self.style.align_items = AlignItems::Center;
+        self
+    }
+
+    pub fn bottom_center(mut self, margin: f32) -> Self {
+        self.style.position_type = PositionType::Absolute;
+        self.style.bottom = Val::Px(margin);
+        self.style.width = Val::Percent(100.0);
+        self.style.align_items = AlignItems::Center;
+        self
+    }
+
+    pub fn flex_column(mut self) -> Self {
+        self.style.flex_direction = FlexDirection::Column;
+        self
+    }
+
+    pub fn row_gap_px(mut self, gap: f32) -> Self {
+        self.style.row_gap = Val::Px(gap);
+        self
+    }
+
     pub fn build(self) -> Style {
         self.style.clone()
     }
@@ -127,6 +185,11 @@ impl NodeBundleBuilder {
         self
     }
 
+    pub fn on_layer(mut self, layer: UiLayer) -> Self {
+        self.node_bundle.z_index = layer.z_index();
+        self
+    }
+
     pub fn build(&self) -> NodeBundle {
         self.node_bundle.clone()
     }
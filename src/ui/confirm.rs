@@ -0,0 +1,227 @@
+use crate::beats::story_time::StoryTime;
+use crate::ui::animation::UiAnimation;
+use crate::ui::layers::UiLayer;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+const CONFIRMATION_FADE_SECONDS: f32 = 0.2;
+const CONFIRM_BUTTON: Color = Color::rgb(0.15, 0.15, 0.25);
+const HOVERED_CONFIRM_BUTTON: Color = Color::rgb(0.25, 0.25, 0.4);
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<PendingConfirmations>()
+        .add_event::<RequestConfirmation>()
+        .add_event::<ConfirmationAnswered>()
+        .add_systems(
+            Update,
+            (
+                queue_confirmations,
+                show_confirmation,
+                confirmation_button_system,
+                confirmation_keys,
+            )
+                .chain(),
+        );
+}
+
+// Asks the player a yes or no question. The answer comes back as `ConfirmationAnswered` with
+// the same id, so the asker can tell its questions apart from everyone else's.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct RequestConfirmation {
+    pub id: String,
+    pub prompt: String,
+}
+
+impl RequestConfirmation {
+    pub fn new(id: impl Into<String>, prompt: impl Into<String>) -> Self {
+        RequestConfirmation {
+            id: id.into(),
+            prompt: prompt.into(),
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationAnswered {
+    pub id: String,
+    pub accepted: bool,
+}
+
+// Questions wait their turn, only one modal is up at a time
+#[derive(Resource, Debug, Default)]
+pub struct PendingConfirmations(pub VecDeque<RequestConfirmation>);
+
+// The story clock stops while the question is up and starts again once it's answered,
+// unless something else had paused it first
+#[derive(Component, Debug)]
+pub struct ConfirmationModal {
+    pub id: String,
+    resume_clock: bool,
+}
+
+#[derive(Component)]
+enum ConfirmationButton {
+    Accept,
+    Decline,
+}
+
+fn queue_confirmations(
+    mut requests: EventReader<RequestConfirmation>,
+    mut pending: ResMut<PendingConfirmations>,
+) {
+    pending.0.extend(requests.read().cloned());
+}
+
+fn show_confirmation(
+    mut commands: Commands,
+    mut pending: ResMut<PendingConfirmations>,
+    mut story_time: ResMut<StoryTime>,
+    modals: Query<(), With<ConfirmationModal>>,
+) {
+    if !modals.is_empty() {
+        return;
+    }
+    let Some(request) = pending.0.pop_front() else {
+        return;
+    };
+    let resume_clock = !story_time.is_paused();
+    story_time.pause();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            ConfirmationModal {
+                id: request.id,
+                resume_clock,
+            },
+            UiLayer::Modal,
+            UiAnimation::fade_in(CONFIRMATION_FADE_SECONDS),
+        ))
+        .with_children(|modal| {
+            modal.spawn(confirmation_text(&request.prompt, 28.0));
+            modal
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(12.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (label, button) in [
+                        ("Yes", ConfirmationButton::Accept),
+                        ("No", ConfirmationButton::Decline),
+                    ] {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(140.0),
+                                    height: Val::Px(44.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: CONFIRM_BUTTON.into(),
+                                ..default()
+                            },
+                            button,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(confirmation_text(label, 24.0));
+                        });
+                    }
+                });
+        });
+}
+
+fn confirmation_text(text: &str, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+fn confirmation_button_system(
+    mut commands: Commands,
+    mut buttons: Query<
+        (&Interaction, &ConfirmationButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    modals: Query<(Entity, &ConfirmationModal)>,
+    mut story_time: ResMut<StoryTime>,
+    mut answers: EventWriter<ConfirmationAnswered>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => answer(
+                &mut commands,
+                &modals,
+                &mut story_time,
+                &mut answers,
+                matches!(button, ConfirmationButton::Accept),
+            ),
+            Interaction::Hovered => *color = HOVERED_CONFIRM_BUTTON.into(),
+            Interaction::None => *color = CONFIRM_BUTTON.into(),
+        }
+    }
+}
+
+// Enter says yes, Escape says no
+fn confirmation_keys(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    modals: Query<(Entity, &ConfirmationModal)>,
+    mut story_time: ResMut<StoryTime>,
+    mut answers: EventWriter<ConfirmationAnswered>,
+) {
+    let accepted = if keyboard_input.just_pressed(KeyCode::Enter) {
+        true
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        false
+    } else {
+        return;
+    };
+    answer(
+        &mut commands,
+        &modals,
+        &mut story_time,
+        &mut answers,
+        accepted,
+    );
+}
+
+fn answer(
+    commands: &mut Commands,
+    modals: &Query<(Entity, &ConfirmationModal)>,
+    story_time: &mut StoryTime,
+    answers: &mut EventWriter<ConfirmationAnswered>,
+    accepted: bool,
+) {
+    for (entity, modal) in modals.iter() {
+        if modal.resume_clock {
+            story_time.resume();
+        }
+        answers.send(ConfirmationAnswered {
+            id: modal.id.clone(),
+            accepted,
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,206 @@
+use crate::beats::StoryProgression;
+use crate::ui::layers::UiLayer;
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, EntityCountDiagnosticsPlugin,
+    FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+};
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+// How long the story systems took this frame, in milliseconds
+pub const STORY_EVALUATION_TIME: DiagnosticPath =
+    DiagnosticPath::const_new("story/evaluation_time");
+
+// Bars in the frame time graph, one per frame of history
+const GRAPH_BARS: usize = 60;
+const GRAPH_HEIGHT: f32 = 40.0;
+// Frame time that fills a bar, two frames at 60 Hz
+const GRAPH_MAX_MS: f64 = 33.3;
+
+pub fn plugin(app: &mut App) {
+    if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+        app.add_plugins(FrameTimeDiagnosticsPlugin);
+    }
+    if !app.is_plugin_added::<EntityCountDiagnosticsPlugin>() {
+        app.add_plugins(EntityCountDiagnosticsPlugin);
+    }
+    app.register_diagnostic(
+        Diagnostic::new(STORY_EVALUATION_TIME)
+            .with_suffix("ms")
+            .with_max_history_length(GRAPH_BARS),
+    )
+    .init_resource::<StoryTiming>()
+    .add_event::<ToggleDiagnosticsOverlay>()
+    .add_systems(
+        Update,
+        (
+            start_story_timing.before(StoryProgression),
+            finish_story_timing.after(StoryProgression),
+            (
+                diagnostics_overlay_keys,
+                toggle_diagnostics_overlay,
+                update_diagnostics_overlay,
+            )
+                .chain(),
+        ),
+    );
+}
+
+// Shows or hides the FPS, frame time graph, entity count and story timing in the corner
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ToggleDiagnosticsOverlay;
+
+#[derive(Component)]
+pub struct DiagnosticsOverlay;
+
+#[derive(Component)]
+struct DiagnosticsText;
+
+#[derive(Component)]
+struct FrameTimeBar(usize);
+
+#[derive(Resource, Default)]
+struct StoryTiming {
+    started: Option<Instant>,
+}
+
+fn start_story_timing(mut timing: ResMut<StoryTiming>) {
+    timing.started = Some(Instant::now());
+}
+
+fn finish_story_timing(mut timing: ResMut<StoryTiming>, mut diagnostics: Diagnostics) {
+    if let Some(started) = timing.started.take() {
+        diagnostics.add_measurement(&STORY_EVALUATION_TIME, || {
+            started.elapsed().as_secs_f64() * 1000.0
+        });
+    }
+}
+
+pub fn diagnostics_overlay_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut toggles: EventWriter<ToggleDiagnosticsOverlay>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        toggles.send(ToggleDiagnosticsOverlay);
+    }
+}
+
+fn toggle_diagnostics_overlay(
+    mut commands: Commands,
+    mut toggles: EventReader<ToggleDiagnosticsOverlay>,
+    overlays: Query<Entity, With<DiagnosticsOverlay>>,
+) {
+    if toggles.read().count() % 2 == 0 {
+        return;
+    }
+    if !overlays.is_empty() {
+        for entity in overlays.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(10.),
+                    top: Val::Px(10.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.)),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: UiLayer::Debug.z_index(),
+                ..default()
+            },
+            DiagnosticsOverlay,
+            UiLayer::Debug,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                DiagnosticsText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Px(GRAPH_HEIGHT),
+                        align_items: AlignItems::FlexEnd,
+                        column_gap: Val::Px(1.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for index in 0..GRAPH_BARS {
+                        graph.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(2.),
+                                    height: Val::Px(0.),
+                                    ..default()
+                                },
+                                background_color: Color::GREEN.into(),
+                                ..default()
+                            },
+                            FrameTimeBar(index),
+                        ));
+                    }
+                });
+        });
+}
+
+fn update_diagnostics_overlay(
+    diagnostics: Res<DiagnosticsStore>,
+    mut texts: Query<&mut Text, With<DiagnosticsText>>,
+    mut bars: Query<(&FrameTimeBar, &mut Style, &mut BackgroundColor)>,
+) {
+    let smoothed = |path: &DiagnosticPath| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or_default()
+    };
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!(
+            "FPS: {:.0}\nFrame time: {:.2} ms\nEntities: {:.0}\nStory evaluation: {:.3} ms",
+            smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+            smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME),
+            smoothed(&EntityCountDiagnosticsPlugin::ENTITY_COUNT),
+            smoothed(&STORY_EVALUATION_TIME),
+        );
+    }
+    let Some(frame_times) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME) else {
+        return;
+    };
+    // Newest frame on the right
+    let frame_times: Vec<f64> = frame_times.values().copied().collect();
+    let skipped = GRAPH_BARS.saturating_sub(frame_times.len());
+    let recent = &frame_times[frame_times.len().saturating_sub(GRAPH_BARS)..];
+    for (FrameTimeBar(index), mut style, mut color) in bars.iter_mut() {
+        let frame_time = index
+            .checked_sub(skipped)
+            .and_then(|index| recent.get(index))
+            .copied()
+            .unwrap_or_default();
+        let fill = (frame_time / GRAPH_MAX_MS).min(1.0) as f32;
+        style.height = Val::Px(fill * GRAPH_HEIGHT);
+        *color = if frame_time < 17.0 {
+            Color::GREEN
+        } else if frame_time < 34.0 {
+            Color::YELLOW
+        } else {
+            Color::RED
+        }
+        .into();
+    }
+}
@@ -0,0 +1,31 @@
+use bevy::prelude::{Component, ZIndex};
+
+// Where a root UI node sits in the stack. Later layers draw on top of earlier ones,
+// whatever order the nodes were spawned in.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UiLayer {
+    Hud,
+    Dialogue,
+    // Captions, above the dialogue box so they're never hidden by it
+    Subtitles,
+    Modal,
+    // Short notices, over modals so an unlock while a screen is open still shows
+    Toast,
+    // Covers the screen between scenes, under the debug tools
+    Transition,
+    Debug,
+}
+
+impl UiLayer {
+    pub fn z_index(self) -> ZIndex {
+        ZIndex::Global(match self {
+            UiLayer::Hud => 100,
+            UiLayer::Dialogue => 200,
+            UiLayer::Subtitles => 250,
+            UiLayer::Modal => 300,
+            UiLayer::Toast => 320,
+            UiLayer::Transition => 350,
+            UiLayer::Debug => 400,
+        })
+    }
+}
@@ -1,3 +1,15 @@
-pub mod builders;
+pub mod animation;
+pub mod announcements;
+pub(crate) mod builders;
+pub mod confirm;
 pub mod banner_widget;
-pub mod fps_widget;
\ No newline at end of file
+pub mod diagnostics_overlay;
+pub mod fps_widget;
+pub mod layers;
+pub mod photo;
+pub mod quest_log;
+pub mod relationships_panel;
+pub mod theme;
+pub mod timeline;
+pub mod toasts;
+pub mod tutorial;
\ No newline at end of file
@@ -0,0 +1,85 @@
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<PhotoMode>()
+        .add_event::<TakePhoto>()
+        .add_systems(Update, (photo_keys, take_photo).chain());
+}
+
+// Hides the UI for a frame and saves a screenshot of the scene underneath.
+// Native builds write the file next to the game, the web build downloads it.
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct TakePhoto;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum PhotoStage {
+    #[default]
+    Idle,
+    // UI was hidden this frame, the next one is drawn without it
+    Hidden,
+    Captured,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct PhotoMode {
+    stage: PhotoStage,
+    hidden: Vec<(Entity, Visibility)>,
+}
+
+impl PhotoMode {
+    pub fn is_active(&self) -> bool {
+        self.stage != PhotoStage::Idle
+    }
+}
+
+// Run condition for UI drawn outside of bevy_ui, like egui windows
+pub fn photo_mode_inactive(photo_mode: Res<PhotoMode>) -> bool {
+    !photo_mode.is_active()
+}
+
+pub fn photo_keys(keyboard_input: Res<ButtonInput<KeyCode>>, mut photos: EventWriter<TakePhoto>) {
+    if keyboard_input.just_pressed(KeyCode::F12) {
+        photos.send(TakePhoto);
+    }
+}
+
+pub fn take_photo(
+    mut photos: EventReader<TakePhoto>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    frame: Res<FrameCount>,
+    windows: Query<Entity, With<PrimaryWindow>>,
+    mut roots: Query<(Entity, &mut Visibility), (With<Node>, Without<Parent>)>,
+) {
+    let requested = photos.read().count() > 0;
+    match photo_mode.stage {
+        PhotoStage::Idle if requested => {
+            for (entity, mut visibility) in roots.iter_mut() {
+                photo_mode.hidden.push((entity, *visibility));
+                *visibility = Visibility::Hidden;
+            }
+            photo_mode.stage = PhotoStage::Hidden;
+        }
+        PhotoStage::Idle => {}
+        PhotoStage::Hidden => {
+            if let Ok(window) = windows.get_single() {
+                let path = format!("photo-{}.png", frame.0);
+                if screenshots.save_screenshot_to_disk(window, path).is_err() {
+                    warn!("A screenshot was already requested this frame");
+                }
+            }
+            photo_mode.stage = PhotoStage::Captured;
+        }
+        PhotoStage::Captured => {
+            for (entity, visibility) in std::mem::take(&mut photo_mode.hidden) {
+                if let Ok((_, mut current)) = roots.get_mut(entity) {
+                    *current = visibility;
+                }
+            }
+            photo_mode.stage = PhotoStage::Idle;
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::beats::data::StoryEngine;
+use crate::settings::Settings;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use crate::ui::theme::UiTheme;
+use bevy::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ToggleQuestLog>().add_systems(
+        Update,
+        (quest_log_keys, toggle_quest_log, refresh_quest_log).chain(),
+    );
+}
+
+// Opens the list of started stories and how far along each one is, or closes it
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ToggleQuestLog;
+
+#[derive(Component)]
+pub struct QuestLogPanel;
+
+#[derive(Component)]
+struct QuestList;
+
+const PROGRESS_BAR_WIDTH: f32 = 160.0;
+
+pub fn quest_log_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut toggle: EventWriter<ToggleQuestLog>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        toggle.send(ToggleQuestLog);
+    }
+}
+
+fn toggle_quest_log(
+    mut commands: Commands,
+    mut toggles: EventReader<ToggleQuestLog>,
+    story_engine: Res<StoryEngine>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    panels: Query<Entity, With<QuestLogPanel>>,
+) {
+    if toggles.read().count().is_multiple_of(2) {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in panels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.top_left(40.))
+                .on_layer(UiLayer::Modal)
+                .build(),
+            UiLayer::Modal,
+            QuestLogPanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(6.),
+                            padding: UiRect::all(Val::Px(12.)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+                        ..default()
+                    },
+                    QuestList,
+                ))
+                .with_children(|list| {
+                    spawn_quest_rows(list, &story_engine, &theme, &settings);
+                });
+        });
+}
+
+// Rebuilds the rows while the log is open and stories move on
+fn refresh_quest_log(
+    mut commands: Commands,
+    story_engine: Res<StoryEngine>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    lists: Query<Entity, With<QuestList>>,
+) {
+    if !story_engine.is_changed() {
+        return;
+    }
+    for entity in lists.iter() {
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|list| {
+                spawn_quest_rows(list, &story_engine, &theme, &settings);
+            });
+    }
+}
+
+fn spawn_quest_rows(
+    list: &mut ChildBuilder,
+    story_engine: &StoryEngine,
+    theme: &UiTheme,
+    settings: &Settings,
+) {
+    let palette = theme.palette(settings.palette);
+    let text_style = TextStyle {
+        font_size: 18.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    let mut started = story_engine
+        .stories
+        .iter()
+        .filter(|story| story.is_started)
+        .peekable();
+    if started.peek().is_none() {
+        list.spawn(TextBundle::from_section("No quests yet", text_style));
+        return;
+    }
+    for story in started {
+        let progress = story.progress();
+        let status = story
+            .active_beat()
+            .map(|beat| beat.name.as_str())
+            .unwrap_or("Done");
+        list.spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(12.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn(TextBundle::from_section(
+                format!(
+                    "{}: {} ({:.0}%)",
+                    story.name,
+                    status,
+                    progress * 100.0
+                ),
+                text_style.clone(),
+            ));
+            row.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(PROGRESS_BAR_WIDTH),
+                    height: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(PROGRESS_BAR_WIDTH * progress.clamp(0., 1.)),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: palette.positive.into(),
+                    ..default()
+                });
+            });
+        });
+    }
+}
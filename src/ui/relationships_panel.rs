@@ -0,0 +1,170 @@
+use crate::beats::relationships::{Relationships, MAX_AFFINITY, MIN_AFFINITY};
+use crate::settings::Settings;
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use crate::ui::theme::UiTheme;
+use bevy::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ToggleRelationshipsPanel>().add_systems(
+        Update,
+        (
+            relationships_panel_keys,
+            toggle_relationships_panel,
+            refresh_relationships_panel,
+        )
+            .chain(),
+    );
+}
+
+// Opens the list of characters and how they feel about the player, or closes it
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ToggleRelationshipsPanel;
+
+#[derive(Component)]
+pub struct RelationshipsPanel;
+
+#[derive(Component)]
+struct RelationshipsList;
+
+const AFFINITY_BAR_WIDTH: f32 = 160.0;
+
+pub fn relationships_panel_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut toggle: EventWriter<ToggleRelationshipsPanel>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        toggle.send(ToggleRelationshipsPanel);
+    }
+}
+
+fn toggle_relationships_panel(
+    mut commands: Commands,
+    mut toggles: EventReader<ToggleRelationshipsPanel>,
+    relationships: Res<Relationships>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    panels: Query<Entity, With<RelationshipsPanel>>,
+) {
+    if toggles.read().count() % 2 == 0 {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in panels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    commands
+        .spawn((
+            NodeBundleBuilder::new()
+                .with_style(|style| style.top_right(40.))
+                .on_layer(UiLayer::Modal)
+                .build(),
+            UiLayer::Modal,
+            RelationshipsPanel,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(6.),
+                            padding: UiRect::all(Val::Px(12.)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.05, 0.05, 0.1, 0.95).into(),
+                        ..default()
+                    },
+                    RelationshipsList,
+                ))
+                .with_children(|list| {
+                    spawn_relationship_rows(list, &relationships, &theme, &settings);
+                });
+        });
+}
+
+// Rebuilds the rows while the panel is open and affinity changes
+fn refresh_relationships_panel(
+    mut commands: Commands,
+    relationships: Res<Relationships>,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    lists: Query<Entity, With<RelationshipsList>>,
+) {
+    if !relationships.is_changed() {
+        return;
+    }
+    for entity in lists.iter() {
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|list| {
+                spawn_relationship_rows(list, &relationships, &theme, &settings);
+            });
+    }
+}
+
+fn spawn_relationship_rows(
+    list: &mut ChildBuilder,
+    relationships: &Relationships,
+    theme: &UiTheme,
+    settings: &Settings,
+) {
+    let palette = theme.palette(settings.palette);
+    let text_style = TextStyle {
+        font_size: 18.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    if relationships.affinity.is_empty() {
+        list.spawn(TextBundle::from_section("Nobody knows you yet", text_style));
+        return;
+    }
+    let mut characters: Vec<(&String, &i32)> = relationships.affinity.iter().collect();
+    characters.sort();
+    for (character, affinity) in characters {
+        let fill = (*affinity - MIN_AFFINITY) as f32 / (MAX_AFFINITY - MIN_AFFINITY) as f32;
+        let color = if *affinity < 0 {
+            palette.negative
+        } else {
+            palette.positive
+        };
+        list.spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(12.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn(TextBundle::from_section(
+                format!("{} ({})", character, affinity),
+                text_style.clone(),
+            ));
+            row.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(AFFINITY_BAR_WIDTH),
+                    height: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(AFFINITY_BAR_WIDTH * fill.clamp(0., 1.)),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                });
+            });
+        });
+    }
+}
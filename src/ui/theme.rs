@@ -0,0 +1,134 @@
+use crate::settings::Settings;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<UiTheme>()
+        .add_systems(Update, (apply_ui_scale, apply_theme_font));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum PaletteMode {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+}
+
+// Colors that carry meaning. Anything the player has to tell apart goes through these,
+// so the colorblind variants can keep them distinct.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Palette {
+    pub positive: Color,
+    pub negative: Color,
+    pub warning: Color,
+    // Behind errors and other bad news, text on it is white
+    pub negative_background: Color,
+}
+
+impl Palette {
+    pub const STANDARD: Palette = Palette {
+        positive: Color::rgb(0.35, 0.75, 0.35),
+        negative: Color::rgb(0.9, 0.3, 0.3),
+        warning: Color::rgb(0.95, 0.8, 0.3),
+        negative_background: Color::rgba(0.3, 0.0, 0.0, 0.9),
+    };
+
+    // Blue against orange, which red-green colorblind players can still tell apart
+    pub const DEUTERANOPIA: Palette = Palette {
+        positive: Color::rgb(0.0, 0.45, 0.7),
+        negative: Color::rgb(0.9, 0.6, 0.0),
+        warning: Color::rgb(0.95, 0.9, 0.25),
+        negative_background: Color::rgba(0.35, 0.2, 0.0, 0.9),
+    };
+
+    // Reds look dark without working red cones, so negative leans to a bright orange
+    pub const PROTANOPIA: Palette = Palette {
+        positive: Color::rgb(0.35, 0.7, 0.9),
+        negative: Color::rgb(0.95, 0.65, 0.1),
+        warning: Color::rgb(0.95, 0.95, 0.45),
+        negative_background: Color::rgba(0.35, 0.25, 0.0, 0.9),
+    };
+}
+
+// Shared look of the game's UI
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct UiTheme {
+    // Swapped in for every text while the dyslexia friendly font setting is on
+    pub dyslexia_font: String,
+    pub standard: Palette,
+    pub deuteranopia: Palette,
+    pub protanopia: Palette,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        UiTheme {
+            dyslexia_font: "fonts/OpenDyslexic-Regular.otf".to_string(),
+            standard: Palette::STANDARD,
+            deuteranopia: Palette::DEUTERANOPIA,
+            protanopia: Palette::PROTANOPIA,
+        }
+    }
+}
+
+impl UiTheme {
+    pub fn palette(&self, mode: PaletteMode) -> &Palette {
+        match mode {
+            PaletteMode::Standard => &self.standard,
+            PaletteMode::Deuteranopia => &self.deuteranopia,
+            PaletteMode::Protanopia => &self.protanopia,
+        }
+    }
+}
+
+// The font a text had before the theme swapped it out
+#[derive(Component, Debug, Clone)]
+pub struct OriginalFont(pub Vec<Handle<Font>>);
+
+pub fn apply_ui_scale(settings: Res<Settings>, mut ui_scale: ResMut<UiScale>) {
+    let scale = settings.ui_scale.max(0.25);
+    if settings.is_changed() && ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+// Runs for new texts, and for all of them when the setting flips
+pub fn apply_theme_font(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    theme: Res<UiTheme>,
+    asset_server: Res<AssetServer>,
+    mut texts: Query<(Entity, &mut Text, Option<&OriginalFont>)>,
+    added: Query<(), Added<Text>>,
+) {
+    let everything = settings.is_changed();
+    if !everything && added.is_empty() {
+        return;
+    }
+    let dyslexia_font = settings
+        .dyslexia_font
+        .then(|| asset_server.load(theme.dyslexia_font.clone()));
+    for (entity, mut text, original) in texts.iter_mut() {
+        if !everything && !added.contains(entity) {
+            continue;
+        }
+        match (&dyslexia_font, original) {
+            (Some(font), None) => {
+                commands.entity(entity).insert(OriginalFont(
+                    text.sections.iter().map(|s| s.style.font.clone()).collect(),
+                ));
+                for section in text.sections.iter_mut() {
+                    section.style.font = font.clone();
+                }
+            }
+            (None, Some(original)) => {
+                for (section, font) in text.sections.iter_mut().zip(original.0.iter()) {
+                    section.style.font = font.clone();
+                }
+                commands.entity(entity).remove::<OriginalFont>();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::beats::journal::{ExportSessionJournal, JournalKind, SessionJournal};
+use crate::ui::layers::UiLayer;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+// One lane each for facts, beats and choices
+const LANES: usize = 3;
+const LANE_HEIGHT: f32 = 24.0;
+const MAX_ZOOM: f32 = 64.0;
+// Share of the visible span moved per frame while an arrow key is held
+const PAN_SPEED: f32 = 0.02;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<TimelineView>()
+        .add_event::<ToggleTimeline>()
+        .add_systems(
+            Update,
+            (
+                timeline_keys,
+                toggle_timeline,
+                timeline_button_system,
+                hover_timeline_markers,
+                draw_timeline,
+            )
+                .chain(),
+        );
+}
+
+// Shows the session journal as a timeline of fact changes, finished beats and choices
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct ToggleTimeline;
+
+#[derive(Component)]
+pub struct TimelinePanel;
+
+// Zoom, scroll position and which kinds of entries are shown
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct TimelineView {
+    pub zoom: f32,
+    // Where the visible window starts, 0.0 at the first entry and 1.0 at the last
+    pub scroll: f32,
+    pub show_facts: bool,
+    pub show_beats: bool,
+    pub show_choices: bool,
+}
+
+impl Default for TimelineView {
+    fn default() -> Self {
+        TimelineView {
+            zoom: 1.0,
+            scroll: 0.0,
+            show_facts: true,
+            show_beats: true,
+            show_choices: true,
+        }
+    }
+}
+
+#[derive(Component)]
+struct TimelineTrack;
+
+#[derive(Component)]
+struct TimelineDetails;
+
+#[derive(Component)]
+struct TimelineMarker(String);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum TimelineButton {
+    Facts,
+    Beats,
+    Choices,
+    Export,
+}
+
+const TIMELINE_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_TIMELINE_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const FACT_MARKER: Color = Color::rgb(0.4, 0.6, 1.0);
+const BEAT_MARKER: Color = Color::rgb(0.4, 1.0, 0.4);
+const CHOICE_MARKER: Color = Color::rgb(1.0, 0.8, 0.3);
+
+pub fn timeline_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut toggles: EventWriter<ToggleTimeline>,
+    mut view: ResMut<TimelineView>,
+    panels: Query<(), With<TimelinePanel>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        toggles.send(ToggleTimeline);
+    }
+    if panels.is_empty() {
+        mouse_wheel.clear();
+        return;
+    }
+    let mut zoom = view.zoom;
+    for event in mouse_wheel.read() {
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 20.0,
+        };
+        zoom *= 1.2_f32.powf(lines);
+    }
+    if keyboard_input.just_pressed(KeyCode::Equal) {
+        zoom *= 2.0;
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus) {
+        zoom /= 2.0;
+    }
+    let zoom = zoom.clamp(1.0, MAX_ZOOM);
+    let step = PAN_SPEED / zoom;
+    let mut scroll = view.scroll;
+    if keyboard_input.pressed(KeyCode::ArrowLeft) {
+        scroll -= step;
+    }
+    if keyboard_input.pressed(KeyCode::ArrowRight) {
+        scroll += step;
+    }
+    // Keeps the last entry reachable without scrolling past it
+    let scroll = scroll.clamp(0.0, 1.0 - 1.0 / zoom);
+    // Only touch the view when it moved, so the markers aren't rebuilt every frame
+    if zoom != view.zoom || scroll != view.scroll {
+        view.zoom = zoom;
+        view.scroll = scroll;
+    }
+}
+
+fn toggle_timeline(
+    mut commands: Commands,
+    mut toggles: EventReader<ToggleTimeline>,
+    panels: Query<Entity, With<TimelinePanel>>,
+    mut view: ResMut<TimelineView>,
+) {
+    if toggles.read().count() % 2 == 0 {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in panels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    // Touched so the freshly spawned track gets drawn
+    view.set_changed();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    bottom: Val::Px(0.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.)),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.85).into(),
+                z_index: UiLayer::Debug.z_index(),
+                ..default()
+            },
+            TimelinePanel,
+            UiLayer::Debug,
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(6.),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|bar| {
+                    for (label, button) in [
+                        ("Facts", TimelineButton::Facts),
+                        ("Beats", TimelineButton::Beats),
+                        ("Choices", TimelineButton::Choices),
+                        ("Export JSON", TimelineButton::Export),
+                    ] {
+                        bar.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::axes(Val::Px(8.), Val::Px(2.)),
+                                    ..default()
+                                },
+                                background_color: TIMELINE_BUTTON.into(),
+                                ..default()
+                            },
+                            button,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(timeline_text(label));
+                        });
+                    }
+                    bar.spawn((timeline_text(""), TimelineDetails));
+                });
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Px(LANES as f32 * LANE_HEIGHT),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    background_color: Color::rgba(1., 1., 1., 0.05).into(),
+                    ..default()
+                },
+                TimelineTrack,
+            ));
+        });
+}
+
+fn timeline_text(text: &str) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size: 14.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+fn timeline_button_system(
+    mut buttons: Query<(&Interaction, &TimelineButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut view: ResMut<TimelineView>,
+    mut exports: EventWriter<ExportSessionJournal>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => match button {
+                TimelineButton::Facts => view.show_facts = !view.show_facts,
+                TimelineButton::Beats => view.show_beats = !view.show_beats,
+                TimelineButton::Choices => view.show_choices = !view.show_choices,
+                TimelineButton::Export => {
+                    exports.send(ExportSessionJournal);
+                }
+            },
+            Interaction::Hovered => *color = HOVERED_TIMELINE_BUTTON.into(),
+            Interaction::None => *color = TIMELINE_BUTTON.into(),
+        }
+    }
+}
+
+fn hover_timeline_markers(
+    markers: Query<(&Interaction, &TimelineMarker), Changed<Interaction>>,
+    mut details: Query<&mut Text, With<TimelineDetails>>,
+) {
+    for (interaction, marker) in markers.iter() {
+        if *interaction == Interaction::Hovered {
+            for mut text in details.iter_mut() {
+                text.sections[0].value = marker.0.clone();
+            }
+        }
+    }
+}
+
+// Markers are respawned whenever the journal or the view changes while the timeline is open
+fn draw_timeline(
+    mut commands: Commands,
+    journal: Res<SessionJournal>,
+    view: Res<TimelineView>,
+    tracks: Query<Entity, With<TimelineTrack>>,
+) {
+    if !journal.is_changed() && !view.is_changed() {
+        return;
+    }
+    let Ok(track) = tracks.get_single() else {
+        return;
+    };
+    commands.entity(track).despawn_descendants();
+    let (Some(first), Some(last)) = (journal.entries.front(), journal.entries.back()) else {
+        return;
+    };
+    let span = (last.at - first.at).max(1.0);
+    let visible = span / view.zoom as f64;
+    let start = first.at + view.scroll as f64 * span;
+    commands.entity(track).with_children(|track| {
+        for entry in journal.entries.iter() {
+            let (lane, color, shown) = match entry.kind {
+                JournalKind::FactChanged { .. } => (0, FACT_MARKER, view.show_facts),
+                JournalKind::BeatFinished { .. } => (1, BEAT_MARKER, view.show_beats),
+                JournalKind::ChoiceMade { .. } => (2, CHOICE_MARKER, view.show_choices),
+            };
+            let position = (entry.at - start) / visible;
+            if !shown || !(0.0..=1.0).contains(&position) {
+                continue;
+            }
+            track.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(position as f32 * 100.),
+                        top: Val::Px(lane as f32 * LANE_HEIGHT + 4.),
+                        width: Val::Px(4.),
+                        height: Val::Px(LANE_HEIGHT - 8.),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                Interaction::default(),
+                TimelineMarker(format!("{:.1}s {}", entry.at, entry.kind.describe())),
+            ));
+        }
+    });
+}
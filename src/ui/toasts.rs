@@ -0,0 +1,90 @@
+use crate::ui::announcements::{AnnouncementKind, UiAnnouncement};
+use crate::ui::builders::NodeBundleBuilder;
+use crate::ui::layers::UiLayer;
+use bevy::prelude::*;
+
+// Seconds a toast stays up
+pub const TOAST_SECONDS: f32 = 4.;
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ShowToast>()
+        .add_systems(Startup, spawn_toast_stack)
+        .add_systems(Update, (show_toasts, expire_toasts).chain());
+}
+
+// A short notice in the corner of the screen, like a codex entry being unlocked
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ShowToast(pub String);
+
+#[derive(Component)]
+pub struct ToastStack;
+
+#[derive(Component, Debug)]
+pub struct Toast {
+    remaining: Timer,
+}
+
+// Kept for the whole session, toasts can come up in any state
+fn spawn_toast_stack(mut commands: Commands) {
+    commands.spawn((
+        NodeBundleBuilder::new()
+            .with_style(|style| style.top_right(16.).flex_column().row_gap_px(6.))
+            .on_layer(UiLayer::Toast)
+            .build(),
+        UiLayer::Toast,
+        ToastStack,
+    ));
+}
+
+pub fn show_toasts(
+    mut commands: Commands,
+    mut requests: EventReader<ShowToast>,
+    stacks: Query<Entity, With<ToastStack>>,
+    mut announcements: EventWriter<UiAnnouncement>,
+) {
+    let Some(stack) = stacks.iter().next() else {
+        requests.clear();
+        return;
+    };
+    for ShowToast(text) in requests.read() {
+        let toast = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(8.)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.1, 0.1, 0.15, 0.9).into(),
+                    ..default()
+                },
+                Toast {
+                    remaining: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    text.clone(),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ));
+            })
+            .id();
+        commands.entity(stack).add_child(toast);
+        announcements.send(UiAnnouncement::new(AnnouncementKind::Toast, text.clone()));
+    }
+}
+
+pub fn expire_toasts(
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut Toast)>,
+    time: Res<Time>,
+) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.remaining.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
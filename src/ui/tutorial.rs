@@ -0,0 +1,223 @@
+use crate::beats::data::EffectOutput;
+use crate::beats::save_location::SaveLocation;
+use crate::beats::sorted;
+use crate::ui::animation::{Easing, UiAnimation};
+use crate::ui::layers::UiLayer;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+pub const DISMISSED_TUTORIALS_FILE: &str = "tutorials.ron";
+// Space between a prompt and the node it points at
+const ANCHOR_GAP: f32 = 8.0;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<DismissedTutorials>()
+        .add_systems(Startup, load_dismissed_tutorials)
+        .add_systems(
+            Update,
+            (
+                show_tutorial_prompts,
+                tutorial_button_system,
+                follow_tutorial_anchors,
+            )
+                .chain(),
+        );
+}
+
+// Tutorials the player asked not to see again, kept apart from saves so starting over
+// doesn't bring them back
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DismissedTutorials {
+    #[serde(serialize_with = "sorted::set")]
+    pub ids: HashSet<String>,
+}
+
+// A hint shown by `Effect::ShowTutorial`, next to the node named by `anchor_to` if there is one
+#[derive(Component, Debug, Clone)]
+pub struct TutorialPrompt {
+    pub id: String,
+    pub anchor_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TutorialAction {
+    Close,
+    NeverAgain,
+}
+
+#[derive(Component)]
+struct TutorialButton {
+    prompt: Entity,
+    action: TutorialAction,
+}
+
+const TUTORIAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.25);
+const HOVERED_TUTORIAL_BUTTON: Color = Color::rgb(0.25, 0.25, 0.4);
+
+pub fn load_dismissed_tutorials(
+    mut dismissed: ResMut<DismissedTutorials>,
+    location: Res<SaveLocation>,
+) {
+    let Ok(source) = location.read(DISMISSED_TUTORIALS_FILE) else {
+        return;
+    };
+    match ron::from_str(&source) {
+        Ok(loaded) => *dismissed = loaded,
+        Err(error) => warn!("Could not read {}: {}", DISMISSED_TUTORIALS_FILE, error),
+    }
+}
+
+pub fn show_tutorial_prompts(
+    mut commands: Commands,
+    mut effect_outputs: EventReader<EffectOutput>,
+    dismissed: Res<DismissedTutorials>,
+    prompts: Query<&TutorialPrompt>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::ShowTutorial {
+            id,
+            text,
+            anchor_to,
+        } = output
+        else {
+            continue;
+        };
+        if dismissed.ids.contains(id) || prompts.iter().any(|prompt| &prompt.id == id) {
+            continue;
+        }
+        let mut prompt = commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    // Centered at the bottom until the anchor has been found
+                    bottom: Val::Px(140.),
+                    align_self: AlignSelf::Center,
+                    max_width: Val::Px(360.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(10.)),
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::rgba(0.05, 0.05, 0.1, 0.9).into(),
+                z_index: UiLayer::Modal.z_index(),
+                ..default()
+            },
+            TutorialPrompt {
+                id: id.clone(),
+                anchor_to: anchor_to.clone(),
+            },
+            UiLayer::Modal,
+            UiAnimation::slide_in_from(Vec2::new(0., 20.), 0.25).with_easing(Easing::EaseOut),
+        ));
+        let prompt_entity = prompt.id();
+        prompt.with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text.clone(),
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(8.),
+                        justify_content: JustifyContent::FlexEnd,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|buttons| {
+                    for (label, action) in [
+                        ("Don't show again", TutorialAction::NeverAgain),
+                        ("Got it", TutorialAction::Close),
+                    ] {
+                        buttons
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::axes(Val::Px(10.), Val::Px(4.)),
+                                        ..default()
+                                    },
+                                    background_color: TUTORIAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                TutorialButton {
+                                    prompt: prompt_entity,
+                                    action,
+                                },
+                            ))
+                            .with_children(|button| {
+                                button.spawn(TextBundle::from_section(
+                                    label,
+                                    TextStyle {
+                                        font_size: 16.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    }
+                });
+        });
+    }
+}
+
+fn tutorial_button_system(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &TutorialButton, &mut BackgroundColor), Changed<Interaction>>,
+    prompts: Query<&TutorialPrompt>,
+    mut dismissed: ResMut<DismissedTutorials>,
+    location: Res<SaveLocation>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                if button.action == TutorialAction::NeverAgain {
+                    if let Ok(prompt) = prompts.get(button.prompt) {
+                        dismissed.ids.insert(prompt.id.clone());
+                        store_dismissed_tutorials(&dismissed, &location);
+                    }
+                }
+                commands.entity(button.prompt).despawn_recursive();
+            }
+            Interaction::Hovered => *color = HOVERED_TUTORIAL_BUTTON.into(),
+            Interaction::None => *color = TUTORIAL_BUTTON.into(),
+        }
+    }
+}
+
+fn store_dismissed_tutorials(dismissed: &DismissedTutorials, location: &SaveLocation) {
+    match ron::ser::to_string_pretty(dismissed, ron::ser::PrettyConfig::default()) {
+        Ok(source) => location.write(DISMISSED_TUTORIALS_FILE, &source),
+        Err(error) => warn!("Could not serialize dismissed tutorials: {}", error),
+    }
+}
+
+// Keeps anchored prompts just below the UI node with the matching Name
+pub fn follow_tutorial_anchors(
+    mut prompts: Query<(&TutorialPrompt, &mut Style)>,
+    anchors: Query<(&Name, &Node, &GlobalTransform)>,
+    ui_scale: Res<UiScale>,
+) {
+    for (prompt, mut style) in prompts.iter_mut() {
+        let Some(anchor_to) = &prompt.anchor_to else {
+            continue;
+        };
+        let Some((_, node, transform)) = anchors
+            .iter()
+            .find(|(name, _, _)| name.as_str() == anchor_to)
+        else {
+            continue;
+        };
+        let scale = ui_scale.0.max(0.01);
+        let position = transform.translation().truncate();
+        let size = node.size();
+        style.left = Val::Px((position.x - size.x / 2.) / scale);
+        style.top = Val::Px((position.y + size.y / 2. + ANCHOR_GAP) / scale);
+        style.bottom = Val::Auto;
+        style.align_self = AlignSelf::Auto;
+    }
+}
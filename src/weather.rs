@@ -0,0 +1,322 @@
+pub use crate::beats::data::Weather;
+use crate::beats::data::{EffectOutput, Fact, FactMutation, FactsOfTheWorld};
+use crate::beats::rng::StoryRng;
+use crate::beats::storage::{write_facts, FactStorage};
+use crate::beats::story_time::StoryTime;
+use crate::beats::{StoryEvaluation, StoryProgressionPass};
+use crate::camera::CameraRig;
+use crate::parallax::ParallaxLayer;
+use crate::particles::ParticleEmitter;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+// String fact holding the weather, as the lowercase name of the variant
+pub const WEATHER_FACT: &str = "weather";
+
+pub struct WeatherPlugin;
+
+/// Weather that changes on its own over story time, tints the background and rains on it.
+/// The `weather` fact always holds the current weather, so rules can check it, and setting
+/// it, or `Effect::SetWeather`, changes the weather.
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherSettings>()
+            .init_resource::<WeatherState>()
+            .add_systems(
+                Update,
+                (
+                    force_weather,
+                    follow_weather_fact,
+                    change_weather,
+                    tint_for_weather,
+                    rain,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Story)),
+            )
+            // In the pass, so rules see the new weather before the frame is out
+            .add_systems(
+                StoryProgressionPass,
+                write_forced_weather.after(StoryEvaluation),
+            )
+            .add_systems(OnExit(GameState::Story), stop_rain);
+    }
+}
+
+impl Weather {
+    pub const ALL: [Weather; 5] = [
+        Weather::Clear,
+        Weather::Cloudy,
+        Weather::Rain,
+        Weather::Storm,
+        Weather::Fog,
+    ];
+
+    pub fn fact_value(self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Cloudy => "cloudy",
+            Weather::Rain => "rain",
+            Weather::Storm => "storm",
+            Weather::Fog => "fog",
+        }
+    }
+
+    pub fn from_fact_value(value: &str) -> Option<Self> {
+        Weather::ALL
+            .into_iter()
+            .find(|weather| weather.fact_value() == value)
+    }
+
+    // Multiplied into the background layers, alpha is left alone
+    pub fn tint(self) -> Color {
+        match self {
+            Weather::Clear => Color::WHITE,
+            Weather::Cloudy => Color::rgb(0.85, 0.85, 0.9),
+            Weather::Rain => Color::rgb(0.7, 0.75, 0.85),
+            Weather::Storm => Color::rgb(0.5, 0.55, 0.65),
+            Weather::Fog => Color::rgb(0.78, 0.8, 0.82),
+        }
+    }
+
+    // Raindrops per second
+    pub fn rain_rate(self) -> f32 {
+        match self {
+            Weather::Rain => 120.,
+            Weather::Storm => 320.,
+            Weather::Clear | Weather::Cloudy | Weather::Fog => 0.,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct WeatherSettings {
+    // Story seconds a spell of weather lasts, picked between the two
+    pub min_spell: f64,
+    pub max_spell: f64,
+    // What each weather can turn into, with weights. Weather missing here never changes on
+    // its own.
+    pub transitions: HashMap<Weather, Vec<(Weather, u32)>>,
+    // Seconds the tint takes to blend into the next weather's
+    pub blend_seconds: f32,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        WeatherSettings {
+            min_spell: 60.,
+            max_spell: 240.,
+            transitions: HashMap::from_iter([
+                (
+                    Weather::Clear,
+                    vec![(Weather::Cloudy, 3), (Weather::Fog, 1)],
+                ),
+                (
+                    Weather::Cloudy,
+                    vec![(Weather::Clear, 2), (Weather::Rain, 2), (Weather::Fog, 1)],
+                ),
+                (
+                    Weather::Rain,
+                    vec![(Weather::Cloudy, 3), (Weather::Storm, 1)],
+                ),
+                (Weather::Storm, vec![(Weather::Rain, 1)]),
+                (
+                    Weather::Fog,
+                    vec![(Weather::Clear, 1), (Weather::Cloudy, 1)],
+                ),
+            ]),
+            blend_seconds: 3.,
+        }
+    }
+}
+
+impl WeatherSettings {
+    // Drawn from the story rng, so a seeded run gets the same weather
+    pub fn next_weather(&self, from: Weather, rng: &mut StoryRng) -> Weather {
+        let Some(options) = self.transitions.get(&from) else {
+            return from;
+        };
+        let total: u32 = options.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return from;
+        }
+        let mut roll = rng.below(total as usize) as u32;
+        for (weather, weight) in options {
+            if roll < *weight {
+                return *weather;
+            }
+            roll -= weight;
+        }
+        from
+    }
+
+    pub fn spell(&self, rng: &mut StoryRng) -> f64 {
+        self.min_spell + (self.max_spell - self.min_spell).max(0.) * rng.next_f32() as f64
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct WeatherState {
+    pub current: Weather,
+    // Story time the weather changes next, none until the first update
+    pub changes_at: Option<f64>,
+}
+
+#[derive(Component)]
+struct RainEmitter;
+
+// Weather forced by an effect holds for as long as the effect says, or a usual spell
+// Writes the weather `Effect::SetWeather` asks for to the `weather` fact
+pub fn write_forced_weather(
+    mut effect_outputs: EventReader<EffectOutput>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::SetWeather { weather, .. } = output else {
+            continue;
+        };
+        let fact = Fact::String(WEATHER_FACT.to_string(), weather.fact_value().to_string());
+        if let Err(error) = write_facts(&mut facts, |facts| facts.try_set(fact)) {
+            warn!("Could not set the weather: {}", error);
+        }
+    }
+}
+
+fn force_weather(
+    mut effect_outputs: EventReader<EffectOutput>,
+    settings: Res<WeatherSettings>,
+    story_time: Res<StoryTime>,
+    mut state: ResMut<WeatherState>,
+    mut rng: ResMut<StoryRng>,
+) {
+    for output in effect_outputs.read() {
+        let EffectOutput::SetWeather { weather, hold } = output else {
+            continue;
+        };
+        let hold = match hold {
+            Some(hold) => hold.as_secs_f64(),
+            None => settings.spell(&mut rng),
+        };
+        state.current = *weather;
+        state.changes_at = Some(story_time.elapsed_seconds() + hold);
+    }
+}
+
+// Picks up the weather stories write to the fact, and loaded saves
+fn follow_weather_fact(
+    facts: Res<FactsOfTheWorld>,
+    settings: Res<WeatherSettings>,
+    story_time: Res<StoryTime>,
+    mut state: ResMut<WeatherState>,
+    mut rng: ResMut<StoryRng>,
+) {
+    let Some(value) = facts.get_string(WEATHER_FACT) else {
+        return;
+    };
+    let Some(weather) = Weather::from_fact_value(value) else {
+        return;
+    };
+    if weather != state.current {
+        state.current = weather;
+        state.changes_at = Some(story_time.elapsed_seconds() + settings.spell(&mut rng));
+    }
+}
+
+fn change_weather(
+    settings: Res<WeatherSettings>,
+    story_time: Res<StoryTime>,
+    mut state: ResMut<WeatherState>,
+    mut rng: ResMut<StoryRng>,
+    mut facts: ResMut<FactsOfTheWorld>,
+) {
+    let now = story_time.elapsed_seconds();
+    match state.changes_at {
+        None => state.changes_at = Some(now + settings.spell(&mut rng)),
+        Some(changes_at) if now >= changes_at => {
+            state.current = settings.next_weather(state.current, &mut rng);
+            state.changes_at = Some(now + settings.spell(&mut rng));
+        }
+        Some(_) => {}
+    }
+    let value = state.current.fact_value();
+    if facts.get_string(WEATHER_FACT).map(String::as_str) == Some(value) {
+        return;
+    }
+    if let Err(error) = facts.apply_batch(vec![FactMutation::StoreString(
+        WEATHER_FACT.to_string(),
+        value.to_string(),
+    )]) {
+        warn!("Could not write the weather: {}", error);
+    }
+}
+
+// Blends over real time, so the change shows even while the story clock is paused
+fn tint_for_weather(
+    time: Res<Time>,
+    settings: Res<WeatherSettings>,
+    state: Res<WeatherState>,
+    mut tint: Local<Option<Vec3>>,
+    mut layers: Query<&mut Sprite, With<ParallaxLayer>>,
+) {
+    let target = Vec3::from_slice(&state.current.tint().as_rgba_f32()[..3]);
+    let current = tint.get_or_insert(target);
+    let amount = if settings.blend_seconds > 0. {
+        (time.delta_seconds() / settings.blend_seconds).min(1.)
+    } else {
+        1.
+    };
+    *current = current.lerp(target, amount);
+    for mut sprite in layers.iter_mut() {
+        let alpha = sprite.color.a();
+        sprite.color = Color::rgba(current.x, current.y, current.z, alpha);
+    }
+}
+
+// Drops fall from a strip above the camera, wide enough to cover the screen
+fn rain(
+    mut commands: Commands,
+    time: Res<Time>,
+    state: Res<WeatherState>,
+    rigs: Query<&Transform, (With<CameraRig>, Without<RainEmitter>)>,
+    mut emitters: Query<(&mut ParticleEmitter, &mut Transform), With<RainEmitter>>,
+    mut owed: Local<f32>,
+) {
+    let rate = state.current.rain_rate();
+    let Ok((mut emitter, mut transform)) = emitters.get_single_mut() else {
+        if rate > 0. {
+            let mut emitter = ParticleEmitter::default();
+            emitter.count = 0;
+            emitter.burst_on_spawn = false;
+            emitter.despawn_when_done = false;
+            emitter.min_speed = 500.;
+            emitter.max_speed = 650.;
+            emitter.lifetime = 1.5;
+            emitter.direction = 260.;
+            emitter.spread = 3.;
+            emitter.gravity = 0.;
+            emitter.size = 3.;
+            emitter.start_color = Color::rgba(0.75, 0.8, 0.95, 0.8);
+            emitter.end_color = Color::rgba(0.75, 0.8, 0.95, 0.3);
+            emitter.area = Vec2::new(1600., 0.);
+            commands.spawn((SpatialBundle::default(), emitter, RainEmitter));
+        }
+        return;
+    };
+    if let Some(camera) = rigs.iter().next() {
+        transform.translation = camera.translation.truncate().extend(50.) + Vec3::Y * 420.;
+    }
+    *owed += rate * time.delta_seconds();
+    let drops = owed.floor();
+    *owed -= drops;
+    if drops >= 1. {
+        emitter.count = drops as u32;
+        emitter.burst();
+    }
+}
+
+fn stop_rain(mut commands: Commands, emitters: Query<Entity, With<RainEmitter>>) {
+    for entity in emitters.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
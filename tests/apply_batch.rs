@@ -0,0 +1,79 @@
+// apply_batch writes every mutation or none: constants, aliases and types are all checked
+// before the first write.
+use barnacle_beats::prelude::*;
+
+fn dock() -> FactsOfTheWorld {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_int("dock.crates".to_string(), 3).unwrap();
+    facts.store_constant(Fact::String("dock.name".to_string(), "Saltmarsh".to_string()));
+    facts.alias("crates", "dock.crates");
+    facts.drain_updated();
+    facts
+}
+
+#[test]
+fn a_whole_batch_is_applied() {
+    let mut facts = dock();
+    facts
+        .apply_batch(vec![
+            FactMutation::StoreInt("crates".to_string(), 5),
+            FactMutation::AddToList("dock.cargo".to_string(), "rope".to_string()),
+            FactMutation::StoreBool("dock.open".to_string(), true),
+        ])
+        .unwrap();
+    assert_eq!(facts.get_int("dock.crates"), Some(&5));
+    assert_eq!(facts.get_bool("dock.open"), Some(&true));
+    assert!(facts.get_list("dock.cargo").unwrap().contains(&"rope".to_string()));
+}
+
+#[test]
+fn a_constant_anywhere_in_the_batch_stops_all_of_it() {
+    let mut facts = dock();
+    let result = facts.apply_batch(vec![
+        FactMutation::StoreInt("dock.crates".to_string(), 10),
+        FactMutation::StoreString("dock.name".to_string(), "Brinewick".to_string()),
+    ]);
+    assert_eq!(
+        result,
+        Err(FactError::ReadOnly {
+            key: "dock.name".to_string()
+        })
+    );
+    assert_eq!(facts.get_int("dock.crates"), Some(&3));
+    assert!(facts.drain_updated().is_empty());
+    assert!(facts.drain_denied().is_empty());
+}
+
+#[test]
+fn types_are_compared_through_aliases() {
+    let mut facts = dock();
+    let result = facts.apply_batch(vec![
+        FactMutation::StoreBool("dock.open".to_string(), true),
+        FactMutation::StoreString("crates".to_string(), "many".to_string()),
+    ]);
+    assert!(matches!(result, Err(FactError::TypeMismatch { stored: "int", .. })));
+    assert_eq!(facts.get_int("dock.crates"), Some(&3));
+    assert_eq!(facts.get_bool("dock.open"), None);
+}
+
+#[test]
+fn list_edits_need_a_list() {
+    let mut facts = dock();
+    let result = facts.apply_batch(vec![
+        FactMutation::StoreInt("dock.crates".to_string(), 4),
+        FactMutation::AddToList("crates".to_string(), "rope".to_string()),
+    ]);
+    assert!(result.is_err());
+    assert_eq!(facts.get_int("dock.crates"), Some(&3));
+}
+
+#[test]
+fn earlier_writes_in_the_batch_count() {
+    let mut facts = dock();
+    let result = facts.apply_batch(vec![
+        FactMutation::StoreInt("dock.tide".to_string(), 1),
+        FactMutation::StoreBool("dock.tide".to_string(), true),
+    ]);
+    assert!(result.is_err());
+    assert!(facts.get("dock.tide").is_none());
+}
@@ -0,0 +1,225 @@
+// A beat finishes once per time it becomes active, however many facts change in the frame
+// it finishes in, and finished beats are reported in the order their stories were declared.
+// Effects that satisfy the next beat's rules finish that beat in the same frame, and a beat
+// finishing twice in one frame is reported as a cycle.
+use barnacle_beats::prelude::*;
+use bevy::prelude::{App, EventReader, ResMut, Resource, Update};
+
+#[derive(Resource, Default)]
+struct Finished(Vec<(String, String)>);
+
+fn collect_finished(mut events: EventReader<StoryBeatFinished>, mut finished: ResMut<Finished>) {
+    for event in events.read() {
+        finished
+            .0
+            .push((event.story.name.clone(), event.beat.name.clone()));
+    }
+}
+
+fn story(name: &str) -> Story {
+    StoryBuilder::new(name)
+        .add_pre_requisite(format!("{} starts", name), |rule| {
+            rule.with_condition(Condition::BoolEquals {
+                fact_name: "open".to_string(),
+                expected_value: true,
+            })
+        })
+        .add_story_beat(format!("{} greeting", name), |beat| {
+            beat.with_rule(format!("{} greeted", name), |rule| {
+                rule.with_condition(Condition::IntMoreThan {
+                    fact_name: "greetings".to_string(),
+                    expected_value: 0,
+                })
+            })
+            .with_effects(|effects| effects.change_affinity(name, 1))
+        })
+        .add_story_beat(format!("{} farewell", name), |beat| {
+            beat.with_rule(format!("{} said farewell", name), |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "farewell".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .init_resource::<Finished>()
+        .add_systems(Update, collect_finished);
+    app.update();
+    let mut engine = app.world.resource_mut::<StoryEngine>();
+    engine.add_story(story("first"));
+    engine.add_story(story("second"));
+    app
+}
+
+#[test]
+fn beat_finishes_once_when_many_facts_change_in_one_frame() {
+    let mut app = app();
+    {
+        let mut facts = app.world.resource_mut::<FactsOfTheWorld>();
+        facts.store_bool("open".to_string(), true).unwrap();
+        for greeting in 1..=5 {
+            facts.store_int("greetings".to_string(), greeting).unwrap();
+            facts.store_int(format!("noise.{}", greeting), greeting).unwrap();
+        }
+    }
+    for _ in 0..5 {
+        app.update();
+    }
+    // More updates in later frames don't finish the beat again either
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("greetings".to_string(), 10).unwrap();
+    for _ in 0..5 {
+        app.update();
+    }
+
+    let finished = &app.world.resource::<Finished>().0;
+    assert_eq!(
+        finished,
+        &vec![
+            ("first".to_string(), "first greeting".to_string()),
+            ("second".to_string(), "second greeting".to_string()),
+        ]
+    );
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_int(&affinity_fact("first")), Some(&1));
+    assert_eq!(facts.get_int(&affinity_fact("second")), Some(&1));
+}
+
+#[test]
+fn beats_finished_in_one_frame_follow_story_declaration_order() {
+    let mut app = app();
+    {
+        let mut facts = app.world.resource_mut::<FactsOfTheWorld>();
+        facts.store_bool("open".to_string(), true).unwrap();
+        facts.store_int("greetings".to_string(), 1).unwrap();
+    }
+    app.update();
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("farewell".to_string(), true).unwrap();
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let finished = &app.world.resource::<Finished>().0;
+    assert_eq!(
+        finished,
+        &vec![
+            ("first".to_string(), "first greeting".to_string()),
+            ("second".to_string(), "second greeting".to_string()),
+            ("first".to_string(), "first farewell".to_string()),
+            ("second".to_string(), "second farewell".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn beat_reported_twice_in_one_frame_applies_its_effects_once() {
+    let mut app = app();
+    let story = app
+        .world
+        .resource::<StoryEngine>()
+        .stories
+        .iter()
+        .find(|story| story.name == "first")
+        .cloned()
+        .expect("first story registered");
+    let beat = story.beats[0].clone();
+    for _ in 0..2 {
+        app.world.send_event(StoryBeatFinished {
+            story: story.clone(),
+            beat: beat.clone(),
+        });
+    }
+    app.update();
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_int(&affinity_fact("first")), Some(&1));
+}
+
+#[test]
+fn effects_cascade_into_the_next_beat_in_the_same_frame() {
+    let mut app = app();
+    let chain = StoryBuilder::new("chain")
+        .add_story_beat("knock", |beat| {
+            beat.with_rule("knocked", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "knock".to_string(),
+                    expected_value: true,
+                })
+            })
+            .with_effects(|effects| effects.set_fact_bool("door_open", true))
+        })
+        .add_story_beat("enter", |beat| {
+            beat.with_rule("entered", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "door_open".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("chain story builds");
+    app.world.resource_mut::<StoryEngine>().add_story(chain);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("knock".to_string(), true).unwrap();
+    app.update();
+
+    let engine = app.world.resource::<StoryEngine>();
+    let chain = engine
+        .stories
+        .iter()
+        .find(|story| story.name == "chain")
+        .expect("chain story registered");
+    assert!(chain.is_finished());
+}
+
+#[derive(Resource, Default)]
+struct Cycles(Vec<NarrativeCycleDetected>);
+
+fn collect_cycles(mut events: EventReader<NarrativeCycleDetected>, mut cycles: ResMut<Cycles>) {
+    cycles.0.extend(events.read().cloned());
+}
+
+#[test]
+fn beat_that_keeps_finishing_itself_is_reported_as_a_cycle() {
+    let mut app = app();
+    app.init_resource::<Cycles>()
+        .add_systems(Update, collect_cycles);
+    let echo = StoryBuilder::new("echo")
+        .add_story_beat("shout", |beat| {
+            beat.with_rule("shouted", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "shout".to_string(),
+                    expected_value: true,
+                })
+            })
+            .transition_to("shout", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "shout".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("echo story builds");
+    app.world.resource_mut::<StoryEngine>().add_story(echo);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("shout".to_string(), true).unwrap();
+    app.update();
+    app.update();
+
+    let cycles = &app.world.resource::<Cycles>().0;
+    assert!(!cycles.is_empty());
+    let shout = ("echo".to_string(), "shout".to_string());
+    assert_eq!(cycles[0].chain, vec![shout.clone(), shout]);
+}
@@ -0,0 +1,46 @@
+// The beats pipeline logs under the `beats` target, as much as `BeatsLogLevel` allows. Quieting
+// it doesn't change how stories play.
+use barnacle_beats::prelude::*;
+use bevy::log::Level;
+use bevy::prelude::App;
+
+#[test]
+fn the_level_caps_what_is_logged() {
+    assert!(BeatsLogLevel::default().logs(Level::TRACE));
+    let quiet = BeatsLogLevel(Level::INFO);
+    assert!(quiet.logs(Level::WARN));
+    assert!(!quiet.logs(Level::DEBUG));
+}
+
+#[test]
+fn stories_play_at_any_level() {
+    for level in [Level::TRACE, Level::ERROR] {
+        let story = StoryBuilder::new("harbour")
+            .add_story_beat("arrival", |beat| {
+                beat.with_rule("docked", |rule| {
+                    rule.with_condition(Condition::BoolEquals {
+                        fact_name: "docked".to_string(),
+                        expected_value: true,
+                    })
+                })
+                .with_effects(|effects| effects.set_fact_int("harbour.visits", 1))
+            })
+            .build()
+            .expect("test story builds");
+        let mut app = App::new();
+        app.add_plugins(MinimalStoryPlugins)
+            .insert_resource(BeatsLogLevel(level));
+        app.world.resource_mut::<StoryEngine>().add_story(story);
+        app.world
+            .resource_mut::<FactsOfTheWorld>()
+            .store_bool("docked".to_string(), true).unwrap();
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world
+                .resource::<FactsOfTheWorld>()
+                .get_int("harbour.visits"),
+            Some(&1)
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// Codex entries unlock once their condition passes and stay unlocked after that, so each one
+// is only reported once.
+use barnacle_beats::codex::{CodexEntries, CodexEntry, CODEX_UNLOCKED_FACT};
+use barnacle_beats::prelude::*;
+
+fn bundled_codex() -> CodexEntries {
+    ron::from_str(include_str!("../assets/codex.ron")).expect("assets/codex.ron parses")
+}
+
+fn ids(entries: Vec<&CodexEntry>) -> Vec<&str> {
+    entries.into_iter().map(|entry| entry.id.as_str()).collect()
+}
+
+#[test]
+fn entries_without_a_condition_unlock_straight_away() {
+    let codex = bundled_codex();
+    let facts = FactsOfTheWorld::new();
+    assert_eq!(ids(codex.newly_unlocked(&facts)), vec!["harbour"]);
+}
+
+#[test]
+fn recorded_entries_are_not_unlocked_again() {
+    let codex = bundled_codex();
+    let mut facts = FactsOfTheWorld::new();
+    facts
+        .add_to_list(CODEX_UNLOCKED_FACT.to_string(), "harbour".to_string())
+        .unwrap();
+    assert!(codex.newly_unlocked(&facts).is_empty());
+
+    facts.store_bool("heard_of_barnacle".to_string(), true).unwrap();
+    assert_eq!(ids(codex.newly_unlocked(&facts)), vec!["lost_barnacle"]);
+}
@@ -0,0 +1,64 @@
+// Constant facts: set up once by the game, every later write that would change them is refused
+// with FactError::ReadOnly and reported as a FactWriteDenied.
+use barnacle_beats::prelude::*;
+
+fn harbour() -> FactsOfTheWorld {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_constant(Fact::Int("harbour.berths".to_string(), 4));
+    facts.drain_updated();
+    facts
+}
+
+#[test]
+fn setters_refuse_writes_to_constants() {
+    let mut facts = harbour();
+    assert_eq!(
+        facts.store_int("harbour.berths".to_string(), 5),
+        Err(FactError::ReadOnly {
+            key: "harbour.berths".to_string()
+        })
+    );
+    assert_eq!(
+        facts.add_to_int("harbour.berths".to_string(), 1),
+        Err(FactError::ReadOnly {
+            key: "harbour.berths".to_string()
+        })
+    );
+    assert_eq!(facts.get_int("harbour.berths"), Some(&4));
+    assert!(facts.drain_updated().is_empty());
+    assert_eq!(facts.drain_denied().len(), 2);
+}
+
+#[test]
+fn try_set_reports_constants_and_keeps_the_value() {
+    let mut facts = harbour();
+    assert!(matches!(
+        facts.try_set(Fact::Int("harbour.berths".to_string(), 9)),
+        Err(FactError::ReadOnly { .. })
+    ));
+    assert_eq!(facts.get_int("harbour.berths"), Some(&4));
+    // Other facts are still writable
+    assert_eq!(facts.try_set(Fact::Int("harbour.boats".to_string(), 2)), Ok(()));
+}
+
+#[test]
+fn aliases_of_constants_are_constant_too() {
+    let mut facts = harbour();
+    facts.alias("berths", "harbour.berths");
+    assert!(facts.check_writable("berths").is_err());
+    assert!(facts.store_int("berths".to_string(), 1).is_err());
+    assert_eq!(facts.get_int("harbour.berths"), Some(&4));
+}
+
+#[test]
+fn refused_writes_stay_out_of_the_event_log() {
+    let mut store = EventSourcedFactStore::new();
+    store.store_constant(Fact::Int("harbour.berths".to_string(), 4));
+    assert!(store.store_int("harbour.berths".to_string(), 5).is_err());
+    assert!(store.store_int("harbour.boats".to_string(), 1).is_ok());
+    // A fact of another type is refused the same way
+    assert!(store.store_bool("harbour.boats".to_string(), true).is_err());
+    assert_eq!(store.log().len(), 1);
+    assert_eq!(store.get_int("harbour.berths"), Some(&4));
+    assert_eq!(store.get_int("harbour.boats"), Some(&1));
+}
@@ -0,0 +1,78 @@
+// A reload compares the content before and after. How far the player got in a story doesn't
+// count as a change, edited rules, data table facts and the theme do.
+use barnacle_beats::content_reload::{ContentChanges, ContentSnapshot};
+use barnacle_beats::prelude::*;
+use barnacle_beats::ui::theme::{Palette, UiTheme};
+
+fn harbour(docked: bool) -> Story {
+    StoryBuilder::new("harbour")
+        .add_story_beat("arrival", |beat| {
+            beat.with_rule("docked", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "docked".to_string(),
+                    expected_value: docked,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+#[test]
+fn progress_is_not_a_change() {
+    let before = ContentSnapshot {
+        stories: vec![harbour(true)],
+        ..Default::default()
+    };
+    let mut started = harbour(true);
+    started.is_started = true;
+    started.beats[0].finished = true;
+    let after = ContentSnapshot {
+        stories: vec![started],
+        ..Default::default()
+    };
+    let changes = ContentChanges::between(&before, &after);
+    assert!(changes.is_empty());
+    assert_eq!(changes.to_string(), "Reloaded content, nothing changed");
+}
+
+#[test]
+fn edits_are_counted() {
+    let before = ContentSnapshot {
+        stories: vec![harbour(true)],
+        table_facts: vec![
+            Fact::Int("ports.vigo.tax".to_string(), 3),
+            Fact::Int("ports.cadiz.tax".to_string(), 2),
+        ],
+        theme: Some(UiTheme::default()),
+    };
+    let theme = UiTheme {
+        protanopia: Palette::STANDARD,
+        ..Default::default()
+    };
+    let mut lighthouse = harbour(true);
+    lighthouse.name = "lighthouse".to_string();
+    let after = ContentSnapshot {
+        stories: vec![harbour(false), lighthouse],
+        table_facts: vec![
+            Fact::Int("ports.vigo.tax".to_string(), 4),
+            Fact::Int("ports.cadiz.tax".to_string(), 2),
+        ],
+        theme: Some(theme),
+    };
+    let changes = ContentChanges::between(&before, &after);
+    assert_eq!(
+        changes,
+        ContentChanges {
+            stories_added: 1,
+            stories_changed: 1,
+            rules_changed: 2,
+            facts_changed: 1,
+            theme_changed: true,
+        }
+    );
+    assert_eq!(
+        changes.to_string(),
+        "Reloaded content: 1 story added, 1 story changed, 2 rules changed, 1 fact changed, theme changed"
+    );
+}
@@ -0,0 +1,46 @@
+// `CountWhere` counts the entities in a namespace with a matching fact, so a rule can ask how
+// many crew members are sick without naming each of them.
+use barnacle_beats::prelude::*;
+
+fn sick_crew(more_than: u32) -> Condition {
+    Condition::CountWhere {
+        prefix: "crew.".to_string(),
+        sub_key: "sick".to_string(),
+        equals: FactValue::Bool(true),
+        more_than,
+    }
+}
+
+#[test]
+fn counts_entities_with_a_matching_fact() {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_bool("crew.ada.sick".to_string(), true).unwrap();
+    facts.store_bool("crew.bo.sick".to_string(), false).unwrap();
+    facts.store_bool("crew.cy.sick".to_string(), true).unwrap();
+    // Not an entity of the namespace, nor a sick fact
+    facts.store_bool("crew.sick".to_string(), true).unwrap();
+    facts.store_int("crew.dee.sick".to_string(), 1).unwrap();
+    facts.store_bool("crew.ada.sickly".to_string(), true).unwrap();
+    facts.store_bool("crew.ship.hold.sick".to_string(), true).unwrap();
+
+    assert!(sick_crew(0).evaluate(&facts));
+    assert!(sick_crew(1).evaluate(&facts));
+    assert!(!sick_crew(2).evaluate(&facts));
+}
+
+#[test]
+fn reads_from_ron_with_any_value_type() {
+    let condition: Condition = ron::from_str(
+        r#"CountWhere(prefix: "crew.", sub_key: "sick", equals: true, more_than: 2)"#,
+    )
+    .expect("the condition parses");
+    assert_eq!(condition, sick_crew(2));
+
+    let condition: Condition =
+        ron::from_str(r#"CountWhere(prefix: "crew.", sub_key: "role", equals: "cook")"#)
+            .expect("more_than can be left out");
+    let mut facts = FactsOfTheWorld::new();
+    assert!(!condition.evaluate(&facts));
+    facts.store_string("crew.ada.role".to_string(), "cook".to_string()).unwrap();
+    assert!(condition.evaluate(&facts));
+}
@@ -0,0 +1,88 @@
+// Hiring writes a crew member's facts and keeps `crew.best.*` up to date, so rules can ask for
+// "a crew member with carpentry > 2" without knowing who is aboard. The crew effects leave the
+// writing to the crew plugin.
+use barnacle_beats::crew::{
+    best_skill_fact, crew_fact, dismiss, hire, CrewRoster, CREW_ROSTER_FACT, CREW_TRAITS_FACT,
+};
+use barnacle_beats::prelude::*;
+
+fn hire_onto(facts: &mut FactsOfTheWorld, member: CrewMember) {
+    hire(facts, &member).expect("the crew facts are writable");
+}
+
+fn carpenter_aboard() -> Condition {
+    Condition::IntMoreThan {
+        fact_name: best_skill_fact("carpentry"),
+        expected_value: 2,
+    }
+}
+
+#[test]
+fn hiring_writes_the_members_facts() {
+    let mut facts = FactsOfTheWorld::new();
+    hire_onto(
+        &mut facts,
+        CrewMember::new("ada")
+            .with_skill("carpentry", 3)
+            .with_trait("brave"),
+    );
+    assert_eq!(facts.get_int(&crew_fact("ada", "carpentry")), Some(&3));
+    assert_eq!(facts.get_bool(&crew_fact("ada", "brave")), Some(&true));
+    assert!(facts
+        .get_list(CREW_ROSTER_FACT)
+        .is_some_and(|roster| roster.contains(&"ada".to_string())));
+    assert!(facts
+        .get_list(CREW_TRAITS_FACT)
+        .is_some_and(|traits| traits.contains(&"brave".to_string())));
+    assert_eq!(
+        CrewRoster::from_facts(&facts.facts).get("ada"),
+        Some(
+            &CrewMember::new("ada")
+                .with_skill("carpentry", 3)
+                .with_trait("brave")
+        )
+    );
+}
+
+#[test]
+fn rules_see_the_best_skill_on_the_crew() {
+    let mut facts = FactsOfTheWorld::new();
+    hire_onto(&mut facts, CrewMember::new("bo").with_skill("carpentry", 1));
+    assert!(!carpenter_aboard().evaluate(&facts));
+    hire_onto(
+        &mut facts,
+        CrewMember::new("ada").with_skill("carpentry", 4),
+    );
+    assert!(carpenter_aboard().evaluate(&facts));
+    assert_eq!(facts.get_int(&best_skill_fact("carpentry")), Some(&4));
+}
+
+#[test]
+fn dismissing_clears_the_member() {
+    let mut facts = FactsOfTheWorld::new();
+    hire_onto(
+        &mut facts,
+        CrewMember::new("ada")
+            .with_skill("carpentry", 4)
+            .with_trait("brave"),
+    );
+    dismiss(&mut facts, "ada").expect("the crew facts are writable");
+    assert!(!carpenter_aboard().evaluate(&facts));
+    assert_eq!(facts.get_int(&best_skill_fact("carpentry")), Some(&0));
+    assert_eq!(facts.get_bool(&crew_fact("ada", "brave")), Some(&false));
+    assert!(facts
+        .get_list(CREW_TRAITS_FACT)
+        .is_some_and(|traits| !traits.contains(&"brave".to_string())));
+    assert!(CrewRoster::from_facts(&facts.facts).members.is_empty());
+}
+
+#[test]
+fn crew_effects_leave_the_facts_to_the_crew_plugin() {
+    let mut facts = FactsOfTheWorld::new();
+    let ada = CrewMember::new("ada").with_skill("carpentry", 3);
+    let outputs = Effect::HireCrew(ada.clone())
+        .apply(&mut facts, &mut StoryRng::new(1))
+        .expect("hiring needs no facts");
+    assert_eq!(outputs, vec![EffectOutput::HireCrew(ada)]);
+    assert_eq!(facts.get_list(CREW_ROSTER_FACT), None);
+}
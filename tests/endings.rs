@@ -0,0 +1,91 @@
+// Once every story is finished an ending is picked, recorded in a fact and the epilogue shown.
+// Coming back to the finished stories later, e.g. through the menu after the credits, doesn't
+// end the run a second time.
+use barnacle_beats::dialogue::history::DialogueHistory;
+use barnacle_beats::endings::{EndingDefinitions, EndingsPlugin, ENDING_FACT};
+use barnacle_beats::prelude::*;
+use bevy::input::ButtonInput;
+use bevy::prelude::{App, Assets, KeyCode, NextState, State};
+
+fn voyage() -> Story {
+    StoryBuilder::new("voyage")
+        .add_story_beat("home", |beat| {
+            beat.with_rule("made port", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "home".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .add_plugins(EndingsPlugin)
+        .init_resource::<DialogueHistory>()
+        .init_resource::<ButtonInput<KeyCode>>();
+    app.world.resource_mut::<StoryEngine>().add_story(voyage());
+    // The endings come from assets/endings.ron, and the bundled stories load alongside ours
+    for _ in 0..200 {
+        app.update();
+        let endings_loaded = app.world.resource::<Assets<EndingDefinitions>>().len() > 0;
+        let stories = app.world.resource::<StoryEngine>().stories.len();
+        if endings_loaded && stories == STORY_FILES.len() + 1 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    // Only our story is left to play
+    for story in app.world.resource_mut::<StoryEngine>().stories.iter_mut() {
+        if story.name != "voyage" {
+            story.is_started = true;
+            story.active_beat_index = story.beats.len();
+        }
+    }
+    app
+}
+
+fn state(app: &App) -> GameState {
+    app.world.resource::<State<GameState>>().get().clone()
+}
+
+fn go_to(app: &mut App, next: GameState) {
+    app.world.resource_mut::<NextState<GameState>>().set(next);
+    app.update();
+}
+
+#[test]
+fn an_ending_is_reached_once() {
+    let mut app = app();
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("home".to_string(), true)
+        .unwrap();
+    app.update();
+    app.update();
+
+    assert_eq!(state(&app), GameState::Epilogue);
+    let reached = app
+        .world
+        .resource::<FactsOfTheWorld>()
+        .get_string(ENDING_FACT)
+        .cloned()
+        .expect("the ending is recorded");
+    assert_ne!(reached, "");
+
+    go_to(&mut app, GameState::Credits);
+    go_to(&mut app, GameState::Menu);
+    go_to(&mut app, GameState::Story);
+    app.update();
+    app.update();
+    assert_eq!(state(&app), GameState::Story);
+    assert_eq!(
+        app.world
+            .resource::<FactsOfTheWorld>()
+            .get_string(ENDING_FACT),
+        Some(&reached)
+    );
+}
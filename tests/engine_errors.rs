@@ -0,0 +1,159 @@
+// Bad content is reported as an EngineError and listed on the error screen instead of
+// panicking: writes of the wrong type are refused and leave the fact as it was, and panics in
+// guarded code come back as errors. Stories that can't work are refused when they are built.
+use barnacle_beats::prelude::*;
+use bevy::prelude::App;
+
+#[test]
+fn writes_of_another_type_are_refused() {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_int("tide".to_string(), 3).unwrap();
+    assert_eq!(
+        facts.store_bool("tide".to_string(), true),
+        Err(FactError::TypeMismatch {
+            key: "tide".to_string(),
+            stored: "int",
+            written: "bool",
+        })
+    );
+    assert_eq!(facts.get_int("tide"), Some(&3));
+    assert!(facts.try_set(Fact::String("tide".to_string(), "high".to_string())).is_err());
+    // `set` logs the mismatch rather than panicking
+    FactStorage::set(&mut facts, Fact::Bool("tide".to_string(), false));
+    assert_eq!(facts.get_int("tide"), Some(&3));
+}
+
+#[test]
+fn a_failing_effect_is_listed_and_the_game_goes_on() {
+    let story = StoryBuilder::new("harbour")
+        .add_story_beat("high tide", |beat| {
+            beat.with_rule("tide rose", |rule| {
+                rule.with_condition(Condition::IntMoreThan {
+                    fact_name: "tide".to_string(),
+                    expected_value: 2,
+                })
+            })
+            .with_effects(|effects| effects.set_fact_bool("tide", true).set_fact_int("docked", 1))
+        })
+        .build()
+        .expect("test story builds");
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins);
+    app.world.resource_mut::<StoryEngine>().add_story(story);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("tide".to_string(), 3)
+        .unwrap();
+    app.update();
+    app.update();
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_int("tide"), Some(&3));
+    // The effects after the failing one still apply
+    assert_eq!(facts.get_int("docked"), Some(&1));
+    let log = app.world.resource::<ErrorLog>();
+    assert_eq!(
+        log.messages,
+        vec![
+            "Effect of beat high tide in harbour failed: fact tide is a int but a bool was written to it"
+                .to_string()
+        ]
+    );
+}
+
+#[test]
+fn panics_are_recovered_as_errors() {
+    assert_eq!(recover(|| 2 + 2), Ok(4));
+    let recovered: Result<(), String> = recover(|| panic!("the kraken woke"));
+    assert_eq!(recovered, Err("the kraken woke".to_string()));
+    let formatted: Result<(), String> = recover(|| panic!("{} krakens", 2));
+    assert_eq!(formatted, Err("2 krakens".to_string()));
+}
+
+#[test]
+fn a_failing_script_is_listed() {
+    let story = StoryBuilder::new("harbour")
+        .add_story_beat("storm", |beat| {
+            beat.with_rule("wind rose", |rule| {
+                rule.with_condition(Condition::IntMoreThan {
+                    fact_name: "wind".to_string(),
+                    expected_value: 2,
+                })
+            })
+            .with_effects(|effects| effects.run_script("docked = 1; throw \"sunk\";"))
+        })
+        .build()
+        .expect("test story builds");
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins);
+    app.world.resource_mut::<StoryEngine>().add_story(story);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("wind".to_string(), 3)
+        .unwrap();
+    app.update();
+    app.update();
+
+    assert_eq!(
+        app.world.resource::<FactsOfTheWorld>().get_int("docked"),
+        None
+    );
+    let log = app.world.resource::<ErrorLog>();
+    assert!(log
+        .messages
+        .iter()
+        .any(|message| message.starts_with("Effect of beat storm in harbour failed: script `")));
+}
+
+#[test]
+fn broken_stories_are_refused_when_built() {
+    let tide = |rule: RuleBuilder| {
+        rule.with_condition(Condition::BoolEquals {
+            fact_name: "tide".to_string(),
+            expected_value: true,
+        })
+    };
+    assert_eq!(
+        RuleBuilder::new("empty").build(),
+        Err(RuleBuildError::NoConditions {
+            rule: "empty".to_string()
+        })
+    );
+
+    let mut engine = StoryEngine::new();
+    engine.add_story(
+        StoryBuilder::new("harbour")
+            .add_pre_requisite("tide rose", tide)
+            .build()
+            .expect("test story builds"),
+    );
+    assert_eq!(
+        tide(RuleBuilder::new("tide rose").checked_against(&engine)).build(),
+        Err(RuleBuildError::DuplicateName {
+            rule: "tide rose".to_string()
+        })
+    );
+
+    assert_eq!(
+        RuleBuilder::new("peeking")
+            .with_condition(Condition::IntEquals {
+                fact_name: "engine.frame".to_string(),
+                expected_value: 1,
+            })
+            .build(),
+        Err(RuleBuildError::ReservedFact {
+            rule: "peeking".to_string(),
+            fact_name: "engine.frame".to_string(),
+        })
+    );
+
+    assert_eq!(
+        StoryBuilder::new("harbour")
+            .add_story_beat("high tide", |beat| beat.transition_to("low tide", tide))
+            .build(),
+        Err(RuleBuildError::UnknownTransitionTarget {
+            beat: "high tide".to_string(),
+            target: "low tide".to_string(),
+        })
+    );
+}
@@ -0,0 +1,238 @@
+// A single rule can be looked up by name anywhere in the stories and evaluated on its own.
+// Every condition is reported, failing or not, and the engine is left exactly as it was. The
+// conditions themselves are checked here too: combinators, costs and the evaluation clock.
+use barnacle_beats::prelude::*;
+
+fn harbour() -> StoryEngine {
+    let story = StoryBuilder::new("harbour")
+        .add_pre_requisite("ship moored", |rule| {
+            rule.with_condition(Condition::BoolEquals {
+                fact_name: "moored".to_string(),
+                expected_value: true,
+            })
+        })
+        .add_story_beat("unload", |beat| {
+            beat.with_rule("cargo ashore", |rule| {
+                rule.with_condition(Condition::IntMoreThan {
+                    fact_name: "crates".to_string(),
+                    expected_value: 2,
+                })
+                .with_condition(Condition::BoolEquals {
+                    fact_name: "crane".to_string(),
+                    expected_value: true,
+                })
+            })
+            .with_effects(|effects| effects.set_fact_bool("unloaded", true))
+            .transition_to("unload", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "more cargo".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds");
+    let mut engine = StoryEngine::new();
+    engine.add_story(story);
+    engine
+}
+
+fn evaluate(engine: &StoryEngine, facts: &FactsOfTheWorld, rule: &str) -> Option<RuleEvaluation> {
+    let mut rng = StoryRng::new(1);
+    let mut context = EvaluationContext::detached(facts, &mut rng);
+    engine.evaluate_single(rule, &mut context)
+}
+
+#[test]
+fn rules_are_found_wherever_they_are_declared() {
+    let engine = harbour();
+    let facts = FactsOfTheWorld::new();
+
+    // Transition rules are named after the beat and their target
+    for rule in ["ship moored", "cargo ashore", "unload -> unload"] {
+        let found = evaluate(&engine, &facts, rule).map(|evaluation| evaluation.rule);
+        assert_eq!(found, Some(rule.to_string()));
+    }
+    assert!(evaluate(&engine, &facts, "no such rule").is_none());
+}
+
+#[test]
+fn every_condition_is_reported() {
+    let engine = harbour();
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_int("crates".to_string(), 1).unwrap();
+    facts.store_bool("crane".to_string(), true).unwrap();
+
+    let evaluation = evaluate(&engine, &facts, "cargo ashore").expect("rule exists");
+    let holds: Vec<bool> = evaluation
+        .conditions
+        .iter()
+        .map(|result| result.holds)
+        .collect();
+    assert_eq!(holds, vec![false, true]);
+    assert!(!evaluation.holds());
+
+    facts.store_int("crates".to_string(), 3).unwrap();
+    assert!(evaluate(&engine, &facts, "cargo ashore")
+        .expect("rule exists")
+        .holds());
+}
+
+#[test]
+fn evaluating_leaves_the_engine_alone() {
+    let engine = harbour();
+    let before = engine.clone();
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_bool("moored".to_string(), true).unwrap();
+    facts.store_int("crates".to_string(), 3).unwrap();
+    facts.store_bool("crane".to_string(), true).unwrap();
+    facts.drain_updated();
+
+    assert!(evaluate(&engine, &facts, "ship moored")
+        .expect("rule exists")
+        .holds());
+    assert!(evaluate(&engine, &facts, "cargo ashore")
+        .expect("rule exists")
+        .holds());
+    assert_eq!(engine, before);
+    assert!(!engine.stories[0].is_started);
+    assert!(!engine.stories[0].beats[0].finished);
+    assert_eq!(facts.get_bool("unloaded"), None);
+    assert!(!facts.has_updates());
+}
+
+fn holds(rule: RuleBuilder, facts: &FactsOfTheWorld) -> bool {
+    rule.build().expect("test rule builds").evaluate(facts)
+}
+
+fn moored() -> Condition {
+    Condition::BoolEquals {
+        fact_name: "moored".to_string(),
+        expected_value: true,
+    }
+}
+
+fn crane() -> Condition {
+    Condition::BoolEquals {
+        fact_name: "crane".to_string(),
+        expected_value: true,
+    }
+}
+
+#[test]
+fn not_any_and_all_combine_conditions() {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_bool("moored".to_string(), true).unwrap();
+    facts.store_bool("crane".to_string(), false).unwrap();
+
+    let any = |rule: ConditionBuilder| rule.with_condition(moored()).with_condition(crane());
+    assert!(holds(RuleBuilder::new("either").any(any), &facts));
+    assert!(!holds(RuleBuilder::new("both").all(any), &facts));
+    // `not` of several conditions holds when they are not all true
+    assert!(holds(RuleBuilder::new("not both").not(any), &facts));
+    assert!(!holds(
+        RuleBuilder::new("not moored").not(|rule| rule.with_condition(moored())),
+        &facts
+    ));
+}
+
+#[test]
+fn list_lengths_are_compared_against_an_int_fact() {
+    let rule = || {
+        RuleBuilder::new("full crew").with_condition(Condition::ListLenEqualsFact {
+            list_fact: "crew".to_string(),
+            int_fact: "berths".to_string(),
+        })
+    };
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_int("berths".to_string(), 2).unwrap();
+    assert!(!holds(rule(), &facts));
+
+    facts
+        .add_to_list("crew".to_string(), "Ada".to_string())
+        .unwrap();
+    assert!(!holds(rule(), &facts));
+    facts
+        .add_to_list("crew".to_string(), "Bo".to_string())
+        .unwrap();
+    assert!(holds(rule(), &facts));
+}
+
+#[test]
+fn string_conditions_share_their_expected_value() {
+    let expected = |condition: &Condition| match condition {
+        Condition::StringEquals { expected_value, .. } => expected_value.clone(),
+        _ => unreachable!(),
+    };
+    let condition = |fact_name: &str| Condition::StringEquals {
+        fact_name: fact_name.to_string(),
+        expected_value: "storm".into(),
+    };
+    let (first, second) = (condition("weather"), condition("forecast"));
+    assert!(std::ptr::eq(
+        expected(&first).as_str(),
+        expected(&second).as_str()
+    ));
+    assert_eq!(expected(&first), Interned::new("storm"));
+
+    let mut facts = FactsOfTheWorld::new();
+    facts
+        .store_string("weather".to_string(), "storm".to_string())
+        .unwrap();
+    assert!(first.evaluate(&facts));
+    assert!(!second.evaluate(&facts));
+}
+
+#[test]
+fn costs_order_conditions_cheapest_first() {
+    let rule = RuleBuilder::new("unloading")
+        .with_conditions(|rule| {
+            rule.with_condition(Condition::Script("crates > 2".to_string()))
+                .with_cost(1, |rule| {
+                    rule.with_condition(Condition::Script("crane".to_string()))
+                })
+                .all(|rule| rule.with_condition(moored()).with_condition(crane()))
+        })
+        .build()
+        .expect("test rule builds");
+    let costs: Vec<u32> = rule.conditions.iter().map(Condition::cost).collect();
+    // Scripts are the most expensive, unless given a cost, and `all` costs what it holds
+    assert_eq!(costs, vec![100, 1, 2]);
+
+    // Running them in another order than authored doesn't change the outcome
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_bool("moored".to_string(), true).unwrap();
+    facts.store_bool("crane".to_string(), false).unwrap();
+    let any = Condition::Any(rule.conditions.to_vec());
+    assert!(!any.evaluate(&facts));
+    facts.store_bool("crane".to_string(), true).unwrap();
+    assert!(any.evaluate(&facts));
+}
+
+#[test]
+fn evaluation_uses_the_clock_and_rng_it_is_given() {
+    let mut facts = FactsOfTheWorld::new();
+    facts.alias("docked", "moored");
+    facts.store_bool("moored".to_string(), true).unwrap();
+    let mut rng = StoryRng::new(7);
+    let context = EvaluationContext::new(42.5, &mut rng, &facts);
+    assert_eq!(context.now, 42.5);
+    assert_eq!(
+        context.fact("docked"),
+        Some(&Fact::Bool("moored".to_string(), true))
+    );
+    assert_eq!(context.rng.clone(), StoryRng::new(7));
+    assert_eq!(EvaluationContext::detached(&facts, &mut rng).now, 0.0);
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn scripts_compare_against_the_given_clock() {
+    let facts = FactsOfTheWorld::new();
+    let late = Condition::Script("now > 30.0".to_string());
+    let mut rng = StoryRng::new(1);
+    assert!(late.evaluate_with(&mut EvaluationContext::new(42.5, &mut rng, &facts)));
+    assert!(!late.evaluate_with(&mut EvaluationContext::new(12.0, &mut rng, &facts)));
+    // Outside of story progression the clock reads zero
+    assert!(!late.evaluate(&facts));
+}
@@ -0,0 +1,88 @@
+// The event-sourced store: undo puts a fact back the way the rest of the log has it and reports
+// the change, and compaction folds the log into a snapshot undo can't reach past.
+use barnacle_beats::prelude::*;
+
+#[test]
+fn undo_reports_the_earlier_value() {
+    let mut store = EventSourcedFactStore::new();
+    store.store_int("lamps.lit".to_string(), 1).unwrap();
+    store.store_int("lamps.lit".to_string(), 2).unwrap();
+    store.drain_updated();
+    let revision = store.revision();
+
+    assert_eq!(store.undo(), Some(FactMutation::StoreInt("lamps.lit".to_string(), 2)));
+    assert_eq!(store.get_int("lamps.lit"), Some(&1));
+    assert!(store.revision() > revision);
+    let updated = store.drain_updated();
+    assert_eq!(updated.len(), 1);
+    assert_eq!(updated[0].fact, Fact::Int("lamps.lit".to_string(), 1));
+    assert_eq!(updated[0].previous, Some(Fact::Int("lamps.lit".to_string(), 2)));
+}
+
+#[test]
+fn undoing_the_write_that_created_a_fact_removes_it() {
+    let mut store = EventSourcedFactStore::new();
+    store.store_bool("gate.open".to_string(), true).unwrap();
+    store.drain_updated();
+
+    assert!(store.undo().is_some());
+    assert_eq!(store.get_bool("gate.open"), None);
+    assert!(store.drain_updated().is_empty());
+    assert_eq!(
+        store.drain_removed(),
+        vec![FactRemoved {
+            previous: Fact::Bool("gate.open".to_string(), true)
+        }]
+    );
+}
+
+#[test]
+fn list_edits_are_undone_one_at_a_time() {
+    let mut store = EventSourcedFactStore::new();
+    store.add_to_list("cargo".to_string(), "rope".to_string()).unwrap();
+    store.add_to_list("cargo".to_string(), "tar".to_string()).unwrap();
+    store.undo();
+    let cargo = store.get_list("cargo").unwrap();
+    assert!(cargo.contains(&"rope".to_string()));
+    assert!(!cargo.contains(&"tar".to_string()));
+}
+
+#[test]
+fn undo_stops_at_the_last_compaction() {
+    let mut store = EventSourcedFactStore::with_compaction_threshold(2);
+    store.store_int("tide".to_string(), 1).unwrap();
+    store.store_int("tide".to_string(), 2).unwrap();
+    assert_eq!(store.log().len(), 2);
+    // The third write goes past the threshold and folds everything into the snapshot
+    store.store_int("tide".to_string(), 3).unwrap();
+    assert!(store.log().is_empty());
+    assert_eq!(store.undo(), None);
+    assert_eq!(store.get_int("tide"), Some(&3));
+
+    store.store_int("tide".to_string(), 4).unwrap();
+    store.undo();
+    assert_eq!(store.get_int("tide"), Some(&3));
+}
+
+#[test]
+fn replaying_the_log_rebuilds_the_facts() {
+    let mut store = EventSourcedFactStore::new();
+    for value in 0..5 {
+        store.store_int(format!("bell.{}", value % 2), value).unwrap();
+    }
+    store.add_to_list("keepers".to_string(), "Ada".to_string()).unwrap();
+    // Refused writes never make it into the log
+    assert!(store.store_bool("bell.0".to_string(), true).is_err());
+    let replayed = EventSourcedFactStore::replay(&Default::default(), store.log());
+    assert_eq!(&replayed.facts, store.facts());
+}
+
+#[test]
+fn constants_survive_undo() {
+    let mut store = EventSourcedFactStore::new();
+    store.store_constant(Fact::Int("harbour.berths".to_string(), 4));
+    store.store_int("harbour.boats".to_string(), 1).unwrap();
+    store.undo();
+    assert_eq!(store.get_int("harbour.berths"), Some(&4));
+    assert!(store.store_int("harbour.berths".to_string(), 5).is_err());
+}
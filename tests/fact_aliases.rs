@@ -0,0 +1,107 @@
+// Renamed facts keep working under their old names: reads, writes and story conditions all
+// follow aliases, and stories using an old name are pointed at the new one once.
+use barnacle_beats::prelude::*;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::{App, EventReader, ResMut, Resource, Update};
+
+fn renamed() -> FactsOfTheWorld {
+    let mut facts = FactsOfTheWorld::new();
+    facts.alias("gold", "purse.gold");
+    facts
+}
+
+#[test]
+fn reads_and_writes_follow_aliases() {
+    let mut facts = renamed();
+    facts.store_int("gold".to_string(), 12).unwrap();
+    assert_eq!(facts.get_int("purse.gold"), Some(&12));
+    assert_eq!(facts.get_int("gold"), Some(&12));
+    assert!(facts.facts.get("gold").is_none());
+    assert_eq!(
+        facts.drain_alias_hits(),
+        vec![FactAliasUsed {
+            alias: "gold".to_string(),
+            target: "purse.gold".to_string()
+        }]
+    );
+}
+
+#[test]
+fn conditions_evaluate_through_aliases() {
+    let mut facts = renamed();
+    facts.store_int("purse.gold".to_string(), 12).unwrap();
+    let rich = Condition::IntMoreThan {
+        fact_name: "gold".to_string(),
+        expected_value: 10,
+    };
+    assert!(rich.evaluate(&facts));
+    let rule = Rule::new("rich".to_string(), vec![rich]);
+    assert!(rule.evaluate(&facts));
+}
+
+#[test]
+fn fact_query_follows_aliases() {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins);
+    {
+        let mut facts = app.world.resource_mut::<FactsOfTheWorld>();
+        facts.alias("gold", "purse.gold");
+        facts.store_int("purse.gold".to_string(), 3).unwrap();
+    }
+    let found = app
+        .world
+        .run_system_once(|facts: FactQuery| facts.get("gold").cloned());
+    assert_eq!(found, Some(Fact::Int("purse.gold".to_string(), 3)));
+}
+
+#[derive(Resource, Default)]
+struct Reported(Vec<FactAliasUsed>);
+
+fn collect_reported(mut events: EventReader<FactAliasUsed>, mut reported: ResMut<Reported>) {
+    reported.0.extend(events.read().cloned());
+}
+
+#[test]
+fn stories_are_pointed_at_the_new_names_once() {
+    let story = StoryBuilder::new("tavern")
+        .add_story_beat("pay", |beat| {
+            beat.with_rule("paid", |rule| {
+                rule.with_condition(Condition::IntMoreThan {
+                    fact_name: "gold".to_string(),
+                    expected_value: 5,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds");
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .init_resource::<Reported>()
+        .add_systems(Update, collect_reported);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .alias("gold", "purse.gold");
+    app.world.resource_mut::<StoryEngine>().add_story(story);
+    app.update();
+    app.update();
+    app.update();
+
+    let engine = app.world.resource::<StoryEngine>();
+    assert!(!engine.aliases_pending);
+    let rule = &engine.stories[0].beats[0].rules[0];
+    assert_eq!(rule.conditions[0].fact_names(), vec!["purse.gold"]);
+    assert_eq!(
+        app.world.resource::<Reported>().0,
+        vec![FactAliasUsed {
+            alias: "gold".to_string(),
+            target: "purse.gold".to_string()
+        }]
+    );
+
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("purse.gold".to_string(), 6)
+        .unwrap();
+    app.update();
+    assert!(app.world.resource::<StoryEngine>().stories[0].beats[0].finished);
+}
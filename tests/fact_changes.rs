@@ -0,0 +1,60 @@
+// The fact store only counts as changed for Bevy change detection when a fact was actually
+// written, so systems can skip work with `Res::is_changed` or FactQuery instead of events.
+use barnacle_beats::prelude::*;
+use bevy::prelude::{App, IntoSystemConfigs, Res, ResMut, Resource, Update};
+
+#[derive(Resource, Default)]
+struct Seen {
+    changed: Vec<bool>,
+}
+
+#[derive(Resource, Default)]
+struct Tide(i32);
+
+fn watch(facts: FactQuery, mut seen: ResMut<Seen>) {
+    seen.changed.push(facts.is_changed());
+}
+
+fn write_tide(tide: Res<Tide>, mut facts: ResMut<FactsOfTheWorld>) {
+    write_facts(&mut facts, |facts| facts.store_int("tide".to_string(), tide.0).unwrap());
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .init_resource::<Seen>()
+        .init_resource::<Tide>()
+        .add_systems(Update, (write_tide, watch).chain());
+    app.update();
+    app.update();
+    app.world.resource_mut::<Seen>().changed.clear();
+    app
+}
+
+#[test]
+fn rewriting_a_value_is_not_a_change() {
+    let mut app = app();
+    app.update();
+    app.update();
+    assert_eq!(app.world.resource::<Seen>().changed, vec![false, false]);
+}
+
+#[test]
+fn a_new_value_is_seen_once() {
+    let mut app = app();
+    app.world.resource_mut::<Tide>().0 = 4;
+    app.update();
+    app.update();
+    assert_eq!(app.world.resource::<Seen>().changed, vec![true, false]);
+    assert_eq!(app.world.resource::<FactsOfTheWorld>().get_int("tide"), Some(&4));
+}
+
+#[test]
+fn draining_updates_is_not_a_change() {
+    let mut app = app();
+    let before = app.world.resource::<FactsOfTheWorld>().revision();
+    // The broadcaster drains the store every frame
+    app.update();
+    assert_eq!(app.world.resource::<FactsOfTheWorld>().revision(), before);
+    assert_eq!(app.world.resource::<Seen>().changed, vec![false]);
+}
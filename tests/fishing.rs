@@ -0,0 +1,64 @@
+// Reels are judged by how far they land from the nearest beat, beats that go by without a reel
+// count as misses, and enough perfect reels land a better fish.
+use barnacle_beats::fishing::{Conductor, FishingRun, FishingSettings, Judgment, FISH_GOT_AWAY};
+use barnacle_beats::prelude::*;
+
+// 60 bpm, so beat n falls on second n
+fn run() -> FishingRun {
+    FishingRun::new(Conductor {
+        bpm: 60.,
+        started_at: 0.,
+    })
+}
+
+#[test]
+fn reels_are_judged_against_the_nearest_beat() {
+    let settings = FishingSettings::default();
+    let mut run = run();
+    assert_eq!(run.reel(2.02, &settings), Judgment::Perfect);
+    assert_eq!(
+        run.reel(2.05, &settings),
+        Judgment::Miss,
+        "twice on one beat"
+    );
+    assert_eq!(run.reel(2.9, &settings), Judgment::Good);
+    assert_eq!(run.reel(3.5, &settings), Judgment::Miss);
+    assert_eq!((run.perfect, run.good, run.misses), (1, 1, 2));
+}
+
+#[test]
+fn the_lead_in_does_not_count() {
+    let settings = FishingSettings::default();
+    let mut run = run();
+    run.pass_time(1.9, &settings);
+    assert_eq!(run.misses, 0);
+    assert_eq!(run.reel(1.0, &settings), Judgment::Miss);
+    run.pass_time(4.5, &settings);
+    assert_eq!(run.misses, 4, "the early reel and beats 2 to 4");
+    assert_eq!(run.outcome(&settings), Some(FISH_GOT_AWAY));
+}
+
+#[test]
+fn perfect_reels_land_a_better_fish() {
+    let settings = FishingSettings::default();
+    let mut run = run();
+    for beat in 2..10 {
+        assert_eq!(run.outcome(&settings), None);
+        run.reel(beat as f64, &settings);
+        run.pass_time(beat as f64 + 0.5, &settings);
+    }
+    assert_eq!(run.outcome(&settings), Some("fish.marlin"));
+
+    let mut run = run_with_good_reels(&settings);
+    assert_eq!(run.outcome(&settings), Some("fish.sprat"));
+    run.misses = settings.misses_allowed + 1;
+    assert_eq!(run.outcome(&settings), Some(FISH_GOT_AWAY));
+}
+
+fn run_with_good_reels(settings: &FishingSettings) -> FishingRun {
+    let mut run = run();
+    for beat in 2..10 {
+        assert_eq!(run.reel(beat as f64 + 0.1, settings), Judgment::Good);
+    }
+    run
+}
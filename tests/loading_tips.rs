@@ -0,0 +1,40 @@
+// Loading tips without conditions are always shown, the others only once the facts they
+// check are there.
+use barnacle_beats::loading::LoadingTips;
+use barnacle_beats::prelude::*;
+use bevy::utils::hashbrown::HashMap;
+
+fn bundled_tips() -> LoadingTips {
+    ron::from_str(include_str!("../assets/tips.ron")).expect("assets/tips.ron parses")
+}
+
+fn facts(facts: Vec<Fact>) -> HashMap<String, Fact> {
+    facts
+        .into_iter()
+        .map(|fact| (fact.key().to_string(), fact))
+        .collect()
+}
+
+#[test]
+fn first_run_only_gets_unconditional_tips() {
+    let tips = bundled_tips();
+    let unlocked = tips.unlocked(&HashMap::new());
+    assert!(!unlocked.is_empty());
+    assert!(unlocked
+        .iter()
+        .all(|index| tips.tips[*index].conditions.is_empty()));
+}
+
+#[test]
+fn unlocked_facts_add_their_tips() {
+    let tips = bundled_tips();
+    let before = tips.unlocked(&HashMap::new()).len();
+    let after = tips
+        .unlocked(&facts(vec![
+            Fact::Bool("heard_of_barnacle".to_string(), true),
+            Fact::Int("karma".to_string(), 3),
+        ]))
+        .len();
+    assert_eq!(after, tips.tips.len());
+    assert!(after > before);
+}
@@ -0,0 +1,66 @@
+// Pins only show up for discovered locations, and locked ones stay on the map without being
+// reachable until their conditions pass.
+use barnacle_beats::map::{discovered_fact, travel, WorldMap, TRAVEL_FACT};
+use barnacle_beats::prelude::*;
+
+fn bundled_map() -> WorldMap {
+    ron::from_str(include_str!("../assets/map.ron")).expect("assets/map.ron parses")
+}
+
+fn discovered_ids(map: &WorldMap, facts: &FactsOfTheWorld) -> Vec<String> {
+    map.discovered(facts)
+        .map(|location| location.id.clone())
+        .collect()
+}
+
+#[test]
+fn undiscovered_locations_have_no_pin() {
+    let map = bundled_map();
+    let mut facts = FactsOfTheWorld::new();
+    assert!(discovered_ids(&map, &facts).is_empty());
+
+    facts.store_bool(discovered_fact("harbour"), true).unwrap();
+    facts.store_bool(discovered_fact("lighthouse"), false).unwrap();
+    assert_eq!(discovered_ids(&map, &facts), vec!["harbour"]);
+}
+
+#[test]
+fn locked_locations_open_up_with_their_facts() {
+    let map = bundled_map();
+    let mut facts = FactsOfTheWorld::new();
+    let lighthouse = map
+        .find("lighthouse")
+        .expect("the lighthouse is on the map");
+    assert!(!lighthouse.is_reachable(&facts));
+
+    facts.store_bool("heard_of_barnacle".to_string(), true).unwrap();
+    assert!(lighthouse.is_reachable(&facts));
+}
+
+#[test]
+fn travelling_moves_the_player_and_the_clock() {
+    let map = bundled_map();
+    let mut facts = FactsOfTheWorld::new();
+    let mut rng = StoryRng::new(1);
+    let mut story_time = StoryTime::default();
+    story_time.pause();
+
+    let harbour = map.find("harbour").expect("the harbour is on the map");
+    let outputs = travel(harbour, &mut facts, &mut rng, &mut story_time);
+    assert_eq!(facts.get_string(TRAVEL_FACT), Some(&"harbour".to_string()));
+    assert_eq!(facts.get_bool(&discovered_fact("harbour")), Some(&true));
+    assert!(outputs.iter().any(
+        |output| matches!(output, EffectOutput::ChangeScene { level, .. } if level == "harbour")
+    ));
+
+    // Travel time passes even though the clock is paused
+    let lighthouse = map
+        .find("lighthouse")
+        .expect("the lighthouse is on the map");
+    travel(lighthouse, &mut facts, &mut rng, &mut story_time);
+    assert_eq!(story_time.elapsed_seconds(), lighthouse.travel_time);
+    assert_eq!(
+        facts.get_string(TRAVEL_FACT),
+        Some(&"lighthouse".to_string())
+    );
+}
@@ -0,0 +1,51 @@
+// A story effect leaves for a registered mini-game, and when the mini-game finishes its result
+// is written to `minigame.<id>.result` before the game goes back to the story.
+use barnacle_beats::mini_games::{
+    mini_game_fact, mini_game_result_fact, MiniGameAppExt, MiniGameFinished, MiniGamesPlugin,
+};
+use barnacle_beats::prelude::*;
+use bevy::prelude::{App, Events, State};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalStoryPlugins, MiniGamesPlugin))
+        .register_mini_game("fishing");
+    app.update();
+    app
+}
+
+fn state(app: &App) -> GameState {
+    app.world.resource::<State<GameState>>().get().clone()
+}
+
+fn start(app: &mut App, id: &str) {
+    app.world
+        .resource_mut::<Events<EffectOutput>>()
+        .send(EffectOutput::StartMiniGame(id.to_string()));
+    app.update();
+    app.update();
+}
+
+#[test]
+fn results_are_written_and_the_story_resumes() {
+    let mut app = app();
+    start(&mut app, "fishing");
+    assert_eq!(state(&app), GameState::MiniGame("fishing".to_string()));
+
+    app.world
+        .resource_mut::<Events<MiniGameFinished>>()
+        .send(MiniGameFinished::new("fishing", FactValue::Int(3)));
+    app.update();
+    app.update();
+    assert_eq!(state(&app), GameState::Story);
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_int(&mini_game_result_fact("fishing")), Some(&3));
+    assert_eq!(facts.get_int(&mini_game_fact("fishing", "plays")), Some(&1));
+}
+
+#[test]
+fn unregistered_mini_games_are_not_started() {
+    let mut app = app();
+    start(&mut app, "chess");
+    assert_eq!(state(&app), GameState::Story);
+}
@@ -0,0 +1,134 @@
+// Packs merge on top of the game's content in load order. Replacing something the game or an
+// earlier pack added is reported as a conflict, and a theme only swaps the parts it sets.
+// Dependencies load before the packs needing them, and packs whose dependencies can't be met
+// are left out.
+use barnacle_beats::mods::{
+    order_packs, parse_mod_index, ModConflict, ModTheme, Mods, PackManifest, PackVersion,
+    BASE_CONTENT,
+};
+use barnacle_beats::prelude::*;
+use barnacle_beats::ui::theme::{Palette, UiTheme};
+
+fn version(version: &str) -> PackVersion {
+    version.parse().expect("test version parses")
+}
+
+fn ids(manifests: &[PackManifest], order: &[usize]) -> Vec<String> {
+    order
+        .iter()
+        .map(|index| manifests[*index].id.clone())
+        .collect()
+}
+
+#[test]
+fn the_bundled_index_loads_no_packs() {
+    let index = parse_mod_index(include_str!("../mods/mods.ron")).expect("mods.ron parses");
+    assert!(index.packs.is_empty());
+}
+
+#[test]
+fn replacing_content_is_a_conflict() {
+    let mut mods = Mods::default();
+    assert!(mods
+        .claim(BASE_CONTENT, "story Lost Barnacle".to_string())
+        .is_none());
+    assert!(mods
+        .claim("harbour_tales", "story Smugglers".to_string())
+        .is_none());
+    assert!(mods
+        .claim("harbour_tales", "story Smugglers".to_string())
+        .is_none());
+
+    let conflict = mods
+        .claim("deep_sea", "story Lost Barnacle".to_string())
+        .cloned();
+    assert_eq!(
+        conflict,
+        Some(ModConflict {
+            pack: "deep_sea".to_string(),
+            content: "story Lost Barnacle".to_string(),
+            replaced: BASE_CONTENT.to_string(),
+        })
+    );
+    mods.claim("more_fish", "story Lost Barnacle".to_string());
+    assert_eq!(mods.conflicts.len(), 2);
+    assert_eq!(
+        mods.conflicts[1].to_string(),
+        "Mod more_fish replaces story Lost Barnacle from deep_sea"
+    );
+}
+
+#[test]
+fn themes_only_swap_what_they_set() {
+    let mut theme = UiTheme::default();
+    let pack_theme = ModTheme {
+        protanopia: Some(Palette::STANDARD),
+        ..Default::default()
+    };
+    assert_eq!(pack_theme.apply(&mut theme), vec!["protanopia"]);
+    assert_eq!(theme.protanopia, Palette::STANDARD);
+    assert_eq!(theme.dyslexia_font, UiTheme::default().dyslexia_font);
+}
+
+#[test]
+fn versions_compare_part_by_part() {
+    assert_eq!(version("1.2"), version("1.2.0"));
+    assert!(version("1.10") > version("1.9.3"));
+    assert!("1.x".parse::<PackVersion>().is_err());
+    assert!("1.2.3.4".parse::<PackVersion>().is_err());
+}
+
+#[test]
+fn dependencies_load_first_then_by_load_order() {
+    let manifests = vec![
+        PackManifest::new("smugglers_cove", version("1.0"))
+            .depending_on("harbour_tales", version("1.2")),
+        PackManifest::new("harbour_tales", version("1.3")).with_load_order(50),
+        PackManifest::new("chapter_two", version("1.0")).with_load_order(-10),
+        PackManifest::new("more_fish", version("0.1")),
+    ];
+    let (order, rejected) = order_packs(&manifests);
+    assert!(rejected.is_empty());
+    assert_eq!(
+        ids(&manifests, &order),
+        vec![
+            "chapter_two",
+            "more_fish",
+            "harbour_tales",
+            "smugglers_cove"
+        ]
+    );
+}
+
+#[test]
+fn packs_with_unmet_dependencies_are_left_out() {
+    let manifests = vec![
+        PackManifest::new("harbour_tales", version("1.1")),
+        PackManifest::new("smugglers_cove", version("1.0"))
+            .depending_on("harbour_tales", version("1.2")),
+        PackManifest::new("smuggler_skins", version("1.0"))
+            .depending_on("smugglers_cove", version("1.0")),
+        PackManifest::new("harbour_tales", version("2.0")),
+        PackManifest::new("tide", version("1.0")).depending_on("moon", version("1.0")),
+        PackManifest::new("moon", version("1.0")).depending_on("tide", version("1.0")),
+    ];
+    let (order, rejected) = order_packs(&manifests);
+    assert_eq!(ids(&manifests, &order), vec!["harbour_tales"]);
+    assert_eq!(
+        rejected
+            .iter()
+            .map(|rejection| rejection.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "Mod harbour_tales is not loaded: another pack already uses this id",
+            "Mod smugglers_cove is not loaded: needs harbour_tales 1.2.0 or newer, found 1.1.0",
+            "Mod smuggler_skins is not loaded: needs smugglers_cove, which isn't loaded",
+            "Mod tide is not loaded: its dependencies go round in a circle",
+            "Mod moon is not loaded: its dependencies go round in a circle",
+        ]
+    );
+    assert!(PackManifest::new("tide", version("1.0"))
+        .depending_on("tide", version("1.0"))
+        .validate()
+        .is_err());
+}
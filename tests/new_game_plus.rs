@@ -0,0 +1,32 @@
+// New Game Plus clears the reached ending so the next run can end again, counts the cycle and
+// rolls the new run from the seed shown in the menu.
+use barnacle_beats::endings::ENDING_FACT;
+use barnacle_beats::prelude::*;
+use bevy::prelude::App;
+
+#[test]
+fn new_game_plus_starts_a_fresh_seeded_run() {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins);
+    app.update();
+    {
+        let mut facts = app.world.resource_mut::<FactsOfTheWorld>();
+        facts
+            .store_string(ENDING_FACT.to_string(), "saint".to_string())
+            .unwrap();
+        facts.store_int("gold".to_string(), 12).unwrap();
+    }
+    app.insert_resource(RunSeed::new("barnacle"));
+    app.world.resource_mut::<StoryRng>().next_u64();
+    app.world.send_event(StartNewGamePlus);
+    app.update();
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_string(ENDING_FACT), Some(&String::new()));
+    assert_eq!(facts.get_int("gold"), Some(&0));
+    assert_eq!(facts.get_int("new_game_plus"), Some(&1));
+    assert_eq!(
+        *app.world.resource::<StoryRng>(),
+        RunSeed::new("barnacle").rng()
+    );
+}
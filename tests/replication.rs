@@ -0,0 +1,342 @@
+// Clients mirror what the host sends without evaluating stories themselves, while the facts
+// they receive are still drained and reported like any other change. The host checks every
+// mutation a client requests and answers the ones it refuses with a denial.
+#![cfg(feature = "net")]
+use barnacle_beats::prelude::*;
+use bevy::prelude::{App, EventReader, ResMut, Resource, Update};
+
+#[derive(Resource, Default)]
+struct Reported(Vec<FactUpdated>);
+
+fn collect_reported(mut events: EventReader<FactUpdated>, mut reported: ResMut<Reported>) {
+    reported.0.extend(events.read().cloned());
+}
+
+fn lighthouse() -> Story {
+    StoryBuilder::new("lighthouse")
+        .add_story_beat("lit", |beat| {
+            beat.with_rule("lamp on", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "lamp".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+fn app(role: NetworkRole) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .insert_resource(role)
+        .init_resource::<Reported>()
+        .add_systems(Update, collect_reported);
+    app.world.resource_mut::<StoryEngine>().add_story(lighthouse());
+    app.update();
+    app
+}
+
+#[test]
+fn clients_drain_replicated_facts_without_evaluating() {
+    let mut app = app(NetworkRole::Client);
+    app.world
+        .resource_mut::<ReplicationInbox>()
+        .receive(
+            PeerId::HOST,
+            ReplicationMessage::FactChanged(Fact::Bool("lamp".to_string(), true)),
+        );
+    app.update();
+    app.update();
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_bool("lamp"), Some(&true));
+    assert!(!facts.has_updates());
+    let lamp_updates = app
+        .world
+        .resource::<Reported>()
+        .0
+        .iter()
+        .filter(|updated| updated.fact.key() == "lamp")
+        .count();
+    assert_eq!(lamp_updates, 1);
+    // Only the host moves stories on
+    let story = &app.world.resource::<StoryEngine>().stories[0];
+    assert_eq!(story.active_beat_index, 0);
+}
+
+#[test]
+fn hosts_evaluate_and_broadcast() {
+    let mut app = app(NetworkRole::Host);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("lamp".to_string(), true)
+        .unwrap();
+    app.update();
+    app.update();
+
+    let story = &app.world.resource::<StoryEngine>().stories[0];
+    assert!(story.is_finished());
+    let outbox = &app.world.resource::<ReplicationOutbox>().0;
+    assert!(outbox.contains(&(
+        Recipient::Everyone,
+        ReplicationMessage::FactChanged(Fact::Bool("lamp".to_string(), true))
+    )));
+}
+
+fn request(app: &mut App, mutation: FactMutation) {
+    app.world
+        .resource_mut::<ReplicationInbox>()
+        .receive(PeerId(7), ReplicationMessage::RequestMutation(mutation));
+    app.update();
+}
+
+fn denials(app: &App) -> Vec<&str> {
+    app.world
+        .resource::<ReplicationOutbox>()
+        .0
+        .iter()
+        .filter_map(|(recipient, message)| match message {
+            ReplicationMessage::MutationDenied { mutation, .. } => {
+                assert_eq!(recipient, &Recipient::Peer(PeerId(7)));
+                Some(mutation.key())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn hosts_apply_requested_mutations() {
+    let mut app = app(NetworkRole::Host);
+    request(
+        &mut app,
+        FactMutation::StoreBool("lamp".to_string(), true),
+    );
+    app.update();
+
+    assert_eq!(
+        app.world.resource::<FactsOfTheWorld>().get_bool("lamp"),
+        Some(&true)
+    );
+    assert!(app.world.resource::<StoryEngine>().stories[0].is_finished());
+    assert!(denials(&app).is_empty());
+}
+
+#[test]
+fn hosts_deny_constant_reserved_and_mistyped_requests() {
+    let mut app = app(NetworkRole::Host);
+    {
+        let mut facts = app.world.resource_mut::<FactsOfTheWorld>();
+        facts.store_constant(Fact::Int("lamp.wicks".to_string(), 3));
+        facts.store_int("oil".to_string(), 5).unwrap();
+    }
+    request(
+        &mut app,
+        FactMutation::StoreInt("lamp.wicks".to_string(), 9),
+    );
+    request(
+        &mut app,
+        FactMutation::StoreBool("engine.paused".to_string(), true),
+    );
+    request(&mut app, FactMutation::StoreBool("__debug".to_string(), true));
+    request(
+        &mut app,
+        FactMutation::AddToList("oil".to_string(), "whale".to_string()),
+    );
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_int("lamp.wicks"), Some(&3));
+    assert_eq!(facts.get_int("oil"), Some(&5));
+    assert_eq!(facts.get_bool("engine.paused"), None);
+    assert_eq!(facts.get_bool("__debug"), None);
+    assert_eq!(
+        denials(&app),
+        vec!["lamp.wicks", "engine.paused", "__debug", "oil"]
+    );
+}
+
+#[derive(Resource, Default)]
+struct Denied(Vec<MutationRequestDenied>);
+
+fn collect_denied(mut events: EventReader<MutationRequestDenied>, mut denied: ResMut<Denied>) {
+    denied.0.extend(events.read().cloned());
+}
+
+#[test]
+fn clients_report_denials() {
+    let mut app = app(NetworkRole::Client);
+    app.init_resource::<Denied>()
+        .add_systems(Update, collect_denied);
+    let mutation = FactMutation::StoreBool("engine.paused".to_string(), true);
+    app.world.resource_mut::<ReplicationInbox>().receive(
+        PeerId::HOST,
+        ReplicationMessage::MutationDenied {
+            mutation: mutation.clone(),
+            reason: "fact engine.paused is reserved for the engine".to_string(),
+        },
+    );
+    app.update();
+    app.update();
+
+    let denied = &app.world.resource::<Denied>().0;
+    assert_eq!(denied.len(), 1);
+    assert_eq!(denied[0].mutation, mutation);
+}
+
+#[test]
+fn hosts_send_joining_peers_a_snapshot() {
+    let mut app = app(NetworkRole::Host);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("oil".to_string(), 5)
+        .unwrap();
+    app.update();
+    app.world.resource_mut::<ReplicationOutbox>().0.clear();
+    app.world.send_event(PeerConnected(PeerId(3)));
+    app.update();
+
+    let outbox = &app.world.resource::<ReplicationOutbox>().0;
+    let peer = Recipient::Peer(PeerId(3));
+    assert!(outbox.contains(&(
+        peer,
+        ReplicationMessage::FactChanged(Fact::Int("oil".to_string(), 5))
+    )));
+    assert!(outbox.contains(&(
+        peer,
+        ReplicationMessage::StoryProgress(StoryProgress {
+            name: "lighthouse".to_string(),
+            version: 1,
+            is_started: true,
+            active_beat_index: 0,
+            active_beat: Some("lit".to_string()),
+            finished_beats: Some(Vec::new()),
+        })
+    )));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn tcp_transport_carries_requests_and_facts() {
+    let mut host = app(NetworkRole::Host);
+    let transport = TcpTransport::host("127.0.0.1:0").expect("host binds");
+    let address = transport.local_addr().expect("host has an address");
+    host.insert_resource(transport);
+    let mut client = app(NetworkRole::Client);
+    client.insert_resource(TcpTransport::connect(address).expect("client connects"));
+
+    let mut requested = false;
+    for _ in 0..200 {
+        host.update();
+        client.update();
+        let joined = host.world.resource::<TcpTransport>().peers().count() == 1;
+        if joined && !requested {
+            client
+                .world
+                .resource_mut::<ReplicationOutbox>()
+                .request(FactMutation::StoreBool("lamp".to_string(), true));
+            requested = true;
+        }
+        if client.world.resource::<StoryEngine>().stories[0].is_finished() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+
+    assert_eq!(
+        host.world.resource::<FactsOfTheWorld>().get_bool("lamp"),
+        Some(&true)
+    );
+    assert_eq!(
+        client.world.resource::<FactsOfTheWorld>().get_bool("lamp"),
+        Some(&true)
+    );
+    assert!(client.world.resource::<StoryEngine>().stories[0].is_finished());
+}
+
+#[test]
+fn hosts_broadcast_removals_and_progress_after_a_load() {
+    let mut app = app(NetworkRole::Host);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("oil".to_string(), 5)
+        .unwrap();
+    app.update();
+    app.world.resource_mut::<ReplicationOutbox>().0.clear();
+
+    // A save from after the lamp was lit, without the oil
+    let save = SaveGame {
+        version: 1,
+        facts: Default::default(),
+        stories: vec![StoryProgress {
+            name: "lighthouse".to_string(),
+            version: 1,
+            is_started: true,
+            active_beat_index: 1,
+            active_beat: None,
+            finished_beats: Some(vec!["lit".to_string()]),
+        }],
+        rng: StoryRng::new(1),
+        time: StoryTime::default(),
+    };
+    {
+        let world = &mut app.world;
+        let mut facts = world.remove_resource::<FactsOfTheWorld>().unwrap();
+        let mut engine = world.remove_resource::<StoryEngine>().unwrap();
+        let mut rng = StoryRng::new(1);
+        let mut time = StoryTime::default();
+        save.clone().restore(&mut facts, &mut engine, &mut rng, &mut time);
+        world.insert_resource(facts);
+        world.insert_resource(engine);
+    }
+    app.update();
+    app.update();
+
+    let outbox = &app.world.resource::<ReplicationOutbox>().0;
+    assert!(outbox.contains(&(
+        Recipient::Everyone,
+        ReplicationMessage::FactRemoved("oil".to_string())
+    )));
+    assert!(outbox.contains(&(
+        Recipient::Everyone,
+        ReplicationMessage::StoryProgress(StoryProgress {
+            active_beat: None,
+            ..save.stories[0].clone()
+        })
+    )));
+}
+
+#[test]
+fn clients_take_the_hosts_facts_and_finished_beats() {
+    let mut app = app(NetworkRole::Client);
+    {
+        let mut facts = app.world.resource_mut::<FactsOfTheWorld>();
+        facts.store_int("oil".to_string(), 5).unwrap();
+        facts.store_bool("fog".to_string(), true).unwrap();
+    }
+    let mut inbox = app.world.resource_mut::<ReplicationInbox>();
+    inbox.receive(
+        PeerId::HOST,
+        ReplicationMessage::FactChanged(Fact::String("oil".to_string(), "whale".to_string())),
+    );
+    inbox.receive(PeerId::HOST, ReplicationMessage::FactRemoved("fog".to_string()));
+    inbox.receive(
+        PeerId::HOST,
+        ReplicationMessage::StoryProgress(StoryProgress {
+            name: "lighthouse".to_string(),
+            version: 1,
+            is_started: true,
+            active_beat_index: 1,
+            active_beat: None,
+            finished_beats: Some(vec!["lit".to_string()]),
+        }),
+    );
+    app.update();
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_string("oil"), Some(&"whale".to_string()));
+    assert_eq!(facts.get_bool("fog"), None);
+    let story = &app.world.resource::<StoryEngine>().stories[0];
+    assert!(story.beats[0].finished);
+    assert!(story.is_finished());
+}
@@ -0,0 +1,125 @@
+// Save files carry a checksum. A save that fails it is reported as corrupted and the previous
+// save, kept as a .bak when the file was last overwritten, is loaded in its place. Saves left
+// where older versions kept them are moved to the save folder when first loaded.
+use barnacle_beats::prelude::*;
+use bevy::prelude::App;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn save_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("barnacle_beats_{}_{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn app(dir: &Path) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .insert_resource(SaveLocation {
+            path: Some(dir.display().to_string()),
+            legacy_path: None,
+            ..Default::default()
+        });
+    app.update();
+    app
+}
+
+fn save_with_gold(app: &mut App, gold: i32) {
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("gold".to_string(), gold)
+        .unwrap();
+    app.world.send_event(SaveGameRequest);
+    app.update();
+}
+
+fn load(app: &mut App) {
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_int("gold".to_string(), 99)
+        .unwrap();
+    app.world.send_event(LoadGameRequest);
+    app.update();
+    // The error log may only pick the load errors up a frame later
+    app.update();
+}
+
+fn corrupt(dir: &Path) {
+    let path = dir.join("save.ron");
+    let mut bytes = fs::read(&path).expect("save was written");
+    bytes[0] ^= 0xff;
+    fs::write(&path, bytes).expect("save can be overwritten");
+}
+
+fn gold(app: &App) -> Option<i32> {
+    app.world
+        .resource::<FactsOfTheWorld>()
+        .get_int("gold")
+        .copied()
+}
+
+#[test]
+fn intact_saves_load() {
+    let dir = save_dir("intact");
+    let mut app = app(&dir);
+    save_with_gold(&mut app, 1);
+    load(&mut app);
+
+    assert_eq!(gold(&app), Some(1));
+    assert!(app.world.resource::<ErrorLog>().messages.is_empty());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn corrupted_saves_fall_back_to_the_backup() {
+    let dir = save_dir("backup");
+    let mut app = app(&dir);
+    save_with_gold(&mut app, 1);
+    save_with_gold(&mut app, 2);
+    assert!(dir.join("save.ron.bak").exists());
+    corrupt(&dir);
+    load(&mut app);
+
+    assert_eq!(gold(&app), Some(1));
+    let messages = &app.world.resource::<ErrorLog>().messages;
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].starts_with("Save save.ron is corrupted (save corrupted, checksum"));
+    assert!(messages[0].ends_with("loaded the previous save instead"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn corrupted_saves_without_a_backup_are_not_loaded() {
+    let dir = save_dir("no_backup");
+    let mut app = app(&dir);
+    save_with_gold(&mut app, 1);
+    corrupt(&dir);
+    load(&mut app);
+
+    assert_eq!(gold(&app), Some(99));
+    let messages = &app.world.resource::<ErrorLog>().messages;
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].starts_with("Could not load save save.ron: save corrupted, checksum"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn saves_in_the_legacy_location_are_moved_over() {
+    let legacy = save_dir("legacy");
+    let mut app = app(&legacy);
+    save_with_gold(&mut app, 3);
+    let dir = save_dir("moved");
+    app.insert_resource(SaveLocation {
+        path: Some(dir.display().to_string()),
+        legacy_path: Some(legacy.display().to_string()),
+        ..Default::default()
+    });
+    load(&mut app);
+
+    assert_eq!(gold(&app), Some(3));
+    assert!(dir.join("save.ron").exists());
+    assert!(!legacy.join("save.ron").exists());
+    assert!(app.world.resource::<ErrorLog>().messages.is_empty());
+    let _ = fs::remove_dir_all(&legacy);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,94 @@
+// Old saves are upgraded one registered step at a time until they reach the game's version.
+// Saves from a newer build, or with a gap or a step backwards in the chain, are refused.
+use barnacle_beats::prelude::*;
+
+fn save(version: u32) -> SaveGame {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_int("gold".to_string(), 10).unwrap();
+    SaveGame::capture(
+        version,
+        &facts,
+        &StoryEngine::new(),
+        &StoryRng::new(1),
+        &StoryTime::default(),
+    )
+}
+
+fn rename(save: &mut SaveGame, from: &str, to: &str) {
+    if let Some(mut fact) = save.facts.remove(from) {
+        *fact.key_mut() = to.to_string();
+        save.facts.insert(to.to_string(), fact);
+    }
+}
+
+fn at_version_3() -> SaveMigrations {
+    let mut migrations = SaveMigrations::default();
+    migrations.current_version = 3;
+    migrations
+}
+
+fn migrations() -> SaveMigrations {
+    let mut migrations = at_version_3();
+    migrations
+        .register_migration(1, 2, |save| rename(save, "gold", "coins"))
+        .register_migration(2, 3, |save| rename(save, "coins", "doubloons"));
+    migrations
+}
+
+#[test]
+fn old_saves_run_every_step_in_order() {
+    let mut old = save(1);
+    migrations().migrate(&mut old).expect("save migrates");
+
+    assert_eq!(old.version, 3);
+    assert_eq!(
+        old.facts.get("doubloons"),
+        Some(&Fact::Int("doubloons".to_string(), 10))
+    );
+    assert!(!old.facts.contains_key("gold"));
+    assert!(!old.facts.contains_key("coins"));
+}
+
+#[test]
+fn saves_halfway_along_only_run_the_remaining_steps() {
+    let mut halfway = save(2);
+    rename(&mut halfway, "gold", "coins");
+    migrations().migrate(&mut halfway).expect("save migrates");
+
+    assert_eq!(halfway.version, 3);
+    assert!(halfway.facts.contains_key("doubloons"));
+}
+
+#[test]
+fn current_saves_are_left_alone() {
+    let mut current = save(3);
+    migrations().migrate(&mut current).expect("save is current");
+
+    assert_eq!(current, save(3));
+}
+
+#[test]
+fn saves_from_a_newer_game_are_refused() {
+    let mut newer = save(4);
+    assert_eq!(
+        migrations().migrate(&mut newer),
+        Err("save version 4 is newer than this game's version 3".to_string())
+    );
+}
+
+#[test]
+fn gaps_and_steps_back_in_the_chain_are_refused() {
+    let mut gap = at_version_3();
+    gap.register_migration(1, 2, |_| {});
+    assert_eq!(
+        gap.migrate(&mut save(1)),
+        Err("no migration from save version 2 towards 3".to_string())
+    );
+
+    let mut backwards = at_version_3();
+    backwards.register_migration(1, 1, |_| {});
+    assert_eq!(
+        backwards.migrate(&mut save(1)),
+        Err("migration from save version 1 goes back to 1".to_string())
+    );
+}
@@ -0,0 +1,44 @@
+// Effect scripts write everything they assigned in one batch. A script that fails, or assigns a
+// fact it may not change, is reported as an error and leaves every fact as it was.
+use barnacle_beats::prelude::*;
+
+fn harbour() -> FactsOfTheWorld {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_constant(Fact::Int("berths".to_string(), 4));
+    facts.store_int("gold".to_string(), 1).unwrap();
+    facts.drain_updated();
+    facts
+}
+
+fn run(script: &str, facts: &mut FactsOfTheWorld) -> Result<Vec<EffectOutput>, FactError> {
+    Effect::Script(script.to_string()).apply(facts, &mut StoryRng::new(1))
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn scripts_write_all_or_nothing() {
+    let mut facts = harbour();
+    run("gold = 5; let cargo = \"fish\";", &mut facts).expect("script runs");
+    assert_eq!(facts.get_int("gold"), Some(&5));
+    assert_eq!(facts.get_string("cargo"), Some(&"fish".to_string()));
+
+    let denied = run("gold = 7; berths = 9;", &mut facts);
+    assert!(matches!(denied, Err(FactError::ReadOnly { .. })));
+    assert_eq!(facts.get_int("gold"), Some(&5));
+    assert_eq!(facts.get_int("berths"), Some(&4));
+
+    let failed = run("gold = 8; throw \"sunk\";", &mut facts);
+    assert!(matches!(failed, Err(FactError::Script { .. })));
+    assert_eq!(facts.get_int("gold"), Some(&5));
+}
+
+#[cfg(not(feature = "scripting"))]
+#[test]
+fn scripts_fail_without_the_scripting_feature() {
+    let mut facts = harbour();
+    assert!(matches!(
+        run("gold = 5;", &mut facts),
+        Err(FactError::Script { .. })
+    ));
+    assert_eq!(facts.get_int("gold"), Some(&1));
+}
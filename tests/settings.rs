@@ -0,0 +1,112 @@
+// Settings are stored in a file of their own, apart from the save. Options the file doesn't
+// mention, like ones added in a later version, keep their defaults. The file is only written
+// when an option changes, and never over a file that is there but couldn't be read.
+use barnacle_beats::prelude::*;
+use barnacle_beats::settings::{self, Settings, SettingsLoadFailed, SETTINGS_FILE};
+use bevy::prelude::App;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn settings_round_trip() {
+    let settings = Settings {
+        subtitles: true,
+        text_speed: 1.5,
+        volume: 0.5,
+        ..Default::default()
+    };
+    let source = settings.to_ron().expect("settings serialize");
+    assert_eq!(
+        Settings::from_ron(&source).expect("settings parse"),
+        settings
+    );
+}
+
+#[test]
+fn missing_options_keep_their_defaults() {
+    let settings = Settings::from_ron("(dyslexia_font: true)").expect("settings parse");
+    assert!(settings.dyslexia_font);
+    assert_eq!(settings.ui_scale, Settings::default().ui_scale);
+}
+
+fn settings_app(dir: &Path) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .add_plugins(settings::plugin)
+        .insert_resource(SaveLocation {
+            path: Some(dir.display().to_string()),
+            legacy_path: None,
+            ..Default::default()
+        });
+    app.update();
+    app.update();
+    app
+}
+
+fn settings_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("barnacle_beats_{}_{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("settings dir");
+    dir
+}
+
+#[test]
+fn launching_leaves_the_file_alone() {
+    let dir = settings_dir("settings_launch");
+    let mut app = settings_app(&dir);
+    assert!(!dir.join(SETTINGS_FILE).exists());
+
+    app.world.resource_mut::<Settings>().subtitles = true;
+    app.update();
+    let source = app
+        .world
+        .resource::<SaveLocation>()
+        .read(SETTINGS_FILE)
+        .expect("settings were written");
+    assert!(
+        Settings::from_ron(&source)
+            .expect("settings parse")
+            .subtitles
+    );
+
+    // Loading the written file on the next launch doesn't write it again
+    fs::remove_file(dir.join(format!("{}.bak", SETTINGS_FILE))).ok();
+    let _app = settings_app(&dir);
+    assert!(!dir.join(format!("{}.bak", SETTINGS_FILE)).exists());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn unreadable_files_are_not_overwritten() {
+    let dir = settings_dir("settings_unreadable");
+    fs::write(dir.join(SETTINGS_FILE), "(subtitles: maybe)").expect("settings file");
+    let mut app = settings_app(&dir);
+    assert!(app.world.resource::<SettingsLoadFailed>().0);
+
+    app.world.resource_mut::<Settings>().subtitles = true;
+    app.update();
+    assert_eq!(
+        fs::read_to_string(dir.join(SETTINGS_FILE)).expect("settings file"),
+        "(subtitles: maybe)"
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn corrupted_files_are_not_overwritten() {
+    let dir = settings_dir("settings_corrupted");
+    let mut app = settings_app(&dir);
+    app.world.resource_mut::<Settings>().volume = 0.5;
+    app.update();
+    let path = dir.join(SETTINGS_FILE);
+    let mut bytes = fs::read(&path).expect("settings were written");
+    bytes[0] ^= 0xff;
+    fs::write(&path, &bytes).expect("settings file");
+
+    let mut app = settings_app(&dir);
+    assert!(app.world.resource::<SettingsLoadFailed>().0);
+    app.world.resource_mut::<Settings>().subtitles = true;
+    app.update();
+    assert_eq!(fs::read(&path).expect("settings file"), bytes);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,77 @@
+// Subscriptions match topics segment by segment, with `*` for any one segment or, at the end,
+// everything below. Finished beats, rules flipping and `Effect::EmitSignal` all reach them.
+use barnacle_beats::prelude::*;
+use bevy::prelude::App;
+
+#[test]
+fn wildcards_match_segments() {
+    assert!(topic_matches("story.*", "story.harbour.arrival.finished"));
+    assert!(topic_matches(
+        "story.*.arrival.finished",
+        "story.harbour.arrival.finished"
+    ));
+    assert!(topic_matches("mod.bell", "mod.bell"));
+    assert!(!topic_matches("story.*", "story"));
+    assert!(!topic_matches(
+        "story.*.finished",
+        "story.harbour.arrival.finished"
+    ));
+    assert!(!topic_matches("mod.bell", "mod.bell.rung"));
+
+    let mut signals = Signals::default();
+    let everything = signals.subscribe("*");
+    let bells = signals.subscribe("mod.bell");
+    signals.emit("mod.bell");
+    signals.emit("mod.horn");
+    assert_eq!(signals.read(everything), vec!["mod.bell", "mod.horn"]);
+    assert_eq!(signals.read(bells), vec!["mod.bell"]);
+    assert!(signals.read(bells).is_empty(), "reading empties the queue");
+}
+
+#[test]
+fn stories_emit_signals() {
+    let story = StoryBuilder::new("harbour")
+        .add_story_beat("arrival", |beat| {
+            beat.with_rule("docked", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "docked".to_string(),
+                    expected_value: true,
+                })
+            })
+            .with_effects(|effects| effects.emit_signal("mod.harbour.bell"))
+        })
+        .build()
+        .expect("test story builds");
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins);
+    app.world.resource_mut::<StoryEngine>().add_story(story);
+    let (story_signals, rule_signals, mod_signals) = {
+        let mut signals = app.world.resource_mut::<Signals>();
+        (
+            signals.subscribe("story.*"),
+            signals.subscribe("rule.*"),
+            signals.subscribe("mod.*"),
+        )
+    };
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("docked".to_string(), false).unwrap();
+    app.update();
+    app.update();
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("docked".to_string(), true).unwrap();
+    app.update();
+    app.update();
+
+    let mut signals = app.world.resource_mut::<Signals>();
+    assert_eq!(
+        signals.read(story_signals),
+        vec![beat_signal("harbour", "arrival")]
+    );
+    assert_eq!(
+        signals.read(rule_signals),
+        vec![rule_signal("docked", true)]
+    );
+    assert_eq!(signals.read(mod_signals), vec!["mod.harbour.bell"]);
+}
@@ -0,0 +1,61 @@
+// Sprites bound to a string fact play the clip the fact names, switching when a story
+// effect changes it
+use barnacle_beats::prelude::*;
+use barnacle_beats::sprite_animation::{AnimationFact, SpriteAnimation, SpriteAnimationPlugin};
+use bevy::prelude::{App, Entity, TextureAtlas};
+
+fn app() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalStoryPlugins, SpriteAnimationPlugin));
+    let sprite = app
+        .world
+        .spawn((
+            SpriteAnimation::new(8.)
+                .with_clip("idle", vec![0, 1])
+                .with_clip("wave", vec![4, 5, 6]),
+            AnimationFact {
+                fact: "npc.greta.anim".to_string(),
+            },
+            TextureAtlas::default(),
+        ))
+        .id();
+    app.update();
+    (app, sprite)
+}
+
+#[test]
+fn first_clip_plays_until_the_fact_names_another() {
+    let (mut app, sprite) = app();
+    assert_eq!(app.world.get::<TextureAtlas>(sprite).unwrap().index, 0);
+
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_string("npc.greta.anim".to_string(), "wave".to_string()).unwrap();
+    app.update();
+
+    let animation = app.world.get::<SpriteAnimation>(sprite).unwrap();
+    assert_eq!(animation.clip, "wave");
+    assert_eq!(app.world.get::<TextureAtlas>(sprite).unwrap().index, 4);
+}
+
+#[test]
+fn unknown_clip_keeps_the_current_one() {
+    let (mut app, sprite) = app();
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_string("npc.greta.anim".to_string(), "juggle".to_string()).unwrap();
+    app.update();
+
+    let animation = app.world.get::<SpriteAnimation>(sprite).unwrap();
+    assert_eq!(animation.clip, "idle");
+}
+
+#[test]
+fn clip_loops_over_its_frames() {
+    let mut animation = SpriteAnimation::new(10.).with_clip("wave", vec![4, 5, 6]);
+    assert_eq!(animation.advance(0.), Some(4));
+    assert_eq!(animation.advance(0.1), Some(5));
+    assert_eq!(animation.advance(0.2), Some(4));
+    animation.play("missing");
+    assert_eq!(animation.advance(0.1), None);
+}
@@ -0,0 +1,77 @@
+// Parses every story under assets/stories and compares the result, written back out as RON,
+// with the checked-in copy in tests/golden. Grammar or model changes that alter parsed stories
+// show up as a diff here.
+//
+// After an intended change, regenerate the golden files with
+//     UPDATE_GOLDEN=1 cargo test --test story_golden
+use barnacle_beats::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn story_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(manifest_dir().join("assets/stories"))
+        .expect("assets/stories should exist")
+        .map(|entry| entry.expect("readable directory entry").path())
+        .filter(|path| path.to_string_lossy().ends_with(".story.ron"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn golden_path(story_file: &Path) -> PathBuf {
+    let name = story_file.file_name().unwrap().to_string_lossy();
+    manifest_dir()
+        .join("tests/golden")
+        .join(format!("{}.golden", name))
+}
+
+fn parsed_form(story_file: &Path) -> String {
+    let source = fs::read_to_string(story_file).expect("readable story file");
+    let story = parse_story(&source)
+        .unwrap_or_else(|error| panic!("{} does not parse: {}", story_file.display(), error));
+    let mut form = ron::ser::to_string_pretty(&story, ron::ser::PrettyConfig::default())
+        .expect("parsed stories serialize");
+    form.push('\n');
+    form
+}
+
+#[test]
+fn parsed_stories_match_golden_files() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let files = story_files();
+    assert!(!files.is_empty(), "no story files found");
+
+    let mut mismatches = Vec::new();
+    for story_file in files {
+        let actual = parsed_form(&story_file);
+        let golden = golden_path(&story_file);
+        if update {
+            fs::write(&golden, &actual).expect("writable golden file");
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!(
+                "{} no longer matches {}\n--- expected\n{}\n--- actual\n{}",
+                story_file.display(),
+                golden.display(),
+                expected,
+                actual
+            )),
+            Err(_) => mismatches.push(format!(
+                "{} has no golden file, expected {}",
+                story_file.display(),
+                golden.display()
+            )),
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "{}\n\nRun with UPDATE_GOLDEN=1 if the change is intended.",
+        mismatches.join("\n\n")
+    );
+}
@@ -0,0 +1,82 @@
+// Linting flags beats waiting on facts nothing writes and facts written that nothing reads.
+// Facts the game's systems or the data tables keep count as written, and a fact named in a
+// script counts as used.
+use barnacle_beats::prelude::*;
+
+fn int_above(fact: &str, value: i32) -> Condition {
+    Condition::IntMoreThan {
+        fact_name: fact.to_string(),
+        expected_value: value,
+    }
+}
+
+fn harbour() -> Story {
+    StoryBuilder::new("harbour")
+        .add_story_beat("arrival", |beat| {
+            beat.with_rule("paid", |rule| {
+                rule.with_condition(int_above("ports.vigo.tax", 0))
+            })
+            .with_effects(|effects| {
+                effects
+                    .set_fact_int("harbour.visits", 1)
+                    .set_fact_bool("harbour.gossip", true)
+                    .start_mini_game("fishing")
+            })
+        })
+        .add_story_beat("departure", |beat| {
+            beat.with_rule("rested", |rule| {
+                rule.with_condition(int_above("harbour.visits", 0))
+                    .with_condition(int_above("crew.bosun.rest", 5))
+            })
+            .with_rule("caught", |rule| {
+                rule.with_condition(int_above("minigame.fishing.catch", 0))
+            })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+#[test]
+fn unwritten_and_unread_facts_are_flagged() {
+    let known = KnownFacts::game().writes("ports.vigo.tax");
+    let lints = lint_stories(&[harbour()], &known);
+    assert_eq!(
+        lints,
+        vec![
+            StoryLint::UnwrittenFact {
+                story: "harbour".to_string(),
+                beat: Some("departure".to_string()),
+                rule: "rested".to_string(),
+                fact: "crew.bosun.rest".to_string(),
+            },
+            StoryLint::UnreadFact {
+                story: "harbour".to_string(),
+                fact: "harbour.gossip".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        lints[0].to_string(),
+        "harbour/departure: rule rested reads crew.bosun.rest, which nothing writes"
+    );
+    assert_eq!(
+        lints[1].to_string(),
+        "harbour writes harbour.gossip, which nothing reads"
+    );
+}
+
+#[test]
+fn scripts_and_known_facts_count() {
+    let gossip = StoryBuilder::new("gossip")
+        .add_story_beat("rumour", |beat| {
+            beat.with_rule("heard", |rule| {
+                rule.with_condition(Condition::Script(
+                    "harbour.gossip && crew.bosun.rest > 5".to_string(),
+                ))
+            })
+        })
+        .build()
+        .expect("test story builds");
+    let known = KnownFacts::game().writes("ports.vigo.tax").writes("crew.");
+    assert!(lint_stories(&[harbour(), gossip], &known).is_empty());
+}
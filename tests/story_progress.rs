@@ -0,0 +1,124 @@
+// Story progress counts the weight of finished beats. Going back through a transition reopens
+// the beats from the target on, and the finished beats and the clock survive a save and load.
+use barnacle_beats::prelude::*;
+
+fn voyage() -> Story {
+    StoryBuilder::new("voyage")
+        .add_story_beat("provision", |beat| {
+            beat.with_rule("provisioned", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "provisioned".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .add_story_beat("crossing", |beat| {
+            beat.with_weight(3)
+                .with_rule("crossed", |rule| {
+                    rule.with_condition(Condition::BoolEquals {
+                        fact_name: "crossed".to_string(),
+                        expected_value: true,
+                    })
+                })
+                .transition_to("provision", |rule| {
+                    rule.with_condition(Condition::BoolEquals {
+                        fact_name: "storm".to_string(),
+                        expected_value: true,
+                    })
+                })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+fn evaluate(story: &mut Story, facts: &FactsOfTheWorld) {
+    let mut rng = StoryRng::new(1);
+    let mut context = EvaluationContext::detached(facts, &mut rng);
+    story.evaluate_active_beat(&mut context);
+}
+
+fn provisioned() -> FactsOfTheWorld {
+    let mut facts = FactsOfTheWorld::new();
+    facts.store_bool("provisioned".to_string(), true).unwrap();
+    facts
+}
+
+#[test]
+fn progress_is_weighted_by_finished_beats() {
+    let mut story = voyage();
+    let mut facts = provisioned();
+    evaluate(&mut story, &facts);
+    assert_eq!(story.progress(), 0.25);
+
+    facts.store_bool("crossed".to_string(), true).unwrap();
+    evaluate(&mut story, &facts);
+    assert_eq!(story.progress(), 1.0);
+}
+
+#[test]
+fn going_back_reopens_later_beats() {
+    let mut story = voyage();
+    let mut facts = provisioned();
+    evaluate(&mut story, &facts);
+    facts.store_bool("crossed".to_string(), true).unwrap();
+    facts.store_bool("storm".to_string(), true).unwrap();
+    evaluate(&mut story, &facts);
+
+    assert_eq!(story.active_beat_index, 0);
+    assert!(story.beats.iter().all(|beat| !beat.finished));
+    assert_eq!(story.progress(), 0.0);
+}
+
+#[test]
+fn finished_beats_survive_a_save_and_load() {
+    let mut facts = provisioned();
+    let mut engine = StoryEngine::new();
+    engine.add_story(voyage());
+    evaluate(&mut engine.stories[0], &facts);
+    let mut rng = StoryRng::new(1);
+    let mut time = StoryTime::default();
+    let save = SaveGame::from_ron(
+        &SaveGame::capture(1, &facts, &engine, &rng, &time)
+            .to_ron()
+            .unwrap(),
+    )
+    .unwrap();
+
+    let mut loaded = StoryEngine::new();
+    loaded.add_story(voyage());
+    save.restore(&mut facts, &mut loaded, &mut rng, &mut time);
+    assert_eq!(loaded.stories[0].progress(), 0.25);
+}
+
+#[test]
+fn saves_without_finished_beats_count_the_beats_passed() {
+    let mut facts = FactsOfTheWorld::new();
+    let mut engine = StoryEngine::new();
+    engine.add_story(voyage());
+    let mut rng = StoryRng::new(1);
+    let mut time = StoryTime::default();
+    let mut save = SaveGame::capture(1, &facts, &engine, &rng, &time);
+    save.stories[0].active_beat_index = 1;
+    save.stories[0].active_beat = None;
+    save.stories[0].finished_beats = None;
+
+    save.restore(&mut facts, &mut engine, &mut rng, &mut time);
+    assert_eq!(engine.stories[0].progress(), 0.25);
+}
+
+#[test]
+fn loading_takes_the_saved_clock_but_keeps_pausing() {
+    let mut facts = provisioned();
+    let mut engine = StoryEngine::new();
+    engine.add_story(voyage());
+    let mut rng = StoryRng::new(1);
+    let mut saved_time = StoryTime::default();
+    saved_time.advance(12.0);
+    let save = SaveGame::capture(1, &facts, &engine, &rng, &saved_time);
+
+    let mut time = StoryTime::default();
+    time.pause();
+    save.restore(&mut facts, &mut engine, &mut rng, &mut time);
+    assert_eq!(time.elapsed_seconds(), 12.0);
+    assert!(time.is_paused());
+}
@@ -0,0 +1,85 @@
+// The story state is entered again after the menu, the credits or the codex. Stories set up on
+// entry replace themselves instead of piling up, and the rng a save brought along is kept.
+use barnacle_beats::prelude::*;
+use bevy::prelude::{App, NextState, OnEnter};
+use std::fs;
+
+fn aboard(rule: RuleBuilder, fact: &str) -> RuleBuilder {
+    rule.with_condition(Condition::BoolEquals {
+        fact_name: fact.to_string(),
+        expected_value: true,
+    })
+}
+
+fn ferry(finished: bool) -> Story {
+    let mut story = StoryBuilder::new("ferry")
+        .add_story_beat("board", |beat| {
+            beat.with_rule("boarded", |rule| aboard(rule, "boarded"))
+        })
+        .add_story_beat("cross", |beat| {
+            beat.with_rule("crossed", |rule| aboard(rule, "crossed"))
+        })
+        .build()
+        .expect("test story builds");
+    if finished {
+        story.is_started = true;
+        story.active_beat_index = 1;
+        story.beats[0].finished = true;
+    }
+    story
+}
+
+#[test]
+fn replacing_a_story_keeps_its_progress() {
+    let mut engine = StoryEngine::new();
+    engine.add_or_replace_story(ferry(true));
+    engine.add_or_replace_story(ferry(false));
+
+    assert_eq!(engine.stories.len(), 1);
+    let story = &engine.stories[0];
+    assert!(story.is_started);
+    assert_eq!(story.active_beat_index, 1);
+    assert!(story.beats[0].finished);
+    assert!(!story.beats[1].finished);
+}
+
+fn go_to(app: &mut App, next: GameState) {
+    app.world.resource_mut::<NextState<GameState>>().set(next);
+    app.update();
+}
+
+#[test]
+fn coming_back_to_the_story_keeps_the_loaded_rng() {
+    let dir = std::env::temp_dir().join(format!("barnacle_beats_reentry_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .add_systems(OnEnter(GameState::Story), apply_run_seed)
+        .insert_resource(SaveLocation {
+            path: Some(dir.display().to_string()),
+            legacy_path: None,
+            ..Default::default()
+        })
+        .insert_resource(RunSeed::new("barnacle"));
+    app.update();
+    assert_eq!(
+        *app.world.resource::<StoryRng>(),
+        RunSeed::new("barnacle").rng()
+    );
+
+    app.world.resource_mut::<StoryRng>().next_u64();
+    app.world.send_event(SaveGameRequest);
+    app.update();
+    app.world.resource_mut::<StoryRng>().next_u64();
+    app.world.send_event(LoadGameRequest);
+    app.update();
+    let loaded = app.world.resource::<StoryRng>().clone();
+    let mut expected = RunSeed::new("barnacle").rng();
+    expected.next_u64();
+    assert_eq!(loaded, expected);
+
+    go_to(&mut app, GameState::Menu);
+    go_to(&mut app, GameState::Story);
+    assert_eq!(*app.world.resource::<StoryRng>(), loaded);
+    let _ = fs::remove_dir_all(&dir);
+}
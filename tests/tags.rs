@@ -0,0 +1,59 @@
+// Tags group facts so they can be found and cleared together. Tagging through an alias tags the
+// fact it stands for, and clearing a tag writes every fact or none of them.
+use barnacle_beats::prelude::*;
+
+fn cargo() -> FactsOfTheWorld {
+    let mut facts = FactsOfTheWorld::new();
+    facts.alias("fish", "hold.fish");
+    facts.store_tagged(Fact::Int("fish".to_string(), 6), &["cargo"]);
+    facts.store_tagged(Fact::Bool("hold.wet".to_string(), true), &["cargo"]);
+    facts.store_int("gold".to_string(), 2).unwrap();
+    facts
+}
+
+fn apply(effect: Effect, facts: &mut FactsOfTheWorld) -> Result<Vec<EffectOutput>, FactError> {
+    effect.apply(facts, &mut StoryRng::new(1))
+}
+
+#[test]
+fn tagged_facts_are_found_under_their_own_names() {
+    let mut facts = cargo();
+    apply(
+        Effect::Tag {
+            fact_name: "fish".to_string(),
+            tag: "catch".to_string(),
+        },
+        &mut facts,
+    )
+    .unwrap();
+    let mut tagged: Vec<&Fact> = facts.facts_with_tag("cargo").collect();
+    tagged.sort_by_key(|fact| fact.key().to_string());
+    assert_eq!(
+        tagged,
+        vec![
+            &Fact::Int("hold.fish".to_string(), 6),
+            &Fact::Bool("hold.wet".to_string(), true),
+        ]
+    );
+    assert_eq!(facts.tagged_keys("catch"), vec!["hold.fish".to_string()]);
+}
+
+#[test]
+fn clearing_a_tag_writes_all_or_nothing() {
+    let mut facts = cargo();
+    facts.drain_updated();
+    apply(Effect::ClearTag("cargo".to_string()), &mut facts).unwrap();
+    assert_eq!(facts.get_int("hold.fish"), Some(&0));
+    assert_eq!(facts.get_bool("hold.wet"), Some(&false));
+    assert_eq!(facts.get_int("gold"), Some(&2));
+    assert_eq!(facts.drain_updated().len(), 2);
+
+    facts.store_int("hold.fish".to_string(), 4).unwrap();
+    facts.store_constant(Fact::String("hold.name".to_string(), "Gull".to_string()));
+    facts.tag("hold.name", "cargo");
+    assert!(matches!(
+        apply(Effect::ClearTag("cargo".to_string()), &mut facts),
+        Err(FactError::ReadOnly { .. })
+    ));
+    assert_eq!(facts.get_int("hold.fish"), Some(&4));
+}
@@ -0,0 +1,60 @@
+// Telemetry is only recorded once the player opts in, and goes to a JSONL file next to the
+// saves rather than into the working directory. Events are stamped with story time, and the
+// first beat of a story is timed from when the player opted in.
+use barnacle_beats::prelude::*;
+use barnacle_beats::settings::Settings;
+use bevy::prelude::App;
+use std::fs;
+
+fn lighthouse() -> Story {
+    StoryBuilder::new("lighthouse")
+        .add_story_beat("lit", |beat| {
+            beat.with_rule("lamp on", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "lamp".to_string(),
+                    expected_value: true,
+                })
+            })
+        })
+        .build()
+        .expect("test story builds")
+}
+
+#[test]
+fn events_are_written_next_to_the_saves_once_opted_in() {
+    let dir = std::env::temp_dir().join(format!("barnacle_beats_telemetry_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut app = App::new();
+    app.add_plugins(MinimalStoryPlugins)
+        .insert_resource(SaveLocation {
+            path: Some(dir.display().to_string()),
+            legacy_path: None,
+            ..Default::default()
+        });
+    app.world.resource_mut::<StoryEngine>().add_story(lighthouse());
+    app.update();
+    assert!(!dir.join("telemetry.jsonl").exists());
+
+    app.world.resource_mut::<StoryTime>().advance(100.0);
+    app.world.resource_mut::<Settings>().telemetry_enabled = true;
+    app.update();
+    app.world.resource_mut::<StoryTime>().advance(5.0);
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("lamp".to_string(), true)
+        .unwrap();
+    app.update();
+    app.update();
+
+    let lines = fs::read_to_string(dir.join("telemetry.jsonl")).expect("telemetry was written");
+    assert_eq!(lines.lines().count(), 1);
+    assert!(lines.contains("\"type\":\"BeatFinished\""));
+    assert!(lines.contains("\"beat\":\"lit\""));
+    let event: serde_json::Value = serde_json::from_str(lines.trim()).expect("one JSON event");
+    let at = event["at"].as_f64().expect("timestamp");
+    let spent = event["seconds_spent"].as_f64().expect("time spent");
+    // Real frame times are added on top of the advanced story time
+    assert!((105.0..106.0).contains(&at), "at {}", at);
+    assert!((5.0..6.0).contains(&spent), "spent {}", spent);
+    let _ = fs::remove_dir_all(&dir);
+}
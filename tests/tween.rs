@@ -0,0 +1,50 @@
+// Tweens follow their easing curve from start to end, and fact tweens move an int fact a whole
+// step at a time, landing exactly on the target.
+use barnacle_beats::prelude::*;
+use barnacle_beats::tween::{FactTween, FactTweens, Tween};
+use bevy::prelude::{Color, Vec3};
+use std::time::Duration;
+
+#[test]
+fn tweens_ease_between_their_ends() {
+    let mut tween = Tween::new(Vec3::ZERO, Vec3::new(10., 0., 0.), 2.).with_easing(Easing::Linear);
+    assert_eq!(tween.tick(0.5), Vec3::new(2.5, 0., 0.));
+    assert_eq!(tween.tick(5.), Vec3::new(10., 0., 0.));
+    assert!(tween.is_finished());
+
+    let mut eased = Tween::new(0., 1., 1.).with_easing(Easing::EaseIn);
+    assert!(eased.tick(0.5) < 0.5, "ease in starts slow");
+
+    let mut dimming = Tween::new(Color::WHITE, Color::BLACK, 1.);
+    assert_eq!(dimming.tick(1.), Color::BLACK.as_rgba_linear());
+}
+
+#[test]
+fn fact_tweens_step_to_the_target() {
+    let mut tension = FactTween::new("tension", 0, 10, Duration::from_secs(10), Easing::Linear);
+    assert_eq!(tension.advance(2.), 2);
+    assert_eq!(tension.advance(3.4), 5);
+    assert_eq!(tension.advance(60.), 10);
+    assert!(tension.tween.is_finished());
+}
+
+#[test]
+fn a_new_fact_tween_takes_over() {
+    let mut tweens = FactTweens::default();
+    tweens.start(FactTween::new(
+        "light",
+        100,
+        0,
+        Duration::from_secs(5),
+        Easing::Linear,
+    ));
+    tweens.start(FactTween::new(
+        "light",
+        40,
+        80,
+        Duration::from_secs(5),
+        Easing::Linear,
+    ));
+    assert_eq!(tweens.tweens.len(), 1);
+    assert_eq!(tweens.tweens[0].tween.to, 80.);
+}
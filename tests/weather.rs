@@ -0,0 +1,66 @@
+// Weather only moves along the settings' transitions, and forcing it through an effect
+// writes the `weather` fact during the story pass, so rules see it in the same frame.
+use barnacle_beats::prelude::*;
+use barnacle_beats::weather::{WeatherPlugin, WeatherSettings, WEATHER_FACT};
+use bevy::prelude::App;
+
+#[test]
+fn weather_names_round_trip_through_the_fact() {
+    for weather in Weather::ALL {
+        assert_eq!(
+            Weather::from_fact_value(weather.fact_value()),
+            Some(weather)
+        );
+    }
+    assert_eq!(Weather::from_fact_value("hail"), None);
+}
+
+#[test]
+fn weather_only_changes_along_its_transitions() {
+    let settings = WeatherSettings::default();
+    let mut rng = StoryRng::new(7);
+    for _ in 0..200 {
+        let next = settings.next_weather(Weather::Storm, &mut rng);
+        assert_eq!(next, Weather::Rain);
+        let next = settings.next_weather(Weather::Clear, &mut rng);
+        assert!(matches!(next, Weather::Cloudy | Weather::Fog));
+    }
+}
+
+#[test]
+fn set_weather_writes_the_fact_in_the_same_frame() {
+    let story = StoryBuilder::new("squall")
+        .add_story_beat("clouds gather", |beat| {
+            beat.with_rule("barometer fell", |rule| {
+                rule.with_condition(Condition::BoolEquals {
+                    fact_name: "barometer_fell".to_string(),
+                    expected_value: true,
+                })
+            })
+            .with_effects(|effects| effects.set_weather(Weather::Storm, None))
+        })
+        .add_story_beat("reef the sails", |beat| {
+            beat.with_rule("storm broke", |rule| {
+                rule.with_condition(Condition::StringEquals {
+                    fact_name: WEATHER_FACT.to_string(),
+                    expected_value: "storm".into(),
+                })
+            })
+            .with_effects(|effects| effects.set_fact_bool("reefed", true))
+        })
+        .build()
+        .expect("test story builds");
+    let mut app = App::new();
+    app.add_plugins((MinimalStoryPlugins, WeatherPlugin));
+    app.world.resource_mut::<StoryEngine>().add_story(story);
+    app.update();
+    app.world
+        .resource_mut::<FactsOfTheWorld>()
+        .store_bool("barometer_fell".to_string(), true)
+        .unwrap();
+    app.update();
+
+    let facts = app.world.resource::<FactsOfTheWorld>();
+    assert_eq!(facts.get_string(WEATHER_FACT), Some(&"storm".to_string()));
+    assert_eq!(facts.get_bool("reefed"), Some(&true));
+}